@@ -1,57 +1,288 @@
 //! CasperFlow Testnet Deployment Script
 //!
-//! Run with: cargo run --bin deploy --features livenet
+//! A small CLI around the core contracts. Deployed addresses are kept in a
+//! TOML artifact (`deployments.toml` unless `--artifact <path>` is given),
+//! so every command after the first re-attaches to the same contracts:
+//!
+//! - `deploy`  deploys whatever the artifact does not list yet, then wires
+//! - `upgrade` installs new code for `vault`, `engine` or `all` and runs
+//!   the engine storage migration
+//! - `wire`    links Vault, Engine and Registry; links already in place
+//!   are left alone, so it is safe to re-run
+//! - `status`  prints the recorded addresses and their on-chain links
+//!
+//! Run with: cargo run --bin deploy --features livenet -- <command>
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use odra::host::{Deployer, HostEnv, HostRefLoader, InstallConfig, NoArgs, Upgrader};
+use odra::prelude::*;
+use casperflow_contracts::vault::{
+    AutomationVault, AutomationVaultHostRef, AutomationVaultInitArgs,
+};
+use casperflow_contracts::automation_engine::{
+    AutomationEngine, AutomationEngineHostRef, AutomationEngineInitArgs,
+};
+use casperflow_contracts::registry::{ContractRegistry, ContractRegistryHostRef};
+use casperflow_contracts::types::ContractKind;
+
+/// Gas limit for contract deployment (500 CSPR)
+const DEPLOY_GAS: u64 = 500_000_000_000;
+/// Gas limit for contract calls (10 CSPR)
+const CALL_GAS: u64 = 10_000_000_000;
+/// Artifact used when `--artifact` is not given
+const DEFAULT_ARTIFACT: &str = "deployments.toml";
+/// Rules converted per `migrate` call after an engine upgrade
+const MIGRATION_BATCH: u32 = 50;
+
+const USAGE: &str = "usage: deploy <deploy|upgrade <vault|engine|all>|wire|status> \
+                     [--artifact <path>]";
+
+/// Addresses of the deployed contracts, as recorded in the artifact
+#[derive(Default)]
+struct Deployment {
+    vault: Option<Address>,
+    engine: Option<Address>,
+    registry: Option<Address>,
+}
+
+impl Deployment {
+    /// Read the artifact, or start empty if it does not exist yet
+    fn load(path: &Path) -> Self {
+        let mut deployment = Deployment::default();
+        let Ok(contents) = fs::read_to_string(path) else {
+            return deployment;
+        };
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line.split_once('=').unwrap_or_else(|| {
+                panic!("malformed line in {}: {line}", path.display())
+            });
+            let value = value.trim().trim_matches('"');
+            let address = Address::from_str(value).unwrap_or_else(|_| {
+                panic!("invalid address for {} in {}", key.trim(), path.display())
+            });
+            match key.trim() {
+                "vault" => deployment.vault = Some(address),
+                "engine" => deployment.engine = Some(address),
+                "registry" => deployment.registry = Some(address),
+                other => panic!("unknown contract {other} in {}", path.display()),
+            }
+        }
+        deployment
+    }
 
-use odra::host::Deployer;
-use odra::prelude::Addressable;
-use casperflow_contracts::vault::{AutomationVault, AutomationVaultInitArgs};
-use casperflow_contracts::automation_engine::{AutomationEngine, AutomationEngineInitArgs};
+    /// Write the artifact; called after every deploy so a failed run can resume
+    fn save(&self, path: &Path) {
+        let mut contents = String::from("# CasperFlow deployment, written by `bin/deploy.rs`\n");
+        for (key, address) in [
+            ("vault", self.vault),
+            ("engine", self.engine),
+            ("registry", self.registry),
+        ] {
+            if let Some(address) = address {
+                contents.push_str(&format!("{key} = \"{}\"\n", address.to_formatted_string()));
+            }
+        }
+        fs::write(path, contents)
+            .unwrap_or_else(|e| panic!("cannot write {}: {e}", path.display()));
+    }
+
+    fn vault(&self, env: &HostEnv) -> AutomationVaultHostRef {
+        let address = self.vault.expect("no vault in the artifact; run `deploy` first");
+        AutomationVault::load(env, address)
+    }
+
+    fn engine(&self, env: &HostEnv) -> AutomationEngineHostRef {
+        let address = self.engine.expect("no engine in the artifact; run `deploy` first");
+        AutomationEngine::load(env, address)
+    }
+
+    fn registry(&self, env: &HostEnv) -> ContractRegistryHostRef {
+        let address = self.registry.expect("no registry in the artifact; run `deploy` first");
+        ContractRegistry::load(env, address)
+    }
+}
 
 fn main() {
     // Load environment from .env file
     dotenv::dotenv().ok();
-    
+
+    let mut args = std::env::args().skip(1);
+    let mut command = Vec::new();
+    let mut artifact = PathBuf::from(DEFAULT_ARTIFACT);
+    while let Some(arg) = args.next() {
+        if arg == "--artifact" {
+            artifact = PathBuf::from(args.next().expect(USAGE));
+        } else {
+            command.push(arg);
+        }
+    }
+
     // Get the livenet environment (reads from ODRA_CASPER_LIVENET_* env vars)
     let env = odra_casper_livenet_env::env();
-    
+    let mut deployment = Deployment::load(&artifact);
+
+    match command.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["deploy"] => {
+            deploy(&env, &mut deployment, &artifact);
+            wire(&env, &deployment);
+            status(&env, &deployment, &artifact);
+        }
+        ["upgrade", target] => upgrade(&env, &deployment, target),
+        ["wire"] => wire(&env, &deployment),
+        ["status"] => status(&env, &deployment, &artifact),
+        _ => {
+            eprintln!("{USAGE}");
+            std::process::exit(2);
+        }
+    }
+}
+
+/// Deploy the contracts the artifact does not list yet
+fn deploy(env: &HostEnv, deployment: &mut Deployment, artifact: &Path) {
     println!("🚀 Deploying CasperFlow to Casper Testnet...\n");
-    
-    // Gas limit for contract deployment (300 CSPR = 300_000_000_000 motes)
-    const DEPLOY_GAS: u64 = 500_000_000_000;
-    // Gas limit for contract calls (10 CSPR)
-    const CALL_GAS: u64 = 10_000_000_000;
-    
-    // Step 1: Deploy AutomationVault
-    println!("📦 Deploying AutomationVault...");
-    env.set_gas(DEPLOY_GAS);
-    let vault = AutomationVault::deploy(&env, AutomationVaultInitArgs {
-        automation_engine: None,
-    });
-    let vault_address = vault.address().clone();
-    println!("✅ Vault deployed at: {:?}\n", vault_address);
-    
-    // Step 2: Deploy AutomationEngine with Vault address
-    println!("📦 Deploying AutomationEngine...");
-    env.set_gas(DEPLOY_GAS);
-    let engine = AutomationEngine::deploy(&env, AutomationEngineInitArgs {
-        vault_address: Some(vault_address.clone()),
-    });
-    let engine_address = engine.address().clone();
-    println!("✅ Engine deployed at: {:?}\n", engine_address);
-    
-    // Step 3: Link Vault to Engine
-    println!("🔗 Linking Vault to Engine...");
-    env.set_gas(CALL_GAS);
-    let mut vault_ref = vault;
-    vault_ref.set_automation_engine(engine_address.clone());
-    println!("✅ Vault linked to Engine\n");
-    
-    // Print summary
+
+    if deployment.vault.is_none() {
+        println!("📦 Deploying AutomationVault...");
+        env.set_gas(DEPLOY_GAS);
+        let vault = AutomationVault::deploy_with_cfg(
+            env,
+            AutomationVaultInitArgs { automation_engine: None },
+            InstallConfig::upgradable::<AutomationVault>(),
+        );
+        deployment.vault = Some(*vault.address());
+        deployment.save(artifact);
+        println!("✅ Vault deployed at: {:?}\n", vault.address());
+    }
+
+    if deployment.engine.is_none() {
+        println!("📦 Deploying AutomationEngine...");
+        env.set_gas(DEPLOY_GAS);
+        let engine = AutomationEngine::deploy_with_cfg(
+            env,
+            AutomationEngineInitArgs { vault_address: deployment.vault },
+            InstallConfig::upgradable::<AutomationEngine>(),
+        );
+        deployment.engine = Some(*engine.address());
+        deployment.save(artifact);
+        println!("✅ Engine deployed at: {:?}\n", engine.address());
+    }
+
+    if deployment.registry.is_none() {
+        println!("📦 Deploying ContractRegistry...");
+        env.set_gas(DEPLOY_GAS);
+        let registry = ContractRegistry::deploy(env, NoArgs);
+        deployment.registry = Some(*registry.address());
+        deployment.save(artifact);
+        println!("✅ Registry deployed at: {:?}\n", registry.address());
+    }
+
+    println!("📋 Addresses saved to {}\n", artifact.display());
+}
+
+/// Install new code for the given contracts, keeping their addresses and state
+fn upgrade(env: &HostEnv, deployment: &Deployment, target: &str) {
+    let (vault, engine) = match target {
+        "vault" => (true, false),
+        "engine" => (false, true),
+        "all" => (true, true),
+        _ => panic!("{USAGE}"),
+    };
+
+    if vault {
+        println!("⬆️  Upgrading AutomationVault...");
+        let address = *deployment.vault(env).address();
+        env.set_gas(DEPLOY_GAS);
+        AutomationVault::try_upgrade(env, address, NoArgs).expect("vault upgrade failed");
+        println!("✅ Vault upgraded\n");
+    }
+
+    if engine {
+        println!("⬆️  Upgrading AutomationEngine...");
+        let address = *deployment.engine(env).address();
+        env.set_gas(DEPLOY_GAS);
+        let mut engine =
+            AutomationEngine::try_upgrade(env, address, NoArgs).expect("engine upgrade failed");
+        println!("✅ Engine upgraded, migrating storage...");
+        loop {
+            env.set_gas(DEPLOY_GAS);
+            if engine.migrate(MIGRATION_BATCH) {
+                break;
+            }
+        }
+        println!("✅ Engine storage at version {}\n", engine.get_storage_version());
+    }
+}
+
+/// Link Vault, Engine and Registry, skipping links that are already in place
+fn wire(env: &HostEnv, deployment: &Deployment) {
+    let mut vault = deployment.vault(env);
+    let mut engine = deployment.engine(env);
+    let mut registry = deployment.registry(env);
+    let (vault_address, engine_address, registry_address) =
+        (*vault.address(), *engine.address(), *registry.address());
+
+    println!("🔗 Wiring contracts...");
+    if vault.get_automation_engine() != Some(engine_address) {
+        env.set_gas(CALL_GAS);
+        vault.set_automation_engine(engine_address);
+        println!("   Vault → Engine");
+    }
+    for (kind, address) in [
+        (ContractKind::Vault, vault_address),
+        (ContractKind::Engine, engine_address),
+    ] {
+        if registry.get_address(kind) != Some(address) {
+            env.set_gas(CALL_GAS);
+            registry.set_address(kind, Some(address));
+            println!("   Registry records {:?}", kind);
+        }
+    }
+
+    // Resolve peers through the registry from now on
+    if vault.get_registry() != Some(registry_address) {
+        env.set_gas(CALL_GAS);
+        vault.set_registry(Some(registry_address));
+        println!("   Vault → Registry");
+    }
+    if engine.get_registry() != Some(registry_address) {
+        env.set_gas(CALL_GAS);
+        engine.set_registry(Some(registry_address));
+        println!("   Engine → Registry");
+    }
+    println!("✅ Contracts wired\n");
+}
+
+/// Print the recorded addresses and how the deployed contracts are linked
+fn status(env: &HostEnv, deployment: &Deployment, artifact: &Path) {
+    let show = |address: Option<Address>| {
+        address.map_or("not deployed".to_string(), |a| a.to_formatted_string())
+    };
     println!("═══════════════════════════════════════════════════════════");
-    println!("                   DEPLOYMENT COMPLETE                      ");
+    println!("                   DEPLOYMENT STATUS                        ");
     println!("═══════════════════════════════════════════════════════════");
-    println!("AutomationVault:  {:?}", vault_address);
-    println!("AutomationEngine: {:?}", engine_address);
+    println!("Artifact:         {}", artifact.display());
+    println!("AutomationVault:  {}", show(deployment.vault));
+    println!("AutomationEngine: {}", show(deployment.engine));
+    println!("ContractRegistry: {}", show(deployment.registry));
+
+    if deployment.vault.is_some() && deployment.engine.is_some() {
+        let vault = deployment.vault(env);
+        let engine = deployment.engine(env);
+        let linked = vault.get_automation_engine() == deployment.engine
+            && engine.get_vault_address() == deployment.vault;
+        let stats = engine.get_global_stats();
+        println!("───────────────────────────────────────────────────────────");
+        println!("Vault ↔ Engine:   {}", if linked { "linked" } else { "NOT linked" });
+        println!("Vault registry:   {}", show(vault.get_registry()));
+        println!("Engine registry:  {}", show(engine.get_registry()));
+        println!("Storage version:  {}", engine.get_storage_version());
+        println!("Executions:       {}", stats.total_executions);
+    }
     println!("═══════════════════════════════════════════════════════════");
-    println!("\n📋 Save these addresses for frontend integration!");
 }
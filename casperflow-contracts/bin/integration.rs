@@ -0,0 +1,176 @@
+//! CasperFlow Testnet Integration Run
+//!
+//! Deploys a fresh vault and engine, then walks one owner through the main
+//! flows: deposit, create a rule of each CSPR payment type, execute them
+//! without waiting for a schedule, and withdraw. After every execution the
+//! owner's vault balance is checked against the engine's own preview, and
+//! the run stops at the first mismatch.
+//!
+//! Needs the deployer key plus two more accounts (used as recipients) in
+//! the ODRA_CASPER_LIVENET_* environment. Staking rules are not covered:
+//! their proceeds take days to unbond.
+//!
+//! Run with: cargo run --bin integration --features livenet
+
+use odra::casper_types::U512;
+use odra::host::{Deployer, HostEnv};
+use odra::prelude::*;
+use casperflow_contracts::vault::{
+    AutomationVault, AutomationVaultHostRef, AutomationVaultInitArgs,
+};
+use casperflow_contracts::automation_engine::{
+    AutomationEngine, AutomationEngineHostRef, AutomationEngineInitArgs,
+};
+use casperflow_contracts::types::{
+    ActionType, DustPolicy, EscrowStatus, PayrollEntry, PayrollFailurePolicy, Schedule,
+    SplitConfig, SplitRecipient, TriggerType,
+};
+
+/// Gas limit for contract deployment (500 CSPR)
+const DEPLOY_GAS: u64 = 500_000_000_000;
+/// Gas limit for contract calls (10 CSPR)
+const CALL_GAS: u64 = 10_000_000_000;
+/// CSPR the owner deposits for the run (100 CSPR)
+const DEPOSIT: u64 = 100_000_000_000;
+/// Amount each rule moves (1 CSPR)
+const PAYMENT: u64 = 1_000_000_000;
+
+fn main() {
+    // Load environment from .env file
+    dotenv::dotenv().ok();
+    let env = odra_casper_livenet_env::env();
+    let owner = env.get_account(0);
+    let (alice, bob) = (env.get_account(1), env.get_account(2));
+    
+    println!("🚀 Running CasperFlow integration flows on Casper Testnet...\n");
+    
+    println!("📦 Deploying AutomationVault and AutomationEngine...");
+    env.set_gas(DEPLOY_GAS);
+    let mut vault = AutomationVault::deploy(&env, AutomationVaultInitArgs {
+        automation_engine: None,
+    });
+    env.set_gas(DEPLOY_GAS);
+    let mut engine = AutomationEngine::deploy(&env, AutomationEngineInitArgs {
+        vault_address: Some(*vault.address()),
+    });
+    env.set_gas(CALL_GAS);
+    vault.set_automation_engine(*engine.address());
+    println!("✅ Vault {:?}\n✅ Engine {:?}\n", vault.address(), engine.address());
+    
+    println!("💰 Depositing {} motes...", DEPOSIT);
+    env.set_gas(CALL_GAS);
+    vault.with_tokens(U512::from(DEPOSIT)).deposit();
+    assert_eq!(vault.get_balance(owner), U512::from(DEPOSIT), "deposit not credited");
+    println!("✅ Deposit credited\n");
+    
+    println!("📝 Transfer rule (manual trigger)...");
+    env.set_gas(CALL_GAS);
+    let transfer = engine.create_rule(
+        "integration_transfer".to_string(),
+        TriggerType::Manual,
+        Schedule::Daily,
+        ActionType::Transfer,
+        Some(alice),
+        U512::from(PAYMENT),
+        None,
+        None,
+        None,
+        None,
+    );
+    execute_checked(&env, &vault, &mut engine, owner, transfer);
+    
+    println!("📝 Split rule (manual trigger, 30/70)...");
+    env.set_gas(CALL_GAS);
+    let split = engine.create_rule(
+        "integration_split".to_string(),
+        TriggerType::Manual,
+        Schedule::Daily,
+        ActionType::Split,
+        Some(alice),
+        U512::from(PAYMENT),
+        None,
+        None,
+        None,
+        None,
+    );
+    env.set_gas(CALL_GAS);
+    engine.set_split_config(split, Some(SplitConfig {
+        recipients: vec![
+            SplitRecipient { recipient: alice, percentage: 30 },
+            SplitRecipient { recipient: bob, percentage: 70 },
+        ],
+        dust_policy: DustPolicy::FirstRecipient,
+    }));
+    execute_checked(&env, &vault, &mut engine, owner, split);
+    
+    // One-shot rules without a first execution time are due right away
+    println!("📝 Payroll rule (one-shot)...");
+    env.set_gas(CALL_GAS);
+    let payroll = engine.create_payroll_rule(
+        "integration_payroll".to_string(),
+        Schedule::Once,
+        vec![
+            PayrollEntry { recipient: alice, amount: U512::from(PAYMENT) },
+            PayrollEntry { recipient: bob, amount: U512::from(PAYMENT) },
+        ],
+        PayrollFailurePolicy::AllOrNothing,
+    );
+    execute_checked(&env, &vault, &mut engine, owner, payroll);
+    
+    println!("📝 Escrow rule (one-shot, cancelled in its dispute window)...");
+    env.set_gas(CALL_GAS);
+    let escrow = engine.create_escrow_rule(
+        "integration_escrow".to_string(),
+        Schedule::Once,
+        alice,
+        U512::from(PAYMENT),
+        86_400,
+    );
+    execute_checked(&env, &vault, &mut engine, owner, escrow);
+    let escrow_id = *vault.get_rule_escrows(escrow).last().expect("no escrow opened");
+    let before_cancel = vault.get_balance(owner);
+    env.set_gas(CALL_GAS);
+    vault.cancel_escrow(escrow_id);
+    assert_eq!(vault.get_escrow(escrow_id).unwrap().status, EscrowStatus::Cancelled);
+    assert_eq!(
+        vault.get_balance(owner),
+        before_cancel + U512::from(PAYMENT),
+        "cancelled escrow not refunded"
+    );
+    println!("✅ Escrow refunded\n");
+    
+    println!("💸 Withdrawing what is left...");
+    let remaining = vault.get_withdrawable_balance(owner);
+    env.set_gas(CALL_GAS);
+    vault.withdraw(remaining);
+    assert!(vault.get_balance(owner).is_zero(), "vault balance left after withdrawal");
+    let stats = engine.get_global_stats();
+    assert_eq!(stats.total_executions, 4, "unexpected execution count");
+    
+    println!("═══════════════════════════════════════════════════════════");
+    println!("                  INTEGRATION RUN PASSED                    ");
+    println!("═══════════════════════════════════════════════════════════");
+    println!("Executions: {}", stats.total_executions);
+    println!("Volume:     {} motes", stats.total_volume);
+    println!("Withdrawn:  {} motes", remaining);
+}
+
+/// Execute a due rule and check the owner's balance moved exactly as the engine previewed
+fn execute_checked(
+    env: &HostEnv,
+    vault: &AutomationVaultHostRef,
+    engine: &mut AutomationEngineHostRef,
+    owner: Address,
+    rule_id: u64,
+) {
+    let preview = engine.preview_execution(rule_id);
+    assert!(preview.would_succeed, "rule {rule_id} would not execute: {preview:?}");
+    env.set_gas(CALL_GAS);
+    engine.execute_rule(rule_id);
+    assert_eq!(
+        vault.get_balance(owner),
+        preview.balance_after,
+        "rule {rule_id} moved a different amount than previewed"
+    );
+    println!("✅ Rule {rule_id} executed, {} motes moved\n", preview.amount);
+}
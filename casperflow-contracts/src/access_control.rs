@@ -0,0 +1,57 @@
+//! CasperFlow Access Control
+//!
+//! Role assignments embedded in contracts with admin functions, so routine
+//! operations can be handed to operator keys while the owner (the
+//! contract's admin, typically a multisig) keeps control of the wiring.
+
+use odra::prelude::*;
+
+use crate::errors::Error;
+use crate::types::Role;
+
+/// Accounts holding each delegated role, embedded as a submodule
+///
+/// The owner is not stored here: the embedding contract passes in its
+/// admin, who holds every role and is the only holder of `Role::Owner`.
+#[odra::module(errors = Error)]
+pub struct AccessControl {
+    /// Whether an account holds a role, keyed by (role, account)
+    members: Mapping<(Role, Address), bool>,
+}
+
+#[odra::module]
+impl AccessControl {
+    /// Grant or revoke a role; the embedding contract checks the caller
+    ///
+    /// Reverts with InvalidRole for `Role::Owner`, which cannot be delegated.
+    pub fn set_role(&mut self, role: Role, account: Address, granted: bool) {
+        if role == Role::Owner {
+            self.env().revert(Error::InvalidRole);
+        }
+        self.members.set(&(role, account), granted);
+    }
+
+    /// Whether the account has been granted the role
+    pub fn has_role(&self, role: Role, account: Address) -> bool {
+        self.members.get_or_default(&(role, account))
+    }
+
+    /// Whether the account is `admin` or has been granted the role
+    pub fn holds(&self, admin: Option<Address>, role: Role, account: Address) -> bool {
+        admin == Some(account) || (role != Role::Owner && self.has_role(role, account))
+    }
+
+    /// Revert unless the caller is `admin`
+    pub fn assert_admin(&self, admin: Option<Address>) {
+        if admin != Some(self.env().caller()) {
+            self.env().revert(Error::NotAdmin);
+        }
+    }
+
+    /// Revert unless the caller is `admin` or has been granted `role`
+    pub fn assert_role(&self, admin: Option<Address>, role: Role) {
+        if !self.holds(admin, role, self.env().caller()) {
+            self.env().revert(Error::NotAdmin);
+        }
+    }
+}
@@ -0,0 +1,162 @@
+//! CasperFlow Attestation Oracle
+//!
+//! A key-value feed of off-chain facts, e.g. a project's sponsor count,
+//! posted by attesters the admin allowlists. Condition rules in the engine
+//! compare an attested value against a threshold, so off-chain events can
+//! trigger automations without a purpose-built oracle for each of them.
+
+use odra::prelude::*;
+use odra::casper_types::U512;
+
+use crate::errors::Error;
+use crate::event_nonce::EventNonce;
+use crate::events::{AttestationPosted, AttesterUpdated};
+use crate::types::{Attestation, ContractKind, ContractVersion};
+
+/// Maximum length of an attestation key in bytes
+pub const MAX_KEY_LEN: usize = 64;
+/// Entrypoint interface version reported by `get_version`; bump on breaking changes
+pub const INTERFACE_VERSION: u32 = 1;
+
+/// The Attestation Oracle contract
+#[odra::module(events = [AttesterUpdated, AttestationPosted], errors = Error)]
+pub struct AttestationOracle {
+    /// The admin allowed to manage attesters
+    admin: Var<Address>,
+    /// Accounts allowed to post attestations
+    attesters: Mapping<Address, bool>,
+    /// Mapping of key to its latest attested value
+    attestations: Mapping<String, Attestation>,
+    /// Sequence number stamped on emitted events
+    event_nonce: SubModule<EventNonce>,
+}
+
+#[odra::module]
+impl AttestationOracle {
+    /// Initialize the oracle with the deployer as admin
+    pub fn init(&mut self) {
+        self.admin.set(self.env().caller());
+    }
+
+    /// Allow or disallow an account to post attestations (admin only)
+    pub fn set_attester(&mut self, attester: Address, allowed: bool) {
+        self.assert_admin();
+        self.attesters.set(&attester, allowed);
+        self.env().emit_event(AttesterUpdated {
+            attester,
+            allowed,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Post the value of `key` as observed off-chain at `timestamp` (attesters only)
+    ///
+    /// Timestamps cannot be in the future or older than the key's current
+    /// attestation, so a delayed post never overwrites a newer value.
+    pub fn post(&mut self, key: String, value: U512, timestamp: u64) {
+        let attester = self.env().caller();
+        if !self.attesters.get_or_default(&attester) {
+            self.env().revert(Error::NotAttester);
+        }
+        if key.is_empty() || key.len() > MAX_KEY_LEN {
+            self.env().revert(Error::InvalidAttestationKey);
+        }
+        let previous = self.attestations.get(&key).map_or(0, |attestation| attestation.timestamp);
+        if timestamp > self.env().get_block_time() || timestamp < previous {
+            self.env().revert(Error::StaleAttestation);
+        }
+        
+        self.attestations.set(&key, Attestation { value, timestamp, attester });
+        self.env().emit_event(AttestationPosted {
+            key,
+            value,
+            timestamp,
+            attester,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Hand the oracle over to a new admin (admin only)
+    pub fn transfer_admin(&mut self, new_admin: Address) {
+        self.assert_admin();
+        self.admin.set(new_admin);
+    }
+
+    /// Get the latest attestation for a key
+    pub fn get_attestation(&self, key: String) -> Option<Attestation> {
+        self.attestations.get(&key)
+    }
+
+    /// Check whether an account may post attestations
+    pub fn is_attester(&self, account: Address) -> bool {
+        self.attesters.get_or_default(&account)
+    }
+
+    /// Get the admin address
+    pub fn get_admin(&self) -> Option<Address> {
+        self.admin.get()
+    }
+
+    /// Get the kind and interface version of this contract
+    pub fn get_version(&self) -> ContractVersion {
+        ContractVersion { kind: ContractKind::AttestationOracle, version: INTERFACE_VERSION }
+    }
+
+    /// Get the nonce of the most recently emitted event
+    pub fn get_event_nonce(&self) -> u64 {
+        self.event_nonce.current()
+    }
+}
+
+impl AttestationOracle {
+    /// Revert unless the caller is the admin
+    fn assert_admin(&self) {
+        if self.admin.get() != Some(self.env().caller()) {
+            self.env().revert(Error::NotAdmin);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use odra::host::{Deployer, HostRef, NoArgs};
+
+    #[test]
+    fn test_post_attestation() {
+        let env = odra_test::env();
+        let mut oracle = AttestationOracle::deploy(&env, NoArgs);
+        let attester = env.get_account(1);
+        let key = "github_sponsor_count".to_string();
+        env.advance_block_time(1_000);
+        
+        // Only allowlisted attesters can post
+        env.set_caller(attester);
+        let result = oracle.try_post(key.clone(), U512::from(10u64), 900);
+        assert_eq!(result, Err(Error::NotAttester.into()));
+        env.set_caller(env.get_account(0));
+        oracle.set_attester(attester, true);
+        
+        env.set_caller(attester);
+        oracle.post(key.clone(), U512::from(10u64), 900);
+        let attestation = oracle.get_attestation(key.clone()).unwrap();
+        assert_eq!((attestation.value, attestation.timestamp), (U512::from(10u64), 900));
+        assert!(env.emitted_event(
+            oracle.address(),
+            AttestationPosted {
+                key: key.clone(),
+                value: U512::from(10u64),
+                timestamp: 900,
+                attester,
+                nonce: oracle.get_event_nonce(),
+            }
+        ));
+        
+        // Older or future observations cannot replace the current value
+        let result = oracle.try_post(key.clone(), U512::from(8u64), 800);
+        assert_eq!(result, Err(Error::StaleAttestation.into()));
+        let timestamp = env.block_time() + 1;
+        let result = oracle.try_post(key, U512::from(12u64), timestamp);
+        assert_eq!(result, Err(Error::StaleAttestation.into()));
+    }
+}
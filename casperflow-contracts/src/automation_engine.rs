@@ -5,40 +5,336 @@
 //! execute_due_rules() to run scheduled automations.
 
 use odra::prelude::*;
-use odra::casper_types::U512;
+use odra::casper_types::bytesrepr::{Bytes, ToBytes};
+use odra::casper_types::{PublicKey, U512};
+use odra::module::ModuleComponent;
 use odra::ContractRef;
 
+use crate::access_control::AccessControl;
+use crate::cep18::{from_token_amount, to_token_amount};
+use crate::cep78::Cep78NftContractRef;
 use crate::errors::Error;
-use crate::events::{RuleCreated, RulePaused, RuleResumed, RuleDeleted, RuleExecuted};
-use crate::types::{AutomationRule, TriggerType, Schedule, ActionType, RuleStatus, StakingTier};
-use crate::vault::AutomationVaultContractRef;
-
-/// Seconds in a day (for scheduling)
-const SECONDS_PER_DAY: u64 = 86_400;
-/// Seconds in a week
-const SECONDS_PER_WEEK: u64 = 604_800;
+use crate::event_nonce::EventNonce;
+use crate::events::{
+    RuleCreated, RulePaused, RuleResumed, RuleDeleted, RuleExecuted, RuleExecutionFailed,
+    RuleUpdated, FeeConfigUpdated, TemplateRegistered, WorkflowExecuted, PriceConditionMet, ManagerApproved,
+    ManagerRevoked, RulePendingApproval, RuleConfirmed, Heartbeat, InheritanceTriggered,
+    AddressBookEntryAdded, AddressBookEntryRemoved, AddressBookRestrictionUpdated,
+    StorageMigrated, BudgetExhausted, HookAllowlistUpdated, VaultUnderfunded, MissedExecution,
+    RulePurged, PayrollEntrySkipped, GoalReached, NftPerksUpdated, NotificationRequested,
+    SplitDustHandled, SessionKeyAdded, SessionKeyRevoked, RuleCompleted, RecipientBlocklistUpdated,
+    RoleGranted, RoleRevoked, ExecutionPauseUpdated, AdminActionQueued, AdminActionExecuted,
+    AdminActionCancelled, RewardsConditionMet, AttestationConditionMet, StakingAdapterUpdated,
+    RuleRestored, ContractRecipientAllowlistUpdated,
+};
+use crate::attestation_oracle::AttestationOracleContractRef;
+use crate::attestation_oracle::MAX_KEY_LEN as MAX_ATTESTATION_KEY_LEN;
+use crate::hook::ExecutionHookContractRef;
+use crate::oracle::PriceOracleContractRef;
+use crate::registry::{ContractRegistryContractRef, VersionedContractContractRef};
+use crate::safe_math::{checked_add, checked_mul};
+use crate::staking_adapter::INTERFACE_VERSION as STAKING_ADAPTER_INTERFACE_VERSION;
+use crate::staking_adapter::StakingAdapterContractRef;
+use crate::template_registry::TemplateRegistry;
+use crate::timelock::Timelock;
+use crate::types::{
+    AutomationRule, RuleConfig, RuleState, TriggerType, Schedule, ActionType, RuleStatus,
+    StakingTier, FeeConfig, AmountSpec, ExecutorPolicy, ExecutionRecord, RuleTemplate, TemplateParams, Comparison,
+    PriceCondition, RebalanceConstraints, InheritanceConfig, AddressBookEntry, ExecutionPreview,
+    PriceData, GlobalStats, UserStats, SwapConfig, SwapDirection, RuleHook, ContractRecipient,
+    RecipientKind, RuleParams, SignedRuleRequest, SessionKeyScope, RuleOverrides, ExecutionWindow,
+    RewardDestination, UserSnapshot, LegacyRuleConfig, BaselineRule,
+    MissedExecutionConfig, MissedExecutionPolicy, ContractKind, ContractVersion, PayrollConfig,
+    PayrollEntry, PayrollFailurePolicy, RewardSplit, NftPerks, UserPerks, SplitConfig, DustPolicy,
+    TimeConfig, Role, AdminAction, AdminActionKind, Attestation, AttestationCondition,
+    ExecutionResult, ExecutionStep, PaymentStream, VestingSchedule,
+};
+use crate::vault::{AutomationVaultContractRef, INTERFACE_VERSION as VAULT_INTERFACE_VERSION};
+
+/// Seconds in an hour (the window for execution rate limits)
+const SECONDS_PER_HOUR: u64 = 3_600;
 /// Seconds in a month (approximate: 30 days)
 const SECONDS_PER_MONTH: u64 = 2_592_000;
+/// Number of executions kept in each rule's history ring buffer
+const EXECUTION_HISTORY_SIZE: u32 = 16;
+/// Maximum number of follow-up rules a single rule may declare
+const MAX_FOLLOW_UPS: usize = 4;
+/// Maximum number of rules in a single workflow chain
+const MAX_WORKFLOW_DEPTH: u32 = 4;
+/// Default maximum age of oracle prices (1 hour)
+const DEFAULT_MAX_PRICE_AGE: u64 = 3_600;
+/// Maximum size of a rule's user-supplied metadata in bytes
+const MAX_RULE_METADATA_LEN: usize = 256;
+/// Maximum number of tags on one rule
+const MAX_RULE_TAGS: usize = 8;
+/// Maximum length of a rule tag in bytes
+const MAX_RULE_TAG_LEN: usize = 32;
+/// Maximum slippage a swap rule may accept (10%)
+const MAX_SWAP_SLIPPAGE_BPS: u16 = 1_000;
+/// Maximum length of a contract recipient's deposit entrypoint name
+const MAX_ENTRY_POINT_LENGTH: usize = 64;
+/// Maximum number of rules created by one `create_rules_batch` call
+const MAX_BATCH_RULES: usize = 25;
+/// Maximum number of upcoming executions a low balance alert can look ahead
+const MAX_ALERT_LOOKAHEAD: u32 = 12;
+/// Maximum number of missed slots a `CatchUpAll` rule executes in one call
+const MAX_CATCH_UP_EXECUTIONS: u64 = 12;
+/// Maximum number of recipients on one payroll rule
+const MAX_PAYROLL_ENTRIES: usize = 20;
+/// Maximum number of recipients on one split rule
+const MAX_SPLIT_RECIPIENTS: usize = 10;
+/// Maximum number of rule IDs one `get_due_rules_by_priority` call scans
+const MAX_DUE_RULE_SCAN: u64 = 200;
+/// Longest dispute window an escrow rule may hold payments for
+const MAX_DISPUTE_WINDOW: u64 = 3 * SECONDS_PER_MONTH;
+/// Longest a claimable transfer rule's payments wait for their recipient before refund
+const MAX_CLAIM_WINDOW: u64 = 12 * SECONDS_PER_MONTH;
+/// Conservative gas (in motes) for execute_rule's own checks and bookkeeping
+const BASE_EXECUTION_GAS: u64 = 2_500_000_000;
+/// Conservative gas (in motes) for each extra contract call an execution makes
+const CONTRACT_CALL_GAS: u64 = 1_000_000_000;
+/// Storage layout version written by this code
+/// 
+/// Contracts deployed before versioning report version 0. Bump this when
+/// the rule layout changes and add the conversion to `migrate_rule`.
+const STORAGE_VERSION: u32 = 3;
+/// Storage version from which rules are counted per owner and status
+const STATUS_COUNTS_VERSION: u32 = 3;
+/// Position of the whole-rule mapping in the first released engine
+const BASELINE_RULES_INDEX: u8 = 1;
+/// Position of the vault address in the first released engine
+const BASELINE_VAULT_ADDRESS_INDEX: u8 = 4;
+/// Entrypoint interface version reported by `get_version`; bump on breaking changes
+pub const INTERFACE_VERSION: u32 = 1;
 
 /// The Automation Engine contract
 /// 
 /// Manages automation rules for all users. Each rule specifies a trigger,
 /// conditions, and actions to execute.
 #[odra::module(
-    events = [RuleCreated, RulePaused, RuleResumed, RuleDeleted, RuleExecuted],
+    events = [
+        RuleCreated, RulePaused, RuleResumed, RuleDeleted, RuleExecuted, RuleExecutionFailed,
+        RuleUpdated, FeeConfigUpdated, TemplateRegistered, WorkflowExecuted, PriceConditionMet,
+        ManagerApproved, ManagerRevoked, RulePendingApproval, RuleConfirmed, Heartbeat,
+        InheritanceTriggered, AddressBookEntryAdded, AddressBookEntryRemoved,
+        AddressBookRestrictionUpdated, StorageMigrated, BudgetExhausted, HookAllowlistUpdated,
+        VaultUnderfunded, MissedExecution, RulePurged, PayrollEntrySkipped, GoalReached,
+        NftPerksUpdated, NotificationRequested, SplitDustHandled, SessionKeyAdded,
+        SessionKeyRevoked, RuleCompleted, RecipientBlocklistUpdated, RoleGranted, RoleRevoked,
+        ExecutionPauseUpdated, AdminActionQueued, AdminActionExecuted, AdminActionCancelled,
+        RewardsConditionMet, AttestationConditionMet, StakingAdapterUpdated, RuleRestored,
+        ContractRecipientAllowlistUpdated
+    ],
     errors = Error
 )]
 pub struct AutomationEngine {
     /// Counter for generating unique rule IDs
     next_rule_id: Var<u64>,
-    /// Mapping of rule ID to rule data
-    rules: Mapping<u64, AutomationRule>,
-    /// Mapping of user address to their rule IDs
+    /// ID of the most recent successful execution (IDs start at 1)
+    last_execution_id: Var<u64>,
+    /// Rule configs as stored by layout version 1, converted by `migrate`
+    legacy_rule_configs: Mapping<u64, LegacyRuleConfig>,
+    /// Mapping of rule ID to its mutable execution state
+    rule_states: Mapping<u64, RuleState>,
+    /// Mapping of rule ID to its user-supplied metadata (empty if cleared)
+    rule_metadata: Mapping<u64, Bytes>,
+    /// Rule IDs of owners' rules created before per-owner indices (cleared on purge)
     user_rules: Mapping<Address, Vec<u64>>,
-    /// Mapping of user address to their rule count (for tier limits)
+    /// Rule totals of layout versions up to 2, cleared by `migrate` (see `rule_status_counts`)
     user_rule_count: Mapping<Address, u32>,
+    /// Mapping of (owner, per-owner index) to the global rule ID
+    local_rule_ids: Mapping<(Address, u32), u64>,
+    /// Mapping of global rule ID to its per-owner index
+    rule_local_indices: Mapping<u64, u32>,
+    /// Mapping of owner to the per-owner index their next rule gets (absent = their rule count)
+    next_local_indices: Mapping<Address, u32>,
+    /// Deleted rules whose records have been purged
+    purged_rules: Mapping<u64, bool>,
+    /// Contract registry whose entries override the addresses set here
+    registry: Var<Option<Address>>,
+    /// Recipients, amounts and failure policy of each payroll rule
+    payroll_configs: Mapping<u64, PayrollConfig>,
+    /// Total CSPR each savings goal rule stops at (absent = no goal)
+    savings_goals: Mapping<u64, U512>,
+    /// CSPR each savings goal rule has moved so far
+    savings_progress: Mapping<u64, U512>,
+    /// Share of rewards each compounding rule donates (absent = none)
+    reward_splits: Mapping<u64, Option<RewardSplit>>,
+    /// Recipients and dust policy of each split rule (absent = the rule's single recipient)
+    split_configs: Mapping<u64, Option<SplitConfig>>,
+    /// Nonce each owner's next signed rule request must carry
+    signature_nonces: Mapping<Address, u64>,
+    /// Seconds each escrow rule's payments are held before the recipient can claim
+    escrow_windows: Mapping<u64, u64>,
     /// The vault contract address for executing transfers
     vault_address: Var<Option<Address>>,
+    /// The admin allowed to manage protocol settings
+    admin: Var<Address>,
+    /// Protocol fee charged on automated transfers
+    fee_config: Var<FeeConfig>,
+    /// Tiers exempt from protocol fees
+    fee_exempt_tiers: Mapping<StakingTier, bool>,
+    /// Tier and fee perks for holders of a membership NFT collection (None = disabled)
+    nft_perks: Var<Option<NftPerks>>,
+    /// Payload hash each rule emits NotificationRequested with (None = no notifications)
+    rule_notifications: Mapping<u64, Option<[u8; 32]>>,
+    /// Keeper priority each rule's owner chose (absent = its action's default)
+    rule_priorities: Mapping<u64, Option<u8>>,
+    /// Ring buffer of recent executions, keyed by (rule ID, slot)
+    execution_history: Mapping<(u64, u32), ExecutionRecord>,
+    /// Total number of history entries ever written per rule
+    execution_history_len: Mapping<u64, u32>,
+    /// Registry of admin-defined rule templates
+    templates: SubModule<TemplateRegistry>,
+    /// Mapping of rule ID to the rules executed after it succeeds
+    follow_up_rules: Mapping<u64, Vec<u64>>,
+    /// CSPR/USD price oracle used by condition rules
+    price_oracle: Var<Option<Address>>,
+    /// Maximum age of an oracle price before it is considered stale
+    max_price_age: Var<u64>,
+    /// Mapping of condition rule ID to its price condition
+    price_conditions: Mapping<u64, PriceCondition>,
+    /// Mapping of condition rule ID to the pending staking rewards it waits to exceed
+    rewards_conditions: Mapping<u64, U512>,
+    /// Attestation oracle whose posted values condition rules can reference
+    attestation_oracle: Var<Option<Address>>,
+    /// Mapping of condition rule ID to its attestation condition
+    attestation_conditions: Mapping<u64, AttestationCondition>,
+    /// Staking adapter used by staking rules
+    staking_adapter: Var<Option<Address>>,
+    /// Mapping of rebalance rule ID to the owner's constraints
+    rebalance_constraints: Mapping<u64, RebalanceConstraints>,
+    /// Mapping of (owner, manager) to the manager's per-rule spend ceiling (zero if not approved)
+    managers: Mapping<(Address, Address), U512>,
+    /// Mapping of (owner, session key account) to what the key may do (absent = nothing)
+    session_keys: Mapping<(Address, Address), Option<SessionKeyScope>>,
+    /// Mapping of owner to the amount above which new rules need confirmation (zero disables)
+    approval_thresholds: Mapping<Address, U512>,
+    /// Mapping of pending rule ID to the time from which it can be confirmed
+    confirmable_at: Mapping<u64, u64>,
+    /// Mapping of owner to the block time of their last signed call
+    last_activity: Mapping<Address, u64>,
+    /// Mapping of dead-man switch rule ID to its settings
+    inheritance_configs: Mapping<u64, InheritanceConfig>,
+    /// Execution-time amount spec and basis points of percentage rules
+    amount_specs: Mapping<u64, (AmountSpec, u16)>,
+    /// Vault balance each top-up rule keeps its recipient at
+    top_up_targets: Mapping<u64, U512>,
+    /// Token, direction and slippage limit of each swap rule
+    swap_configs: Mapping<u64, SwapConfig>,
+    /// Validator each restake or redelegation rule moves stake to
+    restake_destinations: Mapping<u64, PublicKey>,
+    /// Mapping of (owner, recipient) to the owner's address book entry
+    address_book: Mapping<(Address, Address), AddressBookEntry>,
+    /// Mapping of owner to the recipients in their address book
+    address_book_index: Mapping<Address, Vec<Address>>,
+    /// Owners whose rules may only send to address book entries
+    address_book_only: Mapping<Address, bool>,
+    /// Mapping of owner to the time a requested lifting of the restriction takes effect
+    address_book_release: Mapping<Address, u64>,
+    /// Engine-wide activity counters
+    global_stats: Var<GlobalStats>,
+    /// Mapping of owner to their activity aggregates
+    user_stats: Mapping<Address, UserStats>,
+    /// Most times a single rule may execute per hour (zero for no limit)
+    max_executions_per_hour: Var<u32>,
+    /// Mapping of rule ID to the current hour and the executions during it
+    hourly_executions: Mapping<u64, (u64, u32)>,
+    /// Mapping of rule ID to who may execute it (Anyone if unset)
+    executor_policies: Mapping<u64, ExecutorPolicy>,
+    /// Mapping of (owner, keeper) to whether the keeper may run the owner's allowlist-only rules
+    allowed_executors: Mapping<(Address, Address), bool>,
+    /// Mapping of rule ID to the bounty paid to whoever executes it
+    keeper_bounties: Mapping<u64, U512>,
+    /// Smallest non-zero keeper bounty owners may set
+    min_keeper_bounty: Var<U512>,
+    /// Largest keeper bounty owners may set (zero disables bounties)
+    max_keeper_bounty: Var<U512>,
+    /// Shortest schedule interval in seconds new time-triggered rules may use
+    min_schedule_interval: Var<u64>,
+    /// Largest CSPR amount new rules may move per execution (zero for no limit)
+    max_rule_amount: Var<U512>,
+    /// Most rules one `create_rules_batch` call may create (zero for the built-in maximum)
+    max_rules_per_batch: Var<u32>,
+    /// Period lengths and delays used for scheduling (absent = real durations)
+    time_config: Var<TimeConfig>,
+    /// Layout version of the data in storage
+    storage_version: Var<u32>,
+    /// Next rule ID to convert while a migration is in progress
+    migration_cursor: Var<u64>,
+    /// Total CSPR a rule may spend, fees and bounties included (absent = unlimited)
+    rule_budgets: Mapping<u64, U512>,
+    /// CSPR a rule has spent so far against its budget
+    rule_spend: Mapping<u64, U512>,
+    /// Hook contracts rules may register (admin allowlist)
+    allowed_hooks: Mapping<Address, bool>,
+    /// Recipients no rule may pay (admin blocklist)
+    blocked_recipients: Mapping<Address, bool>,
+    /// Hook each rule calls around its executions
+    rule_hooks: Mapping<u64, Option<RuleHook>>,
+    /// Whether a hook call is in progress, to stop hooks executing rules
+    hook_running: Var<bool>,
+    /// How transfer rules with a contract recipient pay into it
+    contract_recipients: Mapping<u64, Option<ContractRecipient>>,
+    /// Transfer rules that pay their recipient's vault account instead of their account
+    vault_recipients: Mapping<u64, bool>,
+    /// Vault envelope each rule spends from (absent = the owner's unassigned balance)
+    rule_envelopes: Mapping<u64, Option<String>>,
+    /// Mapping of owner to how many upcoming executions their funds must cover (zero = off)
+    low_balance_alerts: Mapping<Address, u32>,
+    /// Owners whose vault reserves their active rules' next executions
+    reservation_modes: Mapping<Address, bool>,
+    /// Hours of the day during which each rule may be executed
+    execution_windows: Mapping<u64, Option<ExecutionWindow>>,
+    /// Where each compounding rule sends rewards (absent = restake)
+    reward_destinations: Mapping<u64, RewardDestination>,
+    /// Minimum seconds between two executions of each rule (zero = no minimum)
+    min_execution_intervals: Mapping<u64, u64>,
+    /// How each scheduled rule handles runs after its grace period (absent = execute once, late)
+    missed_execution_configs: Mapping<u64, Option<MissedExecutionConfig>>,
+    /// Total schedule slots each rule has missed without executing
+    missed_executions: Mapping<u64, u64>,
+    /// Admin functions delegated to operator, pauser and fee manager keys
+    roles: SubModule<AccessControl>,
+    /// Whether a pauser has halted rule execution
+    execution_paused: Var<bool>,
+    /// Fee and price oracle changes waiting out the admin delay
+    timelock: SubModule<Timelock>,
+    /// Sequence number stamped on emitted events
+    event_nonce: SubModule<EventNonce>,
+    // Fields are keyed by position: add new ones below, never above
+    /// Mapping of rule ID to its immutable config (written once at creation)
+    rule_configs: Mapping<u64, RuleConfig>,
+    /// Mapping of template name ID to the name, stored once however many rules use it
+    template_names: Mapping<u32, String>,
+    /// Mapping of template name to its ID
+    template_name_ids: Mapping<String, u32>,
+    /// ID the next distinct template name gets
+    next_template_name_id: Var<u32>,
+    /// Mapping of (owner, status) to how many of the owner's unpurged rules have that status
+    rule_status_counts: Mapping<(Address, RuleStatus), u32>,
+    /// When each deleted rule was deleted
+    deleted_at: Mapping<u64, u64>,
+    /// Status each deleted rule had before deletion, given back by `restore_rule`
+    status_before_delete: Mapping<u64, RuleStatus>,
+    /// Seconds after deletion during which a rule can be restored (zero = never)
+    restore_grace_period: Var<u64>,
+    /// Mapping of rule ID to the tags its owner organizes it by
+    rule_tags: Mapping<u64, Vec<String>>,
+    /// Seconds each claimable transfer rule's payments wait to be claimed (zero = off)
+    claim_windows: Mapping<u64, u64>,
+    /// Streams of high-value rules, escrowed in the vault once the rule is confirmed
+    pending_streams: Mapping<u64, Option<PaymentStream>>,
+    /// Vestings of high-value rules, escrowed in the vault once the rule is confirmed
+    pending_vestings: Mapping<u64, Option<VestingSchedule>>,
+    /// Mapping of owner to a raised or disabled approval threshold and when it applies
+    pending_threshold_changes: Mapping<Address, Option<(U512, u64)>>,
+    /// (contract, entrypoint) pairs rules may pay into
+    allowed_contract_recipients: Mapping<(Address, String), bool>,
+    /// Mapping of swap rule ID to the least output each execution accepts
+    swap_min_outs: Mapping<u64, U512>,
+    /// Mapping of rule ID to the rules that run it as a follow-up
+    workflow_parents: Mapping<u64, Vec<u64>>,
 }
 
 #[odra::module]
@@ -47,6 +343,26 @@ impl AutomationEngine {
     pub fn init(&mut self, vault_address: Option<Address>) {
         self.next_rule_id.set(1);
         self.vault_address.set(vault_address);
+        self.admin.set(self.env().caller());
+        self.max_price_age.set(DEFAULT_MAX_PRICE_AGE);
+        self.storage_version.set(STORAGE_VERSION);
+    }
+
+    /// Adopt the first released engine when its code is upgraded
+    /// 
+    /// That engine had no admin and kept its vault address in a field this
+    /// layout has reused, so the account installing the upgrade becomes
+    /// admin, able to run `migrate`, and the vault address is carried over.
+    /// Engines that already have an admin are left as they are.
+    pub fn upgrade(&mut self) {
+        if self.admin.get().is_some() {
+            return;
+        }
+        self.admin.set(self.env().caller());
+        self.max_price_age.set(DEFAULT_MAX_PRICE_AGE);
+        let baseline_vault: Var<Option<Address>> =
+            ModuleComponent::instance(self.env(), BASELINE_VAULT_ADDRESS_INDEX);
+        self.vault_address.set(baseline_vault.get_or_default());
     }
 
     /// Create a new automation rule
@@ -58,6 +374,11 @@ impl AutomationEngine {
     /// * `action_type` - What action to perform
     /// * `recipient` - Target address for transfers (optional for compound)
     /// * `amount` - Amount to transfer per execution
+    /// * `token` - CEP-18 token to transfer, or `None` for native CSPR
+    /// * `validator` - Allowlisted validator for staking actions
+    /// * `metadata` - Optional label or memo of at most 256 bytes, e.g. "Mom's allowance"
+    /// * `first_execution` - When the rule first becomes due, e.g. a bill's due date; must
+    ///   not be in the past, and the current time runs it immediately. `None` waits one interval
     pub fn create_rule(
         &mut self,
         template_name: String,
@@ -66,385 +387,9208 @@ impl AutomationEngine {
         action_type: ActionType,
         recipient: Option<Address>,
         amount: U512,
+        token: Option<Address>,
+        validator: Option<PublicKey>,
+        metadata: Option<Bytes>,
+        first_execution: Option<u64>,
     ) -> u64 {
         let caller = self.env().caller();
-        let current_time = self.env().get_block_time();
-        
-        // Check tier limits
-        let current_count = self.user_rule_count.get_or_default(&caller);
-        let tier = self.get_user_tier(caller);
-        if current_count >= tier.max_rules() {
-            self.env().revert(Error::MaxRulesReached);
+        self.record_activity(caller);
+        self.create_standard_rule(
+            caller,
+            template_name,
+            trigger_type,
+            schedule,
+            action_type,
+            recipient,
+            amount,
+            token,
+            validator,
+            metadata,
+            first_execution,
+        )
+    }
+
+    /// Deposit the attached CSPR into the caller's vault and create a rule
+    /// 
+    /// Takes the same arguments as `create_rule`. Both steps happen in one
+    /// transaction, so if the rule is rejected the deposit is reverted too.
+    /// This is a payable function - attach CSPR when calling.
+    #[odra(payable)]
+    pub fn create_rule_with_deposit(
+        &mut self,
+        template_name: String,
+        trigger_type: TriggerType,
+        schedule: Schedule,
+        action_type: ActionType,
+        recipient: Option<Address>,
+        amount: U512,
+        token: Option<Address>,
+        validator: Option<PublicKey>,
+        metadata: Option<Bytes>,
+        first_execution: Option<u64>,
+    ) -> u64 {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let deposit = self.env().attached_value();
+        if deposit.is_zero() {
+            self.env().revert(Error::ZeroAmount);
         }
         
-        // Generate rule ID
-        let rule_id = self.next_rule_id.get_or_default();
-        self.next_rule_id.set(rule_id + 1);
-        
-        // Calculate next execution time
-        let next_execution = self.calculate_next_execution(current_time, &schedule);
+        // Forward the deposit to the caller's vault balance
+        self.vault_or_revert().with_tokens(deposit).deposit_for(caller);
         
-        // Create rule
-        let rule = AutomationRule::new(
-            rule_id,
+        self.create_standard_rule(
             caller,
-            template_name.clone(),
+            template_name,
             trigger_type,
             schedule,
             action_type,
             recipient,
             amount,
-            next_execution,
-        );
-        
-        // Store rule
-        self.rules.set(&rule_id, rule);
-        
-        // Update user's rule list
-        let mut user_rule_ids = self.user_rules.get_or_default(&caller);
-        user_rule_ids.push(rule_id);
-        self.user_rules.set(&caller, user_rule_ids);
+            token,
+            validator,
+            metadata,
+            first_execution,
+        )
+    }
+
+    /// Create a rule on behalf of `owner` as an approved manager
+    /// 
+    /// Takes the same arguments as `create_rule`. The rule belongs to
+    /// `owner`, and its amount may not exceed the manager's spend ceiling.
+    /// Sweeps move everything above their floor, so no ceiling covers them.
+    pub fn create_rule_for(
+        &mut self,
+        owner: Address,
+        template_name: String,
+        trigger_type: TriggerType,
+        schedule: Schedule,
+        action_type: ActionType,
+        recipient: Option<Address>,
+        amount: U512,
+        token: Option<Address>,
+        validator: Option<PublicKey>,
+        metadata: Option<Bytes>,
+        first_execution: Option<u64>,
+    ) -> u64 {
+        let caller = self.env().caller();
+        let ceiling = self.managers.get_or_default(&(owner, caller));
+        if ceiling.is_zero() {
+            self.env().revert(Error::NotRuleManager);
+        }
+        if amount > ceiling || matches!(action_type, ActionType::Sweep) {
+            self.env().revert(Error::ManagerCeilingExceeded);
+        }
+        self.create_standard_rule(
+            owner,
+            template_name,
+            trigger_type,
+            schedule,
+            action_type,
+            recipient,
+            amount,
+            token,
+            validator,
+            metadata,
+            first_execution,
+        )
+    }
+
+    /// Create a rule from a registered template
+    /// 
+    /// The parameters are validated against the template's schema, and the
+    /// rule takes its name, trigger and (unless overridden) schedule from it.
+    pub fn create_rule_from_template(&mut self, template_id: u32, params: TemplateParams) -> u64 {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let template = self.templates.validate(template_id, &params);
+        let schedule = params.schedule.unwrap_or(template.default_schedule);
+        self.create_standard_rule(
+            caller,
+            template.name,
+            template.trigger_type,
+            schedule,
+            params.action_type,
+            params.recipient,
+            params.amount,
+            params.token,
+            params.validator,
+            None,
+            None,
+        )
+    }
+
+    /// Create several standard rules in one transaction
+    /// 
+    /// Each entry takes the same parameters as `create_rule`, e.g. one
+    /// payroll transfer per employee. The batch is all-or-nothing: if any
+    /// rule is rejected, or the batch would take the caller past their tier's
+    /// rule limit, no rules are created. Returns the new rule IDs in order.
+    pub fn create_rules_batch(&mut self, rules: Vec<RuleParams>) -> Vec<u64> {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        if rules.len() > self.get_rule_bounds().2 as usize {
+            self.env().revert(Error::BatchTooLarge);
+        }
         
-        // Update rule count
-        self.user_rule_count.set(&caller, current_count + 1);
+        // Check the tier limit for the whole batch before creating anything
+        let current_count = self.get_user_rule_count(caller);
+        if current_count.saturating_add(rules.len() as u32) > self.get_user_tier(caller).max_rules() {
+            self.env().revert(Error::MaxRulesReached);
+        }
         
-        // Emit event
-        self.env().emit_event(RuleCreated {
-            rule_id,
-            owner: caller,
-            template_type: template_name,
-        });
+        rules
+            .into_iter()
+            .map(|params| {
+                self.create_standard_rule(
+                    caller,
+                    params.template_name,
+                    params.trigger_type,
+                    params.schedule,
+                    params.action_type,
+                    params.recipient,
+                    params.amount,
+                    params.token,
+                    params.validator,
+                    params.metadata,
+                    params.first_execution,
+                )
+            })
+            .collect()
+    }
+
+    /// Create a rule for the holder of `owner_key` from a request they signed off-chain
+    /// 
+    /// Lets a relayer pay the gas for owners who hold no CSPR yet. The
+    /// signature must cover `get_rule_signing_message(request)`, which binds
+    /// it to this engine, and the request must carry the owner's current
+    /// nonce and not be past its deadline. Each nonce can be used once. The
+    /// rule belongs to the owner, not the relayer.
+    pub fn create_rule_with_signature(
+        &mut self,
+        owner_key: PublicKey,
+        request: SignedRuleRequest,
+        signature: Bytes,
+    ) -> u64 {
+        let owner = Address::Account(owner_key.to_account_hash());
+        if self.env().get_block_time() > request.deadline {
+            self.env().revert(Error::SignatureExpired);
+        }
+        let nonce = self.signature_nonces.get_or_default(&owner);
+        if request.nonce != nonce {
+            self.env().revert(Error::InvalidSignatureNonce);
+        }
+        let message = self.get_rule_signing_message(request.clone());
+        if !self.env().verify_signature(&message, &signature, &owner_key) {
+            self.env().revert(Error::InvalidSignature);
+        }
+        self.signature_nonces.set(&owner, nonce + 1);
+        self.record_activity(owner);
         
-        rule_id
+        let params = request.params;
+        self.create_standard_rule(
+            owner,
+            params.template_name,
+            params.trigger_type,
+            params.schedule,
+            params.action_type,
+            params.recipient,
+            params.amount,
+            params.token,
+            params.validator,
+            params.metadata,
+            params.first_execution,
+        )
     }
 
-    /// Pause an active rule
-    pub fn pause_rule(&mut self, rule_id: u64) {
+    /// Create a new rule copying one of the caller's rules, with some fields replaced
+    /// 
+    /// Only the parameters accepted by `create_rule` are copied; conditions,
+    /// hooks, budgets and other settings of the source rule are not. Rules
+    /// with their own creation entrypoint (streams, stakes, swaps, ...)
+    /// cannot be cloned.
+    pub fn clone_rule(&mut self, rule_id: u64, overrides: RuleOverrides) -> u64 {
         let caller = self.env().caller();
-        let mut rule = self.get_rule_or_revert(rule_id);
+        self.record_activity(caller);
+        let rule = self.get_rule_or_revert(rule_id);
         
         // Verify ownership
         if rule.owner != caller {
             self.env().revert(Error::NotRuleOwner);
         }
         
-        // Check current status
-        match rule.status {
-            RuleStatus::Paused => self.env().revert(Error::RuleAlreadyPaused),
-            RuleStatus::Deleted => self.env().revert(Error::RuleNotFound),
-            RuleStatus::Active => {}
+        self.create_standard_rule(
+            caller,
+            overrides
+                .template_name
+                .unwrap_or_else(|| self.template_names.get_or_default(&rule.template_name_id)),
+            rule.trigger_type,
+            overrides.schedule.unwrap_or(rule.schedule),
+            rule.action_type,
+            overrides.recipient.or(rule.recipient),
+            overrides.amount.unwrap_or(rule.amount),
+            rule.token,
+            rule.validator,
+            overrides.metadata.or(rule.metadata),
+            overrides.first_execution,
+        )
+    }
+
+    /// Create a streaming payment rule
+    /// 
+    /// Locks `rate_per_second * (end_time - start_time)` of the caller's
+    /// vault balance, or holds off until `confirm_rule` if the total is above
+    /// the caller's approval threshold. Funds accrue to the recipient every second between
+    /// `start_time` and `end_time`; the recipient can claim from the vault
+    /// at any time and keepers can push accrued funds by executing the rule.
+    /// Deleting the rule cancels the stream and refunds the unstreamed
+    /// remainder to the owner's vault balance.
+    pub fn create_stream_rule(
+        &mut self,
+        template_name: String,
+        recipient: Address,
+        rate_per_second: U512,
+        start_time: u64,
+        end_time: u64,
+    ) -> u64 {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let current_time = self.env().get_block_time();
+        
+        if rate_per_second.is_zero() || end_time <= start_time || start_time < current_time {
+            self.env().revert(Error::InvalidRuleConfig);
         }
+        let total = checked_mul(&self.env(), rate_per_second, U512::from(end_time - start_time));
         
-        // Update status
-        rule.status = RuleStatus::Paused;
-        self.rules.set(&rule_id, rule);
+        // Check tier limits and generate rule ID
+        let rule_id = self.allocate_rule_id(caller);
         
-        // Emit event
-        self.env().emit_event(RulePaused {
+        // Lock the stream funds in the vault, unless the rule waits for confirmation
+        if self.needs_approval(caller, total) {
+            let stream = PaymentStream {
+                owner: caller,
+                recipient,
+                rate_per_second,
+                start_time,
+                end_time,
+                withdrawn: U512::zero(),
+            };
+            self.pending_streams.set(&rule_id, Some(stream));
+        } else {
+            let mut vault = self.vault_or_revert();
+            vault.open_stream(caller, rule_id, recipient, rate_per_second, start_time, end_time);
+        }
+        
+        // Create and store rule
+        let schedule = Schedule::Daily;
+        let next_execution = self.calculate_next_execution(current_time, &schedule);
+        let rule = AutomationRule::new(
             rule_id,
-            owner: caller,
-        });
+            caller,
+            template_name,
+            TriggerType::Time,
+            schedule,
+            ActionType::Stream,
+            Some(recipient),
+            total,
+            None,
+            next_execution,
+        );
+        self.store_new_rule(rule);
+        
+        rule_id
     }
 
-    /// Resume a paused rule
-    pub fn resume_rule(&mut self, rule_id: u64) {
+    /// Create a vesting rule
+    /// 
+    /// Locks `amount` of the caller's vault balance for `beneficiary`, or holds
+    /// off until `confirm_rule` if it is above the caller's approval threshold. Nothing
+    /// vests before `start_time + cliff_duration`; after that the amount vests
+    /// linearly until `start_time + duration`. The beneficiary can claim from
+    /// the vault at any time and keepers push vested tranches on `schedule`.
+    /// Deleting the rule revokes the unvested remainder back to the owner.
+    pub fn create_vesting_rule(
+        &mut self,
+        template_name: String,
+        beneficiary: Address,
+        amount: U512,
+        start_time: u64,
+        cliff_duration: u64,
+        duration: u64,
+        schedule: Schedule,
+    ) -> u64 {
         let caller = self.env().caller();
-        let mut rule = self.get_rule_or_revert(rule_id);
+        self.record_activity(caller);
+        let current_time = self.env().get_block_time();
         
-        // Verify ownership
-        if rule.owner != caller {
-            self.env().revert(Error::NotRuleOwner);
+        if amount.is_zero() || duration == 0 || cliff_duration > duration {
+            self.env().revert(Error::InvalidRuleConfig);
         }
         
-        // Check current status
-        match rule.status {
-            RuleStatus::Active => self.env().revert(Error::RuleNotPaused),
-            RuleStatus::Deleted => self.env().revert(Error::RuleNotFound),
-            RuleStatus::Paused => {}
-        }
+        // Check tier limits and generate rule ID
+        let rule_id = self.allocate_rule_id(caller);
         
-        // Update status and reschedule
-        let current_time = self.env().get_block_time();
-        rule.status = RuleStatus::Active;
-        rule.next_execution = self.calculate_next_execution(current_time, &rule.schedule);
-        self.rules.set(&rule_id, rule);
+        // Lock the vesting funds in the vault, unless the rule waits for confirmation
+        let vesting = VestingSchedule {
+            owner: caller,
+            beneficiary,
+            total_amount: amount,
+            start_time,
+            cliff_time: checked_add(&self.env(), start_time, cliff_duration),
+            end_time: checked_add(&self.env(), start_time, duration),
+            claimed: U512::zero(),
+            revoked: false,
+        };
+        if self.needs_approval(caller, amount) {
+            self.pending_vestings.set(&rule_id, Some(vesting));
+        } else {
+            self.open_vesting(rule_id, vesting);
+        }
         
-        // Emit event
-        self.env().emit_event(RuleResumed {
+        // Create and store rule
+        let next_execution = self.calculate_next_execution(current_time, &schedule);
+        let rule = AutomationRule::new(
             rule_id,
-            owner: caller,
-        });
+            caller,
+            template_name,
+            TriggerType::Time,
+            schedule,
+            ActionType::Vesting,
+            Some(beneficiary),
+            amount,
+            None,
+            next_execution,
+        );
+        self.store_new_rule(rule);
+        
+        rule_id
     }
 
-    /// Delete a rule permanently
-    pub fn delete_rule(&mut self, rule_id: u64) {
+    /// Create a rule that stakes CSPR from the caller's vault
+    /// 
+    /// On every execution `amount` (plus the protocol fee) is taken from the
+    /// caller's vault balance and delegated to `validator` through the staking
+    /// adapter, with the sCSPR credited to the caller.
+    pub fn create_stake_rule(
+        &mut self,
+        template_name: String,
+        schedule: Schedule,
+        amount: U512,
+        validator: PublicKey,
+    ) -> u64 {
         let caller = self.env().caller();
-        let mut rule = self.get_rule_or_revert(rule_id);
+        self.record_activity(caller);
+        let current_time = self.env().get_block_time();
         
-        // Verify ownership
-        if rule.owner != caller {
-            self.env().revert(Error::NotRuleOwner);
+        if amount.is_zero() {
+            self.env().revert(Error::InvalidRuleConfig);
         }
         
-        // Mark as deleted
-        rule.status = RuleStatus::Deleted;
-        self.rules.set(&rule_id, rule);
+        self.assert_validator_allowed(&validator);
         
-        // Decrement rule count
-        let current_count = self.user_rule_count.get_or_default(&caller);
-        if current_count > 0 {
-            self.user_rule_count.set(&caller, current_count - 1);
-        }
+        // Check tier limits and generate rule ID
+        let rule_id = self.allocate_rule_id(caller);
         
-        // Emit event
-        self.env().emit_event(RuleDeleted {
+        // Create and store rule
+        let next_execution = self.calculate_next_execution(current_time, &schedule);
+        let mut rule = AutomationRule::new(
             rule_id,
-            owner: caller,
-        });
+            caller,
+            template_name,
+            TriggerType::Time,
+            schedule,
+            ActionType::StakeFromVault,
+            None,
+            amount,
+            None,
+            next_execution,
+        );
+        rule.validator = Some(validator);
+        self.store_new_rule(rule);
+        
+        rule_id
     }
 
-    /// Execute a specific rule (called by keeper/cron)
+    /// Create a rule that rebalances the caller's stake between validators
     /// 
-    /// This function checks if the rule is due for execution and performs
-    /// the configured action via the vault contract.
-    pub fn execute_rule(&mut self, rule_id: u64) {
+    /// On every execution the staking adapter moves up to
+    /// `constraints.max_amount` away from validators whose posted metrics
+    /// miss the constraints, towards the best allowlisted validator. The
+    /// caller must have granted the engine permission in the adapter.
+    pub fn create_rebalance_rule(
+        &mut self,
+        template_name: String,
+        schedule: Schedule,
+        constraints: RebalanceConstraints,
+    ) -> u64 {
+        let caller = self.env().caller();
+        self.record_activity(caller);
         let current_time = self.env().get_block_time();
-        let mut rule = self.get_rule_or_revert(rule_id);
         
-        // Check if rule is active
-        match rule.status {
-            RuleStatus::Active => {}
-            _ => self.env().revert(Error::RuleNotActive),
+        if constraints.max_amount.is_zero()
+            || constraints.max_commission_bps > 10_000
+            || constraints.min_performance_bps > 10_000
+        {
+            self.env().revert(Error::InvalidRuleConfig);
         }
         
-        // Check if time-based trigger is due
-        match rule.trigger_type {
-            TriggerType::Time => {
-                if current_time < rule.next_execution {
-                    self.env().revert(Error::TriggerTimeNotReached);
-                }
-            }
-            TriggerType::Manual => {
-                // Manual triggers can be executed anytime by the owner
-                let caller = self.env().caller();
-                if rule.owner != caller {
-                    self.env().revert(Error::NotRuleOwner);
-                }
-            }
-            TriggerType::Condition => {
-                // For condition-based, we'll check in future versions
-                // For now, treat similar to manual
-            }
-        }
+        // Check tier limits and generate rule ID
+        let rule_id = self.allocate_rule_id(caller);
+        let max_amount = constraints.max_amount;
+        self.rebalance_constraints.set(&rule_id, constraints);
         
-        // Execute the action
-        match rule.action_type {
-            ActionType::Transfer => {
-                self.execute_transfer(&rule);
-            }
-            ActionType::Split => {
-                // Split transfers - simplified for MVP (single recipient)
-                self.execute_transfer(&rule);
-            }
-            ActionType::Compound => {
-                // Compound action - will be implemented with staking adapter
-                // For now, this is a no-op placeholder
-            }
+        // Create and store rule
+        let next_execution = self.calculate_next_execution(current_time, &schedule);
+        let rule = AutomationRule::new(
+            rule_id,
+            caller,
+            template_name,
+            TriggerType::Time,
+            schedule,
+            ActionType::Rebalance,
+            None,
+            max_amount,
+            None,
+            next_execution,
+        );
+        self.store_new_rule(rule);
+        
+        rule_id
+    }
+
+    /// Create a rule that keeps the recipient's vault balance topped up
+    /// 
+    /// Each execution moves just enough CSPR from the caller's vault to bring
+    /// `recipient`'s vault balance up to `target`, but never more than
+    /// `max_per_execution`. The recipient's wallet balance is not readable
+    /// on-chain, so the funds are credited to their vault for them to withdraw.
+    pub fn create_top_up_rule(
+        &mut self,
+        template_name: String,
+        schedule: Schedule,
+        recipient: Address,
+        target: U512,
+        max_per_execution: U512,
+    ) -> u64 {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let current_time = self.env().get_block_time();
+        
+        if target.is_zero() || max_per_execution.is_zero() || recipient == caller {
+            self.env().revert(Error::InvalidRuleConfig);
         }
         
-        // Update rule state
-        rule.last_executed = current_time;
-        rule.next_execution = self.calculate_next_execution(current_time, &rule.schedule);
-        rule.execution_count += 1;
-        self.rules.set(&rule_id, rule.clone());
+        // Check tier limits and generate rule ID
+        let rule_id = self.allocate_rule_id(caller);
+        self.top_up_targets.set(&rule_id, target);
         
-        // Emit event
-        self.env().emit_event(RuleExecuted {
+        // Create and store rule
+        let next_execution = self.calculate_next_execution(current_time, &schedule);
+        let rule = AutomationRule::new(
             rule_id,
-            owner: rule.owner,
-            executed_at: current_time,
-        });
-    }
-
-    /// Set the vault contract address
-    pub fn set_vault_address(&mut self, vault: Address) {
-        self.vault_address.set(Some(vault));
+            caller,
+            template_name,
+            TriggerType::Time,
+            schedule,
+            ActionType::TopUp,
+            Some(recipient),
+            max_per_execution,
+            None,
+            next_execution,
+        );
+        self.store_new_rule(rule);
+        
+        rule_id
+    }
+
+    /// Create a rule that pays into escrow for the recipient each period
+    /// 
+    /// Each execution moves `amount` from the caller's vault into an escrowed
+    /// payment in the vault. The recipient can claim it once `dispute_window`
+    /// seconds have passed, and until then the caller can cancel it to get
+    /// the amount back, e.g. for milestone payouts to a freelancer.
+    pub fn create_escrow_rule(
+        &mut self,
+        template_name: String,
+        schedule: Schedule,
+        recipient: Address,
+        amount: U512,
+        dispute_window: u64,
+    ) -> u64 {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let current_time = self.env().get_block_time();
+        
+        if amount.is_zero()
+            || recipient == caller
+            || dispute_window == 0
+            || dispute_window > MAX_DISPUTE_WINDOW
+        {
+            self.env().revert(Error::InvalidRuleConfig);
+        }
+        
+        // Check tier limits and generate rule ID
+        let rule_id = self.allocate_rule_id(caller);
+        self.escrow_windows.set(&rule_id, dispute_window);
+        
+        // Create and store rule
+        let next_execution = self.calculate_next_execution(current_time, &schedule);
+        let rule = AutomationRule::new(
+            rule_id,
+            caller,
+            template_name,
+            TriggerType::Time,
+            schedule,
+            ActionType::Escrow,
+            Some(recipient),
+            amount,
+            None,
+            next_execution,
+        );
+        self.store_new_rule(rule);
+        
+        rule_id
+    }
+
+    /// Create a rule that pays a list of recipients their own amounts each period
+    /// 
+    /// Every entry is paid from the caller's vault in the same execution,
+    /// each with its own transfer fee and AutomationExecuted event. The rule
+    /// amount is the total of the entries. `failure_policy` decides what
+    /// happens when the vault cannot cover all of them.
+    pub fn create_payroll_rule(
+        &mut self,
+        template_name: String,
+        schedule: Schedule,
+        entries: Vec<PayrollEntry>,
+        failure_policy: PayrollFailurePolicy,
+    ) -> u64 {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let current_time = self.env().get_block_time();
+        
+        if entries.is_empty() || entries.len() > MAX_PAYROLL_ENTRIES {
+            self.env().revert(Error::InvalidRuleConfig);
+        }
+        let mut total = U512::zero();
+        for entry in &entries {
+            if entry.amount.is_zero() || entry.recipient == caller {
+                self.env().revert(Error::InvalidRuleConfig);
+            }
+            // Restricted owners can only pay address book entries
+            self.check_address_book(caller, Some(entry.recipient));
+            total += entry.amount;
+        }
+        
+        // Check tier limits and generate rule ID
+        let rule_id = self.allocate_rule_id(caller);
+        self.payroll_configs.set(&rule_id, PayrollConfig { entries, failure_policy });
+        
+        // Create and store rule
+        let next_execution = self.calculate_next_execution(current_time, &schedule);
+        let rule = AutomationRule::new(
+            rule_id,
+            caller,
+            template_name,
+            TriggerType::Time,
+            schedule,
+            ActionType::Payroll,
+            None,
+            total,
+            None,
+            next_execution,
+        );
+        self.store_new_rule(rule);
+        
+        rule_id
+    }
+
+    /// Create a rule that moves staking rewards from one validator to another
+    /// 
+    /// Each execution harvests the rewards the staking adapter's pool has
+    /// earned with `source` and queues them for delegation to `destination`,
+    /// which must be allowlisted. Rewards stay in the pool, so this spreads
+    /// the pool across validators rather than moving the caller's own stake,
+    /// and only the admin or an operator may set it up (operator role).
+    pub fn create_restake_rule(
+        &mut self,
+        template_name: String,
+        schedule: Schedule,
+        source: PublicKey,
+        destination: PublicKey,
+    ) -> u64 {
+        self.assert_role(Role::Operator);
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let current_time = self.env().get_block_time();
+        
+        if source == destination {
+            self.env().revert(Error::InvalidRuleConfig);
+        }
+        self.assert_validator_allowed(&destination);
+        
+        // Check tier limits and generate rule ID
+        let rule_id = self.allocate_rule_id(caller);
+        self.restake_destinations.set(&rule_id, destination);
+        
+        // Create and store rule
+        let next_execution = self.calculate_next_execution(current_time, &schedule);
+        let mut rule = AutomationRule::new(
+            rule_id,
+            caller,
+            template_name,
+            TriggerType::Time,
+            schedule,
+            ActionType::Restake,
+            None,
+            U512::zero(),
+            None,
+            next_execution,
+        );
+        rule.validator = Some(source);
+        self.store_new_rule(rule);
+        
+        rule_id
+    }
+
+    /// Create a rule that moves part of the caller's stake between validators
+    /// 
+    /// Each execution asks the staking adapter to move `amount` of the
+    /// stake the caller placed with `source` to `destination`, which must be
+    /// allowlisted, e.g. to migrate away from a validator in steps. The
+    /// caller must have granted the engine permission in the adapter.
+    pub fn create_redelegate_rule(
+        &mut self,
+        template_name: String,
+        schedule: Schedule,
+        source: PublicKey,
+        destination: PublicKey,
+        amount: U512,
+    ) -> u64 {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let current_time = self.env().get_block_time();
+        
+        if source == destination || amount.is_zero() {
+            self.env().revert(Error::InvalidRuleConfig);
+        }
+        self.assert_validator_allowed(&destination);
+        
+        // Check tier limits and generate rule ID
+        let rule_id = self.allocate_rule_id(caller);
+        self.restake_destinations.set(&rule_id, destination);
+        
+        // Create and store rule
+        let next_execution = self.calculate_next_execution(current_time, &schedule);
+        let mut rule = AutomationRule::new(
+            rule_id,
+            caller,
+            template_name,
+            TriggerType::Time,
+            schedule,
+            ActionType::Redelegate,
+            None,
+            amount,
+            None,
+            next_execution,
+        );
+        rule.validator = Some(source);
+        self.store_new_rule(rule);
+        
+        rule_id
+    }
+
+    /// Create a rule that swaps between the caller's CSPR and CEP-18 vault balances
+    /// 
+    /// Each execution converts `amount` (motes when buying `token`, token
+    /// units when selling it) through the vault's swap adapter, e.g. a weekly
+    /// recurring buy. Swaps revert if the output falls more than
+    /// `max_slippage_bps` short of the DEX quote, at most 10%, or below
+    /// `min_out` (token units when buying, motes when selling); the quote
+    /// comes from the pool being traded, so only `min_out` guards against a
+    /// manipulated price. Buying carries the usual protocol fee; proceeds
+    /// stay in the caller's vault.
+    pub fn create_swap_rule(
+        &mut self,
+        template_name: String,
+        schedule: Schedule,
+        token: Address,
+        direction: SwapDirection,
+        amount: U512,
+        max_slippage_bps: u16,
+        min_out: U512,
+    ) -> u64 {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let current_time = self.env().get_block_time();
+        
+        if amount.is_zero() || to_token_amount(amount).is_none() {
+            self.env().revert(Error::InvalidRuleConfig);
+        }
+        if max_slippage_bps > MAX_SWAP_SLIPPAGE_BPS {
+            self.env().revert(Error::SlippageTooHigh);
+        }
+        self.assert_swap_min_out(min_out);
+        
+        // Check tier limits and generate rule ID
+        let rule_id = self.allocate_rule_id(caller);
+        self.swap_configs.set(&rule_id, SwapConfig {
+            token,
+            direction: direction.clone(),
+            max_slippage_bps,
+        });
+        self.swap_min_outs.set(&rule_id, min_out);
+        
+        // Selling rules are denominated in the token, so funding checks use its balance
+        let rule_token = match direction {
+            SwapDirection::CsprToToken => None,
+            SwapDirection::TokenToCspr => Some(token),
+        };
+        
+        // Create and store rule
+        let next_execution = self.calculate_next_execution(current_time, &schedule);
+        let rule = AutomationRule::new(
+            rule_id,
+            caller,
+            template_name,
+            TriggerType::Time,
+            schedule,
+            ActionType::Swap,
+            None,
+            amount,
+            rule_token,
+            next_execution,
+        );
+        self.store_new_rule(rule);
+        
+        rule_id
+    }
+
+    /// Create a dead-man switch rule
+    /// 
+    /// If the owner makes no signed call to the engine for
+    /// `inactivity_period` seconds, keepers can execute the rule to send
+    /// the owner's entire CSPR vault balance to `beneficiary`, free of
+    /// protocol fees. With `unstake`, the owner's sCSPR is also unstaked;
+    /// the proceeds land in the vault after unbonding and are sent on by the
+    /// next execution, which requires the owner to have granted the engine
+    /// permission in the staking adapter. Owners stay active by calling
+    /// `heartbeat` or managing their rules.
+    pub fn create_inheritance_rule(
+        &mut self,
+        template_name: String,
+        beneficiary: Address,
+        inactivity_period: u64,
+        unstake: bool,
+    ) -> u64 {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let current_time = self.env().get_block_time();
+        
+        if inactivity_period < self.get_time_config().day || beneficiary == caller {
+            self.env().revert(Error::InvalidRuleConfig);
+        }
+        
+        // Check tier limits and generate rule ID
+        let rule_id = self.allocate_rule_id(caller);
+        self.inheritance_configs.set(&rule_id, InheritanceConfig {
+            inactivity_period,
+            unstake,
+        });
+        
+        // Create and store rule; the daily schedule is how often keepers check
+        let schedule = Schedule::Daily;
+        let next_execution = self.calculate_next_execution(current_time, &schedule);
+        let rule = AutomationRule::new(
+            rule_id,
+            caller,
+            template_name,
+            TriggerType::Time,
+            schedule,
+            ActionType::Inheritance,
+            Some(beneficiary),
+            U512::zero(),
+            None,
+            next_execution,
+        );
+        self.store_new_rule(rule);
+        
+        rule_id
+    }
+
+    /// Check in, resetting the inactivity timer of the caller's dead-man switch rules
+    pub fn heartbeat(&mut self) {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        
+        self.env().emit_event(Heartbeat {
+            owner: caller,
+            timestamp: self.env().get_block_time(),
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Add a labeled recipient to the caller's address book, or relabel one
+    /// 
+    /// New recipients can only be paid by restricted rules after a 24 hour
+    /// delay, giving the owner time to notice a swapped address.
+    pub fn add_address_book_entry(&mut self, recipient: Address, label: String) {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        
+        let key = (caller, recipient);
+        let active_at = match self.address_book.get(&key) {
+            Some(entry) if !entry.removed => entry.active_at,
+            _ => {
+                let mut index = self.address_book_index.get_or_default(&caller);
+                index.push(recipient);
+                self.address_book_index.set(&caller, index);
+                self.env().get_block_time() + self.get_time_config().address_book_delay
+            }
+        };
+        self.address_book.set(&key, AddressBookEntry {
+            recipient,
+            label: label.clone(),
+            active_at,
+            removed: false,
+        });
+        
+        self.env().emit_event(AddressBookEntryAdded {
+            owner: caller,
+            recipient,
+            label,
+            active_at,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Remove a recipient from the caller's address book
+    pub fn remove_address_book_entry(&mut self, recipient: Address) {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        
+        let key = (caller, recipient);
+        let mut entry = match self.address_book.get(&key) {
+            Some(entry) if !entry.removed => entry,
+            _ => self.env().revert(Error::AddressBookEntryNotFound),
+        };
+        entry.removed = true;
+        self.address_book.set(&key, entry);
+        
+        let mut index = self.address_book_index.get_or_default(&caller);
+        index.retain(|r| *r != recipient);
+        self.address_book_index.set(&caller, index);
+        
+        self.env().emit_event(AddressBookEntryRemoved {
+            owner: caller,
+            recipient,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Restrict the caller's rules to address book recipients, or lift the restriction
+    /// 
+    /// Enabling applies immediately; lifting only takes effect after the
+    /// address book delay.
+    pub fn set_address_book_only(&mut self, enabled: bool) {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        
+        let effective_at = if enabled {
+            self.address_book_only.set(&caller, true);
+            self.address_book_release.set(&caller, 0);
+            self.env().get_block_time()
+        } else {
+            let effective_at =
+                self.env().get_block_time() + self.get_time_config().address_book_delay;
+            self.address_book_release.set(&caller, effective_at);
+            effective_at
+        };
+        
+        self.env().emit_event(AddressBookRestrictionUpdated {
+            owner: caller,
+            enabled,
+            effective_at,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Pause an active rule
+    pub fn pause_rule(&mut self, rule_id: u64) {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let rule = self.get_rule_or_revert(rule_id);
+        
+        // Verify ownership, delegated management or a session key
+        if !self.session_key_allows(rule.owner, caller, |scope| scope.can_pause_resume) {
+            self.assert_owner_or_manager(rule.owner, caller);
+        }
+        
+        // Check current status
+        match rule.status {
+            RuleStatus::Paused => self.env().revert(Error::RuleAlreadyPaused),
+            RuleStatus::Deleted => self.env().revert(Error::RuleNotFound),
+            RuleStatus::PendingApproval | RuleStatus::Completed => {
+                self.env().revert(Error::RuleNotActive)
+            }
+            RuleStatus::Active => {}
+        }
+        
+        self.pause_unchecked(rule);
+    }
+
+    /// Pause all of the caller's active rules, e.g. while travelling
+    /// 
+    /// Rules that are not active are skipped. Emits `RulePaused` for each
+    /// rule paused and returns how many were.
+    pub fn pause_all(&mut self) -> u32 {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let mut paused = 0;
+        for rule_id in self.owner_rule_ids(caller) {
+            let rule = self.get_rule_or_revert(rule_id);
+            if rule.status == RuleStatus::Active {
+                self.pause_unchecked(rule);
+                paused += 1;
+            }
+        }
+        paused
+    }
+
+    /// Resume a paused rule
+    pub fn resume_rule(&mut self, rule_id: u64) {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let rule = self.get_rule_or_revert(rule_id);
+        
+        // Verify ownership, delegated management or a session key
+        if !self.session_key_allows(rule.owner, caller, |scope| scope.can_pause_resume) {
+            self.assert_owner_or_manager(rule.owner, caller);
+        }
+        
+        // Check current status
+        match rule.status {
+            RuleStatus::Active | RuleStatus::PendingApproval | RuleStatus::Completed => {
+                self.env().revert(Error::RuleNotPaused)
+            }
+            RuleStatus::Deleted => self.env().revert(Error::RuleNotFound),
+            RuleStatus::Paused => {}
+        }
+        if self.budget_exhausted(&rule) {
+            self.env().revert(Error::BudgetExhausted);
+        }
+        if self.goal_reached(rule_id) {
+            self.env().revert(Error::SavingsGoalReached);
+        }
+        
+        self.resume_unchecked(rule);
+    }
+
+    /// Resume all of the caller's paused rules
+    /// 
+    /// Rules that are not paused, have exhausted their budget or reached
+    /// their savings goal are skipped. Emits `RuleResumed` for each rule resumed and returns how
+    /// many were.
+    pub fn resume_all(&mut self) -> u32 {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let mut resumed = 0;
+        for rule_id in self.owner_rule_ids(caller) {
+            let rule = self.get_rule_or_revert(rule_id);
+            if rule.status == RuleStatus::Paused
+                && !self.budget_exhausted(&rule)
+                && !self.goal_reached(rule_id)
+            {
+                self.resume_unchecked(rule);
+                resumed += 1;
+            }
+        }
+        resumed
+    }
+
+    /// Confirm a high-value rule once its confirmation delay has passed
+    /// 
+    /// Only the owner can confirm, so a manager or a compromised session
+    /// cannot push a large rule through alone. The rule is scheduled from
+    /// the time of confirmation, and streams and vestings escrow their funds now.
+    pub fn confirm_rule(&mut self, rule_id: u64) {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let mut rule = self.get_rule_or_revert(rule_id);
+        
+        if rule.owner != caller {
+            self.env().revert(Error::NotRuleOwner);
+        }
+        if !matches!(rule.status, RuleStatus::PendingApproval) {
+            self.env().revert(Error::RuleNotPendingApproval);
+        }
+        let current_time = self.env().get_block_time();
+        if current_time < self.confirmable_at.get_or_default(&rule_id) {
+            self.env().revert(Error::ConfirmationLocked);
+        }
+        
+        if let Some(stream) = self.pending_streams.get_or_default(&rule_id) {
+            self.pending_streams.set(&rule_id, None);
+            self.vault_or_revert().open_stream(
+                stream.owner,
+                rule_id,
+                stream.recipient,
+                stream.rate_per_second,
+                stream.start_time,
+                stream.end_time,
+            );
+        }
+        if let Some(vesting) = self.pending_vestings.get_or_default(&rule_id) {
+            self.pending_vestings.set(&rule_id, None);
+            self.open_vesting(rule_id, vesting);
+        }
+        
+        self.set_rule_status(&mut rule, RuleStatus::Active);
+        rule.next_execution = self.calculate_next_execution(current_time, &rule.schedule);
+        self.rule_states.set(&rule_id, rule.state());
+        self.sync_reservation(rule.owner, rule_id);
+        
+        self.env().emit_event(RuleConfirmed {
+            rule_id,
+            owner: caller,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Set the amount above which the caller's new rules need confirmation
+    /// 
+    /// Rules with a larger amount start in `PendingApproval` and must be
+    /// confirmed with `confirm_rule` after the confirmation delay. Zero
+    /// disables the check. Lowering the threshold applies immediately;
+    /// raising or disabling it only takes effect after the confirmation
+    /// delay, so a stolen key cannot switch it off.
+    pub fn set_approval_threshold(&mut self, threshold: U512) {
+        let owner = self.env().caller();
+        self.record_activity(owner);
+        let current = self.get_approval_threshold(owner);
+        self.approval_thresholds.set(&owner, current);
+        if !threshold.is_zero() && (current.is_zero() || threshold <= current) {
+            self.approval_thresholds.set(&owner, threshold);
+            self.pending_threshold_changes.set(&owner, None);
+        } else {
+            let effective_at =
+                self.env().get_block_time() + self.get_time_config().confirmation_delay;
+            self.pending_threshold_changes.set(&owner, Some((threshold, effective_at)));
+        }
+    }
+
+    /// Approve a manager to create, pause and edit the caller's rules
+    /// 
+    /// Rules the manager creates may not move more than `spend_ceiling` per
+    /// execution. Approving again replaces the ceiling.
+    pub fn approve_manager(&mut self, manager: Address, spend_ceiling: U512) {
+        let owner = self.env().caller();
+        self.record_activity(owner);
+        if spend_ceiling.is_zero() {
+            self.env().revert(Error::ZeroAmount);
+        }
+        self.managers.set(&(owner, manager), spend_ceiling);
+        
+        self.env().emit_event(ManagerApproved {
+            owner,
+            manager,
+            spend_ceiling,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Revoke a manager's access to the caller's rules
+    pub fn revoke_manager(&mut self, manager: Address) {
+        let owner = self.env().caller();
+        self.record_activity(owner);
+        self.managers.set(&(owner, manager), U512::zero());
+        
+        self.env().emit_event(ManagerRevoked { owner, manager, nonce: self.event_nonce.next() });
+    }
+
+    /// Register a low-risk key, e.g. held by a mobile app, that can act on the caller's rules
+    /// 
+    /// The key's account may only pause and resume rules and trigger Manual
+    /// rules, as `scope` allows, until `scope.expires_at`. It can never
+    /// create, edit or delete rules or move funds otherwise. Registering the
+    /// same key again replaces its scope.
+    pub fn add_session_key(&mut self, public_key: PublicKey, scope: SessionKeyScope) {
+        let owner = self.env().caller();
+        self.record_activity(owner);
+        let session = Address::Account(public_key.to_account_hash());
+        if session == owner
+            || !(scope.can_pause_resume || scope.can_trigger_manual)
+            || scope.expires_at <= self.env().get_block_time()
+        {
+            self.env().revert(Error::InvalidSessionKey);
+        }
+        self.session_keys.set(&(owner, session), Some(scope.clone()));
+        
+        self.env().emit_event(SessionKeyAdded {
+            owner,
+            public_key,
+            scope,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Revoke one of the caller's session keys before it expires
+    pub fn revoke_session_key(&mut self, public_key: PublicKey) {
+        let owner = self.env().caller();
+        self.record_activity(owner);
+        let session = Address::Account(public_key.to_account_hash());
+        self.session_keys.set(&(owner, session), None);
+        
+        self.env().emit_event(SessionKeyRevoked {
+            owner,
+            public_key,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Update a rule's user-editable fields
+    /// 
+    /// Currently only the metadata label or memo (at most 256 bytes), which
+    /// `None` clears. The rule's config is otherwise immutable.
+    pub fn update_rule(&mut self, rule_id: u64, metadata: Option<Bytes>) {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let rule = self.get_rule_or_revert(rule_id);
+        
+        // Verify ownership or delegated management
+        self.assert_owner_or_manager(rule.owner, caller);
+        self.assert_metadata_len(&metadata);
+        
+        self.rule_metadata.set(&rule_id, metadata.unwrap_or_default());
+        
+        self.env().emit_event(RuleUpdated {
+            rule_id,
+            owner: rule.owner,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Replace a rule's tags, e.g. "family" or "business"; an empty list clears them
+    /// 
+    /// A rule has at most 8 distinct, non-empty tags of at most 32 bytes each.
+    pub fn set_rule_tags(&mut self, rule_id: u64, tags: Vec<String>) {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let rule = self.get_rule_or_revert(rule_id);
+        
+        // Verify ownership or delegated management
+        self.assert_owner_or_manager(rule.owner, caller);
+        let invalid = tags.len() > MAX_RULE_TAGS
+            || tags.iter().enumerate().any(|(position, tag)| {
+                tag.is_empty() || tag.len() > MAX_RULE_TAG_LEN || tags[..position].contains(tag)
+            });
+        if invalid {
+            self.env().revert(Error::InvalidRuleTags);
+        }
+        
+        self.rule_tags.set(&rule_id, tags);
+        
+        self.env().emit_event(RuleUpdated {
+            rule_id,
+            owner: rule.owner,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Delete a rule permanently
+    pub fn delete_rule(&mut self, rule_id: u64) {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let rule = self.get_rule_or_revert(rule_id);
+        
+        // Verify ownership
+        if rule.owner != caller {
+            self.env().revert(Error::NotRuleOwner);
+        }
+        
+        self.delete_unchecked(rule);
+    }
+
+    /// Delete several of the caller's rules at once
+    /// 
+    /// Reverts if any rule belongs to someone else. Rules already deleted
+    /// are skipped. Emits `RuleDeleted` for each rule deleted and returns
+    /// how many were.
+    pub fn delete_rules(&mut self, rule_ids: Vec<u64>) -> u32 {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let mut deleted = 0;
+        for rule_id in rule_ids {
+            let rule = self.get_rule_or_revert(rule_id);
+            if rule.owner != caller {
+                self.env().revert(Error::NotRuleOwner);
+            }
+            if rule.status != RuleStatus::Deleted {
+                self.delete_unchecked(rule);
+                deleted += 1;
+            }
+        }
+        deleted
+    }
+
+    /// Restore a deleted rule within the restore grace period
+    /// 
+    /// The rule gets back the status it had before deletion; an active
+    /// rule is rescheduled from now. Restoring takes a slot under the
+    /// owner's tier limit again. Purged rules and streams or vestings,
+    /// whose funds were refunded on deletion, cannot be restored.
+    pub fn restore_rule(&mut self, rule_id: u64) {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let mut rule = self.get_rule_or_revert(rule_id);
+        
+        if rule.owner != caller {
+            self.env().revert(Error::NotRuleOwner);
+        }
+        if rule.status != RuleStatus::Deleted {
+            self.env().revert(Error::RuleNotDeleted);
+        }
+        if matches!(rule.action_type, ActionType::Stream | ActionType::Vesting) {
+            self.env().revert(Error::InvalidRuleConfig);
+        }
+        let current_time = self.env().get_block_time();
+        let grace_period = self.restore_grace_period.get_or_default();
+        let restore_until = self.deleted_at.get_or_default(&rule_id) + grace_period;
+        let status = match self.status_before_delete.get(&rule_id) {
+            Some(status) if grace_period > 0 && current_time <= restore_until => status,
+            _ => self.env().revert(Error::RestoreWindowClosed),
+        };
+        let holds_slot = matches!(
+            status,
+            RuleStatus::Active | RuleStatus::Paused | RuleStatus::PendingApproval
+        );
+        let at_limit = self.get_user_rule_count(caller) >= self.get_user_tier(caller).max_rules();
+        if holds_slot && at_limit {
+            self.env().revert(Error::MaxRulesReached);
+        }
+        
+        self.set_rule_status(&mut rule, status.clone());
+        if status == RuleStatus::Active {
+            rule.next_execution = self.calculate_next_execution(current_time, &rule.schedule);
+        }
+        self.rule_states.set(&rule_id, rule.state());
+        self.sync_reservation(caller, rule_id);
+        
+        self.env().emit_event(RuleRestored {
+            rule_id,
+            owner: caller,
+            status,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Purge an owner's deleted rules, returning how many were purged
+    /// 
+    /// Removes them from the owner's rule list and clears their settings,
+    /// leaving a `RulePurged` event as the tombstone. Purged rules are no
+    /// longer returned by any view. Callable by the owner or the admin.
+    pub fn purge_deleted_rules(&mut self, owner: Address) -> u32 {
+        let caller = self.env().caller();
+        if caller != owner && self.admin.get() != Some(caller) {
+            self.env().revert(Error::NotRuleOwner);
+        }
+        self.record_activity(caller);
+        
+        // Pin every rule's per-owner index before the legacy list they may be derived from goes
+        let legacy_rule_ids = self.user_rules.get_or_default(&owner);
+        for (position, rule_id) in legacy_rule_ids.iter().enumerate() {
+            if self.rule_local_indices.get(rule_id).is_none() {
+                self.local_rule_ids.set(&(owner, position as u32), *rule_id);
+                self.rule_local_indices.set(rule_id, position as u32);
+            }
+        }
+        if self.next_local_indices.get(&owner).is_none() {
+            self.next_local_indices.set(&owner, legacy_rule_ids.len() as u32);
+        }
+        if !legacy_rule_ids.is_empty() {
+            self.user_rules.set(&owner, Vec::new());
+        }
+        
+        let mut purged = 0;
+        for rule_id in self.owner_rule_ids(owner) {
+            if let Some(rule) = self.load_rule(rule_id) {
+                if rule.status == RuleStatus::Deleted {
+                    self.purge_rule(rule);
+                    purged += 1;
+                }
+            }
+        }
+        purged
+    }
+
+    /// Execute a specific rule (called by keeper/cron)
+    /// 
+    /// This function checks if the rule is due for execution and performs
+    /// the configured action via the vault contract. Returns the execution's
+    /// ID, which also appears on the RuleExecuted and vault payment events,
+    /// or zero if the rule was underfunded and did not run.
+    pub fn execute_rule(&mut self, rule_id: u64) -> u64 {
+        self.execute_rule_with_result(rule_id).execution_id
+    }
+
+    /// Execute a rule like `execute_rule`, describing the outcome
+    /// 
+    /// An underfunded rule is reported with the InsufficientBalance code,
+    /// the funding check it failed and the owner's shortfall.
+    pub fn execute_rule_with_result(&mut self, rule_id: u64) -> ExecutionResult {
+        self.assert_storage_current();
+        if self.execution_paused.get_or_default() {
+            self.env().revert(Error::ExecutionPaused);
+        }
+        if self.hook_running.get_or_default() {
+            self.env().revert(Error::HookCallDepthExceeded);
+        }
+        let current_time = self.env().get_block_time();
+        let rule = self.get_rule_or_revert(rule_id);
+        let owner = rule.owner;
+        
+        // Check if rule is active
+        match rule.status {
+            RuleStatus::Active => {}
+            _ => self.env().revert(Error::RuleNotActive),
+        }
+        let min_interval = self.min_execution_intervals.get_or_default(&rule_id);
+        if rule.execution_count > 0 && current_time < rule.last_executed + min_interval {
+            self.env().revert(Error::ExecutedTooRecently);
+        }
+        
+        // Check if time-based trigger is due
+        match rule.trigger_type {
+            TriggerType::Time => {
+                if current_time < rule.next_execution {
+                    self.env().revert(Error::TriggerTimeNotReached);
+                }
+            }
+            TriggerType::Manual => {
+                // Manual triggers can be executed anytime by the owner or their session keys
+                let caller = self.env().caller();
+                if rule.owner != caller
+                    && !self.session_key_allows(owner, caller, |scope| scope.can_trigger_manual)
+                {
+                    self.env().revert(Error::NotRuleOwner);
+                }
+            }
+            TriggerType::Condition => {
+                self.check_conditions(&rule, current_time);
+            }
+        }
+        // Manual rules were already limited to the owner and their session keys
+        if !matches!(rule.trigger_type, TriggerType::Manual) {
+            self.check_execution_window(rule_id, current_time);
+            self.check_executor(rule_id, owner);
+        }
+        
+        // Dead-man switches only fire once the owner has gone quiet
+        if matches!(rule.action_type, ActionType::Inheritance) {
+            self.check_inactivity(&rule, current_time);
+        }
+        
+        // An underfunded attempt is recorded as a failure instead of reverting,
+        // so the owner can see why a payment was skipped
+        let mut result = ExecutionResult {
+            rule_id,
+            execution_id: 0,
+            status_code: 0,
+            failed_step: None,
+            shortfall: U512::zero(),
+        };
+        match self.run_scheduled(rule, current_time) {
+            Ok(true) => {}
+            Ok(false) => return result,
+            Err((step, shortfall)) => {
+                result.status_code = Error::InsufficientBalance as u32;
+                result.failed_step = Some(step);
+                result.shortfall = shortfall;
+                return result;
+            }
+        }
+        let execution_id = self.current_execution_id();
+        
+        // Reward whoever ran the rule for the owner
+        self.pay_keeper_bounty(rule_id, owner);
+        
+        // Run any follow-up rules in the same transaction
+        let mut executed = vec![rule_id];
+        self.run_follow_ups(rule_id, current_time, &mut vec![rule_id], &mut executed);
+        if executed.len() > 1 {
+            self.env().emit_event(WorkflowExecuted {
+                root_rule_id: rule_id,
+                owner,
+                executed_rule_ids: executed,
+                nonce: self.event_nonce.next(),
+            });
+        }
+        result.execution_id = execution_id;
+        result
+    }
+
+    /// Execute several rules, returning the outcome of each in order
+    /// 
+    /// Rules that are missing, inactive or not yet due are reported with
+    /// the matching error code and skipped, so one keeper's batch survives
+    /// another executing some of its rules first. Any other failure reverts
+    /// the whole batch.
+    pub fn execute_rules(&mut self, rule_ids: Vec<u64>) -> Vec<ExecutionResult> {
+        if rule_ids.len() > self.get_rule_bounds().2 as usize {
+            self.env().revert(Error::BatchTooLarge);
+        }
+        let current_time = self.env().get_block_time();
+        rule_ids
+            .into_iter()
+            .map(|rule_id| {
+                let skipped = match self.load_rule(rule_id) {
+                    None => Some(Error::RuleNotFound),
+                    Some(rule) if rule.status != RuleStatus::Active => Some(Error::RuleNotActive),
+                    Some(rule)
+                        if rule.trigger_type == TriggerType::Time
+                            && current_time < rule.next_execution =>
+                    {
+                        Some(Error::TriggerTimeNotReached)
+                    }
+                    Some(_) => None,
+                };
+                match skipped {
+                    Some(error) => ExecutionResult {
+                        rule_id,
+                        execution_id: 0,
+                        status_code: error as u32,
+                        failed_step: None,
+                        shortfall: U512::zero(),
+                    },
+                    None => self.execute_rule_with_result(rule_id),
+                }
+            })
+            .collect()
+    }
+
+    /// Execute a rule only if it has not executed since the keeper read its nonce
+    /// 
+    /// `expected_nonce` is the rule's execution count as returned by
+    /// `get_execution_nonce`. When keepers race, only the first execution
+    /// for a nonce succeeds; the others revert instead of paying twice.
+    pub fn execute_rule_with_nonce(&mut self, rule_id: u64, expected_nonce: u64) -> u64 {
+        if self.get_execution_nonce(rule_id) != expected_nonce {
+            self.env().revert(Error::StaleExecutionNonce);
+        }
+        self.execute_rule(rule_id)
+    }
+
+    /// Stake an owner's idle vault balance above their auto-stake floor
+    /// 
+    /// Owners opt in with the vault's `set_auto_stake`. Anyone may trigger
+    /// this, typically a keeper, since the funds stay staked under the
+    /// owner's account. Returns the amount staked.
+    pub fn auto_stake_idle(&mut self, owner: Address) -> U512 {
+        self.vault_or_revert().execute_auto_stake(owner)
+    }
+
+    /// Set the rules that run after `rule_id` executes successfully
+    /// 
+    /// Follow-ups run in the same transaction regardless of their own schedule,
+    /// in order, and may themselves have follow-ups. Their execution windows
+    /// and executor policies still apply, and condition-triggered rules and
+    /// dead-man switches cannot be follow-ups. Paused follow-ups are skipped;
+    /// if an active follow-up is outside its window or cannot be funded the
+    /// whole workflow reverts. Chains may not loop back on themselves or
+    /// exceed a depth of 4, counting the rules that lead to `rule_id` as
+    /// well as those after it. A rule shared by several branches runs once
+    /// for each.
+    pub fn set_follow_up_rules(&mut self, rule_id: u64, follow_up_rule_ids: Vec<u64>) {
+        let caller = self.env().caller();
+        let rule = self.get_rule_or_revert(rule_id);
+        
+        // Verify ownership or delegated management
+        self.assert_owner_or_manager(rule.owner, caller);
+        if follow_up_rule_ids.len() > MAX_FOLLOW_UPS {
+            self.env().revert(Error::WorkflowTooDeep);
+        }
+        
+        let mut depth = 1;
+        for follow_up_id in follow_up_rule_ids.iter() {
+            let follow_up = self.get_rule_or_revert(*follow_up_id);
+            if follow_up.owner != rule.owner {
+                self.env().revert(Error::NotRuleOwner);
+            }
+            if follow_up.trigger_type == TriggerType::Condition
+                || matches!(follow_up.action_type, ActionType::Inheritance)
+            {
+                self.env().revert(Error::InvalidFollowUp);
+            }
+            if *follow_up_id == rule_id || self.workflow_reaches(*follow_up_id, rule_id, 0) {
+                self.env().revert(Error::WorkflowCycle);
+            }
+            depth = depth.max(1 + self.workflow_depth(*follow_up_id, 0));
+        }
+        if self.workflow_height(rule_id, 0) + depth - 1 > MAX_WORKFLOW_DEPTH {
+            self.env().revert(Error::WorkflowTooDeep);
+        }
+        
+        for old_follow_up_id in self.follow_up_rules.get_or_default(&rule_id) {
+            self.unlink_workflow_parent(old_follow_up_id, rule_id);
+        }
+        for follow_up_id in follow_up_rule_ids.iter() {
+            let mut parents = self.workflow_parents.get_or_default(follow_up_id);
+            if !parents.contains(&rule_id) {
+                parents.push(rule_id);
+                self.workflow_parents.set(follow_up_id, parents);
+            }
+        }
+        self.follow_up_rules.set(&rule_id, follow_up_rule_ids);
+    }
+
+    /// Make a condition rule execute only when the CSPR/USD price is above
+    /// or below `threshold` (USD with 8 decimals)
+    pub fn set_price_condition(&mut self, rule_id: u64, comparison: Comparison, threshold: U512) {
+        let caller = self.env().caller();
+        let rule = self.get_rule_or_revert(rule_id);
+        
+        // Verify ownership or delegated management
+        self.assert_owner_or_manager(rule.owner, caller);
+        if !matches!(rule.trigger_type, TriggerType::Condition) {
+            self.env().revert(Error::InvalidRuleConfig);
+        }
+        
+        self.price_conditions.set(&rule_id, PriceCondition { comparison, threshold });
+    }
+
+    /// Make a compound condition rule execute only once the uncompounded
+    /// rewards of its validator are above `threshold`
+    /// 
+    /// E.g. compound whenever rewards pass 50 CSPR, instead of on a schedule
+    /// that often finds nothing to compound. Without a price condition the
+    /// rule waits on rewards alone. The threshold must be non-zero.
+    pub fn set_rewards_condition(&mut self, rule_id: u64, threshold: U512) {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let rule = self.get_rule_or_revert(rule_id);
+        
+        // Verify ownership or delegated management
+        self.assert_owner_or_manager(rule.owner, caller);
+        if !matches!(rule.trigger_type, TriggerType::Condition)
+            || !matches!(rule.action_type, ActionType::Compound)
+            || threshold.is_zero()
+        {
+            self.env().revert(Error::InvalidRuleConfig);
+        }
+        
+        self.rewards_conditions.set(&rule_id, threshold);
+    }
+
+    /// Remove a rule's rewards condition
+    /// 
+    /// Unless it has an attestation condition, the rule goes back to waiting
+    /// on its price condition.
+    pub fn clear_rewards_condition(&mut self, rule_id: u64) {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let rule = self.get_rule_or_revert(rule_id);
+        
+        // Verify ownership or delegated management
+        self.assert_owner_or_manager(rule.owner, caller);
+        
+        // A zero threshold, which the setter rejects, marks the condition as cleared
+        self.rewards_conditions.set(&rule_id, U512::zero());
+    }
+
+    /// Make a condition rule execute only when a value posted to the
+    /// attestation oracle satisfies `condition`
+    /// 
+    /// E.g. run once "github_sponsor_count" is at least 10. Without a price
+    /// condition the rule waits on the attestation alone.
+    pub fn set_attestation_condition(&mut self, rule_id: u64, condition: AttestationCondition) {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let rule = self.get_rule_or_revert(rule_id);
+        
+        // Verify ownership or delegated management
+        self.assert_owner_or_manager(rule.owner, caller);
+        if !matches!(rule.trigger_type, TriggerType::Condition)
+            || condition.key.is_empty()
+            || condition.key.len() > MAX_ATTESTATION_KEY_LEN
+        {
+            self.env().revert(Error::InvalidRuleConfig);
+        }
+        
+        self.attestation_conditions.set(&rule_id, condition);
+    }
+
+    /// Remove a rule's attestation condition
+    /// 
+    /// Unless it has a rewards condition, the rule goes back to waiting on
+    /// its price condition.
+    pub fn clear_attestation_condition(&mut self, rule_id: u64) {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let rule = self.get_rule_or_revert(rule_id);
+        
+        // Verify ownership or delegated management
+        self.assert_owner_or_manager(rule.owner, caller);
+        self.clear_attestation(rule_id);
+    }
+
+    /// Restrict who may execute a rule
+    /// 
+    /// Manual rules are always owner-only regardless of the policy.
+    pub fn set_executor_policy(&mut self, rule_id: u64, policy: ExecutorPolicy) {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let rule = self.get_rule_or_revert(rule_id);
+        
+        // Verify ownership or delegated management
+        self.assert_owner_or_manager(rule.owner, caller);
+        self.executor_policies.set(&rule_id, policy);
+    }
+
+    /// Register a hook contract called before and/or after each execution of a rule
+    /// 
+    /// The hook must be on the admin allowlist and receives the rule ID, owner
+    /// and amount. Hooks may not execute rules themselves, and a reverting
+    /// hook reverts the execution. `None` removes the hook.
+    pub fn set_rule_hook(
+        &mut self,
+        rule_id: u64,
+        hook: Option<Address>,
+        before: bool,
+        after: bool,
+    ) {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let rule = self.get_rule_or_revert(rule_id);
+        
+        // Verify ownership or delegated management
+        self.assert_owner_or_manager(rule.owner, caller);
+        
+        let hook = hook.map(|contract| {
+            if !self.allowed_hooks.get_or_default(&contract) {
+                self.env().revert(Error::HookNotAllowed);
+            }
+            RuleHook { contract, before, after }
+        });
+        self.rule_hooks.set(&rule_id, hook);
+    }
+
+    /// Ask for a NotificationRequested event after each successful execution of a rule
+    /// 
+    /// `payload_hash` is opaque to the contract and lets the off-chain
+    /// notification service find the owner's delivery settings. `None`
+    /// turns notifications off.
+    pub fn set_rule_notification(&mut self, rule_id: u64, payload_hash: Option<[u8; 32]>) {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let rule = self.get_rule_or_revert(rule_id);
+        
+        // Verify ownership or delegated management
+        self.assert_owner_or_manager(rule.owner, caller);
+        self.rule_notifications.set(&rule_id, payload_hash);
+    }
+
+    /// Set how urgently keepers should execute a rule when many are due at once
+    /// 
+    /// Higher runs first. The priority is only a hint to keepers reading
+    /// `get_due_rules_by_priority`; it does not change when a rule may run.
+    /// `None` restores the default for the rule's action.
+    pub fn set_rule_priority(&mut self, rule_id: u64, priority: Option<u8>) {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let rule = self.get_rule_or_revert(rule_id);
+        
+        // Verify ownership or delegated management
+        self.assert_owner_or_manager(rule.owner, caller);
+        self.rule_priorities.set(&rule_id, priority);
+    }
+
+    /// Set the minimum time between two executions of a rule, whatever its trigger
+    /// 
+    /// Guards condition and manual rules, which have no schedule gating
+    /// them, against being run twice in quick succession. Zero removes it.
+    pub fn set_min_execution_interval(&mut self, rule_id: u64, seconds: u64) {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let rule = self.get_rule_or_revert(rule_id);
+        
+        // Verify ownership or delegated management
+        self.assert_owner_or_manager(rule.owner, caller);
+        self.min_execution_intervals.set(&rule_id, seconds);
+    }
+
+    /// Decide what a scheduled rule does when keepers run it late
+    /// 
+    /// A run more than `grace_period` seconds after its slot is handled by
+    /// the policy instead of executing once and rescheduling from now; the
+    /// rule then keeps to its original cadence. `None` restores the default.
+    pub fn set_missed_execution_policy(&mut self, rule_id: u64, config: Option<MissedExecutionConfig>) {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let rule = self.get_rule_or_revert(rule_id);
+        
+        // Verify ownership or delegated management
+        self.assert_owner_or_manager(rule.owner, caller);
+        if config.is_some() && rule.trigger_type != TriggerType::Time {
+            self.env().revert(Error::InvalidRuleConfig);
+        }
+        self.missed_execution_configs.set(&rule_id, config);
+    }
+
+    /// Choose where a compounding rule sends the owner's staking rewards
+    /// 
+    /// `Restake` (the default) keeps them delegated; `Vault` and `External`
+    /// unstake them into the owner's vault or to the rule's recipient, which
+    /// needs the owner's engine permission on the staking adapter.
+    pub fn set_reward_destination(&mut self, rule_id: u64, destination: RewardDestination) {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let rule = self.get_rule_or_revert(rule_id);
+        
+        // Verify ownership or delegated management
+        self.assert_owner_or_manager(rule.owner, caller);
+        if rule.action_type != ActionType::Compound
+            || (destination == RewardDestination::External && rule.recipient.is_none())
+        {
+            self.env().revert(Error::InvalidRuleConfig);
+        }
+        self.reward_destinations.set(&rule_id, destination);
+    }
+
+    /// Donate part of a compounding rule's rewards on every execution
+    /// 
+    /// `share_bps` of the owner's rewards is unstaked for `recipient`, e.g.
+    /// a charity, and the rest is restaked. Takes precedence over the rule's
+    /// reward destination and needs the owner's engine permission on the
+    /// staking adapter. `None` removes the split.
+    pub fn set_reward_split(&mut self, rule_id: u64, split: Option<RewardSplit>) {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let rule = self.get_rule_or_revert(rule_id);
+        
+        // Verify ownership or delegated management
+        self.assert_owner_or_manager(rule.owner, caller);
+        if rule.action_type != ActionType::Compound {
+            self.env().revert(Error::InvalidRuleConfig);
+        }
+        if let Some(split) = &split {
+            if split.share_bps == 0 || split.share_bps > 10_000 || split.recipient == rule.owner {
+                self.env().revert(Error::InvalidRewardSplit);
+            }
+            self.check_address_book(rule.owner, Some(split.recipient));
+        }
+        self.reward_splits.set(&rule_id, split);
+    }
+
+    /// Split a CSPR split rule's amount among several recipients by percentage
+    /// 
+    /// Percentages must be whole, non-zero and add up to exactly 100. Each
+    /// share is rounded down and `dust_policy` decides where the remainder
+    /// goes; a SplitDustHandled event records any remainder. The rule's fee
+    /// is charged once on the whole amount. `None` restores the rule's
+    /// single recipient.
+    pub fn set_split_config(&mut self, rule_id: u64, config: Option<SplitConfig>) {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let rule = self.get_rule_or_revert(rule_id);
+        
+        // Verify ownership or delegated management
+        self.assert_owner_or_manager(rule.owner, caller);
+        if rule.action_type != ActionType::Split || rule.token.is_some() {
+            self.env().revert(Error::InvalidRuleConfig);
+        }
+        if let Some(config) = &config {
+            if config.recipients.is_empty() || config.recipients.len() > MAX_SPLIT_RECIPIENTS {
+                self.env().revert(Error::InvalidRuleConfig);
+            }
+            let mut total = 0u32;
+            for split in &config.recipients {
+                if split.percentage == 0 || split.recipient == rule.owner {
+                    self.env().revert(Error::InvalidRuleConfig);
+                }
+                self.check_address_book(rule.owner, Some(split.recipient));
+                total += split.percentage as u32;
+            }
+            if total != 100 {
+                self.env().revert(Error::InvalidRuleConfig);
+            }
+            if config.dust_policy == DustPolicy::Treasury
+                && self.vault_or_revert().get_treasury().is_none()
+            {
+                self.env().revert(Error::TreasuryNotSet);
+            }
+        }
+        self.split_configs.set(&rule_id, config);
+    }
+
+    /// Restrict the hours of the day during which a rule may be executed
+    /// 
+    /// Keepers executing outside the window are reverted, so a due rule
+    /// waits until the window opens, e.g. business hours in the owner's
+    /// timezone. Manual rules are not affected. `None` removes the window.
+    pub fn set_execution_window(&mut self, rule_id: u64, window: Option<ExecutionWindow>) {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let rule = self.get_rule_or_revert(rule_id);
+        
+        // Verify ownership or delegated management
+        self.assert_owner_or_manager(rule.owner, caller);
+        if window.as_ref().map(|window| !window.is_valid()).unwrap_or(false) {
+            self.env().revert(Error::InvalidRuleConfig);
+        }
+        self.execution_windows.set(&rule_id, window);
+    }
+
+    /// Warn when the caller's funds will not cover their rules' next `executions` runs
+    /// 
+    /// After each successful execution of a fixed CSPR rule, a
+    /// `VaultUnderfunded` event with the projected shortfall is emitted if
+    /// the balance it spends from is too low. Zero turns the alert off.
+    pub fn set_low_balance_alert(&mut self, executions: u32) {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        if executions > MAX_ALERT_LOOKAHEAD {
+            self.env().revert(Error::InvalidAlertLookahead);
+        }
+        self.low_balance_alerts.set(&caller, executions);
+    }
+
+    /// Have the vault reserve each of the caller's active rules' next execution
+    /// 
+    /// While on, the amount and fee of every active fixed CSPR transfer,
+    /// split, stake, escrow and payroll rule not bound to an envelope is
+    /// reserved in the caller's vault, so withdrawals cannot take funds a
+    /// rule is about to spend. Reservations follow the rule as it is
+    /// created, paused, resumed, deleted and executed. Turning it off
+    /// releases them all.
+    pub fn set_reservation_mode(&mut self, enabled: bool) {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        self.reservation_modes.set(&caller, enabled);
+        
+        let mut vault = self.vault_or_revert();
+        for rule_id in self.owner_rule_ids(caller) {
+            if let Some(rule) = self.load_rule(rule_id) {
+                let amount = if enabled { self.reservation_for(&rule) } else { U512::zero() };
+                vault.set_reservation(caller, rule_id, amount);
+            }
+        }
+    }
+
+    /// Bind a rule to one of the owner's vault envelopes
+    /// 
+    /// A bound rule can only spend what is earmarked for the envelope, fees
+    /// included, so one category of automation cannot drain funds set aside
+    /// for another. Keeper bounties still come from the unassigned balance.
+    /// `None` unbinds the rule.
+    pub fn set_rule_envelope(&mut self, rule_id: u64, envelope: Option<String>) {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let rule = self.get_rule_or_revert(rule_id);
+        
+        // Verify ownership or delegated management
+        self.assert_owner_or_manager(rule.owner, caller);
+        if envelope.as_ref().map(|name| name.is_empty()).unwrap_or(false) {
+            self.env().revert(Error::InvalidEnvelope);
+        }
+        self.rule_envelopes.set(&rule_id, envelope);
+    }
+
+    /// Change the least output each execution of a swap rule accepts
+    /// 
+    /// Recurring swaps need this as prices move. Swap rules created before
+    /// minimums existed do not execute until their owner sets one.
+    pub fn set_swap_min_out(&mut self, rule_id: u64, min_out: U512) {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let rule = self.get_rule_or_revert(rule_id);
+        if rule.owner != caller {
+            self.env().revert(Error::NotRuleOwner);
+        }
+        if rule.action_type != ActionType::Swap {
+            self.env().revert(Error::InvalidRuleConfig);
+        }
+        self.assert_swap_min_out(min_out);
+        self.swap_min_outs.set(&rule_id, min_out);
+    }
+
+    /// Pay a transfer rule's contract recipient through a deposit entrypoint
+    /// 
+    /// Contracts cannot receive plain transfers, so each execution calls
+    /// `deposit.entry_point` on the recipient with the amount attached, e.g.
+    /// `deposit_for` on another vault. Only CSPR transfer rules whose
+    /// recipient is a contract qualify, and the admin must have allowed the
+    /// (contract, entrypoint) pair. `None` reverts to a plain transfer.
+    pub fn set_contract_recipient(&mut self, rule_id: u64, deposit: Option<ContractRecipient>) {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let rule = self.get_rule_or_revert(rule_id);
+        
+        // Verify ownership
+        if rule.owner != caller {
+            self.env().revert(Error::NotRuleOwner);
+        }
+        if let Some(deposit) = &deposit {
+            let contract_recipient = rule.recipient.map(|r| r.is_contract()).unwrap_or(false);
+            if rule.action_type != ActionType::Transfer
+                || rule.token.is_some()
+                || !contract_recipient
+                || self.vault_recipients.get_or_default(&rule_id)
+                || self.claim_windows.get_or_default(&rule_id) > 0
+                || deposit.entry_point.is_empty()
+                || deposit.entry_point.len() > MAX_ENTRY_POINT_LENGTH
+            {
+                self.env().revert(Error::InvalidRuleConfig);
+            }
+            let allowed_key = (rule.recipient.unwrap(), deposit.entry_point.clone());
+            if !self.allowed_contract_recipients.get_or_default(&allowed_key) {
+                self.env().revert(Error::InvalidContractRecipient);
+            }
+        }
+        self.contract_recipients.set(&rule_id, deposit);
+    }
+
+    /// Pay a transfer rule's recipient into their vault account instead
+    /// 
+    /// The amount is credited to the recipient's vault balance without a
+    /// token transfer, which is cheaper and lets platform users spend it at
+    /// once. Only CSPR transfer rules without a contract recipient qualify.
+    pub fn set_vault_recipient(&mut self, rule_id: u64, enabled: bool) {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let rule = self.get_rule_or_revert(rule_id);
+        self.assert_owner_or_manager(rule.owner, caller);
+        if enabled
+            && (rule.action_type != ActionType::Transfer
+                || rule.token.is_some()
+                || self.contract_recipients.get_or_default(&rule_id).is_some()
+                || self.claim_windows.get_or_default(&rule_id) > 0)
+        {
+            self.env().revert(Error::InvalidRuleConfig);
+        }
+        self.vault_recipients.set(&rule_id, enabled);
+    }
+
+    /// Hold a transfer rule's payments in the vault until the recipient claims them
+    /// 
+    /// Each execution adds the amount to the recipient's claimable balance
+    /// instead of sending it, so a mistyped recipient that never claims
+    /// does not lose the funds: once `claim_window` seconds have passed the
+    /// owner can reclaim an unclaimed payment with the vault's
+    /// `refund_claimable`. Only CSPR transfer rules paying a plain account
+    /// qualify. A zero window turns claimable mode off.
+    pub fn set_claimable_transfer(&mut self, rule_id: u64, claim_window: u64) {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let rule = self.get_rule_or_revert(rule_id);
+        self.assert_owner_or_manager(rule.owner, caller);
+        if claim_window > 0
+            && (rule.action_type != ActionType::Transfer
+                || rule.token.is_some()
+                || claim_window > MAX_CLAIM_WINDOW
+                || self.contract_recipients.get_or_default(&rule_id).is_some()
+                || self.vault_recipients.get_or_default(&rule_id))
+        {
+            self.env().revert(Error::InvalidRuleConfig);
+        }
+        self.claim_windows.set(&rule_id, claim_window);
+    }
+
+    /// Add or remove a keeper from the caller's executor allowlist
+    pub fn set_allowed_executor(&mut self, keeper: Address, allowed: bool) {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        self.allowed_executors.set(&(caller, keeper), allowed);
+    }
+
+    /// Set the bounty paid to whoever successfully executes a rule
+    /// 
+    /// The flat amount in motes is paid from the owner's vault on each
+    /// successful execution by someone other than the owner, so keep enough
+    /// CSPR in the vault to cover it. The owner's managers and session keys
+    /// are never paid, so they cannot set a bounty for themselves to collect.
+    /// Zero removes the bounty.
+    pub fn set_keeper_bounty(&mut self, rule_id: u64, bounty: U512) {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let rule = self.get_rule_or_revert(rule_id);
+        
+        // Verify ownership or delegated management
+        self.assert_owner_or_manager(rule.owner, caller);
+        if !bounty.is_zero()
+            && (bounty < self.min_keeper_bounty.get_or_default()
+                || bounty > self.max_keeper_bounty.get_or_default())
+        {
+            self.env().revert(Error::BountyOutOfBounds);
+        }
+        
+        self.keeper_bounties.set(&rule_id, bounty);
+    }
+
+    /// Cap the total CSPR a rule may spend from the owner's vault
+    /// 
+    /// Spend is the drop in the owner's CSPR vault balance caused by each
+    /// execution, so fees and keeper bounties count against the budget. An
+    /// execution that would overshoot the budget reverts, and once what is
+    /// left cannot cover another execution the rule is paused; it can only be
+    /// resumed after raising the budget. Lowering the budget to or below what
+    /// the rule needs pauses it right away. Zero removes the budget; spend so
+    /// far is kept.
+    pub fn set_rule_budget(&mut self, rule_id: u64, budget: U512) {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let rule = self.get_rule_or_revert(rule_id);
+        
+        // Verify ownership or delegated management
+        self.assert_owner_or_manager(rule.owner, caller);
+        self.rule_budgets.set(&rule_id, budget);
+        if rule.status == RuleStatus::Active && self.budget_exhausted(&rule) {
+            self.pause_for_budget(rule);
+        }
+    }
+
+    /// Stop a rule automatically once it has moved `target` CSPR in total
+    /// 
+    /// Supports "save 10k CSPR" style rules: each execution is capped at
+    /// what is left of the goal, and when the goal is met the rule is paused
+    /// and GoalReached is emitted, as it is right away when the new target
+    /// is at or below what the rule has already moved. Only CSPR transfer,
+    /// split and stake-from-vault rules support goals. Zero removes the
+    /// goal; the amount saved so far is kept.
+    pub fn set_savings_goal(&mut self, rule_id: u64, target: U512) {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let rule = self.get_rule_or_revert(rule_id);
+        
+        // Verify ownership or delegated management
+        self.assert_owner_or_manager(rule.owner, caller);
+        if !target.is_zero()
+            && (rule.token.is_some()
+                || !matches!(
+                    rule.action_type,
+                    ActionType::Transfer | ActionType::Split | ActionType::StakeFromVault
+                ))
+        {
+            self.env().revert(Error::InvalidRuleConfig);
+        }
+        self.savings_goals.set(&rule_id, target);
+        if !target.is_zero() {
+            let saved = self.savings_progress.get_or_default(&rule_id);
+            self.pause_reached_goal(rule_id, target, saved);
+        }
+    }
+
+    /// Make a rule's amount a share of a balance, evaluated at execution time
+    /// 
+    /// `percent_bps` is in basis points of the owner's vault balance
+    /// (`PercentOfVault`) or staking rewards (`PercentOfRewards`), and is
+    /// ignored for `Fixed`, which restores the rule's stored amount. Only
+    /// transfer, split and stake-from-vault rules support percentages, and
+    /// rewards-based amounts are CSPR only. Fees come on top, so a rule
+    /// sending 100% of the vault needs a fee-exempt owner.
+    pub fn set_amount_spec(&mut self, rule_id: u64, spec: AmountSpec, percent_bps: u16) {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        let rule = self.get_rule_or_revert(rule_id);
+        
+        // Percentages are not bounded by manager ceilings, so this is owner-only
+        if rule.owner != caller {
+            self.env().revert(Error::NotRuleOwner);
+        }
+        if matches!(spec, AmountSpec::Fixed) {
+            self.amount_specs.set(&rule_id, (spec, 0));
+            return;
+        }
+        if percent_bps == 0
+            || percent_bps > 10_000
+            || !matches!(
+                rule.action_type,
+                ActionType::Transfer | ActionType::Split | ActionType::StakeFromVault
+            )
+            || (matches!(spec, AmountSpec::PercentOfRewards) && rule.token.is_some())
+        {
+            self.env().revert(Error::InvalidRuleConfig);
+        }
+        
+        self.amount_specs.set(&rule_id, (spec, percent_bps));
+    }
+
+    /// Set the vault contract address (admin only)
+    /// 
+    /// Reverts with IncompatibleContractVersion unless `vault` is a vault
+    /// speaking the interface this engine was built against. Reverts with
+    /// TimelockRequired while the admin timelock is enabled; use
+    /// `queue_vault_address` instead.
+    pub fn set_vault_address(&mut self, vault: Address) {
+        self.assert_admin();
+        self.assert_no_timelock();
+        self.assert_compatible(vault, ContractKind::Vault, VAULT_INTERFACE_VERSION);
+        self.vault_address.set(Some(vault));
+    }
+
+    /// Point the engine at a contract registry, or detach it with `None` (admin only)
+    /// 
+    /// While set and the admin timelock is disabled, the registry's vault,
+    /// staking adapter and oracle entries take precedence; addresses set on
+    /// the engine are used only for entries the registry leaves empty.
+    /// Registry entries change without a delay, so the engine ignores them
+    /// while the timelock is enabled. Reverts with TimelockRequired while
+    /// the admin timelock is enabled; use `queue_registry` instead.
+    pub fn set_registry(&mut self, registry: Option<Address>) {
+        self.assert_admin();
+        self.assert_no_timelock();
+        self.registry.set(registry);
+    }
+
+    /// Set the protocol fee charged on automated transfers (fee manager role)
+    /// 
+    /// `fee_bps` is capped at 10% of the transferred amount. Reverts with
+    /// TimelockRequired while the admin timelock is enabled; use
+    /// `queue_fee_config` instead.
+    pub fn set_fee_config(&mut self, fee_bps: u16, flat_fee: U512) {
+        self.assert_role(Role::FeeManager);
+        self.assert_no_timelock();
+        self.assert_fee_config(fee_bps);
+        self.apply_fee_config(fee_bps, flat_fee);
+    }
+
+    /// Set the staking adapter used by staking rules (admin only)
+    /// 
+    /// Checked like `set_vault_address` against the adapter's interface version.
+    /// Emits `StakingAdapterUpdated`. Reverts with TimelockRequired while the
+    /// admin timelock is enabled; use `queue_staking_adapter` instead.
+    pub fn set_staking_adapter(&mut self, adapter: Address) {
+        self.assert_admin();
+        self.assert_no_timelock();
+        self.assert_compatible(
+            adapter,
+            ContractKind::StakingAdapter,
+            STAKING_ADAPTER_INTERFACE_VERSION,
+        );
+        self.apply_staking_adapter(adapter);
+    }
+
+    /// Migrate storage to the next layout version (admin only)
+    /// 
+    /// Run after upgrading the contract code. Rules are converted in batches
+    /// of at most `max_rules`, so call repeatedly until it returns true.
+    /// Rule creation and execution revert while a migration is pending;
+    /// vault funds are unaffected and can always be withdrawn.
+    pub fn migrate(&mut self, max_rules: u32) -> bool {
+        self.assert_admin();
+        let from_version = self.storage_version.get_or_default();
+        if from_version >= STORAGE_VERSION {
+            return true;
+        }
+        
+        // Convert the next batch of rules
+        let end = self.next_rule_id.get_or_default();
+        let mut rule_id = self.migration_cursor.get_or_default().max(1);
+        let mut converted = 0;
+        while rule_id < end && converted < max_rules {
+            if !self.purged_rules.get_or_default(&rule_id) {
+                self.migrate_rule(from_version, rule_id);
+            }
+            rule_id += 1;
+            converted += 1;
+        }
+        if rule_id < end {
+            self.migration_cursor.set(rule_id);
+            return false;
+        }
+        
+        // All rules converted; move on to the next version
+        let to_version = from_version + 1;
+        self.migration_cursor.set(0);
+        self.storage_version.set(to_version);
+        self.env().emit_event(StorageMigrated {
+            from_version,
+            to_version,
+            nonce: self.event_nonce.next(),
+        });
+        
+        to_version >= STORAGE_VERSION
+    }
+
+    /// Set the CSPR/USD price oracle (admin only)
+    /// 
+    /// Reverts with TimelockRequired while the admin timelock is enabled;
+    /// use `queue_price_oracle` instead.
+    pub fn set_price_oracle(&mut self, oracle: Address) {
+        self.assert_admin();
+        self.assert_no_timelock();
+        self.price_oracle.set(Some(oracle));
+    }
+
+    /// Set the attestation oracle condition rules read from (admin only)
+    /// 
+    /// Reverts with TimelockRequired while the admin timelock is enabled;
+    /// use `queue_attestation_oracle` instead.
+    pub fn set_attestation_oracle(&mut self, oracle: Address) {
+        self.assert_admin();
+        self.assert_no_timelock();
+        self.attestation_oracle.set(Some(oracle));
+    }
+
+    /// Set how long after deletion a rule can still be restored, in seconds (admin only)
+    /// 
+    /// Zero, the default, turns restoring off.
+    pub fn set_restore_grace_period(&mut self, grace_period: u64) {
+        self.assert_admin();
+        self.restore_grace_period.set(grace_period);
+    }
+
+    /// Set the maximum age of an oracle price in seconds (operator role)
+    pub fn set_max_price_age(&mut self, max_age: u64) {
+        self.assert_role(Role::Operator);
+        self.max_price_age.set(max_age);
+    }
+
+    /// Set the period lengths and delays the engine schedules with (admin only)
+    /// 
+    /// Meant for testnet deployments that compress schedules; mainnet keeps
+    /// the real durations. This is deployment setup: once the first rule is
+    /// created the config is locked, so the confirmation and address book
+    /// delays users rely on cannot be shortened under them.
+    pub fn set_time_config(&mut self, config: TimeConfig) {
+        self.assert_admin();
+        if self.next_rule_id.get_or_default() > 1 {
+            self.env().revert(Error::TimeConfigLocked);
+        }
+        if !config.is_valid() {
+            self.env().revert(Error::InvalidTimeConfig);
+        }
+        self.time_config.set(config);
+    }
+
+    /// Cap how often any single rule may execute per hour (operator role)
+    /// 
+    /// Guards vaults against a buggy or hostile keeper hammering a rule
+    /// whose trigger is always satisfied. Underfunded attempts do not count.
+    /// Zero removes the cap; the vault separately caps each owner's daily
+    /// outflow.
+    pub fn set_max_executions_per_hour(&mut self, limit: u32) {
+        self.assert_role(Role::Operator);
+        self.max_executions_per_hour.set(limit);
+    }
+
+    /// Set the limits new rules must stay within (operator role)
+    /// 
+    /// Time-triggered rules may not be scheduled more often than every
+    /// `min_schedule_interval` seconds, CSPR rules may not move more than
+    /// `max_rule_amount` per execution (zero for no limit), which rules out
+    /// sweeps while a limit is set, and one batch may create at most
+    /// `max_rules_per_batch` rules, up to the built-in maximum of 25.
+    /// Existing rules are not affected.
+    pub fn set_rule_bounds(
+        &mut self,
+        min_schedule_interval: u64,
+        max_rule_amount: U512,
+        max_rules_per_batch: u32,
+    ) {
+        self.assert_role(Role::Operator);
+        if max_rules_per_batch == 0 || max_rules_per_batch as usize > MAX_BATCH_RULES {
+            self.env().revert(Error::InvalidRuleConfig);
+        }
+        self.min_schedule_interval.set(min_schedule_interval);
+        self.max_rule_amount.set(max_rule_amount);
+        self.max_rules_per_batch.set(max_rules_per_batch);
+    }
+
+    /// Set the range owners may choose keeper bounties from (fee manager role)
+    /// 
+    /// A zero `max_bounty` disables bounties for new settings.
+    pub fn set_keeper_bounty_bounds(&mut self, min_bounty: U512, max_bounty: U512) {
+        self.assert_role(Role::FeeManager);
+        if min_bounty > max_bounty {
+            self.env().revert(Error::InvalidFeeConfig);
+        }
+        self.min_keeper_bounty.set(min_bounty);
+        self.max_keeper_bounty.set(max_bounty);
+    }
+
+    /// Register a new rule template (operator role)
+    pub fn register_template(&mut self, template: RuleTemplate) -> u32 {
+        self.assert_role(Role::Operator);
+        let name = template.name.clone();
+        let template_id = self.templates.register(template);
+        
+        self.env().emit_event(TemplateRegistered {
+            template_id,
+            name,
+            nonce: self.event_nonce.next(),
+        });
+        
+        template_id
+    }
+
+    /// Enable or disable a rule template (operator role)
+    pub fn set_template_enabled(&mut self, template_id: u32, enabled: bool) {
+        self.assert_role(Role::Operator);
+        self.templates.set_enabled(template_id, enabled);
+    }
+
+    /// Add or remove a hook contract from the allowlist (operator role)
+    /// 
+    /// Rules keep their registration when a hook is removed, but it is no
+    /// longer called.
+    pub fn set_hook_allowed(&mut self, hook: Address, allowed: bool) {
+        self.assert_role(Role::Operator);
+        self.allowed_hooks.set(&hook, allowed);
+        
+        self.env().emit_event(HookAllowlistUpdated {
+            hook,
+            allowed,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Allow or disallow rules paying `contract` through `entry_point` (admin only)
+    /// 
+    /// Rules already set up keep their contract recipient, but the vault
+    /// checks its own allowlist on every payment.
+    pub fn set_contract_recipient_allowed(
+        &mut self,
+        contract: Address,
+        entry_point: String,
+        allowed: bool,
+    ) {
+        self.assert_admin();
+        self.allowed_contract_recipients.set(&(contract, entry_point.clone()), allowed);
+        
+        self.env().emit_event(ContractRecipientAllowlistUpdated {
+            contract,
+            entry_point,
+            allowed,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Whether rules may pay `contract` through `entry_point`
+    pub fn is_contract_recipient_allowed(&self, contract: Address, entry_point: String) -> bool {
+        self.allowed_contract_recipients.get_or_default(&(contract, entry_point))
+    }
+
+    /// Add or remove a recipient from the blocklist (operator role)
+    /// 
+    /// The blocklist is checked every time a rule pays someone, so existing
+    /// rules paying a newly blocked recipient stop at their next execution.
+    /// The vault also refuses their stream and vesting claims while blocked.
+    pub fn set_recipient_blocked(&mut self, recipient: Address, blocked: bool) {
+        self.assert_role(Role::Operator);
+        self.blocked_recipients.set(&recipient, blocked);
+        
+        self.env().emit_event(RecipientBlocklistUpdated {
+            recipient,
+            blocked,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Exempt a tier from protocol fees, or remove the exemption (fee manager role)
+    pub fn set_fee_exempt(&mut self, tier: StakingTier, exempt: bool) {
+        self.assert_role(Role::FeeManager);
+        self.fee_exempt_tiers.set(&tier, exempt);
+    }
+
+    /// Configure the membership NFT collection whose holders get perks (fee manager role)
+    /// 
+    /// Holders are treated as at least `tier` and get `fee_discount_bps` off
+    /// protocol fees. Pass None to disable NFT perks.
+    pub fn set_nft_perks(&mut self, perks: Option<NftPerks>) {
+        self.assert_role(Role::FeeManager);
+        if perks.as_ref().is_some_and(|perks| perks.fee_discount_bps > 10_000) {
+            self.env().revert(Error::InvalidFeeConfig);
+        }
+        self.nft_perks.set(perks.clone());
+        
+        self.env().emit_event(NftPerksUpdated {
+            perks,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Grant a role to an account (admin only)
+    /// 
+    /// Operators manage limits, templates and allowlists, pausers can halt
+    /// rule execution and fee managers set fees. The owner role stays with
+    /// the admin and cannot be granted.
+    pub fn grant_role(&mut self, role: Role, account: Address) {
+        self.assert_admin();
+        self.roles.set_role(role.clone(), account, true);
+        
+        self.env().emit_event(RoleGranted {
+            role,
+            account,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Revoke a role from an account (admin only)
+    pub fn revoke_role(&mut self, role: Role, account: Address) {
+        self.assert_admin();
+        self.roles.set_role(role.clone(), account, false);
+        
+        self.env().emit_event(RoleRevoked {
+            role,
+            account,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Enable the admin timelock or lengthen its delay (admin only)
+    /// 
+    /// While the delay is non-zero, fee, oracle, vault, staking adapter and
+    /// registry changes must be queued and can only be executed once the
+    /// delay has passed, so users see them coming. Shortening or disabling
+    /// the delay is itself queued with `queue_timelock_delay`.
+    pub fn set_admin_timelock_delay(&mut self, delay: u64) {
+        self.assert_admin();
+        if delay < self.timelock.delay() {
+            self.env().revert(Error::TimelockRequired);
+        }
+        self.timelock.set_delay(delay);
+    }
+
+    /// Queue a protocol fee change behind the timelock (fee manager role)
+    pub fn queue_fee_config(&mut self, fee_bps: u16, flat_fee: U512) -> u64 {
+        self.assert_role(Role::FeeManager);
+        self.assert_fee_config(fee_bps);
+        self.queue_admin_action(AdminAction {
+            kind: AdminActionKind::SetFeeConfig,
+            target: None,
+            fee_bps,
+            flat_fee,
+            delay: 0,
+            eta: 0,
+        })
+    }
+
+    /// Queue a price oracle change behind the timelock (admin only)
+    pub fn queue_price_oracle(&mut self, oracle: Address) -> u64 {
+        self.assert_admin();
+        self.queue_admin_action(AdminAction {
+            kind: AdminActionKind::SetPriceOracle,
+            target: Some(oracle),
+            fee_bps: 0,
+            flat_fee: U512::zero(),
+            delay: 0,
+            eta: 0,
+        })
+    }
+
+    /// Queue an attestation oracle change behind the timelock (admin only)
+    pub fn queue_attestation_oracle(&mut self, oracle: Address) -> u64 {
+        self.assert_admin();
+        self.queue_admin_action(AdminAction {
+            kind: AdminActionKind::SetAttestationOracle,
+            target: Some(oracle),
+            fee_bps: 0,
+            flat_fee: U512::zero(),
+            delay: 0,
+            eta: 0,
+        })
+    }
+
+    /// Queue a vault change behind the timelock (admin only)
+    pub fn queue_vault_address(&mut self, vault: Address) -> u64 {
+        self.assert_admin();
+        self.assert_compatible(vault, ContractKind::Vault, VAULT_INTERFACE_VERSION);
+        self.queue_admin_action(AdminAction {
+            kind: AdminActionKind::SetVaultAddress,
+            target: Some(vault),
+            fee_bps: 0,
+            flat_fee: U512::zero(),
+            delay: 0,
+            eta: 0,
+        })
+    }
+
+    /// Queue a staking adapter change behind the timelock (admin only)
+    pub fn queue_staking_adapter(&mut self, adapter: Address) -> u64 {
+        self.assert_admin();
+        self.assert_compatible(
+            adapter,
+            ContractKind::StakingAdapter,
+            STAKING_ADAPTER_INTERFACE_VERSION,
+        );
+        self.queue_admin_action(AdminAction {
+            kind: AdminActionKind::SetStakingAdapter,
+            target: Some(adapter),
+            fee_bps: 0,
+            flat_fee: U512::zero(),
+            delay: 0,
+            eta: 0,
+        })
+    }
+
+    /// Queue attaching or detaching a contract registry behind the timelock (admin only)
+    pub fn queue_registry(&mut self, registry: Option<Address>) -> u64 {
+        self.assert_admin();
+        self.queue_admin_action(AdminAction {
+            kind: AdminActionKind::SetRegistry,
+            target: registry,
+            fee_bps: 0,
+            flat_fee: U512::zero(),
+            delay: 0,
+            eta: 0,
+        })
+    }
+
+    /// Queue a change of the timelock delay behind the current delay (admin only)
+    pub fn queue_timelock_delay(&mut self, delay: u64) -> u64 {
+        self.assert_admin();
+        self.queue_admin_action(AdminAction {
+            kind: AdminActionKind::SetTimelockDelay,
+            target: None,
+            fee_bps: 0,
+            flat_fee: U512::zero(),
+            delay,
+            eta: 0,
+        })
+    }
+
+    /// Apply a queued admin change once its delay has passed
+    /// 
+    /// Anyone may call this: the change was authorized when it was queued.
+    pub fn execute_admin_action(&mut self, action_id: u64) {
+        let action = self.timelock.take_ready(action_id);
+        match action.kind {
+            AdminActionKind::SetFeeConfig => self.apply_fee_config(action.fee_bps, action.flat_fee),
+            AdminActionKind::SetPriceOracle => self.price_oracle.set(action.target),
+            AdminActionKind::SetAttestationOracle => self.attestation_oracle.set(action.target),
+            AdminActionKind::SetRegistry => self.registry.set(action.target),
+            AdminActionKind::SetVaultAddress => self.vault_address.set(action.target),
+            AdminActionKind::SetStakingAdapter => match action.target {
+                Some(adapter) => self.apply_staking_adapter(adapter),
+                None => self.env().revert(Error::AdminActionNotFound),
+            },
+            AdminActionKind::SetTimelockDelay => self.timelock.set_delay(action.delay),
+            // Only the vault queues engine changes
+            AdminActionKind::SetAutomationEngine => self.env().revert(Error::AdminActionNotFound),
+        }
+        
+        self.env().emit_event(AdminActionExecuted {
+            action_id,
+            kind: action.kind,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Drop a queued admin change (admin only)
+    pub fn cancel_admin_action(&mut self, action_id: u64) {
+        self.assert_admin();
+        let action = self.timelock.cancel(action_id);
+        
+        self.env().emit_event(AdminActionCancelled {
+            action_id,
+            kind: action.kind,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Halt or resume rule execution for every rule (pauser role)
+    /// 
+    /// Meant for incidents: owners keep managing their rules and vault
+    /// funds while execution is paused.
+    pub fn set_execution_paused(&mut self, paused: bool) {
+        self.assert_role(Role::Pauser);
+        self.execution_paused.set(paused);
+        
+        self.env().emit_event(ExecutionPauseUpdated {
+            paused,
+            by: self.env().caller(),
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    // ========================================================================
+    // View Functions
+    // ========================================================================
+
+    /// Get a rule by ID
+    pub fn get_rule(&self, rule_id: u64) -> Option<AutomationRule> {
+        let mut rule = self.load_rule(rule_id)?;
+        rule.template_name = self.template_names.get_or_default(&rule.template_name_id);
+        Some(rule)
+    }
+
+    /// Get a template name by the ID rules and execution events refer to it with
+    pub fn get_template_name(&self, template_name_id: u32) -> Option<String> {
+        self.template_names.get(&template_name_id)
+    }
+
+    /// Get the due scheduled rules among IDs `start_id..start_id + limit`, most urgent first
+    /// 
+    /// Lists active time-triggered rules whose next execution has passed,
+    /// ordered by priority (highest first), then by how long they have been
+    /// due, then by ID, so keepers short on gas pay the critical ones first.
+    /// At most 200 IDs are scanned per call; page through with `start_id`.
+    pub fn get_due_rules_by_priority(&self, start_id: u64, limit: u64) -> Vec<u64> {
+        let current_time = self.env().get_block_time();
+        let end = start_id
+            .saturating_add(limit.min(MAX_DUE_RULE_SCAN))
+            .min(self.next_rule_id.get_or_default());
+        let mut due: Vec<(u64, u8, u64)> = (start_id..end)
+            .filter_map(|rule_id| self.load_rule(rule_id))
+            .filter(|rule| {
+                rule.status == RuleStatus::Active
+                    && rule.trigger_type == TriggerType::Time
+                    && rule.next_execution <= current_time
+            })
+            .map(|rule| (rule.id, self.priority_of(&rule), rule.next_execution))
+            .collect();
+        due.sort_by(|a, b| b.1.cmp(&a.1).then(a.2.cmp(&b.2)).then(a.0.cmp(&b.0)));
+        due.into_iter().map(|(rule_id, _, _)| rule_id).collect()
+    }
+
+    /// Get the keeper priority of a rule, chosen or defaulted from its action
+    pub fn get_rule_priority(&self, rule_id: u64) -> u8 {
+        self.priority_of(&self.get_rule_or_revert(rule_id))
+    }
+
+    /// Get a user's vault balances, rules, stake and pending unstakes in one call
+    /// 
+    /// Staking fields are empty when no staking adapter is configured.
+    pub fn get_user_snapshot(&self, owner: Address) -> UserSnapshot {
+        let vault = self.vault_or_revert();
+        let rules = self
+            .owner_rule_ids(owner)
+            .into_iter()
+            .filter_map(|rule_id| self.get_rule(rule_id))
+            .collect();
+        let staking = self.resolved_staking_adapter();
+        let (staked, staking_principal, pending_unstakes) = match staking {
+            Some(adapter) => {
+                let adapter = StakingAdapterContractRef::new(self.env(), adapter);
+                (
+                    adapter.get_user_stake(owner),
+                    adapter.get_user_principal(owner),
+                    adapter.get_user_pending_unstakes(owner),
+                )
+            }
+            None => (U512::zero(), U512::zero(), Vec::new()),
+        };
+        
+        UserSnapshot {
+            tier: self.get_user_tier(owner),
+            vault_balance: vault.get_balance(owner),
+            spendable_balance: vault.get_spendable_balance(owner),
+            token_balances: vault.get_token_balances(owner),
+            rules,
+            stats: self.get_user_stats(owner),
+            staked,
+            staking_principal,
+            pending_unstakes,
+        }
+    }
+
+    /// Get all rule IDs for a user, oldest first
+    pub fn get_user_rule_ids(&self, owner: Address) -> Vec<u64> {
+        self.owner_rule_ids(owner)
+    }
+
+    /// Get a user's rules carrying `tag`, oldest first
+    pub fn get_user_rules_by_tag(&self, owner: Address, tag: String) -> Vec<AutomationRule> {
+        self.owner_rule_ids(owner)
+            .into_iter()
+            .filter(|rule_id| self.rule_tags.get_or_default(rule_id).contains(&tag))
+            .filter_map(|rule_id| self.get_rule(rule_id))
+            .collect()
+    }
+
+    /// Get a rule's tags
+    pub fn get_rule_tags(&self, rule_id: u64) -> Vec<String> {
+        self.rule_tags.get_or_default(&rule_id)
+    }
+
+    /// Get the global ID of an owner's rule from its per-owner index
+    /// 
+    /// Owners' rules are numbered 0, 1, 2, ... in creation order,
+    /// independently of other users. Rules created before per-owner indices
+    /// were stored are resolved from the owner's rule list.
+    pub fn get_rule_id_by_local_index(&self, owner: Address, local_index: u32) -> Option<u64> {
+        self.local_rule_ids.get(&(owner, local_index)).or_else(|| {
+            self.user_rules.get_or_default(&owner).get(local_index as usize).copied()
+        })
+    }
+
+    /// Get an owner's rule by its per-owner index
+    pub fn get_rule_by_local_index(
+        &self,
+        owner: Address,
+        local_index: u32,
+    ) -> Option<AutomationRule> {
+        self.get_rule(self.get_rule_id_by_local_index(owner, local_index)?)
+    }
+
+    /// Get a rule's per-owner index
+    pub fn get_local_index(&self, rule_id: u64) -> Option<u32> {
+        if let Some(local_index) = self.rule_local_indices.get(&rule_id) {
+            return Some(local_index);
+        }
+        let owner = self.rule_configs.get(&rule_id)?.owner;
+        let position = self.user_rules.get_or_default(&owner).iter().position(|id| *id == rule_id)?;
+        Some(position as u32)
+    }
+
+    /// Get the price condition of a rule
+    pub fn get_price_condition(&self, rule_id: u64) -> Option<PriceCondition> {
+        self.price_conditions.get(&rule_id)
+    }
+
+    /// Get the attestation condition of a rule
+    pub fn get_attestation_condition(&self, rule_id: u64) -> Option<AttestationCondition> {
+        self.attestation_conditions
+            .get(&rule_id)
+            .filter(|condition| !condition.key.is_empty())
+    }
+
+    /// Get the pending rewards threshold of a rule
+    pub fn get_rewards_condition(&self, rule_id: u64) -> Option<U512> {
+        self.rewards_conditions.get(&rule_id).filter(|threshold| !threshold.is_zero())
+    }
+
+    /// Get the amount above which `owner`'s new rules need confirmation
+    pub fn get_approval_threshold(&self, owner: Address) -> U512 {
+        match self.pending_threshold_changes.get_or_default(&owner) {
+            Some((raised, effective_at)) if self.env().get_block_time() >= effective_at => raised,
+            _ => self.approval_thresholds.get_or_default(&owner),
+        }
+    }
+
+    /// Estimate the gas, in motes, a keeper should budget to execute a rule
+    /// 
+    /// Deliberately conservative: covers the rule's action, the contract
+    /// calls its settings add (oracle, hooks, envelopes, budgets), every
+    /// missed slot a late `CatchUpAll` rule runs and the follow-up rules
+    /// executed with it. Use it for `set_gas` and to show
+    /// the expected execution cost; it is not a measurement.
+    pub fn estimate_execution_cost(&self, rule_id: u64) -> U512 {
+        let rule = self.get_rule_or_revert(rule_id);
+        U512::from(self.estimate_rule_gas(&rule, 0))
+    }
+
+    /// Get the time from which a pending rule can be confirmed
+    pub fn get_confirmable_at(&self, rule_id: u64) -> Option<u64> {
+        self.confirmable_at.get(&rule_id)
+    }
+
+    /// Predict what executing a rule now would do, without changing any state
+    /// 
+    /// Lets frontends and keepers pre-check a rule: whether it is due, whether
+    /// its conditions pass, whether the owner can cover it, the fee it would
+    /// charge and the owner's resulting vault balance.
+    pub fn preview_execution(&self, rule_id: u64) -> ExecutionPreview {
+        let mut rule = self.get_rule_or_revert(rule_id);
+        rule.amount = self.resolve_amount(&rule);
+        let current_time = self.env().get_block_time();
+        let vault = self.vault_or_revert();
+        
+        let is_active = matches!(rule.status, RuleStatus::Active);
+        let is_due = match rule.trigger_type {
+            TriggerType::Time => current_time >= rule.next_execution,
+            TriggerType::Manual | TriggerType::Condition => true,
+        };
+        
+        let mut condition_met = true;
+        if matches!(rule.trigger_type, TriggerType::Condition) {
+            if self.needs_price_condition(rule_id) {
+                condition_met &= self.read_price_condition(rule_id, current_time).is_ok();
+            }
+            if let Some(threshold) = self.get_rewards_condition(rule_id) {
+                condition_met &= self.read_rewards_condition(&rule, threshold).is_ok();
+            }
+            if self.get_attestation_condition(rule_id).is_some() {
+                condition_met &= self.read_attestation_condition(rule_id, current_time).is_ok();
+            }
+        }
+        if matches!(rule.action_type, ActionType::Inheritance) {
+            condition_met &= self.owner_inactive(&rule, current_time);
+        }
+        if matches!(
+            rule.action_type,
+            ActionType::Transfer
+                | ActionType::Split
+                | ActionType::Sweep
+                | ActionType::TopUp
+                | ActionType::Escrow
+        ) {
+            condition_met &= self.recipient_allowed(rule.owner, rule.recipient)
+                && !rule.recipient.is_some_and(|recipient| self.is_recipient_blocked(recipient));
+        }
+        
+        // Amount moved and the owner's vault balance in the rule's asset
+        let balance_before = match rule.token {
+            Some(token) => from_token_amount(vault.get_token_balance(rule.owner, token)),
+            None => vault.get_balance(rule.owner),
+        };
+        let (amount, fee, outflow) = match rule.action_type {
+            ActionType::Transfer
+            | ActionType::Split
+            | ActionType::StakeFromVault
+            | ActionType::Swap
+            | ActionType::Escrow => {
+                let fee = match rule.token {
+                    Some(_) => U512::zero(),
+                    None => self.quote_fee(rule.owner, rule.amount),
+                };
+                (rule.amount, fee, rule.amount + fee)
+            }
+            ActionType::Inheritance => (balance_before, U512::zero(), balance_before),
+            ActionType::TopUp => {
+                let amount = self.top_up_amount(&rule);
+                let fee = self.quote_fee(rule.owner, amount);
+                (amount, fee, amount + fee)
+            }
+            ActionType::Sweep => {
+                let fee_config = self.effective_fee_config(rule.owner);
+                let amount = fee_config.max_amount_within(balance_before.saturating_sub(rule.amount));
+                let fee = if amount.is_zero() { U512::zero() } else { fee_config.fee_for(amount) };
+                (amount, fee, amount + fee)
+            }
+            ActionType::Stream => (vault.get_stream_claimable(rule_id), U512::zero(), U512::zero()),
+            ActionType::Vesting => (vault.get_vesting_claimable(rule_id), U512::zero(), U512::zero()),
+            ActionType::Payroll => {
+                let fee = self.payroll_fees(&rule);
+                (rule.amount, fee, rule.amount + fee)
+            }
+            ActionType::Unstake
+            | ActionType::Rebalance
+            | ActionType::Compound
+            | ActionType::Restake
+            | ActionType::Redelegate => (rule.amount, U512::zero(), U512::zero()),
+        };
+        let is_funded = !self.is_underfunded(&rule);
+        
+        ExecutionPreview {
+            is_active,
+            is_due,
+            condition_met,
+            is_funded,
+            amount,
+            fee,
+            balance_before,
+            balance_after: balance_before.saturating_sub(outflow),
+            would_succeed: is_active && is_due && condition_met && is_funded,
+        }
+    }
+
+    /// Get the engine-wide activity counters
+    pub fn get_global_stats(&self) -> GlobalStats {
+        self.global_stats.get_or_default()
+    }
+
+    /// Get an owner's activity aggregates
+    pub fn get_user_stats(&self, owner: Address) -> UserStats {
+        let mut stats = self.user_stats.get_or_default(&owner);
+        if let Some(adapter) = self.resolved_staking_adapter() {
+            stats.total_compounded = StakingAdapterContractRef::new(self.env(), adapter)
+                .get_user_rewards(owner);
+        }
+        stats
+    }
+
+    /// Get the entries in an owner's address book
+    pub fn get_address_book(&self, owner: Address) -> Vec<AddressBookEntry> {
+        self.address_book_index
+            .get_or_default(&owner)
+            .into_iter()
+            .filter_map(|recipient| self.address_book.get(&(owner, recipient)))
+            .collect()
+    }
+
+    /// Check whether an owner's rules may only send to address book entries
+    pub fn is_address_book_only(&self, owner: Address) -> bool {
+        if !self.address_book_only.get_or_default(&owner) {
+            return false;
+        }
+        let release = self.address_book_release.get_or_default(&owner);
+        release == 0 || self.env().get_block_time() < release
+    }
+
+    /// Get the block time of an owner's last signed call to the engine
+    /// 
+    /// Deposits and withdrawals in the vault count as calls too.
+    pub fn get_last_activity(&self, owner: Address) -> u64 {
+        let vault_activity = self
+            .resolved_vault()
+            .map(|vault| {
+                AutomationVaultContractRef::new(self.env(), vault).get_last_activity(owner)
+            })
+            .unwrap_or_default();
+        self.last_activity.get_or_default(&owner).max(vault_activity)
+    }
+
+    /// Get the settings of a dead-man switch rule
+    pub fn get_inheritance_config(&self, rule_id: u64) -> Option<InheritanceConfig> {
+        self.inheritance_configs.get(&rule_id)
+    }
+
+    /// Get the spend ceiling of a manager approved by `owner` (zero if not approved)
+    pub fn get_manager_ceiling(&self, owner: Address, manager: Address) -> U512 {
+        self.managers.get_or_default(&(owner, manager))
+    }
+
+    /// Get what one of an owner's session keys may do, expired or not
+    pub fn get_session_key(
+        &self,
+        owner: Address,
+        public_key: PublicKey,
+    ) -> Option<SessionKeyScope> {
+        let session = Address::Account(public_key.to_account_hash());
+        self.session_keys.get_or_default(&(owner, session))
+    }
+
+    /// Get a rule's execution budget and how much of it has been spent
+    /// 
+    /// A zero budget means the rule is unlimited.
+    pub fn get_rule_budget(&self, rule_id: u64) -> (U512, U512) {
+        (self.rule_budgets.get_or_default(&rule_id), self.rule_spend.get_or_default(&rule_id))
+    }
+
+    /// Get the hook a rule calls around its executions
+    pub fn get_rule_hook(&self, rule_id: u64) -> Option<RuleHook> {
+        self.rule_hooks.get_or_default(&rule_id)
+    }
+
+    /// Get the nonce a keeper passes to `execute_rule_with_nonce` (the rule's execution count)
+    pub fn get_execution_nonce(&self, rule_id: u64) -> u64 {
+        match self.rule_states.get(&rule_id) {
+            Some(state) => state.execution_count as u64,
+            None => self.env().revert(Error::RuleNotFound),
+        }
+    }
+
+    /// Get the minimum time between two executions of a rule
+    pub fn get_min_execution_interval(&self, rule_id: u64) -> u64 {
+        self.min_execution_intervals.get_or_default(&rule_id)
+    }
+
+    /// Get how a scheduled rule handles runs after its grace period
+    pub fn get_missed_execution_policy(&self, rule_id: u64) -> Option<MissedExecutionConfig> {
+        self.missed_execution_configs.get_or_default(&rule_id)
+    }
+
+    /// Get how many schedule slots a rule has missed without executing
+    pub fn get_missed_executions(&self, rule_id: u64) -> u64 {
+        self.missed_executions.get_or_default(&rule_id)
+    }
+
+    /// Get the share of rewards a compounding rule donates
+    pub fn get_reward_split(&self, rule_id: u64) -> Option<RewardSplit> {
+        self.reward_splits.get_or_default(&rule_id)
+    }
+
+    /// Get the nonce an owner's next signed rule request must carry
+    pub fn get_signature_nonce(&self, owner: Address) -> u64 {
+        self.signature_nonces.get_or_default(&owner)
+    }
+
+    /// Get the bytes an owner signs to authorize `request` on this engine
+    pub fn get_rule_signing_message(&self, request: SignedRuleRequest) -> Bytes {
+        match (self.env().self_address(), request).to_bytes() {
+            Ok(message) => Bytes::from(message),
+            Err(_) => self.env().revert(Error::InvalidRuleConfig),
+        }
+    }
+
+    /// Get the recipients and dust policy of a split rule
+    pub fn get_split_config(&self, rule_id: u64) -> Option<SplitConfig> {
+        self.split_configs.get_or_default(&rule_id)
+    }
+
+    /// Get where a compounding rule sends rewards
+    pub fn get_reward_destination(&self, rule_id: u64) -> RewardDestination {
+        self.reward_destinations.get(&rule_id).unwrap_or(RewardDestination::Restake)
+    }
+
+    /// Get the hours of the day during which a rule may be executed
+    pub fn get_execution_window(&self, rule_id: u64) -> Option<ExecutionWindow> {
+        self.execution_windows.get_or_default(&rule_id)
+    }
+
+    /// Get how many upcoming executions an owner's low balance alert covers (zero = off)
+    pub fn get_low_balance_alert(&self, owner: Address) -> u32 {
+        self.low_balance_alerts.get_or_default(&owner)
+    }
+
+    /// Check whether an owner's vault reserves their rules' next executions
+    pub fn get_reservation_mode(&self, owner: Address) -> bool {
+        self.reservation_modes.get_or_default(&owner)
+    }
+
+    /// Get the vault envelope a rule spends from, if it is bound to one
+    pub fn get_rule_envelope(&self, rule_id: u64) -> Option<String> {
+        self.rule_envelopes.get_or_default(&rule_id)
+    }
+
+    /// Get how a rule pays its contract recipient, if it has one
+    pub fn get_contract_recipient(&self, rule_id: u64) -> Option<ContractRecipient> {
+        self.contract_recipients.get_or_default(&rule_id)
+    }
+
+    /// Check whether a hook contract is on the allowlist
+    pub fn is_hook_allowed(&self, hook: Address) -> bool {
+        self.allowed_hooks.get_or_default(&hook)
+    }
+
+    /// Check whether a recipient is on the blocklist
+    pub fn is_recipient_blocked(&self, recipient: Address) -> bool {
+        self.blocked_recipients.get_or_default(&recipient)
+    }
+
+    /// Get who may execute a rule
+    pub fn get_executor_policy(&self, rule_id: u64) -> ExecutorPolicy {
+        self.executor_policies.get(&rule_id).unwrap_or(ExecutorPolicy::Anyone)
+    }
+
+    /// Check whether a keeper is on an owner's executor allowlist
+    pub fn is_allowed_executor(&self, owner: Address, keeper: Address) -> bool {
+        self.allowed_executors.get_or_default(&(owner, keeper))
+    }
+
+    /// Get the bounty paid to whoever executes a rule
+    pub fn get_keeper_bounty(&self, rule_id: u64) -> U512 {
+        self.keeper_bounties.get_or_default(&rule_id)
+    }
+
+    /// Get the admin's keeper bounty bounds as (min, max)
+    pub fn get_keeper_bounty_bounds(&self) -> (U512, U512) {
+        (self.min_keeper_bounty.get_or_default(), self.max_keeper_bounty.get_or_default())
+    }
+
+    /// Get the minimum schedule interval, maximum rule amount and maximum batch size for new rules
+    pub fn get_rule_bounds(&self) -> (u64, U512, u32) {
+        let max_rules_per_batch = match self.max_rules_per_batch.get_or_default() {
+            0 => MAX_BATCH_RULES as u32,
+            limit => limit,
+        };
+        (
+            self.min_schedule_interval.get_or_default(),
+            self.max_rule_amount.get_or_default(),
+            max_rules_per_batch,
+        )
+    }
+
+    /// Get the per-rule hourly execution cap (zero for no limit)
+    pub fn get_max_executions_per_hour(&self) -> u32 {
+        self.max_executions_per_hour.get_or_default()
+    }
+
+    /// Get the layout version of the data in storage
+    pub fn get_storage_version(&self) -> u32 {
+        self.storage_version.get_or_default()
+    }
+
+    /// Get the staking adapter address
+    pub fn get_staking_adapter(&self) -> Option<Address> {
+        self.resolved_staking_adapter()
+    }
+
+    /// Get a rule's savings goal and how much it has saved towards it (zero goal = none)
+    pub fn get_savings_goal(&self, rule_id: u64) -> (U512, U512) {
+        (
+            self.savings_goals.get_or_default(&rule_id),
+            self.savings_progress.get_or_default(&rule_id),
+        )
+    }
+
+    /// Get how long an escrow rule's payments are held before they can be claimed
+    pub fn get_escrow_window(&self, rule_id: u64) -> u64 {
+        self.escrow_windows.get_or_default(&rule_id)
+    }
+
+    /// Get how long a claimable transfer rule's payments wait before the owner can reclaim them
+    /// 
+    /// Zero means the rule pays its recipient directly.
+    pub fn get_claim_window(&self, rule_id: u64) -> u64 {
+        self.claim_windows.get_or_default(&rule_id)
+    }
+
+    /// Get the payload hash a rule's executions request notifications with, if any
+    pub fn get_rule_notification(&self, rule_id: u64) -> Option<[u8; 32]> {
+        self.rule_notifications.get_or_default(&rule_id)
+    }
+
+    /// Get the recipients, amounts and failure policy of a payroll rule
+    pub fn get_payroll_config(&self, rule_id: u64) -> Option<PayrollConfig> {
+        self.payroll_configs.get(&rule_id)
+    }
+
+    /// Get how a rule's amount is worked out, with its basis points for percentages
+    pub fn get_amount_spec(&self, rule_id: u64) -> (AmountSpec, u16) {
+        self.amount_specs.get(&rule_id).unwrap_or((AmountSpec::Fixed, 0))
+    }
+
+    /// Get the validator a restake rule delegates harvested rewards to
+    pub fn get_restake_destination(&self, rule_id: u64) -> Option<PublicKey> {
+        self.restake_destinations.get(&rule_id)
+    }
+
+    /// Get a swap rule's token, direction and slippage limit
+    pub fn get_swap_config(&self, rule_id: u64) -> Option<SwapConfig> {
+        self.swap_configs.get(&rule_id)
+    }
+
+    /// Get the least output each execution of a swap rule accepts
+    pub fn get_swap_min_out(&self, rule_id: u64) -> U512 {
+        self.swap_min_outs.get_or_default(&rule_id)
+    }
+
+    /// Get the balance a top-up rule keeps its recipient's vault at
+    pub fn get_top_up_target(&self, rule_id: u64) -> Option<U512> {
+        self.top_up_targets.get(&rule_id)
+    }
+
+    /// Get the constraints of a rebalance rule
+    pub fn get_rebalance_constraints(&self, rule_id: u64) -> Option<RebalanceConstraints> {
+        self.rebalance_constraints.get(&rule_id)
+    }
+
+    /// Get the validator a stake rule delegates to
+    pub fn get_rule_validator(&self, rule_id: u64) -> Option<PublicKey> {
+        self.rule_configs.get(&rule_id).and_then(|config| config.validator)
+    }
+
+    /// Get the CSPR/USD price oracle address
+    pub fn get_price_oracle(&self) -> Option<Address> {
+        self.resolved_price_oracle()
+    }
+
+    /// Get the attestation oracle address
+    pub fn get_attestation_oracle(&self) -> Option<Address> {
+        self.resolved_attestation_oracle()
+    }
+
+    /// Whether the account holds the role (the admin holds every role)
+    pub fn has_role(&self, role: Role, account: Address) -> bool {
+        self.roles.holds(self.admin.get(), role, account)
+    }
+
+    /// Whether a pauser has halted rule execution
+    pub fn is_execution_paused(&self) -> bool {
+        self.execution_paused.get_or_default()
+    }
+
+    /// Get the admin timelock delay in seconds (zero when disabled)
+    pub fn get_admin_timelock_delay(&self) -> u64 {
+        self.timelock.delay()
+    }
+
+    /// Get a queued admin change by ID
+    pub fn get_admin_action(&self, action_id: u64) -> Option<AdminAction> {
+        self.timelock.get(action_id)
+    }
+
+    /// Get every queued admin change with its ID, oldest first
+    pub fn get_pending_admin_actions(&self) -> Vec<(u64, AdminAction)> {
+        self.timelock.pending()
+    }
+
+    /// Get the period lengths and delays the engine schedules with
+    pub fn get_time_config(&self) -> TimeConfig {
+        self.time_config.get_or_default()
+    }
+
+    /// Get the maximum age of an oracle price in seconds
+    pub fn get_max_price_age(&self) -> u64 {
+        self.max_price_age.get_or_default()
+    }
+
+    /// Get how long after deletion a rule can still be restored, in seconds
+    pub fn get_restore_grace_period(&self) -> u64 {
+        self.restore_grace_period.get_or_default()
+    }
+
+    /// Get a rule template by ID
+    pub fn get_template(&self, template_id: u32) -> Option<RuleTemplate> {
+        self.templates.get(template_id)
+    }
+
+    /// Get the number of registered rule templates
+    pub fn get_template_count(&self) -> u32 {
+        self.templates.count()
+    }
+
+    /// Get the follow-up rules executed after a rule
+    pub fn get_follow_up_rules(&self, rule_id: u64) -> Vec<u64> {
+        self.follow_up_rules.get_or_default(&rule_id)
+    }
+
+    /// Get up to `limit` of a rule's most recent executions, newest first
+    /// 
+    /// Only the last 16 executions are retained per rule.
+    pub fn get_execution_history(&self, rule_id: u64, limit: u32) -> Vec<ExecutionRecord> {
+        let len = self.execution_history_len.get_or_default(&rule_id);
+        let count = limit.min(len).min(EXECUTION_HISTORY_SIZE);
+        let mut records = Vec::new();
+        for i in 0..count {
+            let slot = (len - 1 - i) % EXECUTION_HISTORY_SIZE;
+            if let Some(record) = self.execution_history.get(&(rule_id, slot)) {
+                records.push(record);
+            }
+        }
+        records
+    }
+
+    /// Get the user's current tier
+    /// 
+    /// Everyone gets Starter tier for MVP (sCSPR balances are not read yet);
+    /// holders of the membership NFT collection get at least its perk tier.
+    pub fn get_user_tier(&self, owner: Address) -> StakingTier {
+        let base = StakingTier::Starter;
+        match self.held_nft_perks(owner) {
+            Some(perks) if perks.tier.max_rules() > base.max_rules() => perks.tier,
+            _ => base,
+        }
+    }
+
+    /// Get the membership NFT perks configuration
+    pub fn get_nft_perks(&self) -> Option<NftPerks> {
+        self.nft_perks.get_or_default()
+    }
+
+    /// Get a user's effective tier and fee perks
+    pub fn get_user_perks(&self, owner: Address) -> UserPerks {
+        let tier = self.get_user_tier(owner);
+        let fee_exempt = self.is_fee_exempt(tier.clone());
+        let nft_perks = self.held_nft_perks(owner);
+        let fee_discount_bps = match &nft_perks {
+            Some(perks) if !fee_exempt => perks.fee_discount_bps,
+            _ => 0,
+        };
+        UserPerks {
+            tier,
+            nft_holder: nft_perks.is_some(),
+            fee_exempt,
+            fee_discount_bps,
+        }
+    }
+
+    /// Get the number of a user's rules counting towards their tier limit (active and paused)
+    pub fn get_user_rule_count(&self, owner: Address) -> u32 {
+        let (active, paused, _) = self.get_user_rule_counts(owner);
+        active + paused
+    }
+
+    /// Get how many of a user's rules are active, paused and deleted
+    /// 
+    /// Rules awaiting confirmation count as active. Completed one-shot
+    /// rules and purged rules are not counted.
+    pub fn get_user_rule_counts(&self, owner: Address) -> (u32, u32, u32) {
+        let count = |status: RuleStatus| self.rule_status_counts.get_or_default(&(owner, status));
+        (
+            count(RuleStatus::Active) + count(RuleStatus::PendingApproval),
+            count(RuleStatus::Paused),
+            count(RuleStatus::Deleted),
+        )
+    }
+
+    /// Get the vault address
+    pub fn get_vault_address(&self) -> Option<Address> {
+        self.resolved_vault()
+    }
+
+    /// Get the contract registry the engine resolves its peers from
+    pub fn get_registry(&self) -> Option<Address> {
+        self.registry.get_or_default()
+    }
+
+    /// Get the admin address
+    pub fn get_admin(&self) -> Option<Address> {
+        self.admin.get()
+    }
+
+    /// Get the kind and interface version of this contract
+    pub fn get_version(&self) -> ContractVersion {
+        ContractVersion { kind: ContractKind::Engine, version: INTERFACE_VERSION }
+    }
+
+    /// Get the nonce of the most recently emitted event
+    pub fn get_event_nonce(&self) -> u64 {
+        self.event_nonce.current()
+    }
+
+    /// Get a checkpoint of the engine's state for indexers resuming after downtime
+    /// 
+    /// Returns the next rule ID, the total successful executions, the nonce
+    /// of the last event and the current block time, all read in one call.
+    /// An indexer that has seen fewer events than the nonce, or fewer rules
+    /// or executions than counted here, has missed some.
+    pub fn get_state_checkpoint(&self) -> (u64, u64, u64, u64) {
+        (
+            self.next_rule_id.get_or_default(),
+            self.get_global_stats().total_executions,
+            self.event_nonce.current(),
+            self.env().get_block_time(),
+        )
+    }
+
+    /// Get the protocol fee configuration
+    pub fn get_fee_config(&self) -> FeeConfig {
+        self.fee_config.get_or_default()
+    }
+
+    /// Check whether a tier is exempt from protocol fees
+    pub fn is_fee_exempt(&self, tier: StakingTier) -> bool {
+        self.fee_exempt_tiers.get_or_default(&tier)
+    }
+
+    /// Get the fee `owner` would pay on an automated transfer of `amount`
+    pub fn quote_fee(&self, owner: Address, amount: U512) -> U512 {
+        self.effective_fee_config(owner).fee_for(amount)
     }
 
     // ========================================================================
-    // View Functions
+    // Internal Functions
     // ========================================================================
 
-    /// Get a rule by ID
-    pub fn get_rule(&self, rule_id: u64) -> Option<AutomationRule> {
-        self.rules.get(&rule_id)
+    /// Revert unless the caller is the admin
+    fn assert_admin(&self) {
+        self.roles.assert_admin(self.admin.get());
+    }
+
+    /// Revert unless the caller is the admin or has been granted `role`
+    fn assert_role(&self, role: Role) {
+        self.roles.assert_role(self.admin.get(), role);
+    }
+
+    /// ID of the execution in progress, passed to the vault with each payment
+    fn current_execution_id(&self) -> u64 {
+        self.last_execution_id.get_or_default()
+    }
+
+    /// Revert if the admin timelock is enabled, so a change must be queued
+    fn assert_no_timelock(&self) {
+        if self.timelock.delay() > 0 {
+            self.env().revert(Error::TimelockRequired);
+        }
+    }
+
+    /// Revert if a fee rate is above the 10% cap
+    fn assert_fee_config(&self, fee_bps: u16) {
+        if fee_bps > FeeConfig::MAX_FEE_BPS {
+            self.env().revert(Error::InvalidFeeConfig);
+        }
+    }
+
+    /// Store a new protocol fee and emit FeeConfigUpdated
+    fn apply_fee_config(&mut self, fee_bps: u16, flat_fee: U512) {
+        self.fee_config.set(FeeConfig { fee_bps, flat_fee });
+        
+        self.env().emit_event(FeeConfigUpdated {
+            fee_bps,
+            flat_fee,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Point the engine at a staking adapter and emit StakingAdapterUpdated
+    fn apply_staking_adapter(&mut self, adapter: Address) {
+        self.staking_adapter.set(Some(adapter));
+        self.env().emit_event(StakingAdapterUpdated {
+            adapter,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Queue an admin change in the timelock and emit AdminActionQueued
+    fn queue_admin_action(&mut self, action: AdminAction) -> u64 {
+        let (action_id, action) = self.timelock.queue(action);
+        self.env().emit_event(AdminActionQueued {
+            action_id,
+            action,
+            nonce: self.event_nonce.next(),
+        });
+        action_id
+    }
+
+    /// Revert unless the rule's executor policy allows the caller
+    fn check_executor(&self, rule_id: u64, owner: Address) {
+        let caller = self.env().caller();
+        if caller == owner {
+            return;
+        }
+        let allowed = match self.get_executor_policy(rule_id) {
+            ExecutorPolicy::Anyone => true,
+            ExecutorPolicy::OwnerOnly => false,
+            ExecutorPolicy::AllowlistedKeepers => self.is_allowed_executor(owner, caller),
+        };
+        if !allowed {
+            self.env().revert(Error::ExecutorNotAllowed);
+        }
+    }
+
+    /// Pay the rule's bounty to the caller, unless the owner or their session key ran it
+    fn pay_keeper_bounty(&mut self, rule_id: u64, owner: Address) {
+        let keeper = self.env().caller();
+        let bounty = self.keeper_bounties.get_or_default(&rule_id);
+        if bounty.is_zero()
+            || keeper == owner
+            || self.session_keys.get_or_default(&(owner, keeper)).is_some()
+            || !self.managers.get_or_default(&(owner, keeper)).is_zero()
+        {
+            return;
+        }
+        self.vault_or_revert().pay_keeper(owner, keeper, bounty, rule_id);
+        self.charge_budget(rule_id, bounty);
+    }
+
+    /// Call the rule's hook before or after its action, if it has an allowlisted one
+    fn call_hook(&mut self, rule: &AutomationRule, amount: U512, before: bool) {
+        let hook = match self.rule_hooks.get_or_default(&rule.id) {
+            Some(hook) if (before && hook.before) || (!before && hook.after) => hook,
+            _ => return,
+        };
+        if !self.allowed_hooks.get_or_default(&hook.contract) {
+            return;
+        }
+        
+        self.hook_running.set(true);
+        let mut contract = ExecutionHookContractRef::new(self.env(), hook.contract);
+        if before {
+            contract.before_execution(rule.id, rule.owner, amount);
+        } else {
+            contract.after_execution(rule.id, rule.owner, amount);
+        }
+        self.hook_running.set(false);
+    }
+
+    /// Whether a rule with a budget has spent it, or has too little left for another execution
+    fn budget_exhausted(&self, rule: &AutomationRule) -> bool {
+        let budget = self.rule_budgets.get_or_default(&rule.id);
+        let spent = self.rule_spend.get_or_default(&rule.id);
+        !budget.is_zero() && (spent >= budget || spent + self.budget_cost(rule) > budget)
+    }
+
+    /// What an execution of a rule is expected to take from the owner's CSPR balance
+    /// 
+    /// Zero for rules whose cost is only known once they run, such as sweeps.
+    fn budget_cost(&self, rule: &AutomationRule) -> U512 {
+        let bounty = self.keeper_bounties.get_or_default(&rule.id);
+        let cost = match rule.action_type {
+            _ if rule.token.is_some() => U512::zero(),
+            ActionType::Transfer
+            | ActionType::Split
+            | ActionType::StakeFromVault
+            | ActionType::Swap
+            | ActionType::Escrow => {
+                let amount = self.resolve_amount(rule);
+                amount + self.quote_fee(rule.owner, amount)
+            }
+            ActionType::Payroll => rule.amount + self.payroll_fees(rule),
+            _ => U512::zero(),
+        };
+        cost + bounty
+    }
+
+    /// Add to a budgeted rule's spend, pausing the rule once the budget is used up
+    /// 
+    /// Reverts if the spend would overshoot the budget, so nothing is paid past it.
+    fn charge_budget(&mut self, rule_id: u64, amount: U512) {
+        let budget = self.rule_budgets.get_or_default(&rule_id);
+        if budget.is_zero() {
+            return;
+        }
+        let spent = self.rule_spend.get_or_default(&rule_id) + amount;
+        if spent > budget {
+            self.env().revert(Error::BudgetExhausted);
+        }
+        self.rule_spend.set(&rule_id, spent);
+        
+        let rule = self.get_rule_or_revert(rule_id);
+        if matches!(rule.status, RuleStatus::Active) && self.budget_exhausted(&rule) {
+            self.pause_for_budget(rule);
+        }
+    }
+
+    /// Pause a rule whose budget is used up and emit BudgetExhausted
+    fn pause_for_budget(&mut self, mut rule: AutomationRule) {
+        let rule_id = rule.id;
+        self.set_rule_status(&mut rule, RuleStatus::Paused);
+        self.rule_states.set(&rule_id, rule.state());
+        self.sync_reservation(rule.owner, rule_id);
+        self.env().emit_event(BudgetExhausted {
+            rule_id,
+            owner: rule.owner,
+            budget: self.rule_budgets.get_or_default(&rule_id),
+            spent: self.rule_spend.get_or_default(&rule_id),
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// What is left of a rule's savings goal, or None if it has no goal
+    fn goal_remaining(&self, rule_id: u64) -> Option<U512> {
+        let target = self.savings_goals.get_or_default(&rule_id);
+        if target.is_zero() {
+            return None;
+        }
+        Some(target.saturating_sub(self.savings_progress.get_or_default(&rule_id)))
+    }
+
+    /// Whether a rule has met its savings goal
+    fn goal_reached(&self, rule_id: u64) -> bool {
+        self.goal_remaining(rule_id).is_some_and(|remaining| remaining.is_zero())
+    }
+
+    /// Add to a savings goal rule's progress, pausing the rule once the goal is met
+    fn record_savings(&mut self, rule_id: u64, amount: U512) {
+        let target = self.savings_goals.get_or_default(&rule_id);
+        if target.is_zero() {
+            return;
+        }
+        let saved = self.savings_progress.get_or_default(&rule_id) + amount;
+        self.savings_progress.set(&rule_id, saved);
+        self.pause_reached_goal(rule_id, target, saved);
+    }
+
+    /// Pause an active rule and emit GoalReached if `saved` meets its goal `target`
+    fn pause_reached_goal(&mut self, rule_id: u64, target: U512, saved: U512) {
+        if saved < target {
+            return;
+        }
+        
+        let mut rule = self.get_rule_or_revert(rule_id);
+        if !matches!(rule.status, RuleStatus::Active) {
+            return;
+        }
+        let owner = rule.owner;
+        self.set_rule_status(&mut rule, RuleStatus::Paused);
+        self.rule_states.set(&rule_id, rule.state());
+        self.sync_reservation(owner, rule_id);
+        self.env().emit_event(GoalReached {
+            rule_id,
+            owner,
+            target,
+            saved,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Count a rule execution against the hourly cap, reverting once it is used up
+    fn check_rate_limit(&mut self, rule_id: u64, current_time: u64) {
+        let limit = self.max_executions_per_hour.get_or_default();
+        if limit == 0 {
+            return;
+        }
+        
+        let hour = current_time / SECONDS_PER_HOUR;
+        let count = match self.hourly_executions.get(&rule_id) {
+            Some((window, count)) if window == hour => count,
+            _ => 0,
+        };
+        if count >= limit {
+            self.env().revert(Error::RateLimitExceeded);
+        }
+        self.hourly_executions.set(&rule_id, (hour, count + 1));
+    }
+
+    /// Revert if user-supplied rule metadata is over the size limit
+    fn assert_metadata_len(&self, metadata: &Option<Bytes>) {
+        if metadata.as_ref().is_some_and(|metadata| metadata.len() > MAX_RULE_METADATA_LEN) {
+            self.env().revert(Error::MetadataTooLong);
+        }
+    }
+
+    /// Revert unless a swap minimum is non-zero and fits the CEP-18 U256 range
+    fn assert_swap_min_out(&self, min_out: U512) {
+        if min_out.is_zero() || to_token_amount(min_out).is_none() {
+            self.env().revert(Error::InvalidRuleConfig);
+        }
+    }
+
+    /// Revert while storage is still on an older layout version
+    fn assert_storage_current(&self) {
+        if self.storage_version.get_or_default() != STORAGE_VERSION {
+            self.env().revert(Error::MigrationPending);
+        }
+    }
+
+    /// Convert a rule stored under `from_version` to the next layout version
+    /// 
+    /// The hook for rule layout changes: each version bump adds an arm that
+    /// fills in or reshapes the fields it introduced.
+    fn migrate_rule(&mut self, from_version: u32, rule_id: u64) {
+        match from_version {
+            // Version 1 split the first release's whole-rule records into config and state
+            0 => {
+                let baseline_rules: Mapping<u64, BaselineRule> =
+                    ModuleComponent::instance(self.env(), BASELINE_RULES_INDEX);
+                if let Some(rule) = baseline_rules.get(&rule_id) {
+                    let owner = rule.owner;
+                    let (config, state) = rule.into_records();
+                    self.legacy_rule_configs.set(&rule_id, config);
+                    self.rule_states.set(&rule_id, state);
+                    let local_index = self.next_local_indices.get_or_default(&owner);
+                    self.local_rule_ids.set(&(owner, local_index), rule_id);
+                    self.rule_local_indices.set(&rule_id, local_index);
+                    self.next_local_indices.set(&owner, local_index + 1);
+                }
+            }
+            // Version 2 replaced the inlined template name with an interned ID
+            1 => {
+                if let Some(legacy) = self.legacy_rule_configs.get(&rule_id) {
+                    let template_name_id = self.intern_template_name(legacy.template_name.clone());
+                    self.rule_configs.set(&rule_id, legacy.into_config(template_name_id));
+                }
+            }
+            // Version 3 counts each owner's rules by status
+            2 => {
+                if let Some(rule) = self.load_rule(rule_id) {
+                    self.user_rule_count.set(&rule.owner, 0);
+                    self.shift_status_count(rule.owner, None, Some(rule.status));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Get the ID of a template name, storing the name if it is new
+    fn intern_template_name(&mut self, template_name: String) -> u32 {
+        if let Some(template_name_id) = self.template_name_ids.get(&template_name) {
+            return template_name_id;
+        }
+        let template_name_id = self.next_template_name_id.get_or_default();
+        self.next_template_name_id.set(template_name_id + 1);
+        self.template_names.set(&template_name_id, template_name.clone());
+        self.template_name_ids.set(&template_name, template_name_id);
+        template_name_id
+    }
+
+    /// Get the IDs of an owner's rules that have not been purged, oldest first
+    /// 
+    /// Walks the per-owner indices, so creating a rule never rewrites a
+    /// list that grows with the owner's rule history. Rules created before
+    /// the indices existed are resolved from the owner's legacy rule list.
+    fn owner_rule_ids(&self, owner: Address) -> Vec<u64> {
+        let legacy_rule_ids = self.user_rules.get_or_default(&owner);
+        let count = self
+            .next_local_indices
+            .get(&owner)
+            .unwrap_or(legacy_rule_ids.len() as u32);
+        (0..count)
+            .filter_map(|local_index| {
+                self.local_rule_ids
+                    .get(&(owner, local_index))
+                    .or_else(|| legacy_rule_ids.get(local_index as usize).copied())
+            })
+            .filter(|rule_id| !self.purged_rules.get_or_default(rule_id))
+            .collect()
+    }
+
+    /// Record a signed call by `owner`, keeping their dead-man switch rules from firing
+    fn record_activity(&mut self, owner: Address) {
+        self.last_activity.set(&owner, self.env().get_block_time());
+    }
+
+    /// Revert if `recipient` is blocklisted, or the owner restricts recipients to
+    /// active address book entries and it is not one
+    fn check_address_book(&self, owner: Address, recipient: Option<Address>) {
+        if let Some(recipient) = recipient {
+            self.check_not_blocked(recipient);
+        }
+        if !self.recipient_allowed(owner, recipient) {
+            self.env().revert(Error::RecipientNotInAddressBook);
+        }
+    }
+
+    /// Revert if `recipient` is on the admin's blocklist
+    fn check_not_blocked(&self, recipient: Address) {
+        if self.blocked_recipients.get_or_default(&recipient) {
+            self.env().revert(Error::RecipientBlocked);
+        }
+    }
+
+    /// Whether the owner's address book restriction allows paying `recipient`
+    fn recipient_allowed(&self, owner: Address, recipient: Option<Address>) -> bool {
+        let recipient = match recipient {
+            Some(recipient) if self.is_address_book_only(owner) => recipient,
+            _ => return true,
+        };
+        match self.address_book.get(&(owner, recipient)) {
+            Some(entry) => !entry.removed && self.env().get_block_time() >= entry.active_at,
+            None => false,
+        }
+    }
+
+    /// Revert unless the owner has been inactive for the rule's inactivity period
+    fn check_inactivity(&self, rule: &AutomationRule, current_time: u64) {
+        if self.inheritance_configs.get(&rule.id).is_none() {
+            self.env().revert(Error::InvalidRuleConfig);
+        }
+        if !self.owner_inactive(rule, current_time) {
+            self.env().revert(Error::OwnerStillActive);
+        }
+    }
+
+    /// Whether the owner has been inactive for a dead-man switch rule's inactivity period
+    fn owner_inactive(&self, rule: &AutomationRule, current_time: u64) -> bool {
+        match self.inheritance_configs.get(&rule.id) {
+            Some(config) => {
+                let last_activity = self.get_last_activity(rule.owner);
+                current_time.saturating_sub(last_activity) >= config.inactivity_period
+            }
+            None => false,
+        }
+    }
+
+    /// The fee config that applies to `owner`, which is zero if their tier is exempt
+    fn effective_fee_config(&self, owner: Address) -> FeeConfig {
+        if self.is_fee_exempt(self.get_user_tier(owner)) {
+            return FeeConfig::default();
+        }
+        match self.held_nft_perks(owner) {
+            Some(perks) => self.get_fee_config().discounted(perks.fee_discount_bps),
+            None => self.get_fee_config(),
+        }
+    }
+
+    /// The membership NFT perks, if configured and `owner` holds the collection
+    fn held_nft_perks(&self, owner: Address) -> Option<NftPerks> {
+        let perks = self.nft_perks.get_or_default()?;
+        let holdings = Cep78NftContractRef::new(self.env(), perks.collection).balance_of(owner);
+        (holdings > 0).then_some(perks)
+    }
+
+    /// Whether `caller` holds an unexpired session key of `owner` whose scope passes `allowed`
+    fn session_key_allows(
+        &self,
+        owner: Address,
+        caller: Address,
+        allowed: impl Fn(&SessionKeyScope) -> bool,
+    ) -> bool {
+        match self.session_keys.get_or_default(&(owner, caller)) {
+            Some(scope) => scope.expires_at > self.env().get_block_time() && allowed(&scope),
+            None => false,
+        }
+    }
+
+    /// Revert unless the caller is the owner or one of the owner's approved managers
+    fn assert_owner_or_manager(&self, owner: Address, caller: Address) {
+        if caller != owner && self.managers.get_or_default(&(owner, caller)).is_zero() {
+            self.env().revert(Error::NotRuleManager);
+        }
+    }
+
+    /// Validate and store a rule that needs no setup beyond the rule itself
+    fn create_standard_rule(
+        &mut self,
+        owner: Address,
+        template_name: String,
+        trigger_type: TriggerType,
+        schedule: Schedule,
+        action_type: ActionType,
+        recipient: Option<Address>,
+        amount: U512,
+        token: Option<Address>,
+        validator: Option<PublicKey>,
+        metadata: Option<Bytes>,
+        first_execution: Option<u64>,
+    ) -> u64 {
+        let current_time = self.env().get_block_time();
+        self.assert_metadata_len(&metadata);
+        
+        // Token amounts must fit the CEP-18 U256 range
+        if token.is_some() && to_token_amount(amount).is_none() {
+            self.env().revert(Error::InvalidRuleConfig);
+        }
+        
+        // Sweeps move CSPR only and need somewhere to send it
+        if matches!(action_type, ActionType::Sweep) && (token.is_some() || recipient.is_none()) {
+            self.env().revert(Error::InvalidRuleConfig);
+        }
+        
+        // Only compounding picks a validator here; stake rules have their own entrypoint
+        if let Some(validator) = &validator {
+            if !matches!(action_type, ActionType::Compound) {
+                self.env().revert(Error::InvalidRuleConfig);
+            }
+            self.assert_validator_allowed(validator);
+        }
+        
+        // Stream, vesting, stake, rebalance, top-up, swap, restake, payroll, escrow,
+        // redelegation and inheritance rules carry extra parameters and have their own
+        // creation entrypoints
+        if matches!(
+            action_type,
+            ActionType::Stream
+                | ActionType::Vesting
+                | ActionType::StakeFromVault
+                | ActionType::Rebalance
+                | ActionType::TopUp
+                | ActionType::Swap
+                | ActionType::Restake
+                | ActionType::Payroll
+                | ActionType::Escrow
+                | ActionType::Redelegate
+                | ActionType::Inheritance
+        ) {
+            self.env().revert(Error::InvalidRuleConfig);
+        }
+        
+        // Check tier limits and generate rule ID
+        let rule_id = self.allocate_rule_id(owner);
+        
+        // Start at the requested time, or one interval from now
+        let next_execution = match first_execution {
+            Some(time) if time < current_time => self.env().revert(Error::FirstExecutionInPast),
+            Some(time) => time,
+            None => self.calculate_next_execution(current_time, &schedule),
+        };
+        
+        // Create and store rule
+        let mut rule = AutomationRule::new(
+            rule_id,
+            owner,
+            template_name,
+            trigger_type,
+            schedule,
+            action_type,
+            recipient,
+            amount,
+            token,
+            next_execution,
+        );
+        rule.validator = validator;
+        rule.metadata = metadata;
+        self.store_new_rule(rule);
+        
+        rule_id
+    }
+
+    /// Mark a rule paused and emit RulePaused; callers check ownership and status
+    fn pause_unchecked(&mut self, mut rule: AutomationRule) {
+        self.set_rule_status(&mut rule, RuleStatus::Paused);
+        self.rule_states.set(&rule.id, rule.state());
+        self.sync_reservation(rule.owner, rule.id);
+        
+        self.env().emit_event(RulePaused {
+            rule_id: rule.id,
+            owner: rule.owner,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Reactivate and reschedule a rule and emit RuleResumed; callers check ownership and status
+    fn resume_unchecked(&mut self, mut rule: AutomationRule) {
+        let current_time = self.env().get_block_time();
+        self.set_rule_status(&mut rule, RuleStatus::Active);
+        rule.next_execution = self.calculate_next_execution(current_time, &rule.schedule);
+        self.rule_states.set(&rule.id, rule.state());
+        self.sync_reservation(rule.owner, rule.id);
+        
+        self.env().emit_event(RuleResumed {
+            rule_id: rule.id,
+            owner: rule.owner,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Mark a rule's attestation condition as cleared with an empty key, which the setter rejects
+    fn clear_attestation(&mut self, rule_id: u64) {
+        self.attestation_conditions.set(&rule_id, AttestationCondition {
+            key: String::new(),
+            comparison: Comparison::AtLeast,
+            threshold: U512::zero(),
+            max_age: 0,
+        });
+    }
+
+    /// Clear a deleted rule's settings and hide it from views
+    /// 
+    /// Every per-rule setting with an empty value is reset. The configs some
+    /// rule types write once at creation (payroll, swap, inheritance and the
+    /// like) have none and stay, but purged IDs are never reused and no view
+    /// returns the rule again.
+    fn purge_rule(&mut self, rule: AutomationRule) {
+        let rule_id = rule.id;
+        self.purged_rules.set(&rule_id, true);
+        self.rule_metadata.set(&rule_id, Bytes::default());
+        self.rule_tags.set(&rule_id, Vec::new());
+        for follow_up_id in self.follow_up_rules.get_or_default(&rule_id) {
+            self.unlink_workflow_parent(follow_up_id, rule_id);
+        }
+        self.follow_up_rules.set(&rule_id, Vec::new());
+        self.workflow_parents.set(&rule_id, Vec::new());
+        self.execution_history_len.set(&rule_id, 0);
+        self.contract_recipients.set(&rule_id, None);
+        self.vault_recipients.set(&rule_id, false);
+        self.claim_windows.set(&rule_id, 0);
+        self.rule_envelopes.set(&rule_id, None);
+        self.execution_windows.set(&rule_id, None);
+        self.rule_hooks.set(&rule_id, None);
+        self.missed_execution_configs.set(&rule_id, None);
+        self.rule_notifications.set(&rule_id, None);
+        self.rule_priorities.set(&rule_id, None);
+        self.savings_goals.set(&rule_id, U512::zero());
+        self.savings_progress.set(&rule_id, U512::zero());
+        self.reward_splits.set(&rule_id, None);
+        self.split_configs.set(&rule_id, None);
+        self.escrow_windows.set(&rule_id, 0);
+        self.rewards_conditions.set(&rule_id, U512::zero());
+        self.clear_attestation(rule_id);
+        self.confirmable_at.set(&rule_id, 0);
+        self.top_up_targets.set(&rule_id, U512::zero());
+        self.hourly_executions.set(&rule_id, (0, 0));
+        self.keeper_bounties.set(&rule_id, U512::zero());
+        self.rule_budgets.set(&rule_id, U512::zero());
+        self.rule_spend.set(&rule_id, U512::zero());
+        self.min_execution_intervals.set(&rule_id, 0);
+        self.missed_executions.set(&rule_id, 0);
+        self.deleted_at.set(&rule_id, 0);
+        self.pending_streams.set(&rule_id, None);
+        self.pending_vestings.set(&rule_id, None);
+        self.swap_min_outs.set(&rule_id, U512::zero());
+        if self.status_counted(rule_id) {
+            self.shift_status_count(rule.owner, Some(rule.status.clone()), None);
+        }
+        
+        self.env().emit_event(RulePurged {
+            rule_id,
+            owner: rule.owner,
+            execution_count: rule.execution_count,
+            last_executed: rule.last_executed,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Mark a rule deleted, releasing its tier slot, and emit RuleDeleted; callers check ownership
+    fn delete_unchecked(&mut self, mut rule: AutomationRule) {
+        let rule_id = rule.id;
+        let owner = rule.owner;
+        
+        // Cancel any running stream or vesting and refund the remainder; one
+        // still waiting for confirmation has nothing escrowed yet
+        let unescrowed = self.pending_streams.get_or_default(&rule_id).is_some()
+            || self.pending_vestings.get_or_default(&rule_id).is_some();
+        if unescrowed {
+            self.pending_streams.set(&rule_id, None);
+            self.pending_vestings.set(&rule_id, None);
+        } else if !matches!(rule.status, RuleStatus::Deleted) {
+            match rule.action_type {
+                ActionType::Stream => self.vault_or_revert().cancel_stream(rule_id),
+                ActionType::Vesting => self.vault_or_revert().revoke_vesting(rule_id),
+                _ => {}
+            }
+        }
+        
+        // Mark as deleted, remembering how to restore it
+        if rule.status != RuleStatus::Deleted {
+            self.deleted_at.set(&rule_id, self.env().get_block_time());
+            self.status_before_delete.set(&rule_id, rule.status.clone());
+        }
+        self.set_rule_status(&mut rule, RuleStatus::Deleted);
+        self.rule_states.set(&rule_id, rule.state());
+        self.sync_reservation(owner, rule_id);
+        
+        self.env().emit_event(RuleDeleted {
+            rule_id,
+            owner,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Reserve what a rule's next execution needs in its owner's vault, if they opted in
+    fn sync_reservation(&mut self, owner: Address, rule_id: u64) {
+        if !self.reservation_modes.get_or_default(&owner) {
+            return;
+        }
+        let rule = self.get_rule_or_revert(rule_id);
+        let amount = self.reservation_for(&rule);
+        self.vault_or_revert().set_reservation(owner, rule_id, amount);
+    }
+
+    /// The amount and fee of a rule's next execution, or zero if it reserves nothing
+    fn reservation_for(&self, rule: &AutomationRule) -> U512 {
+        if rule.status != RuleStatus::Active
+            || rule.token.is_some()
+            || self.rule_envelopes.get_or_default(&rule.id).is_some()
+        {
+            return U512::zero();
+        }
+        match rule.action_type {
+            ActionType::Transfer
+            | ActionType::Split
+            | ActionType::StakeFromVault
+            | ActionType::Escrow => {
+                let amount = self.resolve_amount(rule);
+                amount + self.quote_fee(rule.owner, amount)
+            }
+            ActionType::Payroll => rule.amount + self.payroll_fees(rule),
+            _ => U512::zero(),
+        }
+    }
+
+    /// A rule's chosen keeper priority, or its action's default
+    /// 
+    /// Payroll comes first, then other payments to people, then staking
+    /// and other housekeeping.
+    fn priority_of(&self, rule: &AutomationRule) -> u8 {
+        if let Some(priority) = self.rule_priorities.get_or_default(&rule.id) {
+            return priority;
+        }
+        match rule.action_type {
+            ActionType::Payroll => 200,
+            ActionType::Transfer
+            | ActionType::Split
+            | ActionType::Escrow
+            | ActionType::TopUp
+            | ActionType::Inheritance => 150,
+            _ => 100,
+        }
+    }
+
+    /// Move a rule to `status`, keeping its owner's per-status rule counts in step
+    /// 
+    /// Every status change after creation goes through here. Setting the
+    /// status a rule already has changes nothing, so repeated deletes
+    /// cannot free a second slot. Callers store the rule's state.
+    fn set_rule_status(&mut self, rule: &mut AutomationRule, status: RuleStatus) {
+        if rule.status == status {
+            return;
+        }
+        if self.status_counted(rule.id) {
+            self.shift_status_count(rule.owner, Some(rule.status.clone()), Some(status.clone()));
+        }
+        rule.status = status;
+    }
+
+    /// Move one of an owner's rules from one status count to another (`None` = uncounted)
+    fn shift_status_count(
+        &mut self,
+        owner: Address,
+        from: Option<RuleStatus>,
+        to: Option<RuleStatus>,
+    ) {
+        if let Some(from) = from {
+            let count = self.rule_status_counts.get_or_default(&(owner, from.clone()));
+            self.rule_status_counts.set(&(owner, from), count.saturating_sub(1));
+        }
+        if let Some(to) = to {
+            let count = self.rule_status_counts.get_or_default(&(owner, to.clone()));
+            self.rule_status_counts.set(&(owner, to), count + 1);
+        }
+    }
+
+    /// Whether a rule's status is already reflected in its owner's status counts
+    /// 
+    /// While the migration introducing the counts runs, only the rules it
+    /// has passed are counted; it counts the rest with their final status.
+    fn status_counted(&self, rule_id: u64) -> bool {
+        let version = self.storage_version.get_or_default();
+        version >= STATUS_COUNTS_VERSION
+            || (version + 1 == STATUS_COUNTS_VERSION
+                && rule_id < self.migration_cursor.get_or_default())
+    }
+
+    /// Check the caller's tier limit and generate a new rule ID
+    fn allocate_rule_id(&mut self, owner: Address) -> u64 {
+        let current_count = self.get_user_rule_count(owner);
+        let tier = self.get_user_tier(owner);
+        if current_count >= tier.max_rules() {
+            self.env().revert(Error::MaxRulesReached);
+        }
+        
+        let rule_id = self.next_rule_id.get_or_default();
+        self.next_rule_id.set(rule_id + 1);
+        rule_id
+    }
+
+    /// Whether a new rule of `owner`'s moving `amount` must wait for their confirmation
+    fn needs_approval(&self, owner: Address, amount: U512) -> bool {
+        let threshold = self.get_approval_threshold(owner);
+        !threshold.is_zero() && amount > threshold
+    }
+
+    /// Most a rule can move per execution; a Sweep's amount is only the floor it keeps
+    fn spend_ceiling(&self, rule: &AutomationRule) -> U512 {
+        match rule.action_type {
+            ActionType::Sweep => U512::MAX,
+            _ => rule.amount,
+        }
+    }
+
+    /// Lock a vesting rule's funds in the vault
+    fn open_vesting(&mut self, rule_id: u64, vesting: VestingSchedule) {
+        self.vault_or_revert().open_vesting(
+            vesting.owner,
+            rule_id,
+            vesting.beneficiary,
+            vesting.total_amount,
+            vesting.start_time,
+            vesting.cliff_time,
+            vesting.end_time,
+        );
+    }
+
+    /// Store a newly created rule, index it for its owner and emit RuleCreated
+    fn store_new_rule(&mut self, mut rule: AutomationRule) {
+        self.assert_storage_current();
+        let rule_id = rule.id;
+        let owner = rule.owner;
+        let template_type = rule.template_name.clone();
+        rule.template_name_id = self.intern_template_name(template_type.clone());
+        
+        // Restricted owners can only create rules paying address book entries
+        self.check_address_book(owner, rule.recipient);
+        self.assert_within_rule_bounds(&rule);
+        
+        // High-value rules wait for the owner's confirmation
+        let needs_approval = self.needs_approval(owner, self.spend_ceiling(&rule));
+        if needs_approval {
+            rule.status = RuleStatus::PendingApproval;
+        }
+        
+        self.rule_configs.set(&rule_id, rule.config());
+        self.rule_states.set(&rule_id, rule.state());
+        if let Some(metadata) = &rule.metadata {
+            self.rule_metadata.set(&rule_id, metadata.clone());
+        }
+        
+        // Give the rule the next per-owner index, which also lists it for the owner
+        let local_index = self
+            .next_local_indices
+            .get(&owner)
+            .unwrap_or_else(|| self.user_rules.get_or_default(&owner).len() as u32);
+        self.local_rule_ids.set(&(owner, local_index), rule_id);
+        self.rule_local_indices.set(&rule_id, local_index);
+        self.next_local_indices.set(&owner, local_index + 1);
+        
+        // Count the rule under its initial status
+        self.shift_status_count(owner, None, Some(rule.status.clone()));
+        self.sync_reservation(owner, rule_id);
+        
+        // Update statistics
+        let mut global = self.get_global_stats();
+        global.total_rules_created += 1;
+        self.global_stats.set(global);
+        let mut stats = self.user_stats.get_or_default(&owner);
+        stats.rules_created += 1;
+        self.user_stats.set(&owner, stats);
+        
+        // Emit event
+        self.env().emit_event(RuleCreated {
+            rule_id,
+            owner,
+            template_type,
+            nonce: self.event_nonce.next(),
+        });
+        
+        if needs_approval {
+            let confirmable_at =
+                self.env().get_block_time() + self.get_time_config().confirmation_delay;
+            self.confirmable_at.set(&rule_id, confirmable_at);
+            self.env().emit_event(RulePendingApproval {
+                rule_id,
+                owner,
+                amount: rule.amount,
+                confirmable_at,
+                nonce: self.event_nonce.next(),
+            });
+        }
+    }
+
+    /// Look a contract up in the registry, falling back to the address set on the engine
+    /// 
+    /// Registry entries change without a delay, so while the admin timelock
+    /// is enabled only the engine's own, timelocked addresses are used.
+    fn resolve_address(&self, kind: ContractKind, local: Option<Address>) -> Option<Address> {
+        if self.timelock.delay() > 0 {
+            return local;
+        }
+        match self.registry.get_or_default() {
+            Some(registry) => ContractRegistryContractRef::new(self.env(), registry)
+                .get_address(kind)
+                .or(local),
+            None => local,
+        }
+    }
+
+    /// Get the vault address, from the registry if it has one
+    fn resolved_vault(&self) -> Option<Address> {
+        self.resolve_address(ContractKind::Vault, self.vault_address.get_or_default())
+    }
+
+    /// Get the staking adapter address, from the registry if it has one
+    fn resolved_staking_adapter(&self) -> Option<Address> {
+        self.resolve_address(ContractKind::StakingAdapter, self.staking_adapter.get_or_default())
+    }
+
+    /// Get the attestation oracle address, from the registry if it has one
+    fn resolved_attestation_oracle(&self) -> Option<Address> {
+        self.resolve_address(
+            ContractKind::AttestationOracle,
+            self.attestation_oracle.get_or_default(),
+        )
+    }
+
+    /// Get the price oracle address, from the registry if it has one
+    fn resolved_price_oracle(&self) -> Option<Address> {
+        self.resolve_address(ContractKind::PriceOracle, self.price_oracle.get_or_default())
+    }
+
+    /// Revert unless `address` is a `kind` contract reporting interface `version`
+    fn assert_compatible(&self, address: Address, kind: ContractKind, version: u32) {
+        let reported = VersionedContractContractRef::new(self.env(), address).get_version();
+        if reported != (ContractVersion { kind, version }) {
+            self.env().revert(Error::IncompatibleContractVersion);
+        }
+    }
+
+    /// Get a reference to the vault contract or revert if it is not configured
+    fn vault_or_revert(&self) -> AutomationVaultContractRef {
+        match self.resolved_vault() {
+            Some(addr) => AutomationVaultContractRef::new(self.env(), addr),
+            None => self.env().revert(Error::InvalidRuleConfig),
+        }
+    }
+
+    /// Get a reference to the staking adapter or revert if it is not configured
+    fn staking_adapter_or_revert(&self) -> StakingAdapterContractRef {
+        match self.resolved_staking_adapter() {
+            Some(addr) => StakingAdapterContractRef::new(self.env(), addr),
+            None => self.env().revert(Error::StakingAdapterNotSet),
+        }
+    }
+
+    /// Revert unless the staking adapter allowlists the validator
+    fn assert_validator_allowed(&self, validator: &PublicKey) {
+        if !self.staking_adapter_or_revert().is_validator_allowed(validator.clone()) {
+            self.env().revert(Error::InvalidValidator);
+        }
+    }
+
+    /// Load a rule's records without resolving its template name, for internal use
+    fn load_rule(&self, rule_id: u64) -> Option<AutomationRule> {
+        if self.purged_rules.get_or_default(&rule_id) {
+            return None;
+        }
+        let config = self.rule_configs.get(&rule_id)?;
+        let state = self.rule_states.get(&rule_id)?;
+        let mut rule = AutomationRule::from_parts(config, state);
+        rule.metadata = self.rule_metadata.get(&rule_id).filter(|metadata| !metadata.is_empty());
+        if self.contract_recipients.get_or_default(&rule_id).is_some() {
+            rule.recipient_kind = RecipientKind::Contract;
+        } else if self.vault_recipients.get_or_default(&rule_id) {
+            rule.recipient_kind = RecipientKind::Vault;
+        }
+        Some(rule)
+    }
+
+    /// Get a rule or revert if not found
+    /// 
+    /// The template name is not resolved; use `template_name_id`.
+    fn get_rule_or_revert(&self, rule_id: u64) -> AutomationRule {
+        match self.load_rule(rule_id) {
+            Some(rule) => rule,
+            None => {
+                self.env().revert(Error::RuleNotFound);
+            }
+        }
+    }
+
+    /// Revert unless every condition set on a condition rule holds
+    fn check_conditions(&mut self, rule: &AutomationRule, current_time: u64) {
+        if self.needs_price_condition(rule.id) {
+            self.check_price_condition(rule.id, current_time);
+        }
+        if let Some(threshold) = self.get_rewards_condition(rule.id) {
+            let rewards = match self.read_rewards_condition(rule, threshold) {
+                Ok(rewards) => rewards,
+                Err(error) => self.env().revert(error),
+            };
+            self.env().emit_event(RewardsConditionMet {
+                rule_id: rule.id,
+                rewards,
+                threshold,
+                nonce: self.event_nonce.next(),
+            });
+        }
+        if self.get_attestation_condition(rule.id).is_some() {
+            let read = self.read_attestation_condition(rule.id, current_time);
+            let (condition, attestation) = match read {
+                Ok(result) => result,
+                Err(error) => self.env().revert(error),
+            };
+            self.env().emit_event(AttestationConditionMet {
+                rule_id: rule.id,
+                key: condition.key,
+                value: attestation.value,
+                threshold: condition.threshold,
+                nonce: self.event_nonce.next(),
+            });
+        }
+    }
+
+    /// Whether a condition rule is gated on the price, which is the default
+    /// unless it only waits on staking rewards or an attestation
+    fn needs_price_condition(&self, rule_id: u64) -> bool {
+        self.price_conditions.get(&rule_id).is_some()
+            || (self.get_rewards_condition(rule_id).is_none()
+                && self.get_attestation_condition(rule_id).is_none())
+    }
+
+    /// Read a rule's attestation condition and the attested value it holds against
+    fn read_attestation_condition(
+        &self,
+        rule_id: u64,
+        current_time: u64,
+    ) -> Result<(AttestationCondition, Attestation), Error> {
+        let condition = self.get_attestation_condition(rule_id).ok_or(Error::ConditionNotMet)?;
+        let oracle = match self.resolved_attestation_oracle() {
+            Some(oracle) => AttestationOracleContractRef::new(self.env(), oracle),
+            None => return Err(Error::OracleNotSet),
+        };
+        
+        let attestation = oracle
+            .get_attestation(condition.key.clone())
+            .ok_or(Error::ConditionNotMet)?;
+        if condition.max_age > 0
+            && current_time.saturating_sub(attestation.timestamp) > condition.max_age
+        {
+            return Err(Error::StaleAttestation);
+        }
+        if !condition.comparison.holds(attestation.value, condition.threshold) {
+            return Err(Error::ConditionNotMet);
+        }
+        Ok((condition, attestation))
+    }
+
+    /// Read the uncompounded rewards of a rule's validator, checking they exceed `threshold`
+    fn read_rewards_condition(
+        &self,
+        rule: &AutomationRule,
+        threshold: U512,
+    ) -> Result<U512, Error> {
+        let adapter = match self.resolved_staking_adapter() {
+            Some(adapter) => StakingAdapterContractRef::new(self.env(), adapter),
+            None => return Err(Error::StakingAdapterNotSet),
+        };
+        
+        let rewards = adapter.get_pending_rewards(rule.validator.clone());
+        if rewards <= threshold {
+            return Err(Error::ConditionNotMet);
+        }
+        Ok(rewards)
+    }
+
+    /// Revert unless the rule's price condition holds against a fresh oracle price
+    fn check_price_condition(&mut self, rule_id: u64, current_time: u64) {
+        let (condition, data) = match self.read_price_condition(rule_id, current_time) {
+            Ok(result) => result,
+            Err(error) => self.env().revert(error),
+        };
+        
+        self.env().emit_event(PriceConditionMet {
+            rule_id,
+            price: data.price,
+            threshold: condition.threshold,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Read a rule's price condition and the oracle price it holds against
+    fn read_price_condition(
+        &self,
+        rule_id: u64,
+        current_time: u64,
+    ) -> Result<(PriceCondition, PriceData), Error> {
+        let condition = self.price_conditions.get(&rule_id).ok_or(Error::ConditionNotMet)?;
+        let oracle = match self.resolved_price_oracle() {
+            Some(oracle) => PriceOracleContractRef::new(self.env(), oracle),
+            None => return Err(Error::OracleNotSet),
+        };
+        
+        let data = oracle.latest_price();
+        if current_time.saturating_sub(data.updated_at) > self.max_price_age.get_or_default() {
+            return Err(Error::StalePriceData);
+        }
+        if !condition.comparison.holds(data.price, condition.threshold) {
+            return Err(Error::ConditionNotMet);
+        }
+        Ok((condition, data))
+    }
+
+    /// Perform a rule's action and update its state
+    /// 
+    /// Returns the failed funding check and shortfall, after recording the
+    /// failure, if the owner's vault cannot cover the action.
+    fn run_rule(
+        &mut self,
+        mut rule: AutomationRule,
+        current_time: u64,
+    ) -> Result<(), (ExecutionStep, U512)> {
+        let rule_id = rule.id;
+        rule.amount = self.resolve_amount(&rule);
+        
+        // Savings goal rules never move more than what is left of the goal
+        if let Some(remaining) = self.goal_remaining(rule_id) {
+            if remaining.is_zero() {
+                self.env().revert(Error::SavingsGoalReached);
+            }
+            rule.amount = rule.amount.min(remaining);
+        }
+        if let Some((step, shortfall)) = self.funding_shortfall(&rule) {
+            let error_code = Error::InsufficientBalance as u32;
+            self.record_execution(rule_id, current_time, U512::zero(), rule.recipient, error_code);
+            self.env().emit_event(RuleExecutionFailed {
+                rule_id,
+                owner: rule.owner,
+                error_code,
+                template_name_id: rule.template_name_id,
+                step: step.clone(),
+                shortfall,
+                nonce: self.event_nonce.next(),
+            });
+            return Err((step, shortfall));
+        }
+        
+        // Only runs that go ahead count towards the hourly limit
+        self.check_rate_limit(rule_id, current_time);
+        
+        // Budgeted rules are charged for whatever leaves the owner's vault, fees included
+        let balance_before = if self.rule_budgets.get_or_default(&rule_id).is_zero() {
+            None
+        } else {
+            Some(self.vault_or_revert().get_balance(rule.owner))
+        };
+        
+        // Envelope-bound rules get their envelope's funds, and only those, for the action
+        let envelope = self.rule_envelopes.get_or_default(&rule_id).map(|name| {
+            let mut vault = self.vault_or_revert();
+            let funds = vault.open_envelope(rule.owner, name.clone());
+            (name, funds, vault.get_balance(rule.owner))
+        });
+        self.call_hook(&rule, rule.amount, true);
+        
+        // Execute the action under a new execution ID, which the vault stamps on its payments
+        let execution_id = self.last_execution_id.get_or_default() + 1;
+        self.last_execution_id.set(execution_id);
+        let moved = match rule.action_type {
+            ActionType::Transfer => {
+                self.execute_transfer(&rule)
+            }
+            ActionType::Split => match self.split_configs.get_or_default(&rule.id) {
+                Some(config) => self.execute_split(&rule, config),
+                // Without a split config the rule pays its single recipient
+                None => self.execute_transfer(&rule),
+            },
+            ActionType::Compound => match self.reward_splits.get_or_default(&rule.id) {
+                Some(split) => self.staking_adapter_or_revert().donate_rewards(
+                    rule.owner,
+                    rule.validator.clone(),
+                    split.recipient,
+                    split.share_bps,
+                ),
+                None => self.staking_adapter_or_revert().route_rewards(
+                    rule.owner,
+                    rule.validator.clone(),
+                    self.get_reward_destination(rule.id),
+                    rule.recipient,
+                ),
+            },
+            ActionType::Stream => {
+                // Push whatever has accrued to the recipient
+                self.vault_or_revert().claim_stream(rule.id)
+            }
+            ActionType::Vesting => {
+                // Push whatever has vested to the beneficiary
+                self.vault_or_revert().claim_vesting(rule.id)
+            }
+            ActionType::StakeFromVault => {
+                self.execute_stake(&rule)
+            }
+            ActionType::Unstake => {
+                // Proceeds return to the vault once the undelegation unbonds
+                self.staking_adapter_or_revert().queue_unstake(rule.owner, rule.amount);
+                rule.amount
+            }
+            ActionType::Inheritance => {
+                self.execute_inheritance(&rule)
+            }
+            ActionType::Sweep => {
+                self.execute_sweep(&rule)
+            }
+            ActionType::TopUp => {
+                self.execute_top_up(&rule)
+            }
+            ActionType::Swap => {
+                self.execute_swap(&rule)
+            }
+            ActionType::Restake => {
+                self.execute_restake(&rule)
+            }
+            ActionType::Redelegate => {
+                self.execute_redelegate(&rule)
+            }
+            ActionType::Payroll => {
+                self.execute_payroll(&rule)
+            }
+            ActionType::Escrow => {
+                self.execute_escrow(&rule)
+            }
+            ActionType::Rebalance => {
+                let constraints = match self.rebalance_constraints.get(&rule.id) {
+                    Some(constraints) => constraints,
+                    None => self.env().revert(Error::InvalidRuleConfig),
+                };
+                self.staking_adapter_or_revert().rebalance_for(rule.owner, constraints)
+            }
+        };
+        
+        // Update rule state
+        rule.last_executed = current_time;
+        rule.next_execution = self.calculate_next_execution(current_time, &rule.schedule);
+        rule.execution_count += 1;
+        // One-shot rules are done after their only execution and free their tier slot
+        if rule.schedule == Schedule::Once {
+            self.set_rule_status(&mut rule, RuleStatus::Completed);
+        }
+        self.rule_states.set(&rule_id, rule.state());
+        self.record_execution(rule_id, current_time, moved, rule.recipient, 0);
+        self.record_stats(&rule, moved);
+        if let Some((name, funds, envelope_balance_before)) = envelope {
+            let mut vault = self.vault_or_revert();
+            let spent = envelope_balance_before.saturating_sub(vault.get_balance(rule.owner));
+            if spent > funds {
+                self.env().revert(Error::InsufficientEnvelopeBalance);
+            }
+            vault.close_envelope(rule.owner, name, funds - spent);
+        }
+        if let Some(balance_before) = balance_before {
+            let balance_after = self.vault_or_revert().get_balance(rule.owner);
+            self.charge_budget(rule_id, balance_before.saturating_sub(balance_after));
+        }
+        self.record_savings(rule_id, moved);
+        self.sync_reservation(rule.owner, rule_id);
+        self.check_low_balance(&rule);
+        self.call_hook(&rule, moved, false);
+        
+        // Emit event
+        self.env().emit_event(RuleExecuted {
+            rule_id,
+            owner: rule.owner,
+            executed_at: current_time,
+            template_name_id: rule.template_name_id,
+            amount: moved,
+            execution_count: rule.execution_count,
+            execution_id,
+            nonce: self.event_nonce.next(),
+        });
+        if let Some(payload_hash) = self.rule_notifications.get_or_default(&rule_id) {
+            self.env().emit_event(NotificationRequested {
+                rule_id,
+                owner: rule.owner,
+                execution_count: rule.execution_count,
+                payload_hash,
+                nonce: self.event_nonce.next(),
+            });
+        }
+        if rule.status == RuleStatus::Completed {
+            self.env().emit_event(RuleCompleted {
+                rule_id,
+                owner: rule.owner,
+                nonce: self.event_nonce.next(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Run a due rule, applying its missed execution policy if it is past the grace period
+    /// 
+    /// Returns whether the action ran, or the failed run's funding check and
+    /// shortfall if not even the first run could be funded. A catch-up that
+    /// runs out of funds part way stops there, and the slots it did not run
+    /// count as missed.
+    fn run_scheduled(
+        &mut self,
+        rule: AutomationRule,
+        current_time: u64,
+    ) -> Result<bool, (ExecutionStep, U512)> {
+        let (config, due, runs) = match self.missed_slots(&rule, current_time) {
+            Some(missed) => missed,
+            None => return self.run_rule(rule, current_time).map(|_| true),
+        };
+        let rule_id = rule.id;
+        let owner = rule.owner;
+        let interval = self.calculate_next_execution(0, &rule.schedule);
+        let next_execution = rule.next_execution + due * interval;
+        
+        let mut executed = 0;
+        while executed < runs {
+            let rule = self.get_rule_or_revert(rule_id);
+            match self.run_rule(rule, current_time) {
+                Ok(()) => executed += 1,
+                Err(failure) if executed == 0 => return Err(failure),
+                Err(_) => break,
+            }
+        }
+        
+        // Keep to the original cadence rather than counting from this late run
+        let mut rule = self.get_rule_or_revert(rule_id);
+        rule.next_execution = next_execution;
+        self.rule_states.set(&rule_id, rule.state());
+        let missed = due - executed;
+        let total = self.missed_executions.get_or_default(&rule_id);
+        self.missed_executions.set(&rule_id, total + missed);
+        self.env().emit_event(MissedExecution {
+            rule_id,
+            owner,
+            policy: config.policy,
+            missed,
+            executed,
+            next_execution,
+            nonce: self.event_nonce.next(),
+        });
+        Ok(executed > 0)
+    }
+
+    /// The missed execution config of a rule past its grace period, the slots due and how many run
+    /// 
+    /// None if the rule simply runs once: it is not recurring, has no
+    /// missed execution policy or is still within its grace period.
+    fn missed_slots(
+        &self,
+        rule: &AutomationRule,
+        current_time: u64,
+    ) -> Option<(MissedExecutionConfig, u64, u64)> {
+        // A one-shot rule has no later slots to miss, so it runs once however late
+        let recurring = rule.trigger_type == TriggerType::Time && rule.schedule != Schedule::Once;
+        let config = match self.missed_execution_configs.get_or_default(&rule.id) {
+            Some(config) if recurring => config,
+            _ => return None,
+        };
+        if current_time <= rule.next_execution + config.grace_period {
+            return None;
+        }
+        
+        // Every slot from next_execution up to now is due
+        let interval = self.calculate_next_execution(0, &rule.schedule);
+        let due = 1 + (current_time - rule.next_execution) / interval;
+        let runs = match config.policy {
+            MissedExecutionPolicy::Skip => 0,
+            MissedExecutionPolicy::ExecuteOnceLate => 1,
+            MissedExecutionPolicy::CatchUpAll => due.min(MAX_CATCH_UP_EXECUTIONS),
+        };
+        Some((config, due, runs))
+    }
+
+    /// Run the follow-up rules of `rule_id`, depth first
+    /// 
+    /// `path` holds the rules that led to `rule_id`, ending with it. Only a
+    /// follow-up already on the path is a cycle; one reached again through
+    /// another branch simply runs again.
+    fn run_follow_ups(
+        &mut self,
+        rule_id: u64,
+        current_time: u64,
+        path: &mut Vec<u64>,
+        executed: &mut Vec<u64>,
+    ) {
+        let follow_up_ids = self.follow_up_rules.get_or_default(&rule_id);
+        if follow_up_ids.is_empty() {
+            return;
+        }
+        if path.len() as u32 >= MAX_WORKFLOW_DEPTH {
+            self.env().revert(Error::WorkflowTooDeep);
+        }
+        
+        for follow_up_id in follow_up_ids {
+            if path.contains(&follow_up_id) {
+                self.env().revert(Error::WorkflowCycle);
+            }
+            let follow_up = self.get_rule_or_revert(follow_up_id);
+            if !matches!(follow_up.status, RuleStatus::Active) {
+                continue;
+            }
+            
+            // Only the schedule is skipped; links made before condition rules and
+            // dead-man switches were refused still wait for them
+            if follow_up.trigger_type == TriggerType::Condition {
+                self.check_conditions(&follow_up, current_time);
+            }
+            self.check_execution_window(follow_up_id, current_time);
+            self.check_executor(follow_up_id, follow_up.owner);
+            if matches!(follow_up.action_type, ActionType::Inheritance) {
+                self.check_inactivity(&follow_up, current_time);
+            }
+            if let Err((step, _)) = self.run_rule(follow_up, current_time) {
+                self.env().revert(self.shortfall_error(&step));
+            }
+            executed.push(follow_up_id);
+            path.push(follow_up_id);
+            self.run_follow_ups(follow_up_id, current_time, path, executed);
+            path.pop();
+        }
+    }
+
+    /// The error a workflow reverts with when a follow-up fails the funding check `step`
+    fn shortfall_error(&self, step: &ExecutionStep) -> Error {
+        match step {
+            ExecutionStep::VaultBalance | ExecutionStep::TokenBalance => Error::InsufficientBalance,
+            ExecutionStep::Stake | ExecutionStep::Delegation => Error::InsufficientStakingBalance,
+        }
+    }
+
+    /// Whether `target` is reachable through the follow-ups of `from`
+    fn workflow_reaches(&self, from: u64, target: u64, depth: u32) -> bool {
+        if depth > MAX_WORKFLOW_DEPTH {
+            return true;
+        }
+        self.follow_up_rules
+            .get_or_default(&from)
+            .into_iter()
+            .any(|next| next == target || self.workflow_reaches(next, target, depth + 1))
+    }
+
+    /// Number of rules in the longest chain starting at `rule_id`
+    fn workflow_depth(&self, rule_id: u64, depth: u32) -> u32 {
+        if depth > MAX_WORKFLOW_DEPTH {
+            return depth;
+        }
+        self.follow_up_rules
+            .get_or_default(&rule_id)
+            .into_iter()
+            .map(|next| 1 + self.workflow_depth(next, depth + 1))
+            .max()
+            .unwrap_or(1)
+    }
+
+    /// Number of rules in the longest chain ending at `rule_id`
+    fn workflow_height(&self, rule_id: u64, depth: u32) -> u32 {
+        if depth > MAX_WORKFLOW_DEPTH {
+            return depth;
+        }
+        self.workflow_parents
+            .get_or_default(&rule_id)
+            .into_iter()
+            .map(|parent| 1 + self.workflow_height(parent, depth + 1))
+            .max()
+            .unwrap_or(1)
+    }
+
+    /// Forget that `parent` runs `rule_id` as a follow-up
+    fn unlink_workflow_parent(&mut self, rule_id: u64, parent: u64) {
+        let mut parents = self.workflow_parents.get_or_default(&rule_id);
+        parents.retain(|id| *id != parent);
+        self.workflow_parents.set(&rule_id, parents);
+    }
+
+    /// Count a successful execution and the CSPR it moved out of the owner's vault
+    fn record_stats(&mut self, rule: &AutomationRule, moved: U512) {
+        let outflow = match rule.action_type {
+            ActionType::Unstake
+            | ActionType::Rebalance
+            | ActionType::Compound
+            | ActionType::Restake
+            | ActionType::Redelegate => U512::zero(),
+            _ if rule.token.is_some() => U512::zero(),
+            _ => moved,
+        };
+        
+        let mut global = self.get_global_stats();
+        global.total_executions += 1;
+        global.total_volume += outflow;
+        self.global_stats.set(global);
+        
+        let mut stats = self.user_stats.get_or_default(&rule.owner);
+        stats.executions += 1;
+        stats.total_outflow += outflow;
+        self.user_stats.set(&rule.owner, stats);
+    }
+
+    /// Append an entry to a rule's execution history ring buffer
+    fn record_execution(
+        &mut self,
+        rule_id: u64,
+        timestamp: u64,
+        amount: U512,
+        recipient: Option<Address>,
+        status_code: u32,
+    ) {
+        let len = self.execution_history_len.get_or_default(&rule_id);
+        let slot = len % EXECUTION_HISTORY_SIZE;
+        self.execution_history.set(&(rule_id, slot), ExecutionRecord {
+            timestamp,
+            amount,
+            recipient,
+            status_code,
+        });
+        self.execution_history_len.set(&rule_id, len.wrapping_add(1));
+    }
+
+    /// Whether the owner's vault balance (or stake) cannot cover a rule's action
+    fn is_underfunded(&self, rule: &AutomationRule) -> bool {
+        self.funding_shortfall(rule).is_some()
+    }
+
+    /// The funding check a rule's action fails and by how much, if the owner cannot cover it
+    fn funding_shortfall(&self, rule: &AutomationRule) -> Option<(ExecutionStep, U512)> {
+        let short = |step: ExecutionStep, available: U512, needed: U512| {
+            (available < needed).then(|| (step, needed - available))
+        };
+        if matches!(rule.action_type, ActionType::Unstake) {
+            let staked = self.staking_adapter_or_revert().get_user_stake(rule.owner);
+            return short(ExecutionStep::Stake, staked, rule.amount);
+        }
+        if let (ActionType::Redelegate, Some(source)) = (&rule.action_type, rule.validator.clone()) {
+            let placed = self.staking_adapter_or_revert().get_user_delegation(rule.owner, source);
+            return short(ExecutionStep::Delegation, placed, rule.amount);
+        }
+        if matches!(rule.action_type, ActionType::TopUp) {
+            let amount = self.top_up_amount(rule);
+            let fee = self.quote_fee(rule.owner, amount);
+            let balance = self.vault_or_revert().get_balance(rule.owner);
+            return short(ExecutionStep::VaultBalance, balance, amount + fee);
+        }
+        if matches!(rule.action_type, ActionType::Payroll) {
+            let config = self.payroll_config_or_revert(rule.id);
+            let balance = self.vault_or_revert().get_spendable_balance(rule.owner);
+            let needed = match config.failure_policy {
+                PayrollFailurePolicy::AllOrNothing => rule.amount + self.payroll_fees(rule),
+                // Underfunded only if not even one entry can be paid, as with no entries at all
+                PayrollFailurePolicy::PayWhatFits => match config
+                    .entries
+                    .iter()
+                    .map(|entry| entry.amount + self.quote_fee(rule.owner, entry.amount))
+                    .min()
+                {
+                    Some(cheapest) => cheapest,
+                    None => return Some((ExecutionStep::VaultBalance, U512::zero())),
+                },
+            };
+            return short(ExecutionStep::VaultBalance, balance, needed);
+        }
+        if !matches!(
+            rule.action_type,
+            ActionType::Transfer
+                | ActionType::Split
+                | ActionType::StakeFromVault
+                | ActionType::Swap
+                | ActionType::Escrow
+        ) {
+            return None;
+        }
+        let vault = self.vault_or_revert();
+        match rule.token {
+            Some(token) => {
+                to_token_amount(rule.amount)?;
+                let balance = from_token_amount(vault.get_token_balance(rule.owner, token));
+                short(ExecutionStep::TokenBalance, balance, rule.amount)
+            }
+            None => {
+                let fee = self.quote_fee(rule.owner, rule.amount);
+                short(ExecutionStep::VaultBalance, vault.get_balance(rule.owner), rule.amount + fee)
+            }
+        }
+    }
+
+    /// Revert if the rule has an execution window that `current_time` is outside of
+    fn check_execution_window(&self, rule_id: u64, current_time: u64) {
+        if let Some(window) = self.execution_windows.get_or_default(&rule_id) {
+            if !window.contains(current_time) {
+                self.env().revert(Error::OutsideExecutionWindow);
+            }
+        }
+    }
+
+    /// Emit VaultUnderfunded if the owner's funds will not cover the rule's upcoming executions
+    fn check_low_balance(&mut self, rule: &AutomationRule) {
+        let lookahead = self.low_balance_alerts.get_or_default(&rule.owner);
+        if lookahead == 0
+            || rule.token.is_some()
+            || rule.schedule == Schedule::Once
+            || !matches!(
+                rule.action_type,
+                ActionType::Transfer
+                    | ActionType::Split
+                    | ActionType::StakeFromVault
+                    | ActionType::Swap
+                    | ActionType::Escrow
+            )
+        {
+            return;
+        }
+        let per_execution = rule.amount + self.quote_fee(rule.owner, rule.amount);
+        if per_execution.is_zero() {
+            return;
+        }
+        
+        let vault = self.vault_or_revert();
+        let balance = match self.rule_envelopes.get_or_default(&rule.id) {
+            Some(name) => vault.get_envelope_balance(rule.owner, name),
+            None => vault.get_spendable_balance(rule.owner),
+        };
+        let required = per_execution * U512::from(lookahead);
+        if balance >= required {
+            return;
+        }
+        
+        // Skip past the executions the balance still covers (fewer than `lookahead`)
+        let covered = (balance / per_execution).as_u32();
+        let mut first_failing_at = rule.next_execution;
+        for _ in 0..covered {
+            first_failing_at = self.calculate_next_execution(first_failing_at, &rule.schedule);
+        }
+        
+        self.env().emit_event(VaultUnderfunded {
+            rule_id: rule.id,
+            owner: rule.owner,
+            balance,
+            required,
+            shortfall: required - balance,
+            first_failing_at,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Calculate the next execution time based on schedule
+    /// 
+    /// `Once` has no interval, so a one-shot rule created without a first
+    /// execution time is due right away.
+    fn calculate_next_execution(&self, from_time: u64, schedule: &Schedule) -> u64 {
+        from_time + self.get_time_config().interval(schedule)
+    }
+
+    /// Revert if a new rule is scheduled too often or moves too much for the admin's bounds
+    fn assert_within_rule_bounds(&self, rule: &AutomationRule) {
+        let (min_interval, max_amount, _) = self.get_rule_bounds();
+        if rule.trigger_type == TriggerType::Time
+            && rule.schedule != Schedule::Once
+            && self.calculate_next_execution(0, &rule.schedule) < min_interval
+        {
+            self.env().revert(Error::ScheduleTooFrequent);
+        }
+        if !max_amount.is_zero() && rule.token.is_none() && self.spend_ceiling(rule) > max_amount {
+            self.env().revert(Error::RuleAmountTooLarge);
+        }
+    }
+
+    /// Execute a transfer action via the vault, returning the amount moved
+    fn execute_transfer(&self, rule: &AutomationRule) -> U512 {
+        let mut vault = self.vault_or_revert();
+        let execution_id = self.current_execution_id();
+        
+        let recipient = match rule.recipient {
+            Some(addr) => addr,
+            None => self.env().revert(Error::InvalidRuleConfig),
+        };
+        // Entries may have been removed since the rule was created
+        self.check_address_book(rule.owner, Some(recipient));
+        
+        // Call vault contract to execute transfer
+        match rule.token {
+            Some(token) => {
+                let amount = match to_token_amount(rule.amount) {
+                    Some(amount) => amount,
+                    None => self.env().revert(Error::InvalidRuleConfig),
+                };
+                vault.execute_token_transfer(
+                    rule.owner,
+                    token,
+                    recipient,
+                    amount,
+                    rule.id,
+                    execution_id,
+                );
+            }
+            None => {
+                let fee = self.quote_fee(rule.owner, rule.amount);
+                let claim_window = self.claim_windows.get_or_default(&rule.id);
+                match self.contract_recipients.get_or_default(&rule.id) {
+                    Some(deposit) => vault.execute_contract_transfer(
+                        rule.owner,
+                        recipient,
+                        deposit,
+                        rule.amount,
+                        fee,
+                        rule.id,
+                        execution_id,
+                    ),
+                    None if claim_window > 0 => {
+                        let expires_at = self.env().get_block_time() + claim_window;
+                        vault.hold_claimable(
+                            rule.owner,
+                            recipient,
+                            rule.amount,
+                            fee,
+                            rule.id,
+                            execution_id,
+                            expires_at,
+                        );
+                    }
+                    None if self.vault_recipients.get_or_default(&rule.id) => vault
+                        .execute_top_up(
+                            rule.owner,
+                            recipient,
+                            rule.amount,
+                            fee,
+                            rule.id,
+                            execution_id,
+                        ),
+                    None => vault.execute_transfer(
+                        rule.owner,
+                        recipient,
+                        rule.amount,
+                        fee,
+                        rule.id,
+                        execution_id,
+                    ),
+                }
+            }
+        }
+        rule.amount
+    }
+
+    /// Pay each recipient of a split rule their share, returning the amount that left the vault
+    /// 
+    /// The fee on the whole amount rides on the first payment, so the owner
+    /// pays what previews quote however the amount divides.
+    fn execute_split(&mut self, rule: &AutomationRule, config: SplitConfig) -> U512 {
+        let mut vault = self.vault_or_revert();
+        let execution_id = self.current_execution_id();
+        let (shares, dust) = config.shares(rule.amount);
+        let mut fee = self.quote_fee(rule.owner, rule.amount);
+        for (split, share) in config.recipients.iter().zip(shares) {
+            // Entries may have been removed from the address book since the split was set
+            self.check_address_book(rule.owner, Some(split.recipient));
+            if share.is_zero() {
+                continue;
+            }
+            vault.execute_transfer(rule.owner, split.recipient, share, fee, rule.id, execution_id);
+            fee = U512::zero();
+        }
+        if dust.is_zero() {
+            return rule.amount;
+        }
+        
+        let moved = match config.dust_policy {
+            DustPolicy::Treasury => {
+                let treasury = match vault.get_treasury() {
+                    Some(treasury) => treasury,
+                    None => self.env().revert(Error::TreasuryNotSet),
+                };
+                vault.execute_transfer(
+                    rule.owner,
+                    treasury,
+                    dust,
+                    U512::zero(),
+                    rule.id,
+                    execution_id,
+                );
+                rule.amount
+            }
+            // Kept dust never leaves the owner's vault
+            _ => rule.amount - dust,
+        };
+        self.env().emit_event(SplitDustHandled {
+            rule_id: rule.id,
+            owner: rule.owner,
+            dust,
+            dust_policy: config.dust_policy,
+            nonce: self.event_nonce.next(),
+        });
+        moved
+    }
+
+    /// Pay each payroll entry from the owner's vault, returning the total paid
+    /// 
+    /// Under `PayWhatFits`, entries the remaining balance cannot cover are
+    /// skipped with a PayrollEntrySkipped event.
+    fn execute_payroll(&mut self, rule: &AutomationRule) -> U512 {
+        let config = self.payroll_config_or_revert(rule.id);
+        let mut vault = self.vault_or_revert();
+        let execution_id = self.current_execution_id();
+        let mut paid = U512::zero();
+        for entry in config.entries {
+            // Entries may have been removed from the address book since the rule was created
+            self.check_address_book(rule.owner, Some(entry.recipient));
+            let fee = self.quote_fee(rule.owner, entry.amount);
+            if config.failure_policy == PayrollFailurePolicy::PayWhatFits
+                && vault.get_spendable_balance(rule.owner) < entry.amount + fee
+            {
+                self.env().emit_event(PayrollEntrySkipped {
+                    rule_id: rule.id,
+                    owner: rule.owner,
+                    recipient: entry.recipient,
+                    amount: entry.amount,
+                    nonce: self.event_nonce.next(),
+                });
+                continue;
+            }
+            vault.execute_transfer(
+                rule.owner,
+                entry.recipient,
+                entry.amount,
+                fee,
+                rule.id,
+                execution_id,
+            );
+            paid += entry.amount;
+        }
+        paid
+    }
+
+    /// Total transfer fees of one payroll execution paying every entry
+    fn payroll_fees(&self, rule: &AutomationRule) -> U512 {
+        self.payroll_config_or_revert(rule.id)
+            .entries
+            .iter()
+            .fold(U512::zero(), |fees, entry| fees + self.quote_fee(rule.owner, entry.amount))
+    }
+
+    /// Get a payroll rule's config or revert if it has none
+    fn payroll_config_or_revert(&self, rule_id: u64) -> PayrollConfig {
+        match self.payroll_configs.get(&rule_id) {
+            Some(config) => config,
+            None => self.env().revert(Error::InvalidRuleConfig),
+        }
+    }
+
+    /// Send the owner's whole vault balance to the beneficiary, returning the amount sent
+    fn execute_inheritance(&mut self, rule: &AutomationRule) -> U512 {
+        let beneficiary = match rule.recipient {
+            Some(addr) => addr,
+            None => self.env().revert(Error::InvalidRuleConfig),
+        };
+        self.check_not_blocked(beneficiary);
+        let unstake = self
+            .inheritance_configs
+            .get(&rule.id)
+            .map(|config| config.unstake)
+            .unwrap_or_default();
+        
+        // Unstaked proceeds reach the vault after unbonding and go out on a later run
+        if unstake {
+            if let Some(adapter) = self.resolved_staking_adapter() {
+                let mut adapter = StakingAdapterContractRef::new(self.env(), adapter);
+                let stake = adapter.get_user_stake(rule.owner);
+                if !stake.is_zero() {
+                    adapter.queue_unstake(rule.owner, stake);
+                }
+            }
+        }
+        
+        let mut vault = self.vault_or_revert();
+        let amount = vault.get_balance(rule.owner);
+        if !amount.is_zero() {
+            let execution_id = self.current_execution_id();
+            vault.execute_transfer(
+                rule.owner,
+                beneficiary,
+                amount,
+                U512::zero(),
+                rule.id,
+                execution_id,
+            );
+        }
+        
+        self.env().emit_event(InheritanceTriggered {
+            rule_id: rule.id,
+            owner: rule.owner,
+            beneficiary,
+            amount,
+            last_activity: self.get_last_activity(rule.owner),
+            nonce: self.event_nonce.next(),
+        });
+        amount
+    }
+
+    /// The amount a rule moves right now according to its amount spec
+    fn resolve_amount(&self, rule: &AutomationRule) -> U512 {
+        let (spec, percent_bps) = match self.amount_specs.get(&rule.id) {
+            Some(spec) => spec,
+            None => return rule.amount,
+        };
+        let base = match spec {
+            AmountSpec::Fixed => return rule.amount,
+            AmountSpec::PercentOfVault => {
+                let vault = self.vault_or_revert();
+                match rule.token {
+                    Some(token) => from_token_amount(vault.get_token_balance(rule.owner, token)),
+                    None => vault.get_balance(rule.owner),
+                }
+            }
+            AmountSpec::PercentOfRewards => {
+                self.staking_adapter_or_revert().get_user_rewards(rule.owner)
+            }
+        };
+        base * U512::from(percent_bps) / U512::from(10_000u64)
+    }
+
+    /// Gas estimate for one rule and the follow-ups run after it
+    fn estimate_rule_gas(&self, rule: &AutomationRule, depth: u32) -> u64 {
+        let action_gas = match rule.action_type {
+            ActionType::Transfer
+            | ActionType::Split
+            | ActionType::Sweep
+            | ActionType::TopUp
+            | ActionType::Escrow => 1_500_000_000,
+            ActionType::Payroll => {
+                let entries = self.payroll_config_or_revert(rule.id).entries.len() as u64;
+                entries * 1_500_000_000
+            }
+            ActionType::Stream | ActionType::Vesting | ActionType::Inheritance => 2_000_000_000,
+            ActionType::Compound => 4_000_000_000,
+            ActionType::StakeFromVault
+            | ActionType::Unstake
+            | ActionType::Restake
+            | ActionType::Redelegate => 5_000_000_000,
+            ActionType::Swap => 6_000_000_000,
+            ActionType::Rebalance => 8_000_000_000,
+        };
+        
+        // Each of these settings adds a contract call to the execution
+        let hook = self.rule_hooks.get_or_default(&rule.id);
+        let extra_calls = [
+            rule.token.is_some(),
+            self.contract_recipients.get_or_default(&rule.id).is_some(),
+            rule.trigger_type == TriggerType::Condition,
+            self.get_rewards_condition(rule.id).is_some(),
+            self.get_attestation_condition(rule.id).is_some(),
+            hook.as_ref().is_some_and(|hook| hook.before),
+            hook.as_ref().is_some_and(|hook| hook.after),
+            self.rule_envelopes.get_or_default(&rule.id).is_some(),
+            !self.rule_budgets.get_or_default(&rule.id).is_zero(),
+            !self.keeper_bounties.get_or_default(&rule.id).is_zero(),
+            self.nft_perks.get_or_default().is_some(),
+        ]
+        .iter()
+        .filter(|adds_call| **adds_call)
+        .count() as u64;
+        
+        // A late rule runs its action once for each slot its missed execution policy catches up
+        let runs = match self.missed_slots(rule, self.env().get_block_time()) {
+            Some((_, _, runs)) if depth == 0 => runs,
+            _ => 1,
+        };
+        let mut gas = BASE_EXECUTION_GAS + runs * (action_gas + extra_calls * CONTRACT_CALL_GAS);
+        if depth + 1 < MAX_WORKFLOW_DEPTH {
+            for follow_up_id in self.follow_up_rules.get_or_default(&rule.id) {
+                if let Some(follow_up) = self.load_rule(follow_up_id) {
+                    gas += self.estimate_rule_gas(&follow_up, depth + 1);
+                }
+            }
+        }
+        gas
+    }
+
+    /// How much a top-up rule needs to move to reach its target, capped by the rule amount
+    fn top_up_amount(&self, rule: &AutomationRule) -> U512 {
+        let (recipient, target) = match (rule.recipient, self.top_up_targets.get(&rule.id)) {
+            (Some(recipient), Some(target)) => (recipient, target),
+            _ => return U512::zero(),
+        };
+        let balance = self.vault_or_revert().get_balance(recipient);
+        target.saturating_sub(balance).min(rule.amount)
+    }
+
+    /// Credit the recipient's vault up to the rule's target, returning the amount moved
+    fn execute_top_up(&self, rule: &AutomationRule) -> U512 {
+        let recipient = match rule.recipient {
+            Some(addr) => addr,
+            None => self.env().revert(Error::InvalidRuleConfig),
+        };
+        self.check_address_book(rule.owner, Some(recipient));
+        
+        let amount = self.top_up_amount(rule);
+        if !amount.is_zero() {
+            let fee = self.quote_fee(rule.owner, amount);
+            let execution_id = self.current_execution_id();
+            self.vault_or_revert()
+                .execute_top_up(rule.owner, recipient, amount, fee, rule.id, execution_id);
+        }
+        amount
+    }
+
+    /// Move the rule's amount into escrow for the recipient, returning the amount escrowed
+    fn execute_escrow(&self, rule: &AutomationRule) -> U512 {
+        let recipient = match rule.recipient {
+            Some(addr) => addr,
+            None => self.env().revert(Error::InvalidRuleConfig),
+        };
+        self.check_address_book(rule.owner, Some(recipient));
+        
+        let fee = self.quote_fee(rule.owner, rule.amount);
+        let release_at = self.env().get_block_time() + self.escrow_windows.get_or_default(&rule.id);
+        self.vault_or_revert().open_escrow(rule.owner, recipient, rule.amount, fee, rule.id, release_at);
+        rule.amount
+    }
+
+    /// Sweep everything above the rule's floor to the recipient, returning the amount sent
+    fn execute_sweep(&self, rule: &AutomationRule) -> U512 {
+        let recipient = match rule.recipient {
+            Some(addr) => addr,
+            None => self.env().revert(Error::InvalidRuleConfig),
+        };
+        self.check_address_book(rule.owner, Some(recipient));
+        
+        let fee_config = self.effective_fee_config(rule.owner);
+        let execution_id = self.current_execution_id();
+        self.vault_or_revert().execute_sweep(
+            rule.owner,
+            recipient,
+            rule.amount,
+            fee_config,
+            rule.id,
+            execution_id,
+        )
+    }
+
+    /// Move rewards from the rule's source validator to its destination, returning the amount
+    fn execute_restake(&self, rule: &AutomationRule) -> U512 {
+        // Moving the pool's rewards stays with operators; a revoked owner's rule stops
+        if !self.has_role(Role::Operator, rule.owner) {
+            self.env().revert(Error::NotAdmin);
+        }
+        let destination = self.restake_destinations.get(&rule.id);
+        let (source, destination) = match (rule.validator.clone(), destination) {
+            (Some(source), Some(destination)) => (source, destination),
+            _ => self.env().revert(Error::InvalidRuleConfig),
+        };
+        self.staking_adapter_or_revert().restake_rewards(source, destination)
+    }
+
+    /// Move the rule's amount of the owner's stake to the destination validator
+    fn execute_redelegate(&self, rule: &AutomationRule) -> U512 {
+        let destination = self.restake_destinations.get(&rule.id);
+        let (source, destination) = match (rule.validator.clone(), destination) {
+            (Some(source), Some(destination)) => (source, destination),
+            _ => self.env().revert(Error::InvalidRuleConfig),
+        };
+        self.staking_adapter_or_revert()
+            .redelegate_for(rule.owner, source, destination, rule.amount);
+        rule.amount
+    }
+
+    /// Swap through the vault's swap adapter, returning the amount spent
+    fn execute_swap(&self, rule: &AutomationRule) -> U512 {
+        let config = match self.swap_configs.get(&rule.id) {
+            Some(config) => config,
+            None => self.env().revert(Error::InvalidRuleConfig),
+        };
+        let min_out = self.swap_min_outs.get_or_default(&rule.id);
+        if min_out.is_zero() {
+            self.env().revert(Error::InvalidRuleConfig);
+        }
+        let fee = match config.direction {
+            SwapDirection::CsprToToken => self.quote_fee(rule.owner, rule.amount),
+            SwapDirection::TokenToCspr => U512::zero(),
+        };
+        self.vault_or_revert().execute_swap(
+            rule.owner,
+            config.token,
+            config.direction,
+            rule.amount,
+            fee,
+            config.max_slippage_bps,
+            min_out,
+            rule.id,
+        );
+        rule.amount
+    }
+
+    /// Stake from the owner's vault via the staking adapter, returning the amount staked
+    fn execute_stake(&self, rule: &AutomationRule) -> U512 {
+        let validator = match rule.validator.clone() {
+            Some(validator) => validator,
+            None => self.env().revert(Error::InvalidRuleConfig),
+        };
+        let fee = self.quote_fee(rule.owner, rule.amount);
+        self.vault_or_revert().execute_stake(rule.owner, validator, rule.amount, fee, rule.id);
+        rule.amount
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use odra::casper_types::U256;
+    use odra::host::{Deployer, HostEnv, HostRef, NoArgs};
+    use crate::cep18::test_token::TestToken;
+    use crate::events::AutomationExecuted;
+    use crate::cep78::test_nft::TestNft;
+    use crate::oracle::test_oracle::TestOracle;
+    use crate::attestation_oracle::AttestationOracle;
+    use crate::registry::ContractRegistry;
+    use crate::testing::deploy_core;
+    use crate::staking_adapter::{StakingAdapter, StakingAdapterInitArgs};
+    use crate::hook::test_hook::TestHook;
+    use crate::swap_adapter::test_dex::{TestDex, TestDexInitArgs};
+    use crate::swap_adapter::{SwapAdapter, SwapAdapterInitArgs};
+    use crate::types::{AutoStakeConfig, ClaimStatus, EscrowStatus, SplitRecipient};
+    use crate::vault::{
+        AutomationVault, AutomationVaultHostRef, AutomationVaultInitArgs, MAX_CLAIM_BATCH,
+    };
+
+    const SECONDS_PER_DAY: u64 = 86_400;
+    const SECONDS_PER_WEEK: u64 = 604_800;
+
+    fn setup() -> (odra::host::HostEnv, AutomationVaultHostRef, AutomationEngineHostRef) {
+        let env = odra_test::env();
+        let (vault, engine) = deploy_core(&env);
+        (env, vault, engine)
+    }
+
+    /// Give `owner` a Gold membership NFT so they can hold more than two rules (caller is admin)
+    fn grant_gold_tier(env: &HostEnv, engine: &mut AutomationEngineHostRef, owner: Address) {
+        let mut collection = TestNft::deploy(env, NoArgs);
+        engine.set_nft_perks(Some(NftPerks {
+            collection: *collection.address(),
+            tier: StakingTier::Gold,
+            fee_discount_bps: 0,
+        }));
+        collection.mint(owner);
+    }
+
+    #[test]
+    fn test_create_rule() {
+        let (env, _vault, mut engine) = setup();
+        let user = env.get_account(0);
+        env.set_caller(user);
+        
+        let rule_id = engine.create_rule(
+            "recurring_payment".to_string(),
+            TriggerType::Time,
+            Schedule::Daily,
+            ActionType::Transfer,
+            Some(env.get_account(1)),
+            U512::from(100_000_000u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        
+        assert_eq!(rule_id, 1);
+        
+        let rule = engine.get_rule(rule_id);
+        assert!(rule.is_some());
+        assert_eq!(rule.unwrap().owner, user);
+    }
+
+    #[test]
+    fn test_pause_and_resume() {
+        let (env, _vault, mut engine) = setup();
+        let user = env.get_account(0);
+        env.set_caller(user);
+        
+        let rule_id = engine.create_rule(
+            "test_rule".to_string(),
+            TriggerType::Manual,
+            Schedule::Daily,
+            ActionType::Transfer,
+            Some(env.get_account(1)),
+            U512::from(100_000_000u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        
+        // Pause
+        engine.pause_rule(rule_id);
+        let rule = engine.get_rule(rule_id).unwrap();
+        assert!(matches!(rule.status, RuleStatus::Paused));
+        
+        // Resume
+        engine.resume_rule(rule_id);
+        let rule = engine.get_rule(rule_id).unwrap();
+        assert!(matches!(rule.status, RuleStatus::Active));
+    }
+
+    #[test]
+    fn test_execute_updates_state_only() {
+        let (env, mut vault, mut engine) = setup();
+        let user = env.get_account(0);
+        env.set_caller(user);
+        vault.with_tokens(U512::from(1_000u64)).deposit();
+        
+        let rule_id = engine.create_rule(
+            "allowance".to_string(),
+            TriggerType::Manual,
+            Schedule::Weekly,
+            ActionType::Transfer,
+            Some(env.get_account(1)),
+            U512::from(100u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        env.advance_block_time(5);
+        engine.execute_rule(rule_id);
+        
+        let rule = engine.get_rule(rule_id).unwrap();
+        assert_eq!(rule.execution_count, 1);
+        assert_eq!(rule.last_executed, env.block_time());
+        assert_eq!(rule.next_execution, env.block_time() + SECONDS_PER_WEEK);
+        // Config fields survive the state rewrite
+        assert_eq!(rule.template_name, "allowance");
+        assert_eq!(rule.amount, U512::from(100u64));
+    }
+
+    #[test]
+    fn test_protocol_fee_charged() {
+        let (env, mut vault, mut engine) = setup();
+        let admin = env.get_account(0);
+        let user = env.get_account(1);
+        let recipient = env.get_account(2);
+        let treasury = env.get_account(3);
+        
+        // 1% plus a flat 5 motes
+        env.set_caller(admin);
+        engine.set_fee_config(100, U512::from(5u64));
+        vault.set_treasury(treasury);
+        assert!(engine.try_set_fee_config(5_000, U512::zero()).is_err());
+        
+        env.set_caller(user);
+        assert!(engine.try_set_fee_config(0, U512::zero()).is_err());
+        vault.with_tokens(U512::from(10_000u64)).deposit();
+        let rule_id = engine.create_rule(
+            "payment".to_string(),
+            TriggerType::Manual,
+            Schedule::Daily,
+            ActionType::Transfer,
+            Some(recipient),
+            U512::from(1_000u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        engine.execute_rule(rule_id);
+        
+        assert_eq!(vault.get_balance(user), U512::from(8_985u64));
+        assert_eq!(vault.get_accrued_fees(), U512::from(15u64));
+        
+        // Exempt tiers pay nothing
+        env.set_caller(admin);
+        engine.set_fee_exempt(StakingTier::Starter, true);
+        assert_eq!(engine.quote_fee(user, U512::from(1_000u64)), U512::zero());
+        
+        let treasury_before = env.balance_of(&treasury);
+        vault.distribute_fees();
+        assert_eq!(env.balance_of(&treasury), treasury_before + U512::from(15u64));
+        assert_eq!(vault.get_accrued_fees(), U512::zero());
+    }
+
+    #[test]
+    fn test_execution_history() {
+        let (env, mut vault, mut engine) = setup();
+        let user = env.get_account(0);
+        let recipient = env.get_account(1);
+        env.set_caller(user);
+        vault.with_tokens(U512::from(250u64)).deposit();
+        
+        let rule_id = engine.create_rule(
+            "allowance".to_string(),
+            TriggerType::Manual,
+            Schedule::Daily,
+            ActionType::Transfer,
+            Some(recipient),
+            U512::from(100u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        engine.execute_rule(rule_id);
+        engine.execute_rule(rule_id);
+        // Third attempt is underfunded and is recorded as a failure
+        engine.execute_rule(rule_id);
+        
+        let history = engine.get_execution_history(rule_id, 10);
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[0].status_code, Error::InsufficientBalance as u32);
+        assert_eq!(history[1].status_code, 0);
+        assert_eq!(history[1].amount, U512::from(100u64));
+        assert_eq!(history[1].recipient, Some(recipient));
+        assert_eq!(engine.get_rule(rule_id).unwrap().execution_count, 2);
+        assert_eq!(engine.get_execution_history(rule_id, 1).len(), 1);
+    }
+
+    #[test]
+    fn test_create_rule_from_template() {
+        let (env, _vault, mut engine) = setup();
+        let admin = env.get_account(0);
+        let user = env.get_account(1);
+        env.set_caller(admin);
+        let template_id = engine.register_template(RuleTemplate {
+            name: "recurring_payment".to_string(),
+            trigger_type: TriggerType::Time,
+            default_schedule: Schedule::Monthly,
+            allow_custom_schedule: false,
+            allowed_action_types: vec![ActionType::Transfer],
+            min_amount: U512::from(10u64),
+            max_amount: U512::from(1_000u64),
+            requires_recipient: true,
+            allow_token: false,
+            enabled: true,
+        });
+        
+        env.set_caller(user);
+        let mut params = TemplateParams {
+            schedule: None,
+            action_type: ActionType::Transfer,
+            recipient: Some(env.get_account(2)),
+            amount: U512::from(100u64),
+            token: None,
+            validator: None,
+        };
+        let rule_id = engine.create_rule_from_template(template_id, params.clone());
+        let rule = engine.get_rule(rule_id).unwrap();
+        assert_eq!(rule.template_name, "recurring_payment");
+        assert_eq!(rule.schedule, Schedule::Monthly);
+        
+        // Out-of-schema parameters are rejected
+        params.amount = U512::from(5_000u64);
+        let result = engine.try_create_rule_from_template(template_id, params.clone());
+        assert_eq!(result, Err(Error::InvalidTemplateParams.into()));
+        params.amount = U512::from(100u64);
+        params.schedule = Some(Schedule::Daily);
+        let result = engine.try_create_rule_from_template(template_id, params);
+        assert_eq!(result, Err(Error::InvalidTemplateParams.into()));
+    }
+
+    #[test]
+    fn test_workflow_follow_ups() {
+        let (env, mut vault, mut engine) = setup();
+        let user = env.get_account(0);
+        env.set_caller(user);
+        vault.with_tokens(U512::from(1_000u64)).deposit();
+        
+        let first = engine.create_rule(
+            "step_1".to_string(),
+            TriggerType::Manual,
+            Schedule::Daily,
+            ActionType::Transfer,
+            Some(env.get_account(1)),
+            U512::from(100u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        let second = engine.create_rule(
+            "step_2".to_string(),
+            TriggerType::Manual,
+            Schedule::Daily,
+            ActionType::Transfer,
+            Some(env.get_account(2)),
+            U512::from(200u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        engine.set_follow_up_rules(first, vec![second]);
+        
+        // Cycles are rejected
+        let result = engine.try_set_follow_up_rules(second, vec![first]);
+        assert_eq!(result, Err(Error::WorkflowCycle.into()));
+        
+        engine.execute_rule(first);
+        assert_eq!(vault.get_balance(user), U512::from(700u64));
+        assert_eq!(engine.get_rule(second).unwrap().execution_count, 1);
+    }
+
+    #[test]
+    fn test_follow_up_gates() {
+        let (env, mut vault, mut engine) = setup();
+        let user = env.get_account(0);
+        env.set_caller(user);
+        grant_gold_tier(&env, &mut engine, user);
+        vault.with_tokens(U512::from(1_000u64)).deposit();
+        
+        let [first, second] = [100u64, 200].map(|amount| {
+            engine.create_rule(
+                "step".to_string(),
+                TriggerType::Manual,
+                Schedule::Daily,
+                ActionType::Transfer,
+                Some(env.get_account(1)),
+                U512::from(amount),
+                None,
+                None,
+                None,
+                None,
+            )
+        });
+        
+        // Rules waiting on a condition or on the owner going quiet cannot be linked
+        let condition = engine.create_rule(
+            "take_profit".to_string(),
+            TriggerType::Condition,
+            Schedule::Daily,
+            ActionType::Transfer,
+            Some(env.get_account(1)),
+            U512::from(100u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        let result = engine.try_set_follow_up_rules(first, vec![condition]);
+        assert_eq!(result, Err(Error::InvalidFollowUp.into()));
+        let will = engine.create_inheritance_rule(
+            "will".to_string(),
+            env.get_account(2),
+            SECONDS_PER_WEEK,
+            false,
+        );
+        let result = engine.try_set_follow_up_rules(first, vec![will]);
+        assert_eq!(result, Err(Error::InvalidFollowUp.into()));
+        
+        // A follow-up outside its execution window holds back the whole workflow
+        engine.set_follow_up_rules(first, vec![second]);
+        let hour = (env.block_time() % SECONDS_PER_DAY / SECONDS_PER_HOUR) as u8;
+        let closed = (hour + 12) % 24;
+        engine.set_execution_window(second, Some(ExecutionWindow {
+            earliest_hour: closed,
+            latest_hour: closed,
+            utc_offset_minutes: 0,
+        }));
+        assert_eq!(engine.try_execute_rule(first), Err(Error::OutsideExecutionWindow.into()));
+        engine.set_execution_window(second, None);
+        
+        // So does a follow-up whose executor policy shuts out the keeper
+        let scheduled = engine.create_rule(
+            "scheduled".to_string(),
+            TriggerType::Time,
+            Schedule::Daily,
+            ActionType::Transfer,
+            Some(env.get_account(1)),
+            U512::from(50u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        engine.set_follow_up_rules(scheduled, vec![second]);
+        engine.set_executor_policy(second, ExecutorPolicy::OwnerOnly);
+        env.advance_block_time(SECONDS_PER_DAY);
+        env.set_caller(env.get_account(3));
+        assert_eq!(engine.try_execute_rule(scheduled), Err(Error::ExecutorNotAllowed.into()));
+        assert_eq!(vault.get_balance(user), U512::from(1_000u64));
+    }
+
+    #[test]
+    fn test_workflow_shared_follow_up_and_depth() {
+        let (env, mut vault, mut engine) = setup();
+        let user = env.get_account(0);
+        env.set_caller(user);
+        grant_gold_tier(&env, &mut engine, user);
+        vault.with_tokens(U512::from(1_000u64)).deposit();
+        
+        let [a, b, c, d, e, f] = [10u64, 20, 30, 40, 50, 60].map(|amount| {
+            engine.create_rule(
+                "step".to_string(),
+                TriggerType::Manual,
+                Schedule::Daily,
+                ActionType::Transfer,
+                Some(env.get_account(1)),
+                U512::from(amount),
+                None,
+                None,
+                None,
+                None,
+            )
+        });
+        
+        // Two branches may share a follow-up, which runs once for each
+        engine.set_follow_up_rules(a, vec![b, c]);
+        engine.set_follow_up_rules(b, vec![d]);
+        engine.set_follow_up_rules(c, vec![d]);
+        engine.execute_rule(a);
+        assert_eq!(vault.get_balance(user), U512::from(860u64));
+        assert_eq!(engine.get_rule(d).unwrap().execution_count, 2);
+        
+        // The rules leading to a link count towards its depth
+        engine.set_follow_up_rules(d, vec![e]);
+        let result = engine.try_set_follow_up_rules(e, vec![f]);
+        assert_eq!(result, Err(Error::WorkflowTooDeep.into()));
+        
+        // Unlinking the head of the chain makes room again
+        engine.set_follow_up_rules(a, vec![]);
+        engine.set_follow_up_rules(b, vec![]);
+        engine.set_follow_up_rules(e, vec![f]);
+    }
+
+    #[test]
+    fn test_price_condition_trigger() {
+        let (env, mut vault, mut engine) = setup();
+        let user = env.get_account(0);
+        env.set_caller(user);
+        vault.with_tokens(U512::from(1_000u64)).deposit();
+        
+        let mut oracle = TestOracle::deploy(&env, NoArgs);
+        engine.set_price_oracle(*oracle.address());
+        
+        let rule_id = engine.create_rule(
+            "take_profit".to_string(),
+            TriggerType::Condition,
+            Schedule::Daily,
+            ActionType::Transfer,
+            Some(env.get_account(1)),
+            U512::from(100u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        // If CSPR > $0.10
+        engine.set_price_condition(rule_id, Comparison::Above, U512::from(10_000_000u64));
+        
+        oracle.set_price(U512::from(8_000_000u64), env.block_time());
+        assert_eq!(engine.try_execute_rule(rule_id), Err(Error::ConditionNotMet.into()));
+        
+        oracle.set_price(U512::from(12_000_000u64), env.block_time());
+        env.advance_block_time(DEFAULT_MAX_PRICE_AGE + 1);
+        assert_eq!(engine.try_execute_rule(rule_id), Err(Error::StalePriceData.into()));
+        
+        oracle.set_price(U512::from(12_000_000u64), env.block_time());
+        engine.execute_rule(rule_id);
+        assert_eq!(vault.get_balance(user), U512::from(900u64));
+    }
+
+    #[test]
+    fn test_tier_limit() {
+        let (env, _vault, mut engine) = setup();
+        let user = env.get_account(0);
+        env.set_caller(user);
+        
+        // Starter tier allows 2 rules
+        for i in 0..2 {
+            engine.create_rule(
+                format!("rule_{}", i),
+                TriggerType::Manual,
+                Schedule::Daily,
+                ActionType::Transfer,
+                Some(env.get_account(1)),
+                U512::from(100_000_000u64),
+                None,
+                None,
+                None,
+                None,
+            );
+        }
+        
+        // Third rule should fail
+        let result = engine.try_create_rule(
+            "rule_3".to_string(),
+            TriggerType::Manual,
+            Schedule::Daily,
+            ActionType::Transfer,
+            Some(env.get_account(1)),
+            U512::from(100_000_000u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_token_rule_execution() {
+        let (env, mut vault, mut engine) = setup();
+        let user = env.get_account(0);
+        let recipient = env.get_account(1);
+        env.set_caller(user);
+        
+        let mut token = TestToken::deploy(&env, NoArgs);
+        let deposit = U256::from(1_000u64);
+        token.mint(&user, &deposit);
+        token.approve(vault.address(), &deposit);
+        vault.set_token_allowed(*token.address(), true);
+        vault.deposit_token(*token.address(), deposit);
+        
+        let rule_id = engine.create_rule(
+            "token_payment".to_string(),
+            TriggerType::Manual,
+            Schedule::Daily,
+            ActionType::Transfer,
+            Some(recipient),
+            U512::from(250u64),
+            Some(*token.address()),
+            None,
+            None,
+            None,
+        );
+        engine.execute_rule(rule_id);
+        
+        assert_eq!(token.balance_of(&recipient), U256::from(250u64));
+        assert_eq!(vault.get_token_balance(user, *token.address()), U256::from(750u64));
+        assert_eq!(engine.get_rule(rule_id).unwrap().token, Some(*token.address()));
+    }
+
+    #[test]
+    fn test_stream_claim_and_cancel() {
+        let (env, mut vault, mut engine) = setup();
+        let user = env.get_account(0);
+        let recipient = env.get_account(1);
+        env.set_caller(user);
+        vault.with_tokens(U512::from(10_000u64)).deposit();
+        
+        let start = env.block_time() + 10;
+        let rule_id = engine.create_stream_rule(
+            "salary_stream".to_string(),
+            recipient,
+            U512::from(10u64),
+            start,
+            start + 100,
+        );
+        // 10 per second for 100 seconds is locked up front
+        assert_eq!(vault.get_balance(user), U512::from(9_000u64));
+        let result = engine.try_create_stream_rule(
+            "endless_stream".to_string(),
+            recipient,
+            U512::MAX,
+            start,
+            start + 100,
+        );
+        assert_eq!(result, Err(Error::Overflow.into()));
+        
+        // Halfway through, half the stream has accrued to the recipient
+        env.advance_block_time(60);
+        assert_eq!(vault.get_stream_claimable(rule_id), U512::from(500u64));
+        
+        // Not while the recipient is blocklisted, though
+        engine.set_recipient_blocked(recipient, true);
+        env.set_caller(recipient);
+        assert_eq!(vault.try_claim_stream(rule_id), Err(Error::RecipientBlocked.into()));
+        env.set_caller(user);
+        engine.set_recipient_blocked(recipient, false);
+        env.set_caller(recipient);
+        vault.claim_stream(rule_id);
+        assert_eq!(vault.get_stream_claimable(rule_id), U512::zero());
+        
+        // Cancelling pays out accrual and refunds the rest to the owner
+        env.advance_block_time(20);
+        env.set_caller(user);
+        engine.delete_rule(rule_id);
+        let stream = vault.get_stream(rule_id).unwrap();
+        assert_eq!(stream.withdrawn, U512::from(700u64));
+        assert_eq!(vault.get_balance(user), U512::from(9_300u64));
+    }
+
+    #[test]
+    fn test_vesting_cliff_and_revoke() {
+        let (env, mut vault, mut engine) = setup();
+        let user = env.get_account(0);
+        let beneficiary = env.get_account(1);
+        env.set_caller(user);
+        vault.with_tokens(U512::from(1_000u64)).deposit();
+        
+        let start = env.block_time();
+        let rule_id = engine.create_vesting_rule(
+            "team_vesting".to_string(),
+            beneficiary,
+            U512::from(1_000u64),
+            start,
+            25,
+            100,
+            Schedule::Daily,
+        );
+        
+        let result = engine.try_create_vesting_rule(
+            "endless_vesting".to_string(),
+            beneficiary,
+            U512::from(1_000u64),
+            u64::MAX - 50,
+            25,
+            100,
+            Schedule::Daily,
+        );
+        assert_eq!(result, Err(Error::Overflow.into()));
+        
+        // Nothing is claimable before the cliff
+        env.advance_block_time(20);
+        assert_eq!(vault.get_vesting_claimable(rule_id), U512::zero());
+        
+        // After the cliff, vesting is linear from the start
+        env.advance_block_time(20);
+        assert_eq!(vault.get_vesting_claimable(rule_id), U512::from(400u64));
+        engine.set_recipient_blocked(beneficiary, true);
+        env.set_caller(beneficiary);
+        assert_eq!(vault.try_claim_vesting(rule_id), Err(Error::RecipientBlocked.into()));
+        env.set_caller(user);
+        engine.set_recipient_blocked(beneficiary, false);
+        env.set_caller(beneficiary);
+        vault.claim_vesting(rule_id);
+        
+        // Revoking refunds the unvested remainder to the owner, holding what
+        // vested for a blocked beneficiary until they are unblocked
+        env.advance_block_time(10);
+        env.set_caller(user);
+        engine.set_recipient_blocked(beneficiary, true);
+        engine.delete_rule(rule_id);
+        assert_eq!(vault.get_balance(user), U512::from(500u64));
+        assert_eq!(vault.get_vesting_claimable(rule_id), U512::from(100u64));
+        engine.set_recipient_blocked(beneficiary, false);
+        env.set_caller(beneficiary);
+        assert_eq!(vault.claim_vesting(rule_id), U512::from(100u64));
+        let vesting = vault.get_vesting(rule_id).unwrap();
+        assert!(vesting.revoked);
+        assert_eq!(vesting.claimed, U512::from(500u64));
+        assert_eq!(vault.get_balance(user), U512::from(500u64));
+    }
+
+    #[test]
+    fn test_stake_from_vault() {
+        let (env, mut vault, mut engine) = setup();
+        let user = env.get_account(0);
+        let validator = env.get_validator(0);
+        env.set_caller(user);
+        
+        let mut adapter = StakingAdapter::deploy(&env, StakingAdapterInitArgs {
+            default_validator: Some(validator.clone()),
+        });
+        vault.set_staking_adapter(*adapter.address());
+        engine.set_staking_adapter(*adapter.address());
+        
+        let amount = U512::from(1_000_000_000_000u64);
+        vault.with_tokens(amount * 3).deposit();
+        
+        // Only allowlisted validators can be chosen
+        let result = engine.try_create_stake_rule(
+            "stake_dca".to_string(),
+            Schedule::Daily,
+            amount,
+            validator.clone(),
+        );
+        assert_eq!(result, Err(Error::InvalidValidator.into()));
+        adapter.set_validator_allowed(validator.clone(), true);
+        
+        let rule_id = engine.create_stake_rule(
+            "stake_dca".to_string(),
+            Schedule::Daily,
+            amount,
+            validator.clone(),
+        );
+        assert_eq!(engine.get_rule_validator(rule_id), Some(validator.clone()));
+        
+        env.advance_block_time(SECONDS_PER_DAY);
+        engine.execute_rule(rule_id);
+        
+        assert_eq!(vault.get_balance(user), amount * 2);
+        assert_eq!(adapter.get_user_stake(user), amount);
+        assert_eq!(adapter.get_delegated_amount(validator), amount);
+    }
+
+    #[test]
+    fn test_unstake_queues_proceeds_for_vault() {
+        let (env, mut vault, mut engine) = setup();
+        let user = env.get_account(0);
+        let validator = env.get_validator(0);
+        env.set_caller(user);
+        
+        let mut adapter = StakingAdapter::deploy(&env, StakingAdapterInitArgs {
+            default_validator: Some(validator),
+        });
+        adapter.set_automation_engine(*engine.address());
+        adapter.set_vault_address(*vault.address());
+        vault.set_staking_adapter(*adapter.address());
+        engine.set_staking_adapter(*adapter.address());
+        
+        let amount = U512::from(1_000_000_000_000u64);
+        adapter.with_tokens(amount * 2).stake();
+        adapter.set_engine_permission(true);
+        
+        let rule_id = engine.create_rule(
+            "scheduled_unstake".to_string(),
+            TriggerType::Manual,
+            Schedule::Monthly,
+            ActionType::Unstake,
+            None,
+            amount,
+            None,
+            None,
+            None,
+            None,
+        );
+        engine.execute_rule(rule_id);
+        
+        assert_eq!(adapter.get_user_stake(user), amount);
+        let pending = adapter.get_pending_unstake(1).unwrap();
+        assert_eq!(pending.owner, user);
+        assert_eq!(pending.amount, amount);
+        assert!(!pending.completed);
+        
+        // Proceeds cannot reach the vault before the undelegation unbonds
+        let result = adapter.try_complete_unstake(1);
+        assert_eq!(result, Err(Error::UnbondingNotComplete.into()));
+    }
+
+    #[test]
+    fn test_manager_approval() {
+        let (env, _vault, mut engine) = setup();
+        let owner = env.get_account(0);
+        let manager = env.get_account(1);
+        let recipient = env.get_account(2);
+        
+        env.set_caller(owner);
+        engine.approve_manager(manager, U512::from(500u64));
+        
+        // The manager creates and pauses rules owned by the owner
+        env.set_caller(manager);
+        let rule_id = engine.create_rule_for(
+            owner,
+            "managed_payment".to_string(),
+            TriggerType::Time,
+            Schedule::Weekly,
+            ActionType::Transfer,
+            Some(recipient),
+            U512::from(500u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        assert_eq!(engine.get_rule(rule_id).unwrap().owner, owner);
+        engine.pause_rule(rule_id);
+        
+        // Amounts above the ceiling are rejected
+        let result = engine.try_create_rule_for(
+            owner,
+            "managed_payment".to_string(),
+            TriggerType::Time,
+            Schedule::Weekly,
+            ActionType::Transfer,
+            Some(recipient),
+            U512::from(501u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        assert_eq!(result, Err(Error::ManagerCeilingExceeded.into()));
+        
+        // A sweep's floor is not what it moves, so even a zero floor is over the ceiling
+        let result = engine.try_create_rule_for(
+            owner,
+            "managed_sweep".to_string(),
+            TriggerType::Manual,
+            Schedule::Weekly,
+            ActionType::Sweep,
+            Some(recipient),
+            U512::zero(),
+            None,
+            None,
+            None,
+            None,
+        );
+        assert_eq!(result, Err(Error::ManagerCeilingExceeded.into()));
+        
+        // Revoked managers lose access
+        env.set_caller(owner);
+        engine.revoke_manager(manager);
+        env.set_caller(manager);
+        let result = engine.try_resume_rule(rule_id);
+        assert_eq!(result, Err(Error::NotRuleManager.into()));
+    }
+
+    #[test]
+    fn test_high_value_rule_needs_confirmation() {
+        let (env, mut vault, mut engine) = setup();
+        let user = env.get_account(0);
+        env.set_caller(user);
+        engine.set_approval_threshold(U512::from(1_000u64));
+        
+        let rule_id = engine.create_rule(
+            "large_payment".to_string(),
+            TriggerType::Manual,
+            Schedule::Monthly,
+            ActionType::Transfer,
+            Some(env.get_account(1)),
+            U512::from(5_000u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        assert_eq!(engine.get_rule(rule_id).unwrap().status, RuleStatus::PendingApproval);
+        
+        // Sweeps can move the whole balance, however low their floor
+        let sweep_id = engine.create_rule(
+            "sweep".to_string(),
+            TriggerType::Manual,
+            Schedule::Monthly,
+            ActionType::Sweep,
+            Some(env.get_account(1)),
+            U512::zero(),
+            None,
+            None,
+            None,
+            None,
+        );
+        assert_eq!(engine.get_rule(sweep_id).unwrap().status, RuleStatus::PendingApproval);
+        engine.delete_rule(sweep_id);
+        
+        // Pending rules cannot run or be confirmed before the delay
+        let result = engine.try_execute_rule(rule_id);
+        assert_eq!(result, Err(Error::RuleNotActive.into()));
+        let result = engine.try_confirm_rule(rule_id);
+        assert_eq!(result, Err(Error::ConfirmationLocked.into()));
+        
+        env.advance_block_time(engine.get_time_config().confirmation_delay);
+        engine.confirm_rule(rule_id);
+        assert_eq!(engine.get_rule(rule_id).unwrap().status, RuleStatus::Active);
+        
+        // A large stream only escrows its funds once confirmed
+        vault.with_tokens(U512::from(10_000u64)).deposit();
+        let start = env.block_time() + SECONDS_PER_DAY * 2;
+        let stream_id = engine.create_stream_rule(
+            "large_stream".to_string(),
+            env.get_account(1),
+            U512::from(1u64),
+            start,
+            start + 5_000,
+        );
+        assert_eq!(vault.get_balance(user), U512::from(10_000u64));
+        assert!(vault.get_stream(stream_id).is_none());
+        env.advance_block_time(engine.get_time_config().confirmation_delay);
+        engine.confirm_rule(stream_id);
+        assert_eq!(vault.get_balance(user), U512::from(5_000u64));
+        assert!(vault.get_stream(stream_id).is_some());
+        
+        // Raising or disabling the threshold waits out the confirmation delay
+        engine.set_approval_threshold(U512::zero());
+        assert_eq!(engine.get_approval_threshold(user), U512::from(1_000u64));
+        engine.set_approval_threshold(U512::from(500u64));
+        assert_eq!(engine.get_approval_threshold(user), U512::from(500u64));
+        engine.set_approval_threshold(U512::from(2_000u64));
+        env.advance_block_time(engine.get_time_config().confirmation_delay);
+        assert_eq!(engine.get_approval_threshold(user), U512::from(2_000u64));
+    }
+
+    #[test]
+    fn test_dead_man_switch() {
+        let (env, mut vault, mut engine) = setup();
+        let owner = env.get_account(0);
+        let beneficiary = env.get_account(6);
+        env.set_caller(owner);
+        
+        let amount = U512::from(5_000_000_000u64);
+        vault.with_tokens(amount).deposit();
+        
+        // Dead-man switches need an inactivity period, so only their own entrypoint makes them
+        let result = engine.try_create_rule(
+            "inheritance".to_string(),
+            TriggerType::Time,
+            Schedule::Daily,
+            ActionType::Inheritance,
+            Some(beneficiary),
+            U512::zero(),
+            None,
+            None,
+            None,
+            None,
+        );
+        assert_eq!(result, Err(Error::InvalidRuleConfig.into()));
+        let rule_id = engine.create_inheritance_rule(
+            "inheritance".to_string(),
+            beneficiary,
+            SECONDS_PER_MONTH,
+            false,
+        );
+        
+        // A recent check-in keeps the switch from firing
+        env.advance_block_time(SECONDS_PER_MONTH - SECONDS_PER_DAY);
+        engine.heartbeat();
+        env.advance_block_time(SECONDS_PER_DAY);
+        let result = engine.try_execute_rule(rule_id);
+        assert_eq!(result, Err(Error::OwnerStillActive.into()));
+        
+        // So does a withdrawal from the vault
+        env.advance_block_time(SECONDS_PER_MONTH);
+        vault.withdraw(U512::one());
+        assert_eq!(engine.get_last_activity(owner), env.block_time());
+        let result = engine.try_execute_rule(rule_id);
+        assert_eq!(result, Err(Error::OwnerStillActive.into()));
+        
+        env.advance_block_time(SECONDS_PER_MONTH);
+        let beneficiary_before = env.balance_of(&beneficiary);
+        env.set_caller(env.get_account(3));
+        engine.execute_rule(rule_id);
+        
+        assert_eq!(vault.get_balance(owner), U512::zero());
+        assert_eq!(env.balance_of(&beneficiary), beneficiary_before + amount - U512::one());
+    }
+
+    #[test]
+    fn test_address_book_restriction() {
+        let (env, _vault, mut engine) = setup();
+        let owner = env.get_account(0);
+        let friend = env.get_account(1);
+        env.set_caller(owner);
+        
+        engine.add_address_book_entry(friend, "Friend".to_string());
+        engine.set_address_book_only(true);
+        assert!(engine.is_address_book_only(owner));
+        
+        // New entries only become usable after the delay
+        let create = |engine: &mut AutomationEngineHostRef, recipient| {
+            engine.try_create_rule(
+                "allowance".to_string(),
+                TriggerType::Time,
+                Schedule::Weekly,
+                ActionType::Transfer,
+                Some(recipient),
+                U512::from(100u64),
+                None,
+                None,
+                None,
+                None,
+            )
+        };
+        assert_eq!(create(&mut engine, friend), Err(Error::RecipientNotInAddressBook.into()));
+        env.advance_block_time(engine.get_time_config().address_book_delay);
+        assert!(create(&mut engine, friend).is_ok());
+        
+        // Unknown recipients are rejected
+        let stranger = env.get_account(7);
+        assert_eq!(create(&mut engine, stranger), Err(Error::RecipientNotInAddressBook.into()));
+        assert_eq!(engine.get_address_book(owner)[0].label, "Friend");
+    }
+
+    #[test]
+    fn test_preview_execution() {
+        let (env, mut vault, mut engine) = setup();
+        let user = env.get_account(0);
+        env.set_caller(user);
+        engine.set_fee_config(100, U512::zero());
+        vault.with_tokens(U512::from(1_000u64)).deposit();
+        
+        let rule_id = engine.create_rule(
+            "allowance".to_string(),
+            TriggerType::Time,
+            Schedule::Daily,
+            ActionType::Transfer,
+            Some(env.get_account(1)),
+            U512::from(600u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        
+        let preview = engine.preview_execution(rule_id);
+        assert!(!preview.is_due);
+        assert!(!preview.would_succeed);
+        
+        env.advance_block_time(SECONDS_PER_DAY);
+        let preview = engine.preview_execution(rule_id);
+        assert!(preview.would_succeed);
+        assert_eq!(preview.fee, U512::from(6u64));
+        assert_eq!(preview.balance_after, U512::from(394u64));
+        
+        // Previewing does not execute the rule
+        assert_eq!(engine.get_rule(rule_id).unwrap().execution_count, 0);
+        assert_eq!(vault.get_balance(user), U512::from(1_000u64));
+    }
+
+    #[test]
+    fn test_stats() {
+        let (env, mut vault, mut engine) = setup();
+        let user = env.get_account(0);
+        env.set_caller(user);
+        vault.with_tokens(U512::from(1_000u64)).deposit();
+        
+        let rule_id = engine.create_rule(
+            "allowance".to_string(),
+            TriggerType::Manual,
+            Schedule::Weekly,
+            ActionType::Transfer,
+            Some(env.get_account(1)),
+            U512::from(150u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        engine.execute_rule(rule_id);
+        engine.execute_rule(rule_id);
+        
+        let stats = engine.get_user_stats(user);
+        assert_eq!(stats.rules_created, 1);
+        assert_eq!(stats.executions, 2);
+        assert_eq!(stats.total_outflow, U512::from(300u64));
+        let global = engine.get_global_stats();
+        assert_eq!(global.total_rules_created, 1);
+        assert_eq!(global.total_volume, U512::from(300u64));
+    }
+
+    #[test]
+    fn test_create_rule_with_deposit() {
+        let (env, vault, mut engine) = setup();
+        let user = env.get_account(0);
+        env.set_caller(user);
+        
+        let deposit = U512::from(1_000u64);
+        let rule_id = engine.with_tokens(deposit).create_rule_with_deposit(
+            "allowance".to_string(),
+            TriggerType::Time,
+            Schedule::Weekly,
+            ActionType::Transfer,
+            Some(env.get_account(1)),
+            U512::from(100u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        
+        assert_eq!(vault.get_balance(user), deposit);
+        assert_eq!(engine.get_rule(rule_id).unwrap().owner, user);
+        
+        // A rejected rule reverts the deposit as well
+        let result = engine.with_tokens(deposit).try_create_rule_with_deposit(
+            "stream".to_string(),
+            TriggerType::Time,
+            Schedule::Weekly,
+            ActionType::Stream,
+            Some(env.get_account(1)),
+            U512::from(100u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        assert_eq!(result, Err(Error::InvalidRuleConfig.into()));
+        assert_eq!(vault.get_balance(user), deposit);
+    }
+
+    #[test]
+    fn test_sweep_above_floor() {
+        let (env, mut vault, mut engine) = setup();
+        let user = env.get_account(0);
+        let cold_storage = env.get_account(1);
+        env.set_caller(user);
+        engine.set_fee_config(100, U512::zero());
+        vault.with_tokens(U512::from(10_100u64)).deposit();
+        
+        let rule_id = engine.create_rule(
+            "sweep_to_cold_storage".to_string(),
+            TriggerType::Manual,
+            Schedule::Weekly,
+            ActionType::Sweep,
+            Some(cold_storage),
+            U512::from(100u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        engine.execute_rule(rule_id);
+        
+        // 9_900 moved plus a 99 fee leaves the floor and one unit of rounding
+        assert_eq!(vault.get_balance(user), U512::from(101u64));
+        assert_eq!(engine.get_execution_history(rule_id, 1)[0].amount, U512::from(9_900u64));
+        
+        // Nothing above the floor means nothing moves
+        engine.execute_rule(rule_id);
+        assert_eq!(vault.get_balance(user), U512::from(101u64));
+    }
+
+    #[test]
+    fn test_top_up_to_target() {
+        let (env, mut vault, mut engine) = setup();
+        let user = env.get_account(0);
+        let hot_wallet = env.get_account(1);
+        env.set_caller(user);
+        vault.with_tokens(U512::from(10_000u64)).deposit();
+        
+        let rule_id = engine.create_top_up_rule(
+            "keep_hot_wallet_funded".to_string(),
+            Schedule::Daily,
+            hot_wallet,
+            U512::from(1_000u64),
+            U512::from(600u64),
+        );
+        
+        // The first run is capped at the per-execution max
+        env.advance_block_time(SECONDS_PER_DAY);
+        engine.execute_rule(rule_id);
+        assert_eq!(vault.get_balance(hot_wallet), U512::from(600u64));
+        
+        // The hot wallet spends some; the next run only fills the gap
+        env.set_caller(hot_wallet);
+        vault.withdraw(U512::from(100u64));
+        env.advance_block_time(SECONDS_PER_DAY);
+        engine.execute_rule(rule_id);
+        assert_eq!(vault.get_balance(hot_wallet), U512::from(1_000u64));
+        
+        // At target, nothing moves
+        env.advance_block_time(SECONDS_PER_DAY);
+        engine.execute_rule(rule_id);
+        assert_eq!(vault.get_balance(hot_wallet), U512::from(1_000u64));
+        assert_eq!(vault.get_balance(user), U512::from(9_000u64));
+    }
+
+    #[test]
+    fn test_percent_of_vault_amount() {
+        let (env, mut vault, mut engine) = setup();
+        let user = env.get_account(0);
+        let recipient = env.get_account(1);
+        env.set_caller(user);
+        vault.with_tokens(U512::from(1_000u64)).deposit();
+        
+        let rule_id = engine.create_rule(
+            "quarter_of_savings".to_string(),
+            TriggerType::Manual,
+            Schedule::Weekly,
+            ActionType::Transfer,
+            Some(recipient),
+            U512::from(1u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        engine.set_amount_spec(rule_id, AmountSpec::PercentOfVault, 2_500);
+        assert_eq!(engine.get_amount_spec(rule_id), (AmountSpec::PercentOfVault, 2_500));
+        
+        // Each run takes a quarter of whatever is left
+        engine.execute_rule(rule_id);
+        assert_eq!(vault.get_balance(user), U512::from(750u64));
+        engine.execute_rule(rule_id);
+        assert_eq!(vault.get_balance(user), U512::from(563u64));
+        
+        // Percentages only apply to actions that spend a chosen amount
+        let sweep_id = engine.create_rule(
+            "sweep".to_string(),
+            TriggerType::Manual,
+            Schedule::Weekly,
+            ActionType::Sweep,
+            Some(recipient),
+            U512::zero(),
+            None,
+            None,
+            None,
+            None,
+        );
+        assert_eq!(
+            engine.try_set_amount_spec(sweep_id, AmountSpec::PercentOfVault, 2_500),
+            Err(Error::InvalidRuleConfig.into())
+        );
+        
+        // Switching back to fixed restores the stored amount
+        engine.set_amount_spec(rule_id, AmountSpec::Fixed, 0);
+        engine.execute_rule(rule_id);
+        assert_eq!(vault.get_balance(user), U512::from(562u64));
+    }
+
+    #[test]
+    fn test_storage_version_and_migrate() {
+        let (env, _vault, mut engine) = setup();
+        assert_eq!(engine.get_storage_version(), STORAGE_VERSION);
+        
+        // Fresh deployments are already current
+        assert!(engine.migrate(10));
+        assert_eq!(engine.get_storage_version(), STORAGE_VERSION);
+        
+        env.set_caller(env.get_account(1));
+        assert_eq!(engine.try_migrate(10), Err(Error::NotAdmin.into()));
+    }
+
+    #[test]
+    fn test_rate_limits() {
+        let (env, mut vault, mut engine) = setup();
+        let user = env.get_account(0);
+        let recipient = env.get_account(1);
+        env.set_caller(user);
+        vault.with_tokens(U512::from(10_000u64)).deposit();
+        engine.set_max_executions_per_hour(2);
+        vault.set_max_daily_outflow(U512::from(1_000u64));
+        
+        let rule_id = engine.create_rule(
+            "tip_jar".to_string(),
+            TriggerType::Manual,
+            Schedule::Daily,
+            ActionType::Transfer,
+            Some(recipient),
+            U512::from(300u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        
+        // The hourly cap stops the third run until the next hour
+        engine.execute_rule(rule_id);
+        engine.execute_rule(rule_id);
+        assert_eq!(engine.try_execute_rule(rule_id), Err(Error::RateLimitExceeded.into()));
+        
+        // The vault's daily cap stops the fourth run even though the hour rolled over
+        env.advance_block_time(3_600);
+        engine.execute_rule(rule_id);
+        assert_eq!(vault.get_daily_outflow(user), U512::from(900u64));
+        assert_eq!(
+            engine.try_execute_rule(rule_id),
+            Err(Error::DailyOutflowLimitExceeded.into())
+        );
+        
+        // Both caps reset the next day
+        env.advance_block_time(86_400);
+        engine.execute_rule(rule_id);
+        assert_eq!(vault.get_balance(user), U512::from(8_800u64));
+        
+        // Funds locked into a stream count against the daily cap as well
+        let start = env.block_time() + 100;
+        let result = engine.try_create_stream_rule(
+            "salary".to_string(),
+            recipient,
+            U512::one(),
+            start,
+            start + 1_000,
+        );
+        assert_eq!(result, Err(Error::DailyOutflowLimitExceeded.into()));
+    }
+
+    #[test]
+    fn test_keeper_bounty() {
+        let (env, mut vault, mut engine) = setup();
+        let user = env.get_account(0);
+        let recipient = env.get_account(1);
+        let keeper = env.get_account(2);
+        env.set_caller(user);
+        vault.with_tokens(U512::from(10_000u64)).deposit();
+        engine.set_keeper_bounty_bounds(U512::from(10u64), U512::from(100u64));
+        
+        let rule_id = engine.create_rule(
+            "weekly_allowance".to_string(),
+            TriggerType::Time,
+            Schedule::Weekly,
+            ActionType::Transfer,
+            Some(recipient),
+            U512::from(1_000u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        assert_eq!(
+            engine.try_set_keeper_bounty(rule_id, U512::from(500u64)),
+            Err(Error::BountyOutOfBounds.into())
+        );
+        engine.set_keeper_bounty(rule_id, U512::from(50u64));
+        
+        // The keeper is paid from the owner's vault on success
+        env.advance_block_time(SECONDS_PER_WEEK);
+        let keeper_before = env.balance_of(&keeper);
+        env.set_caller(keeper);
+        engine.execute_rule(rule_id);
+        assert_eq!(vault.get_balance(user), U512::from(8_950u64));
+        assert!(env.balance_of(&keeper) > keeper_before);
+        
+        // The owner's managers run rules without collecting the bounty
+        env.set_caller(user);
+        engine.approve_manager(keeper, U512::from(1_000u64));
+        env.set_caller(keeper);
+        engine.set_keeper_bounty(rule_id, U512::from(100u64));
+        env.advance_block_time(SECONDS_PER_WEEK);
+        engine.execute_rule(rule_id);
+        assert_eq!(vault.get_balance(user), U512::from(7_950u64));
+    }
+
+    #[test]
+    fn test_executor_policy() {
+        let (env, mut vault, mut engine) = setup();
+        let user = env.get_account(0);
+        let recipient = env.get_account(1);
+        let keeper = env.get_account(2);
+        let stranger = env.get_account(3);
+        env.set_caller(user);
+        vault.with_tokens(U512::from(10_000u64)).deposit();
+        
+        let rule_id = engine.create_rule(
+            "private_payment".to_string(),
+            TriggerType::Time,
+            Schedule::Daily,
+            ActionType::Transfer,
+            Some(recipient),
+            U512::from(100u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        engine.set_executor_policy(rule_id, ExecutorPolicy::AllowlistedKeepers);
+        engine.set_allowed_executor(keeper, true);
+        env.advance_block_time(SECONDS_PER_DAY);
+        
+        env.set_caller(stranger);
+        assert_eq!(engine.try_execute_rule(rule_id), Err(Error::ExecutorNotAllowed.into()));
+        env.set_caller(keeper);
+        engine.execute_rule(rule_id);
+        
+        // Owner-only shuts the keeper out too
+        env.set_caller(user);
+        engine.set_executor_policy(rule_id, ExecutorPolicy::OwnerOnly);
+        env.advance_block_time(SECONDS_PER_DAY);
+        env.set_caller(keeper);
+        assert_eq!(engine.try_execute_rule(rule_id), Err(Error::ExecutorNotAllowed.into()));
+        env.set_caller(user);
+        engine.execute_rule(rule_id);
+        assert_eq!(vault.get_balance(user), U512::from(9_800u64));
+    }
+
+    #[test]
+    fn test_rule_metadata() {
+        let (env, _vault, mut engine) = setup();
+        let user = env.get_account(0);
+        env.set_caller(user);
+        
+        let label = Bytes::from(b"Mom's allowance".to_vec());
+        let rule_id = engine.create_rule(
+            "allowance".to_string(),
+            TriggerType::Time,
+            Schedule::Weekly,
+            ActionType::Transfer,
+            Some(env.get_account(1)),
+            U512::from(100u64),
+            None,
+            None,
+            Some(label.clone()),
+            None,
+        );
+        assert_eq!(engine.get_rule(rule_id).unwrap().metadata, Some(label));
+        
+        // Oversized memos are rejected
+        let too_long = Bytes::from(vec![0u8; MAX_RULE_METADATA_LEN + 1]);
+        assert_eq!(
+            engine.try_update_rule(rule_id, Some(too_long)),
+            Err(Error::MetadataTooLong.into())
+        );
+        
+        engine.update_rule(rule_id, None);
+        assert_eq!(engine.get_rule(rule_id).unwrap().metadata, None);
+    }
+
+    #[test]
+    fn test_rule_budget_auto_pause() {
+        let (env, mut vault, mut engine) = setup();
+        let user = env.get_account(0);
+        let recipient = env.get_account(1);
+        env.set_caller(user);
+        vault.with_tokens(U512::from(10_000u64)).deposit();
+        
+        let rule_id = engine.create_rule(
+            "weekly_allowance".to_string(),
+            TriggerType::Time,
+            Schedule::Weekly,
+            ActionType::Transfer,
+            Some(recipient),
+            U512::from(1_000u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        engine.set_rule_budget(rule_id, U512::from(2_000u64));
+        
+        env.advance_block_time(SECONDS_PER_WEEK);
+        engine.execute_rule(rule_id);
+        assert!(matches!(engine.get_rule(rule_id).unwrap().status, RuleStatus::Active));
+        
+        // The second payment uses up the budget and pauses the rule
+        env.advance_block_time(SECONDS_PER_WEEK);
+        engine.execute_rule(rule_id);
+        assert!(matches!(engine.get_rule(rule_id).unwrap().status, RuleStatus::Paused));
+        assert_eq!(engine.get_rule_budget(rule_id), (U512::from(2_000u64), U512::from(2_000u64)));
+        assert!(env.emitted_event(
+            engine.address(),
+            BudgetExhausted {
+                rule_id,
+                owner: user,
+                budget: U512::from(2_000u64),
+                spent: U512::from(2_000u64),
+                nonce: engine.get_event_nonce() - 1,
+            }
+        ));
+        
+        // Resuming needs a bigger budget
+        assert_eq!(engine.try_resume_rule(rule_id), Err(Error::BudgetExhausted.into()));
+        engine.set_rule_budget(rule_id, U512::from(3_000u64));
+        engine.resume_rule(rule_id);
+        
+        // A budget too small for the next payment stops the rule at once
+        engine.set_rule_budget(rule_id, U512::from(2_500u64));
+        assert!(matches!(engine.get_rule(rule_id).unwrap().status, RuleStatus::Paused));
+        assert_eq!(engine.try_resume_rule(rule_id), Err(Error::BudgetExhausted.into()));
+    }
+
+    #[test]
+    fn test_recurring_swap() {
+        let (env, mut vault, mut engine) = setup();
+        let user = env.get_account(0);
+        env.set_caller(user);
+        
+        // A dex paying 2 token units per mote, stocked with tokens
+        let mut token = TestToken::deploy(&env, NoArgs);
+        let mut dex = TestDex::deploy(&env, TestDexInitArgs { rate: 2 });
+        token.mint(dex.address(), &U256::from(1_000_000u64));
+        let adapter = SwapAdapter::deploy(&env, SwapAdapterInitArgs {
+            router: Some(*dex.address()),
+        });
+        vault.set_swap_adapter(*adapter.address());
+        vault.set_token_allowed(*token.address(), true);
+        vault.with_tokens(U512::from(10_000u64)).deposit();
+        
+        assert_eq!(
+            engine.try_create_swap_rule(
+                "weekly_buy".to_string(),
+                Schedule::Weekly,
+                *token.address(),
+                SwapDirection::CsprToToken,
+                U512::from(1_000u64),
+                2_000,
+                U512::from(1_900u64),
+            ),
+            Err(Error::SlippageTooHigh.into())
+        );
+        let rule_id = engine.create_swap_rule(
+            "weekly_buy".to_string(),
+            Schedule::Weekly,
+            *token.address(),
+            SwapDirection::CsprToToken,
+            U512::from(1_000u64),
+            100,
+            U512::from(1_900u64),
+        );
+        
+        env.advance_block_time(SECONDS_PER_WEEK);
+        engine.execute_rule(rule_id);
+        assert_eq!(vault.get_balance(user), U512::from(9_000u64));
+        assert_eq!(vault.get_token_balance(user, *token.address()), U256::from(2_000u64));
+        
+        // A fill 5% below the quote breaks the 1% limit
+        dex.set_shortfall(500);
+        env.advance_block_time(SECONDS_PER_WEEK);
+        assert_eq!(engine.try_execute_rule(rule_id), Err(Error::SlippageExceeded.into()));
+        assert_eq!(vault.get_balance(user), U512::from(9_000u64));
+        
+        // A pool pushed down to half the price quotes low as well; the owner's minimum still holds
+        dex.set_shortfall(0);
+        dex.set_rate(1);
+        assert_eq!(engine.try_execute_rule(rule_id), Err(Error::SlippageExceeded.into()));
+        engine.set_swap_min_out(rule_id, U512::from(950u64));
+        engine.execute_rule(rule_id);
+        assert_eq!(vault.get_token_balance(user, *token.address()), U256::from(3_000u64));
+    }
+
+    #[test]
+    fn test_restake_rule() {
+        let (env, _vault, mut engine) = setup();
+        let user = env.get_account(0);
+        let source = env.get_validator(0);
+        let destination = env.get_validator(1);
+        env.set_caller(user);
+        
+        let mut adapter = StakingAdapter::deploy(&env, StakingAdapterInitArgs {
+            default_validator: Some(source.clone()),
+        });
+        adapter.set_automation_engine(*engine.address());
+        adapter.set_validator_allowed(destination.clone(), true);
+        engine.set_staking_adapter(*adapter.address());
+        adapter.with_tokens(U512::from(1_000_000_000_000u64)).stake();
+        
+        // Only the admin or an operator moves the pool's rewards
+        env.set_caller(env.get_account(1));
+        let result = engine.try_create_restake_rule(
+            "diversify".to_string(),
+            Schedule::Weekly,
+            source.clone(),
+            destination.clone(),
+        );
+        assert_eq!(result, Err(Error::NotAdmin.into()));
+        env.set_caller(user);
+        let result = engine.try_create_restake_rule(
+            "diversify".to_string(),
+            Schedule::Weekly,
+            destination.clone(),
+            destination.clone(),
+        );
+        assert_eq!(result, Err(Error::InvalidRuleConfig.into()));
+        let rule_id = engine.create_restake_rule(
+            "diversify".to_string(),
+            Schedule::Weekly,
+            source.clone(),
+            destination.clone(),
+        );
+        assert_eq!(engine.get_rule_validator(rule_id), Some(source));
+        assert_eq!(engine.get_restake_destination(rule_id), Some(destination));
+        
+        // No rewards have accrued yet, so the run moves nothing
+        env.advance_block_time(SECONDS_PER_WEEK);
+        engine.execute_rule(rule_id);
+        assert_eq!(engine.get_execution_history(rule_id, 1)[0].amount, U512::zero());
+        assert!(adapter.get_pending_redelegation(1).is_none());
+    }
+
+    #[test]
+    fn test_execution_hooks() {
+        let (env, mut vault, mut engine) = setup();
+        let user = env.get_account(0);
+        let recipient = env.get_account(1);
+        env.set_caller(user);
+        vault.with_tokens(U512::from(10_000u64)).deposit();
+        let hook = TestHook::deploy(&env, NoArgs);
+        
+        let rule_id = engine.create_rule(
+            "weekly_allowance".to_string(),
+            TriggerType::Time,
+            Schedule::Weekly,
+            ActionType::Transfer,
+            Some(recipient),
+            U512::from(1_000u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        
+        // Only allowlisted hooks can be registered
+        let result = engine.try_set_rule_hook(rule_id, Some(*hook.address()), true, true);
+        assert_eq!(result, Err(Error::HookNotAllowed.into()));
+        engine.set_hook_allowed(*hook.address(), true);
+        engine.set_rule_hook(rule_id, Some(*hook.address()), true, true);
+        
+        env.advance_block_time(SECONDS_PER_WEEK);
+        engine.execute_rule(rule_id);
+        assert_eq!(hook.calls(), (1, 1));
+        assert_eq!(hook.last_amount(), U512::from(1_000u64));
+        
+        // Delisted hooks are skipped rather than blocking the rule
+        engine.set_hook_allowed(*hook.address(), false);
+        env.advance_block_time(SECONDS_PER_WEEK);
+        engine.execute_rule(rule_id);
+        assert_eq!(hook.calls(), (1, 1));
+    }
+
+    #[test]
+    fn test_contract_recipient_deposit() {
+        let (env, mut vault, mut engine) = setup();
+        let user = env.get_account(0);
+        let beneficiary = env.get_account(1);
+        env.set_caller(user);
+        vault.with_tokens(U512::from(10_000u64)).deposit();
+        let other_vault = AutomationVault::deploy(&env, AutomationVaultInitArgs {
+            automation_engine: None,
+        });
+        
+        let rule_id = engine.create_rule(
+            "fund_savings_vault".to_string(),
+            TriggerType::Time,
+            Schedule::Weekly,
+            ActionType::Transfer,
+            Some(*other_vault.address()),
+            U512::from(1_000u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        
+        // Only entrypoints the admin vetted on both contracts can be paid into
+        let deposit = ContractRecipient {
+            entry_point: "deposit_for".to_string(),
+            beneficiary: Some(beneficiary),
+        };
+        let result = engine.try_set_contract_recipient(rule_id, Some(deposit.clone()));
+        assert_eq!(result, Err(Error::InvalidContractRecipient.into()));
+        let other_address = *other_vault.address();
+        engine.set_contract_recipient_allowed(other_address, "deposit_for".to_string(), true);
+        engine.set_contract_recipient(rule_id, Some(deposit));
+        assert_eq!(engine.get_rule(rule_id).unwrap().recipient_kind, RecipientKind::Contract);
+        
+        env.advance_block_time(SECONDS_PER_WEEK);
+        let result = engine.try_execute_rule(rule_id);
+        assert_eq!(result, Err(Error::InvalidContractRecipient.into()));
+        vault.set_contract_recipient_allowed(other_address, "deposit_for".to_string(), true);
+        engine.execute_rule(rule_id);
+        assert_eq!(other_vault.get_balance(beneficiary), U512::from(1_000u64));
+        
+        // Account recipients are paid with plain transfers
+        let account_rule = engine.create_rule(
+            "weekly_allowance".to_string(),
+            TriggerType::Time,
+            Schedule::Weekly,
+            ActionType::Transfer,
+            Some(beneficiary),
+            U512::from(1_000u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        let result = engine.try_set_contract_recipient(account_rule, Some(ContractRecipient {
+            entry_point: "deposit_for".to_string(),
+            beneficiary: None,
+        }));
+        assert_eq!(result, Err(Error::InvalidRuleConfig.into()));
+    }
+
+    #[test]
+    fn test_create_rules_batch_and_clone() {
+        let (env, _vault, mut engine) = setup();
+        let user = env.get_account(0);
+        env.set_caller(user);
+        let payroll = |employee: Address| RuleParams {
+            template_name: "payroll".to_string(),
+            trigger_type: TriggerType::Time,
+            schedule: Schedule::Monthly,
+            action_type: ActionType::Transfer,
+            recipient: Some(employee),
+            amount: U512::from(1_000u64),
+            token: None,
+            validator: None,
+            metadata: None,
+            first_execution: None,
+        };
+        
+        // A batch past the tier limit creates nothing
+        let employees = [env.get_account(1), env.get_account(2), env.get_account(3)];
+        let result = engine.try_create_rules_batch(employees.iter().map(|e| payroll(*e)).collect());
+        assert_eq!(result, Err(Error::MaxRulesReached.into()));
+        assert!(engine.get_user_rule_ids(user).is_empty());
+        
+        let ids = engine.create_rules_batch(vec![payroll(employees[0])]);
+        assert_eq!(ids.len(), 1);
+        
+        let clone_id = engine.clone_rule(ids[0], RuleOverrides {
+            template_name: None,
+            schedule: None,
+            recipient: Some(employees[1]),
+            amount: Some(U512::from(2_000u64)),
+            metadata: None,
+            first_execution: None,
+        });
+        let clone = engine.get_rule(clone_id).unwrap();
+        assert_eq!(clone.template_name, "payroll");
+        assert_eq!(clone.schedule, Schedule::Monthly);
+        assert_eq!(clone.recipient, Some(employees[1]));
+        assert_eq!(clone.amount, U512::from(2_000u64));
+        
+        // Clones count against the tier limit too
+        let result = engine.try_clone_rule(ids[0], RuleOverrides {
+            template_name: None,
+            schedule: None,
+            recipient: None,
+            amount: None,
+            metadata: None,
+            first_execution: None,
+        });
+        assert_eq!(result, Err(Error::MaxRulesReached.into()));
+    }
+
+    #[test]
+    fn test_bulk_pause_resume_delete() {
+        let (env, _vault, mut engine) = setup();
+        let user = env.get_account(0);
+        env.set_caller(user);
+        let mut create = |name: &str| engine.create_rule(
+            name.to_string(),
+            TriggerType::Time,
+            Schedule::Weekly,
+            ActionType::Transfer,
+            Some(env.get_account(1)),
+            U512::from(1_000u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        let rent = create("rent");
+        let allowance = create("allowance");
+        engine.pause_rule(allowance);
+        
+        // Already-paused rules are skipped
+        assert_eq!(engine.pause_all(), 1);
+        assert_eq!(engine.get_rule(rent).unwrap().status, RuleStatus::Paused);
+        assert!(env.emitted_event(
+            engine.address(),
+            RulePaused { rule_id: rent, owner: user, nonce: engine.get_event_nonce() },
+        ));
+        
+        assert_eq!(engine.resume_all(), 2);
+        assert_eq!(engine.get_rule(allowance).unwrap().status, RuleStatus::Active);
+        
+        // Someone else's rule reverts the whole batch
+        env.set_caller(env.get_account(1));
+        let result = engine.try_delete_rules(vec![rent]);
+        assert_eq!(result, Err(Error::NotRuleOwner.into()));
+        
+        env.set_caller(user);
+        assert_eq!(engine.delete_rules(vec![rent, allowance, rent]), 2);
+        assert_eq!(engine.get_rule(rent).unwrap().status, RuleStatus::Deleted);
+    }
+
+    #[test]
+    fn test_rule_spends_only_its_envelope() {
+        let (env, mut vault, mut engine) = setup();
+        let user = env.get_account(0);
+        env.set_caller(user);
+        vault.with_tokens(U512::from(10_000u64)).deposit();
+        vault.fund_envelope("bills".to_string(), U512::from(3_000u64));
+        vault.fund_envelope("savings".to_string(), U512::from(5_000u64));
+        
+        let rule_id = engine.create_rule(
+            "electricity".to_string(),
+            TriggerType::Time,
+            Schedule::Weekly,
+            ActionType::Transfer,
+            Some(env.get_account(1)),
+            U512::from(2_000u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        engine.set_rule_envelope(rule_id, Some("bills".to_string()));
+        
+        env.advance_block_time(SECONDS_PER_WEEK);
+        engine.execute_rule(rule_id);
+        assert_eq!(vault.get_envelope_balance(user, "bills".to_string()), U512::from(1_000u64));
+        assert_eq!(vault.get_envelope_balance(user, "savings".to_string()), U512::from(5_000u64));
+        
+        // Unassigned funds could cover it, but the envelope cannot
+        env.advance_block_time(SECONDS_PER_WEEK);
+        let result = engine.try_execute_rule(rule_id);
+        assert_eq!(result, Err(Error::InsufficientEnvelopeBalance.into()));
+    }
+
+    #[test]
+    fn test_low_balance_alert() {
+        let (env, mut vault, mut engine) = setup();
+        let user = env.get_account(0);
+        env.set_caller(user);
+        vault.with_tokens(U512::from(5_000u64)).deposit();
+        assert_eq!(
+            engine.try_set_low_balance_alert(MAX_ALERT_LOOKAHEAD + 1),
+            Err(Error::InvalidAlertLookahead.into())
+        );
+        engine.set_low_balance_alert(3);
+        
+        let rule_id = engine.create_rule(
+            "rent".to_string(),
+            TriggerType::Time,
+            Schedule::Weekly,
+            ActionType::Transfer,
+            Some(env.get_account(1)),
+            U512::from(2_000u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        env.advance_block_time(SECONDS_PER_WEEK);
+        engine.execute_rule(rule_id);
+        
+        // 3,000 left covers the next run but not the one after
+        let next_execution = engine.get_rule(rule_id).unwrap().next_execution;
+        assert!(env.emitted_event(
+            engine.address(),
+            VaultUnderfunded {
+                rule_id,
+                owner: user,
+                balance: U512::from(3_000u64),
+                required: U512::from(6_000u64),
+                shortfall: U512::from(3_000u64),
+                first_failing_at: next_execution + SECONDS_PER_WEEK,
+                nonce: engine.get_event_nonce() - 1,
+            }
+        ));
+    }
+
+    #[test]
+    fn test_first_execution() {
+        let (env, mut vault, mut engine) = setup();
+        let user = env.get_account(0);
+        env.set_caller(user);
+        vault.with_tokens(U512::from(10_000u64)).deposit();
+        env.advance_block_time(SECONDS_PER_DAY);
+        let now = env.block_time();
+        let mut create = |first_execution: Option<u64>| engine.try_create_rule(
+            "rent".to_string(),
+            TriggerType::Time,
+            Schedule::Monthly,
+            ActionType::Transfer,
+            Some(env.get_account(1)),
+            U512::from(1_000u64),
+            None,
+            None,
+            None,
+            first_execution,
+        );
+        
+        assert_eq!(create(Some(now - 1)), Err(Error::FirstExecutionInPast.into()));
+        let immediate = create(Some(now)).unwrap();
+        let due_date = create(Some(now + 3 * SECONDS_PER_DAY)).unwrap();
+        
+        engine.execute_rule(immediate);
+        assert_eq!(engine.get_rule(immediate).unwrap().next_execution, now + SECONDS_PER_MONTH);
+        assert_eq!(engine.try_execute_rule(due_date), Err(Error::TriggerTimeNotReached.into()));
+        env.advance_block_time(3 * SECONDS_PER_DAY);
+        engine.execute_rule(due_date);
+    }
+
+    #[test]
+    fn test_execution_window() {
+        let (env, mut vault, mut engine) = setup();
+        let user = env.get_account(0);
+        env.set_caller(user);
+        vault.with_tokens(U512::from(10_000u64)).deposit();
+        
+        let rule_id = engine.create_rule(
+            "rent".to_string(),
+            TriggerType::Time,
+            Schedule::Daily,
+            ActionType::Transfer,
+            Some(env.get_account(1)),
+            U512::from(1_000u64),
+            None,
+            None,
+            None,
+            Some(env.block_time()),
+        );
+        // 9:00-17:59 in UTC-5, i.e. 14:00-22:59 UTC
+        let window = ExecutionWindow { earliest_hour: 9, latest_hour: 17, utc_offset_minutes: -300 };
+        engine.set_execution_window(rule_id, Some(window));
+        
+        // 00:00 UTC is 19:00 the evening before locally
+        let midnight = SECONDS_PER_DAY - env.block_time() % SECONDS_PER_DAY;
+        env.advance_block_time(midnight);
+        assert_eq!(engine.try_execute_rule(rule_id), Err(Error::OutsideExecutionWindow.into()));
+        
+        env.advance_block_time(14 * SECONDS_PER_HOUR);
+        engine.execute_rule(rule_id);
+        
+        let result = engine.try_set_execution_window(rule_id, Some(ExecutionWindow {
+            earliest_hour: 24,
+            latest_hour: 2,
+            utc_offset_minutes: 0,
+        }));
+        assert_eq!(result, Err(Error::InvalidRuleConfig.into()));
+    }
+
+    #[test]
+    fn test_per_owner_rule_indices() {
+        let (env, _vault, mut engine) = setup();
+        let (alice, bob) = (env.get_account(0), env.get_account(1));
+        let mut create = |owner: Address| {
+            env.set_caller(owner);
+            engine.create_rule(
+                "allowance".to_string(),
+                TriggerType::Time,
+                Schedule::Weekly,
+                ActionType::Transfer,
+                Some(env.get_account(2)),
+                U512::from(1_000u64),
+                None,
+                None,
+                None,
+                None,
+            )
+        };
+        let alice_first = create(alice);
+        let bob_first = create(bob);
+        let alice_second = create(alice);
+        
+        assert_eq!(engine.get_local_index(alice_second), Some(1));
+        assert_eq!(engine.get_local_index(bob_first), Some(0));
+        assert_eq!(engine.get_rule_id_by_local_index(alice, 0), Some(alice_first));
+        assert_eq!(engine.get_rule_by_local_index(alice, 1).unwrap().id, alice_second);
+        assert_eq!(engine.get_rule_id_by_local_index(bob, 1), None);
+    }
+
+    #[test]
+    fn test_user_snapshot() {
+        let (env, mut vault, mut engine) = setup();
+        let user = env.get_account(0);
+        env.set_caller(user);
+        vault.with_tokens(U512::from(10_000u64)).deposit();
+        vault.fund_envelope("bills".to_string(), U512::from(4_000u64));
+        let token = TestToken::deploy(&env, NoArgs);
+        vault.set_token_allowed(*token.address(), true);
+        
+        let rule_id = engine.create_rule(
+            "allowance".to_string(),
+            TriggerType::Time,
+            Schedule::Weekly,
+            ActionType::Transfer,
+            Some(env.get_account(1)),
+            U512::from(1_000u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        
+        let snapshot = engine.get_user_snapshot(user);
+        assert_eq!(snapshot.tier, StakingTier::Starter);
+        assert_eq!(snapshot.vault_balance, U512::from(10_000u64));
+        assert_eq!(snapshot.spendable_balance, U512::from(6_000u64));
+        assert_eq!(snapshot.token_balances, vec![(*token.address(), U256::zero())]);
+        assert_eq!(snapshot.rules.len(), 1);
+        assert_eq!(snapshot.rules[0].id, rule_id);
+        assert_eq!(snapshot.staked, U512::zero());
+        assert!(snapshot.pending_unstakes.is_empty());
+    }
+
+    #[test]
+    fn test_execution_nonce_and_min_interval() {
+        let (env, mut vault, mut engine) = setup();
+        let user = env.get_account(0);
+        env.set_caller(user);
+        vault.with_tokens(U512::from(10_000u64)).deposit();
+        
+        let rule_id = engine.create_rule(
+            "top_up".to_string(),
+            TriggerType::Manual,
+            Schedule::Daily,
+            ActionType::Transfer,
+            Some(env.get_account(1)),
+            U512::from(1_000u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        
+        // Two executions racing on the same nonce: only the first goes through
+        let nonce = engine.get_execution_nonce(rule_id);
+        engine.execute_rule_with_nonce(rule_id, nonce);
+        let result = engine.try_execute_rule_with_nonce(rule_id, nonce);
+        assert_eq!(result, Err(Error::StaleExecutionNonce.into()));
+        assert_eq!(engine.get_execution_nonce(rule_id), nonce + 1);
+        
+        engine.set_min_execution_interval(rule_id, SECONDS_PER_HOUR);
+        assert_eq!(engine.try_execute_rule(rule_id), Err(Error::ExecutedTooRecently.into()));
+        env.advance_block_time(SECONDS_PER_HOUR);
+        engine.execute_rule(rule_id);
+    }
+
+    #[test]
+    fn test_missed_execution_policy() {
+        let (env, mut vault, mut engine) = setup();
+        let user = env.get_account(0);
+        env.set_caller(user);
+        vault.with_tokens(U512::from(10_000u64)).deposit();
+        
+        let start = env.block_time();
+        let mut rule_ids = Vec::new();
+        for name in ["allowance", "rent"] {
+            rule_ids.push(engine.create_rule(
+                name.to_string(),
+                TriggerType::Time,
+                Schedule::Daily,
+                ActionType::Transfer,
+                Some(env.get_account(1)),
+                U512::from(1_000u64),
+                None,
+                None,
+                None,
+                Some(start),
+            ));
+        }
+        let (skipped, caught_up) = (rule_ids[0], rule_ids[1]);
+        engine.set_missed_execution_policy(skipped, Some(MissedExecutionConfig {
+            policy: MissedExecutionPolicy::Skip,
+            grace_period: SECONDS_PER_HOUR,
+        }));
+        engine.set_missed_execution_policy(caught_up, Some(MissedExecutionConfig {
+            policy: MissedExecutionPolicy::CatchUpAll,
+            grace_period: SECONDS_PER_HOUR,
+        }));
+        
+        // No keeper for two and a half days: three daily slots are due
+        let one_run = engine.estimate_execution_cost(caught_up);
+        env.advance_block_time(2 * SECONDS_PER_DAY + SECONDS_PER_DAY / 2);
+        let base = U512::from(BASE_EXECUTION_GAS);
+        assert_eq!(engine.estimate_execution_cost(caught_up), base + (one_run - base) * 3);
+        assert_eq!(engine.estimate_execution_cost(skipped), base);
+        engine.execute_rule(skipped);
+        let rule = engine.get_rule(skipped).unwrap();
+        assert_eq!(rule.execution_count, 0);
+        assert_eq!(rule.next_execution, start + 3 * SECONDS_PER_DAY);
+        assert_eq!(engine.get_missed_executions(skipped), 3);
+        assert!(env.emitted_event(
+            engine.address(),
+            MissedExecution {
+                rule_id: skipped,
+                owner: user,
+                policy: MissedExecutionPolicy::Skip,
+                missed: 3,
+                executed: 0,
+                next_execution: start + 3 * SECONDS_PER_DAY,
+                nonce: engine.get_event_nonce(),
+            }
+        ));
+        
+        engine.execute_rule(caught_up);
+        let rule = engine.get_rule(caught_up).unwrap();
+        assert_eq!(rule.execution_count, 3);
+        assert_eq!(rule.next_execution, start + 3 * SECONDS_PER_DAY);
+        assert_eq!(engine.get_missed_executions(caught_up), 0);
+        
+        // Back on schedule, the skipped rule runs normally
+        env.advance_block_time(SECONDS_PER_DAY / 2);
+        engine.execute_rule(skipped);
+        assert_eq!(engine.get_rule(skipped).unwrap().execution_count, 1);
+        
+        // A catch-up that runs out of funds keeps the runs it paid and misses the rest
+        vault.withdraw(U512::from(4_500u64));
+        env.advance_block_time(2 * SECONDS_PER_DAY + SECONDS_PER_DAY / 2);
+        assert_ne!(engine.execute_rule(caught_up), 0);
+        let rule = engine.get_rule(caught_up).unwrap();
+        assert_eq!(rule.execution_count, 4);
+        assert_eq!(rule.next_execution, start + 6 * SECONDS_PER_DAY);
+        assert_eq!(engine.get_missed_executions(caught_up), 2);
+        assert_eq!(vault.get_balance(user), U512::from(500u64));
+    }
+
+    #[test]
+    fn test_purge_deleted_rules() {
+        let (env, _vault, mut engine) = setup();
+        let user = env.get_account(0);
+        env.set_caller(user);
+        let mut create = |name: &str| {
+            engine.create_rule(
+                name.to_string(),
+                TriggerType::Time,
+                Schedule::Weekly,
+                ActionType::Transfer,
+                Some(env.get_account(1)),
+                U512::from(1_000u64),
+                None,
+                None,
+                None,
+                None,
+            )
+        };
+        let old = create("old");
+        let kept = create("kept");
+        engine.set_savings_goal(old, U512::from(5_000u64));
+        engine.set_rule_budget(old, U512::from(10_000u64));
+        engine.delete_rule(old);
+        
+        env.set_caller(env.get_account(2));
+        assert_eq!(engine.try_purge_deleted_rules(user), Err(Error::NotRuleOwner.into()));
+        env.set_caller(user);
+        assert_eq!(engine.purge_deleted_rules(user), 1);
+        assert!(env.emitted_event(
+            engine.address(),
+            RulePurged {
+                rule_id: old,
+                owner: user,
+                execution_count: 0,
+                last_executed: 0,
+                nonce: engine.get_event_nonce(),
+            }
+        ));
+        assert_eq!(engine.get_user_rule_ids(user), vec![kept]);
+        assert_eq!(engine.get_rule(old), None);
+        assert_eq!(engine.try_execute_rule(old), Err(Error::RuleNotFound.into()));
+        assert_eq!(engine.get_savings_goal(old), (U512::zero(), U512::zero()));
+        assert_eq!(engine.get_rule_budget(old), (U512::zero(), U512::zero()));
+        
+        // Per-owner indices stay unique after the list shrinks
+        let new = engine.create_rule(
+            "new".to_string(),
+            TriggerType::Time,
+            Schedule::Weekly,
+            ActionType::Transfer,
+            Some(env.get_account(1)),
+            U512::from(1_000u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        assert_eq!(engine.get_local_index(kept), Some(1));
+        assert_eq!(engine.get_local_index(new), Some(2));
+        assert_eq!(engine.get_rule_id_by_local_index(user, 1), Some(kept));
+        assert_eq!(engine.get_user_rule_ids(user), vec![kept, new]);
+    }
+
+    #[test]
+    fn test_registry_replaces_vault() {
+        let (env, _vault, mut engine) = setup();
+        let user = env.get_account(0);
+        let recipient = env.get_account(1);
+        
+        // A replacement vault that only knows the engine through the registry
+        let mut new_vault = AutomationVault::deploy(&env, AutomationVaultInitArgs {
+            automation_engine: None,
+        });
+        let mut registry = ContractRegistry::deploy(&env, NoArgs);
+        registry.set_address(ContractKind::Vault, Some(*new_vault.address()));
+        registry.set_address(ContractKind::Engine, Some(*engine.address()));
+        new_vault.set_registry(Some(*registry.address()));
+        engine.set_registry(Some(*registry.address()));
+        assert_eq!(engine.get_vault_address(), Some(*new_vault.address()));
+        assert_eq!(new_vault.get_automation_engine(), Some(*engine.address()));
+        
+        env.set_caller(user);
+        new_vault.with_tokens(U512::from(10_000u64)).deposit();
+        let rule_id = engine.create_rule(
+            "allowance".to_string(),
+            TriggerType::Manual,
+            Schedule::Daily,
+            ActionType::Transfer,
+            Some(recipient),
+            U512::from(1_000u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        let recipient_before = env.balance_of(&recipient);
+        engine.execute_rule(rule_id);
+        assert_eq!(env.balance_of(&recipient), recipient_before + U512::from(1_000u64));
+        
+        env.set_caller(recipient);
+        assert_eq!(engine.try_set_registry(None), Err(Error::NotAdmin.into()));
+    }
+
+    #[test]
+    fn test_version_handshake() {
+        let (env, vault, mut engine) = setup();
+        let adapter = StakingAdapter::deploy(&env, StakingAdapterInitArgs {
+            default_validator: None,
+        });
+        assert_eq!(
+            vault.get_version(),
+            ContractVersion { kind: ContractKind::Vault, version: VAULT_INTERFACE_VERSION }
+        );
+        
+        // Linking the wrong kind of contract fails up front, not at execution time
+        let result = engine.try_set_vault_address(*adapter.address());
+        assert_eq!(result, Err(Error::IncompatibleContractVersion.into()));
+        let result = engine.try_set_staking_adapter(*vault.address());
+        assert_eq!(result, Err(Error::IncompatibleContractVersion.into()));
+        
+        engine.set_vault_address(*vault.address());
+        engine.set_staking_adapter(*adapter.address());
+        assert_eq!(engine.get_staking_adapter(), Some(*adapter.address()));
+        assert!(env.emitted_event(
+            engine.address(),
+            StakingAdapterUpdated { adapter: *adapter.address(), nonce: engine.get_event_nonce() }
+        ));
+    }
+
+    #[test]
+    fn test_payroll_rule() {
+        let (env, mut vault, mut engine) = setup();
+        let owner = env.get_account(0);
+        let staff = [env.get_account(1), env.get_account(2), env.get_account(3)];
+        env.set_caller(owner);
+        vault.with_tokens(U512::from(5_000u64)).deposit();
+        
+        let entries: Vec<PayrollEntry> = staff
+            .iter()
+            .zip([2_000u64, 1_500, 2_500])
+            .map(|(recipient, amount)| PayrollEntry { recipient: *recipient, amount: U512::from(amount) })
+            .collect();
+        let result = engine.try_create_payroll_rule(
+            "payroll".to_string(),
+            Schedule::Monthly,
+            Vec::new(),
+            PayrollFailurePolicy::AllOrNothing,
+        );
+        assert_eq!(result, Err(Error::InvalidRuleConfig.into()));
+        let all_or_nothing = engine.create_payroll_rule(
+            "payroll".to_string(),
+            Schedule::Monthly,
+            entries.clone(),
+            PayrollFailurePolicy::AllOrNothing,
+        );
+        let pay_what_fits = engine.create_payroll_rule(
+            "payroll".to_string(),
+            Schedule::Monthly,
+            entries,
+            PayrollFailurePolicy::PayWhatFits,
+        );
+        assert_eq!(engine.get_rule(all_or_nothing).unwrap().amount, U512::from(6_000u64));
+        
+        // 5,000 cannot cover the 6,000 total: nobody is paid
+        env.advance_block_time(SECONDS_PER_MONTH);
+        let before: Vec<U512> = staff.iter().map(|account| env.balance_of(account)).collect();
+        engine.execute_rule(all_or_nothing);
+        assert_eq!(vault.get_balance(owner), U512::from(5_000u64));
+        
+        // The first two entries fit; the third is skipped
+        engine.execute_rule(pay_what_fits);
+        assert_eq!(env.balance_of(&staff[0]), before[0] + U512::from(2_000u64));
+        assert_eq!(env.balance_of(&staff[1]), before[1] + U512::from(1_500u64));
+        assert_eq!(env.balance_of(&staff[2]), before[2]);
+        assert!(env.emitted_event(
+            engine.address(),
+            PayrollEntrySkipped {
+                rule_id: pay_what_fits,
+                owner,
+                recipient: staff[2],
+                amount: U512::from(2_500u64),
+                nonce: engine.get_event_nonce() - 1,
+            }
+        ));
+    }
+
+    #[test]
+    fn test_savings_goal() {
+        let (env, mut vault, mut engine) = setup();
+        let user = env.get_account(0);
+        let savings = env.get_account(1);
+        env.set_caller(user);
+        vault.with_tokens(U512::from(10_000u64)).deposit();
+        
+        let rule_id = engine.create_rule(
+            "savings_goal".to_string(),
+            TriggerType::Manual,
+            Schedule::Weekly,
+            ActionType::Transfer,
+            Some(savings),
+            U512::from(1_000u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        engine.set_savings_goal(rule_id, U512::from(2_500u64));
+        
+        // The third transfer is cut to the 500 left and completes the goal
+        let savings_before = env.balance_of(&savings);
+        for _ in 0..3 {
+            engine.execute_rule(rule_id);
+        }
+        assert_eq!(env.balance_of(&savings), savings_before + U512::from(2_500u64));
+        assert_eq!(engine.get_savings_goal(rule_id), (U512::from(2_500u64), U512::from(2_500u64)));
+        assert_eq!(engine.get_rule(rule_id).unwrap().status, RuleStatus::Paused);
+        assert!(env.emitted_event(
+            engine.address(),
+            GoalReached {
+                rule_id,
+                owner: user,
+                target: U512::from(2_500u64),
+                saved: U512::from(2_500u64),
+                nonce: engine.get_event_nonce() - 1,
+            }
+        ));
+        assert_eq!(engine.try_resume_rule(rule_id), Err(Error::SavingsGoalReached.into()));
+        
+        // Lowering a goal to what the rule has already moved also stops it
+        let rule_id = engine.create_rule(
+            "savings_goal".to_string(),
+            TriggerType::Manual,
+            Schedule::Weekly,
+            ActionType::Transfer,
+            Some(savings),
+            U512::from(1_000u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        engine.set_savings_goal(rule_id, U512::from(5_000u64));
+        engine.execute_rule(rule_id);
+        engine.execute_rule(rule_id);
+        engine.set_savings_goal(rule_id, U512::from(1_500u64));
+        assert_eq!(engine.get_rule(rule_id).unwrap().status, RuleStatus::Paused);
+        assert!(env.emitted_event(
+            engine.address(),
+            GoalReached {
+                rule_id,
+                owner: user,
+                target: U512::from(1_500u64),
+                saved: U512::from(2_000u64),
+                nonce: engine.get_event_nonce(),
+            }
+        ));
+    }
+
+    #[test]
+    fn test_escrow_rule() {
+        let (env, mut vault, mut engine) = setup();
+        let owner = env.get_account(0);
+        let freelancer = env.get_account(1);
+        env.set_caller(owner);
+        vault.with_tokens(U512::from(10_000u64)).deposit();
+        
+        let result = engine.try_create_escrow_rule(
+            "milestones".to_string(),
+            Schedule::Weekly,
+            freelancer,
+            U512::from(1_000u64),
+            0,
+        );
+        assert_eq!(result, Err(Error::InvalidRuleConfig.into()));
+        let rule_id = engine.create_escrow_rule(
+            "milestones".to_string(),
+            Schedule::Weekly,
+            freelancer,
+            U512::from(1_000u64),
+            SECONDS_PER_DAY,
+        );
+        let fee = engine.quote_fee(owner, U512::from(1_000u64));
+        
+        // Each execution moves the amount out of the owner's balance into escrow
+        env.advance_block_time(SECONDS_PER_WEEK);
+        engine.execute_rule(rule_id);
+        env.advance_block_time(SECONDS_PER_WEEK);
+        engine.execute_rule(rule_id);
+        let escrows = vault.get_rule_escrows(rule_id);
+        assert_eq!(escrows.len(), 2);
+        assert_eq!(vault.get_balance(owner), U512::from(8_000u64) - fee - fee);
+        
+        // The recipient must wait out the dispute window
+        env.set_caller(freelancer);
+        assert_eq!(vault.try_claim_escrow(escrows[1]), Err(Error::EscrowLocked.into()));
+        
+        // The owner disputes the second payment and gets the amount back
+        env.set_caller(owner);
+        vault.cancel_escrow(escrows[1]);
+        assert_eq!(vault.get_balance(owner), U512::from(9_000u64) - fee - fee);
+        assert_eq!(vault.get_escrow(escrows[1]).unwrap().status, EscrowStatus::Cancelled);
+        
+        // The first payment's window has passed, so only the recipient can take it
+        assert_eq!(vault.try_cancel_escrow(escrows[0]), Err(Error::DisputeWindowClosed.into()));
+        assert_eq!(vault.try_claim_escrow(escrows[0]), Err(Error::NotEscrowParty.into()));
+        engine.set_recipient_blocked(freelancer, true);
+        env.set_caller(freelancer);
+        assert_eq!(vault.try_claim_escrow(escrows[0]), Err(Error::RecipientBlocked.into()));
+        env.set_caller(owner);
+        engine.set_recipient_blocked(freelancer, false);
+        env.set_caller(freelancer);
+        let before = env.balance_of(&freelancer);
+        assert_eq!(vault.claim_escrow(escrows[0]), U512::from(1_000u64));
+        assert_eq!(env.balance_of(&freelancer), before + U512::from(1_000u64));
+        assert_eq!(vault.try_claim_escrow(escrows[0]), Err(Error::EscrowNotFound.into()));
+    }
+
+    #[test]
+    fn test_nft_perks() {
+        let (env, _vault, mut engine) = setup();
+        let admin = env.get_account(0);
+        let member = env.get_account(1);
+        let outsider = env.get_account(2);
+        let mut collection = TestNft::deploy(&env, NoArgs);
+        engine.set_fee_config(100, U512::from(10u64));
+        
+        let perks = NftPerks {
+            collection: collection.address().clone(),
+            tier: StakingTier::Silver,
+            fee_discount_bps: 5_000,
+        };
+        env.set_caller(member);
+        assert_eq!(engine.try_set_nft_perks(Some(perks.clone())), Err(Error::NotAdmin.into()));
+        env.set_caller(admin);
+        engine.set_nft_perks(Some(perks.clone()));
+        assert!(env.emitted_event(
+            engine.address(),
+            NftPerksUpdated {
+                perks: Some(perks),
+                nonce: engine.get_event_nonce(),
+            }
+        ));
+        collection.mint(member);
+        
+        // Holders get the perk tier and half off both fee components
+        let amount = U512::from(10_000u64);
+        assert_eq!(engine.get_user_tier(member), StakingTier::Silver);
+        assert_eq!(engine.quote_fee(member, amount), U512::from(55u64));
+        assert_eq!(engine.get_user_perks(member), UserPerks {
+            tier: StakingTier::Silver,
+            nft_holder: true,
+            fee_exempt: false,
+            fee_discount_bps: 5_000,
+        });
+        assert_eq!(engine.get_user_tier(outsider), StakingTier::Starter);
+        assert_eq!(engine.quote_fee(outsider, amount), U512::from(110u64));
+        
+        // An exempt perk tier pays nothing, so no discount is reported
+        engine.set_fee_exempt(StakingTier::Silver, true);
+        assert_eq!(engine.quote_fee(member, amount), U512::zero());
+        assert_eq!(engine.get_user_perks(member).fee_discount_bps, 0);
+        
+        // Holders can create more rules than the Starter tier allows
+        env.set_caller(member);
+        for _ in 0..3 {
+            engine.create_rule(
+                "payment".to_string(),
+                TriggerType::Time,
+                Schedule::Weekly,
+                ActionType::Transfer,
+                Some(outsider),
+                U512::from(100u64),
+                None,
+                None,
+                None,
+                None,
+            );
+        }
+        assert_eq!(engine.get_user_rule_count(member), 3);
+    }
+
+    #[test]
+    fn test_rule_notification() {
+        let (env, mut vault, mut engine) = setup();
+        let user = env.get_account(0);
+        env.set_caller(user);
+        vault.with_tokens(U512::from(1_000u64)).deposit();
+        let rule_id = engine.create_rule(
+            "payment".to_string(),
+            TriggerType::Time,
+            Schedule::Daily,
+            ActionType::Transfer,
+            Some(env.get_account(1)),
+            U512::from(100u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        
+        env.set_caller(env.get_account(2));
+        assert_eq!(
+            engine.try_set_rule_notification(rule_id, Some([7u8; 32])),
+            Err(Error::NotRuleManager.into())
+        );
+        env.set_caller(user);
+        engine.set_rule_notification(rule_id, Some([7u8; 32]));
+        assert_eq!(engine.get_rule_notification(rule_id), Some([7u8; 32]));
+        
+        env.advance_block_time(SECONDS_PER_DAY);
+        engine.execute_rule(rule_id);
+        assert!(env.emitted_event(
+            engine.address(),
+            NotificationRequested {
+                rule_id,
+                owner: user,
+                execution_count: 1,
+                payload_hash: [7u8; 32],
+                nonce: engine.get_event_nonce(),
+            }
+        ));
+        
+        // Without the flag, executions emit only RuleExecuted
+        engine.set_rule_notification(rule_id, None);
+        env.advance_block_time(SECONDS_PER_DAY);
+        engine.execute_rule(rule_id);
+        assert!(!env.emitted_event(
+            engine.address(),
+            NotificationRequested {
+                rule_id,
+                owner: user,
+                execution_count: 2,
+                payload_hash: [7u8; 32],
+                nonce: engine.get_event_nonce(),
+            }
+        ));
+    }
+
+    #[test]
+    fn test_rule_bounds() {
+        let (env, _vault, mut engine) = setup();
+        let admin = env.get_account(0);
+        let user = env.get_account(1);
+        let payment = |schedule: Schedule, amount: u64| RuleParams {
+            template_name: "payment".to_string(),
+            trigger_type: TriggerType::Time,
+            schedule,
+            action_type: ActionType::Transfer,
+            recipient: Some(env.get_account(2)),
+            amount: U512::from(amount),
+            token: None,
+            validator: None,
+            metadata: None,
+            first_execution: None,
+        };
+        assert_eq!(engine.get_rule_bounds(), (0, U512::zero(), 25));
+        
+        env.set_caller(user);
+        let result = engine.try_set_rule_bounds(SECONDS_PER_WEEK, U512::from(1_000u64), 1);
+        assert_eq!(result, Err(Error::NotAdmin.into()));
+        env.set_caller(admin);
+        let result = engine.try_set_rule_bounds(SECONDS_PER_WEEK, U512::from(1_000u64), 0);
+        assert_eq!(result, Err(Error::InvalidRuleConfig.into()));
+        engine.set_rule_bounds(SECONDS_PER_WEEK, U512::from(1_000u64), 1);
+        
+        env.set_caller(user);
+        let result = engine.try_create_rules_batch(vec![payment(Schedule::Daily, 500)]);
+        assert_eq!(result, Err(Error::ScheduleTooFrequent.into()));
+        let result = engine.try_create_rules_batch(vec![payment(Schedule::Weekly, 1_001)]);
+        assert_eq!(result, Err(Error::RuleAmountTooLarge.into()));
+        let result = engine.try_create_rules_batch(vec![
+            payment(Schedule::Weekly, 500),
+            payment(Schedule::Monthly, 500),
+        ]);
+        assert_eq!(result, Err(Error::BatchTooLarge.into()));
+        
+        let ids = engine.create_rules_batch(vec![payment(Schedule::Weekly, 1_000)]);
+        assert_eq!(ids.len(), 1);
+        
+        // Clones are new rules and must stay within the bounds too
+        let result = engine.try_clone_rule(ids[0], RuleOverrides {
+            template_name: None,
+            schedule: Some(Schedule::Daily),
+            recipient: None,
+            amount: None,
+            metadata: None,
+            first_execution: None,
+        });
+        assert_eq!(result, Err(Error::ScheduleTooFrequent.into()));
+    }
+
+    #[test]
+    fn test_auto_stake_idle_funds() {
+        let (env, mut vault, mut engine) = setup();
+        let user = env.get_account(0);
+        let keeper = env.get_account(1);
+        let validator = env.get_validator(0);
+        env.set_caller(user);
+        
+        let mut adapter = StakingAdapter::deploy(&env, StakingAdapterInitArgs {
+            default_validator: Some(validator.clone()),
+        });
+        adapter.set_validator_allowed(validator.clone(), true);
+        adapter.set_vault_address(*vault.address());
+        vault.set_staking_adapter(*adapter.address());
+        
+        let cspr = U512::from(1_000_000_000_000u64);
+        vault.with_tokens(cspr * 3).deposit();
+        env.set_caller(keeper);
+        assert_eq!(
+            engine.try_auto_stake_idle(user),
+            Err(Error::AutoStakeNotEnabled.into())
+        );
+        
+        // Everything above the floor is staked under the owner's account
+        env.set_caller(user);
+        vault.set_auto_stake(Some(AutoStakeConfig { floor: cspr, validator: validator.clone() }));
+        env.set_caller(keeper);
+        assert_eq!(engine.auto_stake_idle(user), cspr * 2);
+        assert_eq!(engine.auto_stake_idle(user), U512::zero());
+        assert_eq!(vault.get_balance(user), cspr);
+        assert_eq!(vault.get_custodial_stake(user), cspr * 2);
+        assert_eq!(adapter.get_user_stake(user), cspr * 2);
+        
+        // A withdrawal past the liquid balance pays that out and unstakes the rest
+        env.set_caller(user);
+        vault.withdraw(cspr * 2);
+        assert_eq!(vault.get_balance(user), U512::zero());
+        assert_eq!(vault.get_custodial_stake(user), cspr);
+        assert_eq!(adapter.get_user_stake(user), cspr);
+        let pending = adapter.get_pending_unstake(1).unwrap();
+        assert_eq!((pending.owner, pending.amount), (user, cspr));
+        
+        // Shortfalls beyond the auto-staked funds still revert
+        let result = vault.try_withdraw(cspr * 2);
+        assert_eq!(result, Err(Error::InsufficientBalance.into()));
+    }
+
+    #[test]
+    fn test_redelegate_rule() {
+        let (env, _vault, mut engine) = setup();
+        let user = env.get_account(0);
+        let source = env.get_validator(0);
+        let destination = env.get_validator(1);
+        env.set_caller(user);
+        
+        let mut adapter = StakingAdapter::deploy(&env, StakingAdapterInitArgs {
+            default_validator: Some(source.clone()),
+        });
+        adapter.set_automation_engine(*engine.address());
+        adapter.set_validator_allowed(destination.clone(), true);
+        engine.set_staking_adapter(*adapter.address());
+        let cspr = U512::from(1_000_000_000_000u64);
+        adapter.with_tokens(cspr * 2).stake();
+        assert_eq!(adapter.get_user_delegation(user, source.clone()), cspr * 2);
+        
+        let rule_id = engine.create_redelegate_rule(
+            "migrate".to_string(),
+            Schedule::Weekly,
+            source.clone(),
+            destination.clone(),
+            cspr,
+        );
+        
+        // The engine needs the owner's permission to move their stake
+        env.advance_block_time(SECONDS_PER_WEEK);
+        let result = engine.try_execute_rule(rule_id);
+        assert_eq!(result, Err(Error::UnauthorizedExecutor.into()));
+        adapter.set_engine_permission(true);
+        engine.execute_rule(rule_id);
+        
+        // The moved stake keeps backing the owner's sCSPR while it unbonds
+        assert_eq!(adapter.get_user_delegation(user, source.clone()), cspr);
+        assert_eq!(adapter.get_user_delegation(user, destination.clone()), cspr);
+        assert_eq!(adapter.get_user_stake(user), cspr * 2);
+        let pending = adapter.get_pending_redelegation(1).unwrap();
+        assert_eq!((pending.validator, pending.amount), (destination.clone(), cspr));
+        
+        // Owners can move the rest themselves, but not more than they placed
+        let result = adapter.try_redelegate(source.clone(), destination.clone(), cspr * 2);
+        assert_eq!(result, Err(Error::InsufficientStakingBalance.into()));
+        assert_eq!(adapter.redelegate(source, destination, cspr), 2);
+    }
+
+    #[test]
+    fn test_split_dust_policy() {
+        let (env, mut vault, mut engine) = setup();
+        let owner = env.get_account(0);
+        let payees = [env.get_account(1), env.get_account(2), env.get_account(3)];
+        let treasury = env.get_account(4);
+        let recipients: Vec<SplitRecipient> = payees
+            .iter()
+            .zip([33u8, 33, 34])
+            .map(|(recipient, percentage)| SplitRecipient { recipient: *recipient, percentage })
+            .collect();
+        
+        // Shares and dust add up to exactly the amount under every policy
+        let policies = [DustPolicy::FirstRecipient, DustPolicy::KeepInVault, DustPolicy::Treasury];
+        for dust_policy in policies {
+            let config = SplitConfig { recipients: recipients.clone(), dust_policy };
+            for amount in 0..500u64 {
+                let (shares, dust) = config.shares(U512::from(amount));
+                let total = shares.iter().fold(dust, |total, share| total + *share);
+                assert_eq!(total, U512::from(amount));
+                if config.dust_policy == DustPolicy::FirstRecipient {
+                    assert!(dust.is_zero());
+                }
+            }
+        }
+        
+        env.set_caller(owner);
+        vault.with_tokens(U512::from(1_000u64)).deposit();
+        let rule_id = engine.create_rule(
+            "split".to_string(),
+            TriggerType::Manual,
+            Schedule::Daily,
+            ActionType::Split,
+            Some(payees[0]),
+            U512::from(101u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        let mut config = SplitConfig { recipients, dust_policy: DustPolicy::Treasury };
+        assert_eq!(
+            engine.try_set_split_config(rule_id, Some(config.clone())),
+            Err(Error::TreasuryNotSet.into())
+        );
+        let mut uneven = config.clone();
+        uneven.recipients[2].percentage = 33;
+        assert_eq!(
+            engine.try_set_split_config(rule_id, Some(uneven)),
+            Err(Error::InvalidRuleConfig.into())
+        );
+        
+        // 101 splits into 33/33/34 and the 1 mote of dust stays in the vault
+        config.dust_policy = DustPolicy::KeepInVault;
+        engine.set_split_config(rule_id, Some(config.clone()));
+        assert_eq!(engine.get_split_config(rule_id), Some(config.clone()));
+        let before: Vec<U512> = payees.iter().map(|account| env.balance_of(account)).collect();
+        engine.execute_rule(rule_id);
+        assert_eq!(env.balance_of(&payees[0]), before[0] + U512::from(33u64));
+        assert_eq!(env.balance_of(&payees[1]), before[1] + U512::from(33u64));
+        assert_eq!(env.balance_of(&payees[2]), before[2] + U512::from(34u64));
+        assert_eq!(vault.get_balance(owner), U512::from(900u64));
+        assert!(env.emitted_event(
+            engine.address(),
+            SplitDustHandled {
+                rule_id,
+                owner,
+                dust: U512::one(),
+                dust_policy: DustPolicy::KeepInVault,
+                nonce: engine.get_event_nonce() - 1,
+            }
+        ));
+        
+        // Under the treasury policy the dust is sent to the treasury instead
+        vault.set_treasury(treasury);
+        config.dust_policy = DustPolicy::Treasury;
+        engine.set_split_config(rule_id, Some(config));
+        let treasury_before = env.balance_of(&treasury);
+        engine.execute_rule(rule_id);
+        assert_eq!(env.balance_of(&treasury), treasury_before + U512::one());
+        assert_eq!(vault.get_balance(owner), U512::from(799u64));
     }
 
-    /// Get all rule IDs for a user
-    pub fn get_user_rule_ids(&self, owner: Address) -> Vec<u64> {
-        self.user_rules.get_or_default(&owner)
+    #[test]
+    fn test_create_rule_with_signature() {
+        let (env, _vault, mut engine) = setup();
+        let (owner, relayer, other) = (env.get_account(1), env.get_account(2), env.get_account(3));
+        let owner_key = env.public_key(&owner);
+        let mut request = SignedRuleRequest {
+            params: RuleParams {
+                template_name: "relayed".to_string(),
+                trigger_type: TriggerType::Time,
+                schedule: Schedule::Daily,
+                action_type: ActionType::Transfer,
+                recipient: Some(other),
+                amount: U512::from(100u64),
+                token: None,
+                validator: None,
+                metadata: None,
+                first_execution: None,
+            },
+            nonce: 0,
+            deadline: env.block_time() + SECONDS_PER_DAY,
+        };
+        let signature = env.sign_message(&engine.get_rule_signing_message(request.clone()), &owner);
+        
+        // Only the owner's own signature is accepted
+        env.set_caller(relayer);
+        let forged = env.sign_message(&engine.get_rule_signing_message(request.clone()), &other);
+        assert_eq!(
+            engine.try_create_rule_with_signature(owner_key.clone(), request.clone(), forged),
+            Err(Error::InvalidSignature.into())
+        );
+        
+        // The relayer submits it and the rule belongs to the signer
+        let rule_id = engine.create_rule_with_signature(
+            owner_key.clone(),
+            request.clone(),
+            signature.clone(),
+        );
+        assert_eq!(engine.get_rule(rule_id).unwrap().owner, owner);
+        assert_eq!(engine.get_signature_nonce(owner), 1);
+        assert_eq!(engine.get_signature_nonce(relayer), 0);
+        
+        // The same request cannot be replayed
+        assert_eq!(
+            engine.try_create_rule_with_signature(owner_key.clone(), request.clone(), signature),
+            Err(Error::InvalidSignatureNonce.into())
+        );
+        
+        // Nor submitted after its deadline
+        request.nonce = 1;
+        let signature = env.sign_message(&engine.get_rule_signing_message(request.clone()), &owner);
+        env.advance_block_time(SECONDS_PER_DAY + 1);
+        assert_eq!(
+            engine.try_create_rule_with_signature(owner_key, request, signature),
+            Err(Error::SignatureExpired.into())
+        );
     }
 
-    /// Get the user's current tier (placeholder - returns Starter for MVP)
-    pub fn get_user_tier(&self, _owner: Address) -> StakingTier {
-        // In production, this would query sCSPR balance
-        // For MVP, everyone gets Starter tier (2 rules max)
-        StakingTier::Starter
+    #[test]
+    fn test_session_keys() {
+        let (env, mut vault, mut engine) = setup();
+        let (owner, phone) = (env.get_account(1), env.get_account(2));
+        let phone_key = env.public_key(&phone);
+        env.set_caller(owner);
+        vault.with_tokens(U512::from(1_000u64)).deposit();
+        let rule_id = engine.create_rule(
+            "tip".to_string(),
+            TriggerType::Manual,
+            Schedule::Daily,
+            ActionType::Transfer,
+            Some(env.get_account(3)),
+            U512::from(100u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        let scope = SessionKeyScope {
+            can_pause_resume: true,
+            can_trigger_manual: false,
+            expires_at: env.block_time() + SECONDS_PER_DAY,
+        };
+        let empty = SessionKeyScope { can_pause_resume: false, ..scope.clone() };
+        assert_eq!(
+            engine.try_add_session_key(phone_key.clone(), empty),
+            Err(Error::InvalidSessionKey.into())
+        );
+        engine.add_session_key(phone_key.clone(), scope.clone());
+        assert_eq!(engine.get_session_key(owner, phone_key.clone()), Some(scope.clone()));
+        
+        // The phone can pause and resume but not trigger or delete
+        env.set_caller(phone);
+        engine.pause_rule(rule_id);
+        engine.resume_rule(rule_id);
+        assert_eq!(engine.try_execute_rule(rule_id), Err(Error::NotRuleOwner.into()));
+        assert_eq!(engine.try_delete_rule(rule_id), Err(Error::NotRuleOwner.into()));
+        
+        // Granting the trigger permission lets it run Manual rules
+        env.set_caller(owner);
+        let scope = SessionKeyScope { can_trigger_manual: true, ..scope };
+        engine.add_session_key(phone_key.clone(), scope);
+        env.set_caller(phone);
+        engine.execute_rule(rule_id);
+        assert_eq!(vault.get_balance(owner), U512::from(900u64));
+        
+        // Revoked keys stop working, as do expired ones
+        env.set_caller(owner);
+        engine.revoke_session_key(phone_key.clone());
+        env.set_caller(phone);
+        assert_eq!(engine.try_pause_rule(rule_id), Err(Error::NotRuleManager.into()));
+        env.set_caller(owner);
+        engine.add_session_key(phone_key, SessionKeyScope {
+            can_pause_resume: true,
+            can_trigger_manual: true,
+            expires_at: env.block_time() + 60,
+        });
+        env.advance_block_time(60);
+        env.set_caller(phone);
+        assert_eq!(engine.try_pause_rule(rule_id), Err(Error::NotRuleManager.into()));
     }
 
-    /// Get the number of active rules for a user
-    pub fn get_user_rule_count(&self, owner: Address) -> u32 {
-        self.user_rule_count.get_or_default(&owner)
+    #[test]
+    fn test_one_shot_rule() {
+        let (env, mut vault, mut engine) = setup();
+        let (owner, alice) = (env.get_account(1), env.get_account(2));
+        env.set_caller(owner);
+        vault.with_tokens(U512::from(1_000u64)).deposit();
+        let friday = env.block_time() + 3 * SECONDS_PER_DAY;
+        let rule_id = engine.create_rule(
+            "pay_alice".to_string(),
+            TriggerType::Time,
+            Schedule::Once,
+            ActionType::Transfer,
+            Some(alice),
+            U512::from(200u64),
+            None,
+            None,
+            None,
+            Some(friday),
+        );
+        assert_eq!(engine.get_rule(rule_id).unwrap().next_execution, friday);
+        assert_eq!(engine.try_execute_rule(rule_id), Err(Error::TriggerTimeNotReached.into()));
+        
+        // It runs once at the requested time, then completes and frees its slot
+        env.advance_block_time(3 * SECONDS_PER_DAY);
+        let before = env.balance_of(&alice);
+        engine.execute_rule(rule_id);
+        assert_eq!(env.balance_of(&alice), before + U512::from(200u64));
+        assert_eq!(engine.get_rule(rule_id).unwrap().status, RuleStatus::Completed);
+        assert!(env.emitted_event(
+            engine.address(),
+            RuleCompleted { rule_id, owner, nonce: engine.get_event_nonce() }
+        ));
+        assert_eq!(engine.get_user_rule_count(owner), 0);
+        
+        // A completed rule cannot run or be resumed again
+        env.advance_block_time(SECONDS_PER_MONTH);
+        assert_eq!(engine.try_execute_rule(rule_id), Err(Error::RuleNotActive.into()));
+        assert_eq!(engine.try_resume_rule(rule_id), Err(Error::RuleNotPaused.into()));
+        assert_eq!(vault.get_balance(owner), U512::from(800u64));
     }
 
-    /// Get the vault address
-    pub fn get_vault_address(&self) -> Option<Address> {
-        self.vault_address.get_or_default()
+    #[test]
+    fn test_recipient_blocklist() {
+        let (env, mut vault, mut engine) = setup();
+        let (admin, owner, recipient) = (env.get_account(0), env.get_account(1), env.get_account(2));
+        env.set_caller(owner);
+        vault.with_tokens(U512::from(1_000u64)).deposit();
+        let rule_id = engine.create_rule(
+            "payment".to_string(),
+            TriggerType::Manual,
+            Schedule::Daily,
+            ActionType::Transfer,
+            Some(recipient),
+            U512::from(100u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        assert_eq!(
+            engine.try_set_recipient_blocked(recipient, true),
+            Err(Error::NotAdmin.into())
+        );
+        
+        // Blocking applies to rules created before the recipient was blocked
+        env.set_caller(admin);
+        engine.set_recipient_blocked(recipient, true);
+        assert!(engine.is_recipient_blocked(recipient));
+        assert!(env.emitted_event(
+            engine.address(),
+            RecipientBlocklistUpdated { recipient, blocked: true, nonce: engine.get_event_nonce() }
+        ));
+        env.set_caller(owner);
+        assert_eq!(engine.try_execute_rule(rule_id), Err(Error::RecipientBlocked.into()));
+        assert!(!engine.preview_execution(rule_id).condition_met);
+        
+        // Unblocked recipients are paid again
+        env.set_caller(admin);
+        engine.set_recipient_blocked(recipient, false);
+        env.set_caller(owner);
+        engine.execute_rule(rule_id);
+        assert_eq!(vault.get_balance(owner), U512::from(900u64));
     }
 
-    // ========================================================================
-    // Internal Functions
-    // ========================================================================
+    #[test]
+    fn test_vault_reservations() {
+        let (env, mut vault, mut engine) = setup();
+        let (owner, landlord) = (env.get_account(1), env.get_account(2));
+        env.set_caller(owner);
+        vault.with_tokens(U512::from(1_000u64)).deposit();
+        let rent = engine.create_rule(
+            "rent".to_string(),
+            TriggerType::Time,
+            Schedule::Monthly,
+            ActionType::Transfer,
+            Some(landlord),
+            U512::from(300u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        
+        // Opting in reserves the rent; withdrawals can only take the rest
+        engine.set_reservation_mode(true);
+        assert_eq!(vault.get_reservation(rent), U512::from(300u64));
+        assert_eq!(vault.get_withdrawable_balance(owner), U512::from(700u64));
+        assert_eq!(vault.try_withdraw(U512::from(800u64)), Err(Error::FundsReserved.into()));
+        vault.withdraw(U512::from(400u64));
+        
+        // The rule can still spend its reservation, which then covers the next run
+        env.advance_block_time(SECONDS_PER_MONTH);
+        engine.execute_rule(rent);
+        assert_eq!(vault.get_balance(owner), U512::from(300u64));
+        assert_eq!(vault.get_reserved_balance(owner), U512::from(300u64));
+        assert_eq!(vault.try_withdraw(U512::from(1u64)), Err(Error::FundsReserved.into()));
+        
+        // Pausing the rule releases its reservation, as does opting out
+        engine.pause_rule(rent);
+        assert_eq!(vault.get_reserved_balance(owner), U512::zero());
+        engine.resume_rule(rent);
+        assert_eq!(vault.get_reservation(rent), U512::from(300u64));
+        engine.set_reservation_mode(false);
+        assert_eq!(vault.get_withdrawable_balance(owner), U512::from(300u64));
+        
+        // A sweep leaves other rules' reservations in place
+        vault.with_tokens(U512::from(500u64)).deposit();
+        engine.set_reservation_mode(true);
+        let sweep = engine.create_rule(
+            "sweep".to_string(),
+            TriggerType::Manual,
+            Schedule::Weekly,
+            ActionType::Sweep,
+            Some(landlord),
+            U512::zero(),
+            None,
+            None,
+            None,
+            None,
+        );
+        engine.execute_rule(sweep);
+        assert_eq!(vault.get_balance(owner), U512::from(300u64));
+        assert_eq!(vault.get_reservation(rent), U512::from(300u64));
+    }
 
-    /// Get a rule or revert if not found
-    fn get_rule_or_revert(&self, rule_id: u64) -> AutomationRule {
-        match self.rules.get(&rule_id) {
-            Some(rule) => rule,
-            None => {
-                self.env().revert(Error::RuleNotFound);
-            }
-        }
+    #[test]
+    fn test_due_rules_by_priority() {
+        let (env, _vault, mut engine) = setup();
+        let (alice, bob) = (env.get_account(1), env.get_account(2));
+        let mut create = |owner: Address, schedule: Schedule| {
+            env.set_caller(owner);
+            engine.create_rule(
+                "payment".to_string(),
+                TriggerType::Time,
+                schedule,
+                ActionType::Transfer,
+                Some(env.get_account(3)),
+                U512::from(100u64),
+                None,
+                None,
+                None,
+                None,
+            )
+        };
+        let rent = create(alice, Schedule::Daily);
+        let weekly = create(alice, Schedule::Weekly);
+        let payroll = create(bob, Schedule::Daily);
+        assert_eq!(engine.get_rule_priority(rent), 150);
+        
+        // Bob marks his rule critical, so it is listed first; the weekly rule is not due yet
+        engine.set_rule_priority(payroll, Some(250));
+        env.advance_block_time(SECONDS_PER_DAY);
+        assert_eq!(engine.get_due_rules_by_priority(0, 100), vec![payroll, rent]);
+        assert!(!engine.get_due_rules_by_priority(0, 100).contains(&weekly));
+        
+        // Equal priorities fall back to rule ID order
+        engine.set_rule_priority(payroll, None);
+        assert_eq!(engine.get_due_rules_by_priority(0, 100), vec![rent, payroll]);
+        env.set_caller(alice);
+        assert_eq!(
+            engine.try_set_rule_priority(payroll, Some(1)),
+            Err(Error::NotRuleManager.into())
+        );
     }
 
-    /// Calculate the next execution time based on schedule
-    fn calculate_next_execution(&self, from_time: u64, schedule: &Schedule) -> u64 {
-        match schedule {
-            Schedule::Daily => from_time + SECONDS_PER_DAY,
-            Schedule::Weekly => from_time + SECONDS_PER_WEEK,
-            Schedule::Monthly => from_time + SECONDS_PER_MONTH,
-        }
+    #[test]
+    fn test_state_checkpoint() {
+        let (env, mut vault, mut engine) = setup();
+        let user = env.get_account(1);
+        env.set_caller(user);
+        vault.with_tokens(U512::from(1_000u64)).deposit();
+        let (_, executions, nonce, block_time) = engine.get_state_checkpoint();
+        assert_eq!((executions, nonce, block_time), (0, 0, env.block_time()));
+        
+        let rule_id = engine.create_rule(
+            "tip".to_string(),
+            TriggerType::Manual,
+            Schedule::Daily,
+            ActionType::Transfer,
+            Some(env.get_account(2)),
+            U512::from(100u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        engine.execute_rule(rule_id);
+        
+        // Every counter moves forward and the nonce matches the last event
+        let checkpoint = engine.get_state_checkpoint();
+        assert_eq!(checkpoint.0, rule_id + 1);
+        assert_eq!(checkpoint.1, 1);
+        assert_eq!(checkpoint.2, engine.get_event_nonce());
+        assert!(checkpoint.2 > 0);
     }
 
-    /// Execute a transfer action via the vault
-    fn execute_transfer(&self, rule: &AutomationRule) {
-        let vault_addr = match self.vault_address.get_or_default() {
-            Some(addr) => addr,
-            None => self.env().revert(Error::InvalidRuleConfig),
-        };
+    #[test]
+    fn test_compressed_time_config() {
+        let (env, mut vault, mut engine) = setup();
+        let (owner, alice) = (env.get_account(1), env.get_account(2));
+        assert_eq!(engine.get_time_config(), TimeConfig::default());
         
-        let recipient = match rule.recipient {
-            Some(addr) => addr,
-            None => self.env().revert(Error::InvalidRuleConfig),
+        // Periods must be non-zero and in order, and only the admin sets them
+        let minutes = TimeConfig {
+            day: 60,
+            week: 420,
+            month: 1_800,
+            confirmation_delay: 60,
+            address_book_delay: 60,
         };
+        let result = engine.try_set_time_config(TimeConfig { week: 30, ..minutes.clone() });
+        assert_eq!(result, Err(Error::InvalidTimeConfig.into()));
+        env.set_caller(owner);
+        assert_eq!(engine.try_set_time_config(minutes.clone()), Err(Error::NotAdmin.into()));
+        env.set_caller(env.get_account(0));
+        engine.set_time_config(minutes.clone());
+        assert_eq!(engine.get_time_config(), minutes);
         
-        // Call vault contract to execute transfer
-        let mut vault = AutomationVaultContractRef::new(self.env(), vault_addr);
-        vault.execute_transfer(rule.owner, recipient, rule.amount, rule.id);
+        // A monthly rule now comes due every half hour
+        env.set_caller(owner);
+        vault.with_tokens(U512::from(1_000u64)).deposit();
+        let rule_id = engine.create_rule(
+            "rent".to_string(),
+            TriggerType::Time,
+            Schedule::Monthly,
+            ActionType::Transfer,
+            Some(alice),
+            U512::from(100u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        let start = env.block_time();
+        assert_eq!(engine.get_rule(rule_id).unwrap().next_execution, start + 1_800);
+        env.advance_block_time(1_800);
+        engine.execute_rule(rule_id);
+        assert_eq!(engine.get_rule(rule_id).unwrap().next_execution, start + 3_600);
+        assert_eq!(vault.get_balance(owner), U512::from(900u64));
+        
+        // Once rules exist the config is locked
+        env.set_caller(env.get_account(0));
+        let result = engine.try_set_time_config(TimeConfig::default());
+        assert_eq!(result, Err(Error::TimeConfigLocked.into()));
+        assert_eq!(engine.get_time_config(), minutes);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use odra::host::Deployer;
-    use crate::vault::{AutomationVault, AutomationVaultHostRef, AutomationVaultInitArgs};
+    #[test]
+    fn test_role_separation() {
+        let (env, mut vault, mut engine) = setup();
+        let (operator, pauser, owner, alice) =
+            (env.get_account(1), env.get_account(2), env.get_account(3), env.get_account(4));
+        
+        // Only the admin grants roles, and ownership cannot be granted
+        assert_eq!(engine.try_grant_role(Role::Owner, operator), Err(Error::InvalidRole.into()));
+        engine.grant_role(Role::Operator, operator);
+        assert!(env.emitted_event(
+            engine.address(),
+            RoleGranted { role: Role::Operator, account: operator, nonce: engine.get_event_nonce() }
+        ));
+        engine.grant_role(Role::Pauser, pauser);
+        vault.grant_role(Role::Operator, operator);
+        assert!(engine.has_role(Role::Operator, operator));
+        assert!(!engine.has_role(Role::FeeManager, operator));
+        assert!(engine.has_role(Role::FeeManager, env.get_account(0)));
+        
+        // The operator tunes limits but cannot touch fees, wiring or roles
+        env.set_caller(operator);
+        engine.set_max_executions_per_hour(10);
+        vault.set_max_daily_outflow(U512::from(5_000u64));
+        let result = engine.try_set_fee_config(100, U512::zero());
+        assert_eq!(result, Err(Error::NotAdmin.into()));
+        assert_eq!(engine.try_set_registry(None), Err(Error::NotAdmin.into()));
+        assert_eq!(engine.try_grant_role(Role::Pauser, alice), Err(Error::NotAdmin.into()));
+        assert_eq!(vault.try_set_automation_engine(alice), Err(Error::NotAdmin.into()));
+        
+        // The pauser halts every rule's execution until it resumes
+        env.set_caller(owner);
+        vault.with_tokens(U512::from(1_000u64)).deposit();
+        let rule_id = engine.create_rule(
+            "pay_alice".to_string(),
+            TriggerType::Manual,
+            Schedule::Daily,
+            ActionType::Transfer,
+            Some(alice),
+            U512::from(100u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        assert_eq!(engine.try_set_execution_paused(true), Err(Error::NotAdmin.into()));
+        env.set_caller(pauser);
+        engine.set_execution_paused(true);
+        assert!(engine.is_execution_paused());
+        env.set_caller(owner);
+        assert_eq!(engine.try_execute_rule(rule_id), Err(Error::ExecutionPaused.into()));
+        env.set_caller(pauser);
+        engine.set_execution_paused(false);
+        env.set_caller(owner);
+        engine.execute_rule(rule_id);
+        
+        // Revoked operators lose access at once
+        env.set_caller(env.get_account(0));
+        engine.revoke_role(Role::Operator, operator);
+        env.set_caller(operator);
+        let result = engine.try_set_max_executions_per_hour(0);
+        assert_eq!(result, Err(Error::NotAdmin.into()));
+    }
 
-    fn setup() -> (odra::host::HostEnv, AutomationVaultHostRef, AutomationEngineHostRef) {
-        let env = odra_test::env();
+    #[test]
+    fn test_admin_timelock() {
+        let (env, mut vault, mut engine) = setup();
+        let (keeper, oracle) = (env.get_account(1), env.get_account(2));
+        let delay = 2 * SECONDS_PER_DAY;
+        engine.set_admin_timelock_delay(delay);
+        vault.set_admin_timelock_delay(delay);
         
-        // Deploy vault first
-        let vault = AutomationVault::deploy(&env, AutomationVaultInitArgs {
-            automation_engine: None,
-        });
+        // Fee changes must be queued, and shortening the delay is queued too
+        let result = engine.try_set_fee_config(50, U512::zero());
+        assert_eq!(result, Err(Error::TimelockRequired.into()));
+        let result = engine.try_set_admin_timelock_delay(0);
+        assert_eq!(result, Err(Error::TimelockRequired.into()));
+        let action_id = engine.queue_fee_config(50, U512::zero());
+        let pending = engine.get_pending_admin_actions();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].0, action_id);
+        assert_eq!(pending[0].1.eta, env.block_time() + delay);
+        
+        // It takes effect only after the delay, and anyone may apply it
+        env.set_caller(keeper);
+        let result = engine.try_execute_admin_action(action_id);
+        assert_eq!(result, Err(Error::AdminActionNotReady.into()));
+        env.advance_block_time(delay);
+        engine.execute_admin_action(action_id);
+        assert_eq!(engine.get_fee_config().fee_bps, 50);
+        assert!(env.emitted_event(
+            engine.address(),
+            AdminActionExecuted {
+                action_id,
+                kind: AdminActionKind::SetFeeConfig,
+                nonce: engine.get_event_nonce(),
+            }
+        ));
+        assert!(engine.get_pending_admin_actions().is_empty());
+        
+        // Cancelled changes never apply
+        env.set_caller(env.get_account(0));
+        let action_id = engine.queue_price_oracle(oracle);
+        engine.cancel_admin_action(action_id);
+        env.advance_block_time(delay);
+        let result = engine.try_execute_admin_action(action_id);
+        assert_eq!(result, Err(Error::AdminActionNotFound.into()));
+        assert_eq!(engine.get_price_oracle(), None);
+        
+        // The vault's authorized engine is switched the same way
+        let replacement = env.get_account(3);
+        let result = vault.try_set_automation_engine(replacement);
+        assert_eq!(result, Err(Error::TimelockRequired.into()));
+        let action_id = vault.queue_automation_engine(replacement);
+        assert_eq!(vault.get_automation_engine(), Some(*engine.address()));
+        env.advance_block_time(delay);
+        vault.execute_admin_action(action_id);
+        assert_eq!(vault.get_automation_engine(), Some(replacement));
         
-        // Deploy engine with vault address
-        let engine = AutomationEngine::deploy(&env, AutomationEngineInitArgs {
-            vault_address: Some(vault.address().clone()),
+        // Attaching a registry is queued too, and its entries cannot bypass the delay
+        let mut registry = ContractRegistry::deploy(&env, NoArgs);
+        registry.set_address(ContractKind::Vault, Some(replacement));
+        let result = engine.try_set_registry(Some(*registry.address()));
+        assert_eq!(result, Err(Error::TimelockRequired.into()));
+        let action_id = engine.queue_registry(Some(*registry.address()));
+        env.advance_block_time(delay);
+        engine.execute_admin_action(action_id);
+        assert_eq!(engine.get_registry(), Some(*registry.address()));
+        assert_eq!(engine.get_vault_address(), Some(*vault.address()));
+        
+        // As are staking adapter changes
+        let adapter = StakingAdapter::deploy(&env, StakingAdapterInitArgs {
+            default_validator: None,
         });
+        let result = engine.try_set_staking_adapter(*adapter.address());
+        assert_eq!(result, Err(Error::TimelockRequired.into()));
+        let result = vault.try_set_staking_adapter(*adapter.address());
+        assert_eq!(result, Err(Error::TimelockRequired.into()));
+        let action_id = engine.queue_staking_adapter(*adapter.address());
+        env.advance_block_time(delay);
+        engine.execute_admin_action(action_id);
+        assert_eq!(engine.get_staking_adapter(), Some(*adapter.address()));
+    }
+
+    #[test]
+    fn test_execution_ids() {
+        let (env, mut vault, mut engine) = setup();
+        let (owner, alice) = (env.get_account(1), env.get_account(2));
+        env.set_caller(owner);
+        vault.with_tokens(U512::from(1_000u64)).deposit();
+        let create = |engine: &mut AutomationEngineHostRef, amount: u64| {
+            engine.create_rule(
+                "pay_alice".to_string(),
+                TriggerType::Manual,
+                Schedule::Daily,
+                ActionType::Transfer,
+                Some(alice),
+                U512::from(amount),
+                None,
+                None,
+                None,
+                None,
+            )
+        };
+        let (first, second) = (create(&mut engine, 100), create(&mut engine, 200));
         
-        // Set engine as authorized in vault
-        let mut vault_mut = vault;
-        vault_mut.set_automation_engine(engine.address().clone());
+        // Every execution gets the next ID, whichever rule it belongs to
+        assert_eq!(engine.execute_rule(first), 1);
+        assert_eq!(engine.execute_rule(second), 2);
+        assert_eq!(engine.execute_rule(first), 3);
         
-        (env, vault_mut, engine)
+        // The engine and vault events of one execution carry the same ID
+        assert!(env.emitted_event(
+            vault.address(),
+            AutomationExecuted {
+                owner,
+                rule_id: first,
+                execution_id: 3,
+                recipient: alice,
+                amount: U512::from(100u64),
+                fee: U512::zero(),
+                balance_before: U512::from(700u64),
+                balance_after: U512::from(600u64),
+                nonce: vault.get_event_nonce(),
+            }
+        ));
+        assert!(env.emitted_event(
+            engine.address(),
+            RuleExecuted {
+                rule_id: first,
+                owner,
+                executed_at: env.block_time(),
+                template_name_id: engine.get_rule(first).unwrap().template_name_id,
+                amount: U512::from(100u64),
+                execution_count: 2,
+                execution_id: 3,
+                nonce: engine.get_event_nonce(),
+            }
+        ));
     }
 
     #[test]
-    fn test_create_rule() {
+    fn test_vault_recipient_rule() {
+        let (env, mut vault, mut engine) = setup();
+        let (owner, alice) = (env.get_account(1), env.get_account(2));
+        env.set_caller(owner);
+        vault.with_tokens(U512::from(1_000u64)).deposit();
+        let rule_id = engine.create_rule(
+            "allowance".to_string(),
+            TriggerType::Manual,
+            Schedule::Daily,
+            ActionType::Transfer,
+            Some(alice),
+            U512::from(100u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        engine.set_vault_recipient(rule_id, true);
+        assert_eq!(engine.get_rule(rule_id).unwrap().recipient_kind, RecipientKind::Vault);
+        
+        // Alice's vault is credited; her account receives nothing
+        let before = env.balance_of(&alice);
+        engine.execute_rule(rule_id);
+        assert_eq!(vault.get_balance(alice), U512::from(100u64));
+        assert_eq!(vault.get_balance(owner), U512::from(900u64));
+        assert_eq!(env.balance_of(&alice), before);
+        
+        // Turning it off goes back to plain transfers
+        engine.set_vault_recipient(rule_id, false);
+        engine.execute_rule(rule_id);
+        assert_eq!(vault.get_balance(alice), U512::from(100u64));
+        assert_eq!(env.balance_of(&alice), before + U512::from(100u64));
+        
+        // Only CSPR transfer rules can pay into a vault
+        let split_rule = engine.create_rule(
+            "split".to_string(),
+            TriggerType::Manual,
+            Schedule::Daily,
+            ActionType::Split,
+            Some(alice),
+            U512::from(100u64),
+            None,
+            None,
+            None,
+            None,
+        );
+        let result = engine.try_set_vault_recipient(split_rule, true);
+        assert_eq!(result, Err(Error::InvalidRuleConfig.into()));
+    }
+
+    #[test]
+    fn test_rewards_condition() {
         let (env, _vault, mut engine) = setup();
         let user = env.get_account(0);
+        let validator = env.get_validator(0);
         env.set_caller(user);
         
-        let rule_id = engine.create_rule(
-            "recurring_payment".to_string(),
-            TriggerType::Time,
+        let mut adapter = StakingAdapter::deploy(&env, StakingAdapterInitArgs {
+            default_validator: Some(validator.clone()),
+        });
+        adapter.set_automation_engine(*engine.address());
+        engine.set_staking_adapter(*adapter.address());
+        adapter.with_tokens(U512::from(1_000_000_000_000u64)).stake();
+        
+        let threshold = U512::from(50_000_000_000u64);
+        let transfer_id = engine.create_rule(
+            "take_profit".to_string(),
+            TriggerType::Condition,
             Schedule::Daily,
             ActionType::Transfer,
             Some(env.get_account(1)),
-            U512::from(100_000_000u64),
+            U512::from(100u64),
+            None,
+            None,
+            None,
+            None,
         );
+        let result = engine.try_set_rewards_condition(transfer_id, threshold);
+        assert_eq!(result, Err(Error::InvalidRuleConfig.into()));
         
-        assert_eq!(rule_id, 1);
+        let rule_id = engine.create_rule(
+            "compound".to_string(),
+            TriggerType::Condition,
+            Schedule::Daily,
+            ActionType::Compound,
+            None,
+            U512::zero(),
+            None,
+            None,
+            None,
+            None,
+        );
+        engine.set_rewards_condition(rule_id, threshold);
+        assert_eq!(engine.get_rewards_condition(rule_id), Some(threshold));
         
-        let rule = engine.get_rule(rule_id);
-        assert!(rule.is_some());
-        assert_eq!(rule.unwrap().owner, user);
+        // Nothing has accrued yet, so the rule waits instead of running empty
+        assert_eq!(adapter.get_pending_rewards(None), U512::zero());
+        assert!(!engine.preview_execution(rule_id).condition_met);
+        let result = engine.try_execute_rule(rule_id);
+        assert_eq!(result, Err(Error::ConditionNotMet.into()));
+        
+        // Once rewards pass the threshold the rule compounds them
+        adapter.set_engine_permission(true);
+        env.advance_with_auctions(SECONDS_PER_WEEK);
+        let rewards = adapter.get_pending_rewards(None);
+        assert!(!rewards.is_zero());
+        let threshold = rewards - U512::one();
+        engine.set_rewards_condition(rule_id, threshold);
+        assert!(engine.preview_execution(rule_id).condition_met);
+        let nonce = engine.get_event_nonce() + 1;
+        engine.execute_rule(rule_id);
+        assert_eq!(adapter.get_pending_rewards(None), U512::zero());
+        assert!(env.emitted_event(
+            engine.address(),
+            RewardsConditionMet { rule_id, rewards, threshold, nonce }
+        ));
+        
+        // Without the rewards condition the rule waits on the price again
+        let result = engine.try_set_rewards_condition(rule_id, U512::zero());
+        assert_eq!(result, Err(Error::InvalidRuleConfig.into()));
+        engine.clear_rewards_condition(rule_id);
+        assert_eq!(engine.get_rewards_condition(rule_id), None);
+        assert!(!engine.preview_execution(rule_id).condition_met);
     }
 
     #[test]
-    fn test_pause_and_resume() {
-        let (env, _vault, mut engine) = setup();
+    fn test_attestation_condition() {
+        let (env, mut vault, mut engine) = setup();
         let user = env.get_account(0);
+        let attester = env.get_account(2);
         env.set_caller(user);
+        vault.with_tokens(U512::from(1_000u64)).deposit();
+        
+        let mut oracle = AttestationOracle::deploy(&env, NoArgs);
+        oracle.set_attester(attester, true);
+        engine.set_attestation_oracle(*oracle.address());
         
         let rule_id = engine.create_rule(
-            "test_rule".to_string(),
-            TriggerType::Manual,
+            "sponsor_bonus".to_string(),
+            TriggerType::Condition,
             Schedule::Daily,
             ActionType::Transfer,
             Some(env.get_account(1)),
-            U512::from(100_000_000u64),
+            U512::from(100u64),
+            None,
+            None,
+            None,
+            None,
         );
+        // If github_sponsor_count >= 10, seen within the last day
+        let key = "github_sponsor_count".to_string();
+        engine.set_attestation_condition(rule_id, AttestationCondition {
+            key: key.clone(),
+            comparison: Comparison::AtLeast,
+            threshold: U512::from(10u64),
+            max_age: SECONDS_PER_DAY,
+        });
+        assert_eq!(engine.try_execute_rule(rule_id), Err(Error::ConditionNotMet.into()));
         
-        // Pause
-        engine.pause_rule(rule_id);
-        let rule = engine.get_rule(rule_id).unwrap();
-        assert!(matches!(rule.status, RuleStatus::Paused));
+        env.set_caller(attester);
+        oracle.post(key.clone(), U512::from(9u64), env.block_time());
+        env.set_caller(user);
+        assert_eq!(engine.try_execute_rule(rule_id), Err(Error::ConditionNotMet.into()));
         
-        // Resume
-        engine.resume_rule(rule_id);
-        let rule = engine.get_rule(rule_id).unwrap();
-        assert!(matches!(rule.status, RuleStatus::Active));
+        env.set_caller(attester);
+        oracle.post(key.clone(), U512::from(10u64), env.block_time());
+        env.advance_block_time(SECONDS_PER_DAY + 1);
+        env.set_caller(user);
+        assert_eq!(engine.try_execute_rule(rule_id), Err(Error::StaleAttestation.into()));
+        
+        env.set_caller(attester);
+        oracle.post(key.clone(), U512::from(10u64), env.block_time());
+        env.set_caller(user);
+        assert!(engine.preview_execution(rule_id).condition_met);
+        let nonce = engine.get_event_nonce() + 1;
+        engine.execute_rule(rule_id);
+        assert_eq!(vault.get_balance(user), U512::from(900u64));
+        assert!(env.emitted_event(
+            engine.address(),
+            AttestationConditionMet {
+                rule_id,
+                key,
+                value: U512::from(10u64),
+                threshold: U512::from(10u64),
+                nonce,
+            }
+        ));
+        
+        // Without the attestation condition the rule waits on the price again
+        engine.clear_attestation_condition(rule_id);
+        assert_eq!(engine.get_attestation_condition(rule_id), None);
+        env.advance_block_time(SECONDS_PER_DAY);
+        assert_eq!(engine.try_execute_rule(rule_id), Err(Error::ConditionNotMet.into()));
     }
 
     #[test]
-    fn test_tier_limit() {
+    fn test_user_rule_counts() {
         let (env, _vault, mut engine) = setup();
-        let user = env.get_account(0);
-        env.set_caller(user);
+        let owner = env.get_account(0);
+        let create = |engine: &mut AutomationEngineHostRef| {
+            engine.try_create_rule(
+                "counted".to_string(),
+                TriggerType::Time,
+                Schedule::Weekly,
+                ActionType::Transfer,
+                Some(env.get_account(1)),
+                U512::from(1_000u64),
+                None,
+                None,
+                None,
+                None,
+            )
+        };
+        let first = create(&mut engine).unwrap();
+        let second = create(&mut engine).unwrap();
+        engine.pause_rule(second);
+        assert_eq!(engine.get_user_rule_counts(owner), (1, 1, 0));
         
-        // Starter tier allows 2 rules
-        for i in 0..2 {
+        // Paused rules still hold a slot under the Starter tier's limit
+        assert_eq!(create(&mut engine), Err(Error::MaxRulesReached.into()));
+        
+        // Deleting twice frees one slot only
+        engine.delete_rule(second);
+        engine.delete_rule(second);
+        assert_eq!(engine.get_user_rule_counts(owner), (1, 0, 1));
+        assert_eq!(engine.get_user_rule_count(owner), 1);
+        let third = create(&mut engine).unwrap();
+        assert_eq!(create(&mut engine), Err(Error::MaxRulesReached.into()));
+        
+        // Purged rules drop out of the deleted count
+        engine.delete_rule(first);
+        assert_eq!(engine.get_user_rule_counts(owner), (1, 0, 2));
+        assert_eq!(engine.purge_deleted_rules(owner), 2);
+        assert_eq!(engine.get_user_rule_counts(owner), (1, 0, 0));
+        assert_eq!(engine.get_rule(third).unwrap().status, RuleStatus::Active);
+    }
+
+    #[test]
+    fn test_execute_rules_results() {
+        let (env, mut vault, mut engine) = setup();
+        let owner = env.get_account(0);
+        vault.with_tokens(U512::from(1_000u64)).deposit();
+        let mut create = || {
             engine.create_rule(
-                format!("rule_{}", i),
-                TriggerType::Manual,
+                "rent".to_string(),
+                TriggerType::Time,
                 Schedule::Daily,
                 ActionType::Transfer,
                 Some(env.get_account(1)),
-                U512::from(100_000_000u64),
-            );
+                U512::from(600u64),
+                None,
+                None,
+                None,
+                None,
+            )
+        };
+        let (paid, short) = (create(), create());
+        env.advance_block_time(SECONDS_PER_DAY);
+        
+        // Each rule of the batch reports its own outcome
+        let results = engine.execute_rules(vec![paid, short, 99]);
+        let ok = |rule_id: u64, execution_id: u64| ExecutionResult {
+            rule_id,
+            execution_id,
+            status_code: 0,
+            failed_step: None,
+            shortfall: U512::zero(),
+        };
+        let failed = |rule_id: u64, error: Error, step: Option<ExecutionStep>, shortfall: u64| {
+            ExecutionResult {
+                rule_id,
+                execution_id: 0,
+                status_code: error as u32,
+                failed_step: step,
+                shortfall: U512::from(shortfall),
+            }
+        };
+        assert_eq!(results, vec![
+            ok(paid, 1),
+            failed(short, Error::InsufficientBalance, Some(ExecutionStep::VaultBalance), 200),
+            failed(99, Error::RuleNotFound, None, 0),
+        ]);
+        
+        // Rules another keeper already ran are skipped, not reverted
+        let results = engine.execute_rules(vec![paid]);
+        assert_eq!(results, vec![failed(paid, Error::TriggerTimeNotReached, None, 0)]);
+        let result = engine.execute_rule_with_result(short);
+        assert_eq!(result.shortfall, U512::from(200u64));
+        assert!(env.emitted_event(
+            engine.address(),
+            RuleExecutionFailed {
+                rule_id: short,
+                owner,
+                error_code: Error::InsufficientBalance as u32,
+                template_name_id: engine.get_rule(short).unwrap().template_name_id,
+                step: ExecutionStep::VaultBalance,
+                shortfall: U512::from(200u64),
+                nonce: engine.get_event_nonce(),
+            }
+        ));
+    }
+
+    #[test]
+    fn test_restore_rule() {
+        let (env, _vault, mut engine) = setup();
+        let owner = env.get_account(0);
+        let create = |engine: &mut AutomationEngineHostRef| {
+            engine.create_rule(
+                "restorable".to_string(),
+                TriggerType::Time,
+                Schedule::Weekly,
+                ActionType::Transfer,
+                Some(env.get_account(1)),
+                U512::from(1_000u64),
+                None,
+                None,
+                None,
+                None,
+            )
+        };
+        let rule_id = create(&mut engine);
+        engine.pause_rule(rule_id);
+        engine.delete_rule(rule_id);
+        
+        // Restoring is off until the admin sets a grace period
+        assert_eq!(engine.try_restore_rule(rule_id), Err(Error::RestoreWindowClosed.into()));
+        engine.set_restore_grace_period(SECONDS_PER_DAY);
+        env.advance_block_time(3_600);
+        engine.restore_rule(rule_id);
+        assert_eq!(engine.get_rule(rule_id).unwrap().status, RuleStatus::Paused);
+        assert_eq!(engine.get_user_rule_counts(owner), (0, 1, 0));
+        assert!(env.emitted_event(
+            engine.address(),
+            RuleRestored {
+                rule_id,
+                owner,
+                status: RuleStatus::Paused,
+                nonce: engine.get_event_nonce(),
+            }
+        ));
+        assert_eq!(engine.try_restore_rule(rule_id), Err(Error::RuleNotDeleted.into()));
+        
+        // A restored rule needs a free slot under the tier limit
+        create(&mut engine);
+        engine.delete_rule(rule_id);
+        let newer = create(&mut engine);
+        assert_eq!(engine.try_restore_rule(rule_id), Err(Error::MaxRulesReached.into()));
+        
+        // The window closes once the grace period has passed
+        engine.delete_rule(newer);
+        env.advance_block_time(SECONDS_PER_DAY + 1);
+        assert_eq!(engine.try_restore_rule(rule_id), Err(Error::RestoreWindowClosed.into()));
+    }
+
+    #[test]
+    fn test_rule_tags() {
+        let (env, _vault, mut engine) = setup();
+        let owner = env.get_account(0);
+        let mut create = |name: &str| {
+            engine.create_rule(
+                name.to_string(),
+                TriggerType::Manual,
+                Schedule::Monthly,
+                ActionType::Transfer,
+                Some(env.get_account(1)),
+                U512::from(1_000u64),
+                None,
+                None,
+                None,
+                None,
+            )
+        };
+        let (allowance, payroll) = (create("allowance"), create("payroll"));
+        let tags = |tags: &[&str]| tags.iter().map(|tag| tag.to_string()).collect::<Vec<_>>();
+        engine.set_rule_tags(allowance, tags(&["family", "monthly"]));
+        engine.set_rule_tags(payroll, tags(&["business", "monthly"]));
+        
+        let tagged = |engine: &AutomationEngineHostRef, tag: &str| {
+            engine
+                .get_user_rules_by_tag(owner, tag.to_string())
+                .into_iter()
+                .map(|rule| rule.id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(tagged(&engine, "family"), vec![allowance]);
+        assert_eq!(tagged(&engine, "monthly"), vec![allowance, payroll]);
+        assert_eq!(engine.get_rule_tags(payroll), tags(&["business", "monthly"]));
+        
+        // Repeated, empty, over-long or too many tags are rejected
+        for invalid in [
+            tags(&["family", "family"]),
+            tags(&[""]),
+            vec!["x".repeat(MAX_RULE_TAG_LEN + 1)],
+            (0..=MAX_RULE_TAGS).map(|n| n.to_string()).collect(),
+        ] {
+            let result = engine.try_set_rule_tags(allowance, invalid);
+            assert_eq!(result, Err(Error::InvalidRuleTags.into()));
         }
         
-        // Third rule should fail
-        let result = engine.try_create_rule(
-            "rule_3".to_string(),
-            TriggerType::Manual,
-            Schedule::Daily,
+        // Only the owner or their managers can tag a rule
+        env.set_caller(env.get_account(2));
+        let result = engine.try_set_rule_tags(allowance, tags(&["mine"]));
+        assert_eq!(result, Err(Error::NotRuleManager.into()));
+        env.set_caller(owner);
+        engine.set_rule_tags(allowance, Vec::new());
+        assert!(tagged(&engine, "family").is_empty());
+    }
+
+    #[test]
+    fn test_claimable_transfer_rule() {
+        let (env, mut vault, mut engine) = setup();
+        let owner = env.get_account(0);
+        let recipient = env.get_account(1);
+        vault.with_tokens(U512::from(10_000u64)).deposit();
+        let rule_id = engine.create_rule(
+            "pocket money".to_string(),
+            TriggerType::Time,
+            Schedule::Weekly,
             ActionType::Transfer,
-            Some(env.get_account(1)),
-            U512::from(100_000_000u64),
+            Some(recipient),
+            U512::from(1_000u64),
+            None,
+            None,
+            None,
+            None,
         );
+        let result = engine.try_set_claimable_transfer(rule_id, MAX_CLAIM_WINDOW + 1);
+        assert_eq!(result, Err(Error::InvalidRuleConfig.into()));
+        engine.set_claimable_transfer(rule_id, SECONDS_PER_MONTH);
+        let result = engine.try_set_vault_recipient(rule_id, true);
+        assert_eq!(result, Err(Error::InvalidRuleConfig.into()));
         
-        assert!(result.is_err());
+        // Executions hold the amount for the recipient instead of sending it
+        let before = env.balance_of(&recipient);
+        env.advance_block_time(SECONDS_PER_WEEK);
+        let execution_id = engine.execute_rule(rule_id);
+        env.advance_block_time(SECONDS_PER_WEEK);
+        engine.execute_rule(rule_id);
+        assert_eq!(vault.get_balance(owner), U512::from(8_000u64));
+        assert_eq!(vault.get_claimable_balance(recipient), U512::from(2_000u64));
+        assert_eq!(env.balance_of(&recipient), before);
+        let held = vault.get_claimable_transfer_ids(recipient, 0);
+        assert_eq!(held.len(), 2);
+        let transfer = vault.get_claimable_transfer(held[0]).unwrap();
+        assert_eq!(transfer.execution_id, execution_id);
+        
+        // The owner cannot take a payment back before it expires, nor someone else's
+        assert_eq!(vault.try_refund_claimable(held[1]), Err(Error::ClaimNotExpired.into()));
+        env.set_caller(env.get_account(2));
+        let result = vault.try_refund_claimable(held[1]);
+        assert_eq!(result, Err(Error::NotClaimableSender.into()));
+        
+        // The second payment expires unclaimed and returns to the owner's balance
+        env.set_caller(owner);
+        env.advance_block_time(SECONDS_PER_MONTH);
+        vault.refund_claimable(held[1]);
+        assert_eq!(vault.get_balance(owner), U512::from(9_000u64));
+        assert_eq!(vault.get_claimable_transfer(held[1]).unwrap().status, ClaimStatus::Refunded);
+        assert_eq!(vault.get_claimable_transfer_ids(recipient, 0), vec![held[0]]);
+        
+        // Only the recipient can claim, in bounded batches, even after expiry
+        assert_eq!(vault.try_claim(vec![held[0]]), Err(Error::NotClaimableRecipient.into()));
+        env.set_caller(recipient);
+        let result = vault.try_claim(vec![held[0]; MAX_CLAIM_BATCH as usize + 1]);
+        assert_eq!(result, Err(Error::BatchTooLarge.into()));
+        env.set_caller(owner);
+        engine.set_recipient_blocked(recipient, true);
+        env.set_caller(recipient);
+        assert_eq!(vault.try_claim(vec![held[0]]), Err(Error::RecipientBlocked.into()));
+        env.set_caller(owner);
+        engine.set_recipient_blocked(recipient, false);
+        env.set_caller(recipient);
+        let before = env.balance_of(&recipient);
+        assert_eq!(vault.claim(vec![held[0]]), U512::from(1_000u64));
+        assert_eq!(env.balance_of(&recipient), before + U512::from(1_000u64));
+        assert_eq!(vault.get_claimable_balance(recipient), U512::zero());
+        assert_eq!(vault.try_claim(Vec::new()), Err(Error::NothingToClaim.into()));
+        let result = vault.try_claim(vec![held[0]]);
+        assert_eq!(result, Err(Error::ClaimableTransferNotFound.into()));
+        env.set_caller(owner);
+        let result = vault.try_refund_claimable(held[0]);
+        assert_eq!(result, Err(Error::ClaimableTransferNotFound.into()));
     }
 }
@@ -0,0 +1,195 @@
+//! Gas Benchmarks
+//!
+//! Executes one rule of each common action type and compares the gas used
+//! with the engine's `estimate_execution_cost`, and tracks the storage cost
+//! of creating rules. The OdraVM backend does not meter gas, so real
+//! numbers need the Casper backend:
+//!
+//! `ODRA_BACKEND=casper cargo odra test -- benchmarks --nocapture`
+
+use odra::casper_types::bytesrepr::ToBytes;
+use odra::casper_types::U512;
+use odra::host::{DeployReport, Deployer, HostEnv, HostRef};
+use odra::prelude::*;
+
+use crate::automation_engine::{
+    AutomationEngine, AutomationEngineHostRef, AutomationEngineInitArgs,
+};
+use crate::staking_adapter::{StakingAdapter, StakingAdapterInitArgs};
+use crate::types::{ActionType, LegacyRuleConfig, RuleConfig, Schedule, TriggerType};
+use crate::vault::{AutomationVault, AutomationVaultHostRef, AutomationVaultInitArgs};
+
+/// Seconds in a day
+const DAY: u64 = 86_400;
+/// Vault deposit of each benchmark owner (2,000 CSPR)
+const DEPOSIT: u64 = 2_000_000_000_000;
+
+fn setup() -> (HostEnv, AutomationVaultHostRef, AutomationEngineHostRef) {
+    let env = odra_test::env();
+    let mut vault = AutomationVault::deploy(&env, AutomationVaultInitArgs {
+        automation_engine: None,
+    });
+    let engine = AutomationEngine::deploy(&env, AutomationEngineInitArgs {
+        vault_address: Some(*vault.address()),
+    });
+    vault.set_automation_engine(*engine.address());
+    (env, vault, engine)
+}
+
+/// Gas used by the most recent contract call (zero on OdraVM)
+fn last_gas(env: &HostEnv) -> U512 {
+    match env.gas_report().last() {
+        Some(DeployReport::ContractCall { gas, .. }) => *gas,
+        _ => U512::zero(),
+    }
+}
+
+/// Execute a rule, print its gas next to the estimate and check the estimate covers it
+fn measure(env: &HostEnv, engine: &mut AutomationEngineHostRef, rule_id: u64, label: &str) {
+    let estimate = engine.estimate_execution_cost(rule_id);
+    engine.execute_rule(rule_id);
+    let gas = last_gas(env);
+    println!("{label:<16} used {gas:>14} estimated {estimate:>14}");
+    assert!(gas <= estimate, "{label} used more gas than estimated");
+}
+
+#[test]
+fn benchmark_execution_costs() {
+    let (env, mut vault, mut engine) = setup();
+    let validator = env.get_validator(0);
+    let mut adapter = StakingAdapter::deploy(&env, StakingAdapterInitArgs {
+        default_validator: Some(validator.clone()),
+    });
+    adapter.set_validator_allowed(validator.clone(), true);
+    vault.set_staking_adapter(*adapter.address());
+    engine.set_staking_adapter(*adapter.address());
+    let recipient = env.get_account(5);
+    
+    // Each owner gets their own account so the Starter tier's rule limit is never hit
+    let owners: Vec<Address> = (0..4).map(|n| env.get_account(n)).collect();
+    for owner in &owners {
+        env.set_caller(*owner);
+        vault.with_tokens(U512::from(DEPOSIT)).deposit();
+    }
+    
+    env.set_caller(owners[0]);
+    let transfer = engine.create_rule(
+        "transfer".to_string(),
+        TriggerType::Manual,
+        Schedule::Daily,
+        ActionType::Transfer,
+        Some(recipient),
+        U512::from(1_000_000_000u64),
+        None,
+        None,
+        None,
+        None,
+    );
+    
+    env.set_caller(owners[1]);
+    let top_up = engine.create_top_up_rule(
+        "top_up".to_string(),
+        Schedule::Daily,
+        recipient,
+        U512::from(5_000_000_000u64),
+        U512::from(5_000_000_000u64),
+    );
+    
+    env.set_caller(owners[2]);
+    let stake = engine.create_stake_rule(
+        "stake".to_string(),
+        Schedule::Daily,
+        U512::from(500_000_000_000u64),
+        validator,
+    );
+    
+    // A two-rule workflow is estimated and executed as one
+    env.set_caller(owners[3]);
+    let mut workflow = Vec::new();
+    for name in ["pay_rent", "pay_utilities"] {
+        workflow.push(engine.create_rule(
+            name.to_string(),
+            TriggerType::Manual,
+            Schedule::Daily,
+            ActionType::Transfer,
+            Some(recipient),
+            U512::from(1_000_000_000u64),
+            None,
+            None,
+            None,
+            None,
+        ));
+    }
+    engine.set_follow_up_rules(workflow[0], vec![workflow[1]]);
+    assert!(engine.estimate_execution_cost(workflow[0]) > engine.estimate_execution_cost(transfer));
+    
+    env.advance_block_time(DAY);
+    env.set_caller(owners[0]);
+    measure(&env, &mut engine, transfer, "transfer");
+    measure(&env, &mut engine, top_up, "top_up");
+    measure(&env, &mut engine, stake, "stake_from_vault");
+    env.set_caller(owners[3]);
+    measure(&env, &mut engine, workflow[0], "workflow (2)");
+}
+
+#[test]
+fn benchmark_rule_storage() {
+    let (env, _vault, mut engine) = setup();
+    let owner = env.get_account(0);
+    let recipient = env.get_account(5);
+    let template_name = "recurring_payment".to_string();
+    let create = |engine: &mut AutomationEngineHostRef| {
+        engine.create_rule(
+            template_name.clone(),
+            TriggerType::Manual,
+            Schedule::Daily,
+            ActionType::Transfer,
+            Some(recipient),
+            U512::from(1_000_000_000u64),
+            None,
+            None,
+            None,
+            None,
+        )
+    };
+    
+    // Each config record stores a four-byte name ID instead of the name itself
+    let rule_id = create(&mut engine);
+    let rule = engine.get_rule(rule_id).unwrap();
+    let config = rule.config();
+    let legacy = LegacyRuleConfig {
+        id: config.id,
+        owner: config.owner,
+        trigger_type: config.trigger_type.clone(),
+        schedule: config.schedule.clone(),
+        action_type: config.action_type.clone(),
+        template_name: rule.template_name.clone(),
+        recipient: config.recipient,
+        amount: config.amount,
+        token: config.token,
+        validator: config.validator.clone(),
+    };
+    let (before, after) = (legacy.serialized_length(), config.serialized_length());
+    println!("rule config      {before:>4} bytes before {after:>4} bytes after");
+    assert_eq!(before - after, template_name.len());
+    assert_eq!(legacy.into_config(rule.template_name_id), config);
+    assert_eq!(engine.get_template_name(rule.template_name_id), Some(template_name.clone()));
+    
+    // Creating a rule no longer rewrites a list that grows with the owner's history
+    engine.delete_rule(rule_id);
+    let first_gas = {
+        let rule_id = create(&mut engine);
+        let gas = last_gas(&env);
+        engine.delete_rule(rule_id);
+        gas
+    };
+    for _ in 0..20 {
+        let rule_id = create(&mut engine);
+        engine.delete_rule(rule_id);
+    }
+    create(&mut engine);
+    let latest_gas = last_gas(&env);
+    println!("create_rule      used {first_gas:>14} first {latest_gas:>14} after 20 more");
+    assert!(latest_gas <= first_gas, "rule creation cost grew with the owner's rule count");
+    assert_eq!(engine.get_user_rule_ids(owner).len(), 23);
+}
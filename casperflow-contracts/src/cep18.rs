@@ -0,0 +1,97 @@
+//! CEP-18 Token Interface
+//!
+//! External contract definition used by CasperFlow contracts to move
+//! CEP-18 fungible tokens (stablecoins, project tokens) on behalf of users.
+
+use odra::prelude::*;
+use odra::casper_types::{U256, U512};
+
+/// Subset of the CEP-18 standard used by the vault
+#[odra::external_contract]
+pub trait Cep18Token {
+    /// Transfer tokens from the caller to a recipient
+    fn transfer(&mut self, recipient: &Address, amount: &U256);
+    /// Allow `spender` to move up to `amount` of the caller's tokens
+    fn approve(&mut self, spender: &Address, amount: &U256);
+    /// Transfer tokens from `owner` to a recipient using the caller's allowance
+    fn transfer_from(&mut self, owner: &Address, recipient: &Address, amount: &U256);
+    /// Get the token balance of an address
+    fn balance_of(&self, address: &Address) -> U256;
+}
+
+/// Convert a CSPR-denominated amount to a CEP-18 token amount, if it fits in a U256
+pub fn to_token_amount(amount: U512) -> Option<U256> {
+    if amount.bits() > 256 {
+        return None;
+    }
+    let mut bytes = [0u8; 64];
+    amount.to_little_endian(&mut bytes);
+    Some(U256::from_little_endian(&bytes[..32]))
+}
+
+/// Convert a CEP-18 token amount to a CSPR-denominated amount
+pub fn from_token_amount(amount: U256) -> U512 {
+    let mut bytes = [0u8; 32];
+    amount.to_little_endian(&mut bytes);
+    U512::from_little_endian(&bytes)
+}
+
+/// Minimal CEP-18 token used by the unit tests
+#[cfg(test)]
+pub mod test_token {
+    use odra::prelude::*;
+    use odra::casper_types::U256;
+
+    use crate::errors::Error;
+
+    #[odra::module(errors = Error)]
+    pub struct TestToken {
+        balances: Mapping<Address, U256>,
+        allowances: Mapping<(Address, Address), U256>,
+    }
+
+    #[odra::module]
+    impl TestToken {
+        /// Mint tokens to an address (no access control, tests only)
+        pub fn mint(&mut self, owner: &Address, amount: &U256) {
+            let balance = self.balances.get_or_default(owner);
+            self.balances.set(owner, balance + *amount);
+        }
+
+        pub fn approve(&mut self, spender: &Address, amount: &U256) {
+            let caller = self.env().caller();
+            self.allowances.set(&(caller, *spender), *amount);
+        }
+
+        pub fn transfer(&mut self, recipient: &Address, amount: &U256) {
+            let caller = self.env().caller();
+            self.move_tokens(&caller, recipient, amount);
+        }
+
+        pub fn transfer_from(&mut self, owner: &Address, recipient: &Address, amount: &U256) {
+            let spender = self.env().caller();
+            let allowance = self.allowances.get_or_default(&(*owner, spender));
+            if allowance < *amount {
+                self.env().revert(Error::InsufficientBalance);
+            }
+            self.allowances.set(&(*owner, spender), allowance - *amount);
+            self.move_tokens(owner, recipient, amount);
+        }
+
+        pub fn balance_of(&self, address: &Address) -> U256 {
+            self.balances.get_or_default(address)
+        }
+    }
+
+    impl TestToken {
+        fn move_tokens(&mut self, from: &Address, to: &Address, amount: &U256) {
+            let from_balance = self.balances.get_or_default(from);
+            if from_balance < *amount {
+                self.env().revert(Error::InsufficientBalance);
+            }
+            self.balances.set(from, from_balance - *amount);
+            let to_balance = self.balances.get_or_default(to);
+            self.balances.set(to, to_balance + *amount);
+        }
+    }
+}
@@ -0,0 +1,37 @@
+//! CEP-78 NFT Interface
+//!
+//! External contract definition used by the automation engine to check
+//! membership NFT holdings for tier and fee perks.
+
+use odra::prelude::*;
+
+/// Subset of the CEP-78 standard used by the automation engine
+#[odra::external_contract]
+pub trait Cep78Nft {
+    /// Get the number of tokens owned by an address
+    fn balance_of(&self, token_owner: Address) -> u64;
+}
+
+/// Minimal CEP-78 collection used by the unit tests
+#[cfg(test)]
+pub mod test_nft {
+    use odra::prelude::*;
+
+    #[odra::module]
+    pub struct TestNft {
+        balances: Mapping<Address, u64>,
+    }
+
+    #[odra::module]
+    impl TestNft {
+        /// Mint a token to an address (no access control, tests only)
+        pub fn mint(&mut self, token_owner: Address) {
+            let balance = self.balances.get_or_default(&token_owner);
+            self.balances.set(&token_owner, balance + 1);
+        }
+
+        pub fn balance_of(&self, token_owner: Address) -> u64 {
+            self.balances.get_or_default(&token_owner)
+        }
+    }
+}
@@ -16,6 +16,70 @@ pub enum Error {
     UnauthorizedExecutor = 3,
     /// Zero amount is not allowed
     ZeroAmount = 4,
+    /// Caller is not the contract admin and lacks the role the function needs
+    NotAdmin = 5,
+    /// Token is not on the vault's allowlist
+    TokenNotAllowed = 6,
+    /// Payment stream not found
+    StreamNotFound = 7,
+    /// Caller is not the stream recipient
+    NotStreamRecipient = 8,
+    /// Vesting schedule not found
+    VestingNotFound = 9,
+    /// Caller is not the vesting beneficiary
+    NotVestingBeneficiary = 10,
+    /// No treasury address has been configured
+    TreasuryNotSet = 11,
+    /// No staking adapter has been configured
+    StakingAdapterNotSet = 12,
+    /// No vault has been configured
+    VaultNotSet = 13,
+    /// Withdrawals from this vault must be requested and wait for the delay
+    WithdrawalTimelocked = 14,
+    /// Withdrawal request not found or already closed
+    WithdrawalRequestNotFound = 15,
+    /// Withdrawal delay has not elapsed
+    WithdrawalNotReady = 16,
+    /// A guarded entrypoint was re-entered before it finished
+    ReentrancyDetected = 17,
+    /// The owner's automated outflow for the day is used up
+    DailyOutflowLimitExceeded = 18,
+    /// Withdrawal exceeds the fees accrued in the vault
+    InsufficientFees = 19,
+    /// The vault holds less CSPR than it owes users and the fee pool
+    SolvencyViolation = 20,
+    /// Contract recipient's entrypoint is not allowlisted, or its arguments could not be encoded
+    InvalidContractRecipient = 21,
+    /// The debit would spend funds earmarked for an envelope
+    EnvelopeFundsLocked = 22,
+    /// Envelope holds less than the amount requested
+    InsufficientEnvelopeBalance = 23,
+    /// Envelope name is empty or too long, or the owner has too many envelopes
+    InvalidEnvelope = 24,
+    /// Escrowed payment not found or already settled
+    EscrowNotFound = 25,
+    /// Caller is not the party allowed to settle the escrowed payment
+    NotEscrowParty = 26,
+    /// The escrowed payment's dispute window has not ended
+    EscrowLocked = 27,
+    /// The escrowed payment's dispute window has ended
+    DisputeWindowClosed = 28,
+    /// The owner has not opted into auto-staking idle funds
+    AutoStakeNotEnabled = 29,
+    /// The withdrawal would take funds reserved for upcoming rule executions
+    FundsReserved = 30,
+    /// Deposit routing shares exceed 100% or lack a validator or envelope
+    InvalidDepositRouting = 31,
+    /// The caller has no claimable transfers waiting
+    NothingToClaim = 32,
+    /// Claimable transfer not found or already settled
+    ClaimableTransferNotFound = 33,
+    /// Caller is not the owner who sent the claimable transfer
+    NotClaimableSender = 34,
+    /// The claimable transfer has not expired yet
+    ClaimNotExpired = 35,
+    /// Caller is not the recipient of the claimable transfer
+    NotClaimableRecipient = 36,
     
     // Automation Engine Errors (100-199)
     /// Rule not found
@@ -36,6 +100,102 @@ pub enum Error {
     MaxRulesReached = 107,
     /// Trigger time not yet reached
     TriggerTimeNotReached = 108,
+    /// Fee configuration is out of bounds
+    InvalidFeeConfig = 109,
+    /// Template not found or disabled
+    TemplateNotFound = 110,
+    /// Parameters do not match the template's schema
+    InvalidTemplateParams = 111,
+    /// Follow-up rules would form a cycle
+    WorkflowCycle = 112,
+    /// Workflow exceeds the maximum chain depth or width
+    WorkflowTooDeep = 113,
+    /// No price oracle has been configured
+    OracleNotSet = 114,
+    /// Oracle price is older than the allowed maximum age
+    StalePriceData = 115,
+    /// Caller is neither the rule owner nor an approved manager
+    NotRuleManager = 116,
+    /// Rule amount exceeds the manager's spend ceiling
+    ManagerCeilingExceeded = 117,
+    /// Rule is not waiting for confirmation
+    RuleNotPendingApproval = 118,
+    /// Confirmation timelock has not elapsed
+    ConfirmationLocked = 119,
+    /// Owner has been active within the rule's inactivity period
+    OwnerStillActive = 120,
+    /// Recipient is not an active address book entry
+    RecipientNotInAddressBook = 121,
+    /// Address book entry not found
+    AddressBookEntryNotFound = 122,
+    /// Storage must be migrated to the current version first
+    MigrationPending = 123,
+    /// The rule has run as often as allowed this hour
+    RateLimitExceeded = 124,
+    /// Keeper bounty is outside the admin's bounds
+    BountyOutOfBounds = 125,
+    /// The rule's executor policy does not allow the caller to execute it
+    ExecutorNotAllowed = 126,
+    /// Rule metadata exceeds the size limit
+    MetadataTooLong = 127,
+    /// Rule has spent its whole execution budget
+    BudgetExhausted = 128,
+    /// Hook contract is not on the admin allowlist
+    HookNotAllowed = 129,
+    /// A hook tried to execute a rule from inside another execution
+    HookCallDepthExceeded = 130,
+    /// Too many rules in one batch
+    BatchTooLarge = 131,
+    /// A rule's first execution time is in the past
+    FirstExecutionInPast = 132,
+    /// The current time is outside the rule's execution window
+    OutsideExecutionWindow = 133,
+    /// The rule has executed since the caller read its execution nonce
+    StaleExecutionNonce = 134,
+    /// The rule's minimum interval since its last execution has not passed
+    ExecutedTooRecently = 135,
+    /// The linked contract is of the wrong kind or reports an unsupported interface version
+    IncompatibleContractVersion = 136,
+    /// The rule has saved its whole goal amount
+    SavingsGoalReached = 137,
+    /// The rule's schedule is more frequent than the admin's minimum interval
+    ScheduleTooFrequent = 138,
+    /// The rule's amount is above the admin's maximum
+    RuleAmountTooLarge = 139,
+    /// Signature does not match the request and public key
+    InvalidSignature = 140,
+    /// Signed request's nonce is not the owner's current one
+    InvalidSignatureNonce = 141,
+    /// Signed request's deadline has passed
+    SignatureExpired = 142,
+    /// Session key is the owner's own, grants nothing or is already expired
+    InvalidSessionKey = 143,
+    /// Recipient is on the admin's blocklist
+    RecipientBlocked = 144,
+    /// Time config has a zero period or periods out of order
+    InvalidTimeConfig = 145,
+    /// The owner role cannot be granted or revoked
+    InvalidRole = 146,
+    /// Rule execution is paused by a pauser
+    ExecutionPaused = 147,
+    /// The change must be queued in the admin timelock
+    TimelockRequired = 148,
+    /// Queued admin action not found, or already executed or cancelled
+    AdminActionNotFound = 149,
+    /// The queued admin action's delay has not passed
+    AdminActionNotReady = 150,
+    /// Only deleted rules can be restored
+    RuleNotDeleted = 151,
+    /// The rule was deleted longer ago than the restore grace period
+    RestoreWindowClosed = 152,
+    /// Too many tags, or a tag is empty, too long or repeated
+    InvalidRuleTags = 153,
+    /// Time config can only change before the first rule is created
+    TimeConfigLocked = 154,
+    /// Low balance alert looks further ahead than the maximum number of executions
+    InvalidAlertLookahead = 155,
+    /// Condition-triggered rules and dead-man switches cannot be follow-ups
+    InvalidFollowUp = 156,
     
     // Staking Errors (200-299)
     /// Insufficient staking balance
@@ -44,4 +204,64 @@ pub enum Error {
     InvalidValidator = 201,
     /// Minimum stake amount not met
     MinimumStakeNotMet = 202,
+    /// Spender allowance is too low for the transfer
+    InsufficientAllowance = 203,
+    /// Pending unstake not found
+    UnstakeNotFound = 204,
+    /// Undelegated CSPR has not finished unbonding
+    UnbondingNotComplete = 205,
+    /// No allowlisted validator satisfies the rebalance constraints
+    NoRebalanceTarget = 206,
+    /// Pending redelegation not found
+    RedelegationNotFound = 207,
+    /// Validator is at its delegation cap and no fallback validator has room
+    ValidatorSaturated = 208,
+    /// Rewards are routed to an external address but none is set
+    RewardRecipientNotSet = 209,
+    /// Donated share of rewards is zero or above 100%
+    InvalidRewardSplit = 210,
+    /// The owner's stake was rebalanced too recently
+    RebalanceCooldown = 211,
+    
+    // Multisig Errors (300-399)
+    /// Caller is not a signer of the multisig account
+    NotSigner = 300,
+    /// Proposal not found
+    ProposalNotFound = 301,
+    /// Signer has already approved the proposal
+    AlreadyApproved = 302,
+    /// Proposal does not have enough approvals
+    ThresholdNotMet = 303,
+    /// Proposal has already been executed
+    ProposalAlreadyExecuted = 304,
+    /// Threshold must be between 1 and the number of signers
+    InvalidThreshold = 305,
+    /// Address is already a signer
+    SignerAlreadyExists = 306,
+    /// Proposal is missing the parameters its kind needs
+    InvalidProposal = 307,
+    
+    // Arithmetic Errors (400-499)
+    /// Addition overflowed the value's integer type
+    Overflow = 400,
+    /// Subtraction went below zero
+    Underflow = 401,
+    
+    // Swap Errors (500-599)
+    /// No swap adapter has been configured
+    SwapAdapterNotSet = 500,
+    /// No DEX router has been configured
+    DexRouterNotSet = 501,
+    /// Slippage limit is above what is allowed
+    SlippageTooHigh = 502,
+    /// Swap output fell short of the slippage limit
+    SlippageExceeded = 503,
+    
+    // Attestation Errors (600-699)
+    /// Caller is not an allowlisted attester
+    NotAttester = 600,
+    /// Attestation is from the future, older than the current one, or too old for the rule
+    StaleAttestation = 601,
+    /// Attestation key is empty or too long
+    InvalidAttestationKey = 602,
 }
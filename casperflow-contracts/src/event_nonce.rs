@@ -0,0 +1,28 @@
+//! CasperFlow Event Nonce
+//!
+//! A per-contract sequence number stamped on every emitted event, so
+//! off-chain indexers can order events and detect any they missed.
+
+use odra::prelude::*;
+
+/// Monotonic event counter, embedded as a submodule
+#[odra::module]
+pub struct EventNonce {
+    /// Nonce of the most recently emitted event (zero before the first)
+    last: Var<u64>,
+}
+
+#[odra::module]
+impl EventNonce {
+    /// Allocate the nonce for the next event; the first event gets 1
+    pub fn next(&mut self) -> u64 {
+        let nonce = self.last.get_or_default() + 1;
+        self.last.set(nonce);
+        nonce
+    }
+
+    /// Get the nonce of the most recently emitted event
+    pub fn current(&self) -> u64 {
+        self.last.get_or_default()
+    }
+}
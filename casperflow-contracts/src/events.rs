@@ -3,7 +3,13 @@
 //! Defines all events emitted by the CasperFlow contracts.
 
 use odra::prelude::*;
-use odra::casper_types::U512;
+use odra::casper_types::{PublicKey, U256, U512};
+
+use crate::types::{
+    AdminAction, AdminActionKind, AutoStakeConfig, ContractKind, DepositRouting, DustPolicy,
+    ExecutionStep, MissedExecutionPolicy, NftPerks, ProposalKind, RebalanceMove, Role,
+    RuleStatus, SessionKeyScope, SwapDirection,
+};
 
 // ============================================================================
 // Vault Events
@@ -15,6 +21,26 @@ pub struct Deposited {
     pub owner: Address,
     pub amount: U512,
     pub new_balance: U512,
+    pub nonce: u64,
+}
+
+/// Emitted when someone funds another user's vault
+#[odra::event]
+pub struct DepositedFor {
+    pub funder: Address,
+    pub owner: Address,
+    pub amount: U512,
+    pub new_balance: U512,
+    pub nonce: u64,
+}
+
+/// Emitted when a user moves part of their vault balance to another user's
+#[odra::event]
+pub struct InternalTransfer {
+    pub from: Address,
+    pub to: Address,
+    pub amount: U512,
+    pub nonce: u64,
 }
 
 /// Emitted when tokens are withdrawn from a vault
@@ -23,6 +49,46 @@ pub struct Withdrawn {
     pub owner: Address,
     pub amount: U512,
     pub new_balance: U512,
+    pub nonce: u64,
+}
+
+/// Emitted when an owner changes their withdrawal delay
+#[odra::event]
+pub struct WithdrawalDelayUpdated {
+    pub owner: Address,
+    pub delay: u64,
+    pub effective_at: u64,
+    pub nonce: u64,
+}
+
+/// Emitted when a timelocked withdrawal is requested
+#[odra::event]
+pub struct WithdrawalRequested {
+    pub request_id: u64,
+    pub owner: Address,
+    pub token: Option<Address>,
+    pub amount: U512,
+    pub unlock_time: u64,
+    pub nonce: u64,
+}
+
+/// Emitted when a timelocked withdrawal is cancelled and the funds return to the vault
+#[odra::event]
+pub struct WithdrawalCancelled {
+    pub request_id: u64,
+    pub owner: Address,
+    pub amount: U512,
+    pub nonce: u64,
+}
+
+/// Emitted when a timelocked withdrawal is paid out
+#[odra::event]
+pub struct WithdrawalCompleted {
+    pub request_id: u64,
+    pub owner: Address,
+    pub token: Option<Address>,
+    pub amount: U512,
+    pub nonce: u64,
 }
 
 /// Emitted when an automation executes a transfer from the vault
@@ -30,8 +96,422 @@ pub struct Withdrawn {
 pub struct AutomationExecuted {
     pub owner: Address,
     pub rule_id: u64,
+    /// Engine execution the payment belongs to
+    pub execution_id: u64,
+    pub recipient: Address,
+    pub amount: U512,
+    pub fee: U512,
+    pub balance_before: U512,
+    pub balance_after: U512,
+    pub nonce: u64,
+}
+
+/// Emitted when CEP-18 tokens are deposited into a vault
+#[odra::event]
+pub struct TokenDeposited {
+    pub owner: Address,
+    pub token: Address,
+    pub amount: U256,
+    pub new_balance: U256,
+    pub nonce: u64,
+}
+
+/// Emitted when CEP-18 tokens are withdrawn from a vault
+#[odra::event]
+pub struct TokenWithdrawn {
+    pub owner: Address,
+    pub token: Address,
+    pub amount: U256,
+    pub new_balance: U256,
+    pub nonce: u64,
+}
+
+/// Emitted when an automation executes a CEP-18 transfer from the vault
+#[odra::event]
+pub struct TokenAutomationExecuted {
+    pub owner: Address,
+    pub rule_id: u64,
+    /// Engine execution the payment belongs to
+    pub execution_id: u64,
+    pub token: Address,
+    pub recipient: Address,
+    pub amount: U256,
+    pub balance_before: U256,
+    pub balance_after: U256,
+    pub nonce: u64,
+}
+
+/// Emitted when the admin adds or removes a token from the allowlist
+#[odra::event]
+pub struct TokenAllowlistUpdated {
+    pub token: Address,
+    pub allowed: bool,
+    pub nonce: u64,
+}
+
+/// Emitted when a rule moves a payment from the owner's vault into escrow
+#[odra::event]
+pub struct EscrowFunded {
+    pub escrow_id: u64,
+    pub rule_id: u64,
+    pub owner: Address,
+    pub recipient: Address,
+    pub amount: U512,
+    pub release_at: u64,
+    pub nonce: u64,
+}
+
+/// Emitted when the recipient claims an escrowed payment
+#[odra::event]
+pub struct EscrowClaimed {
+    pub escrow_id: u64,
+    pub recipient: Address,
+    pub amount: U512,
+    pub nonce: u64,
+}
+
+/// Emitted when the owner cancels an escrowed payment during its dispute window
+#[odra::event]
+pub struct EscrowCancelled {
+    pub escrow_id: u64,
+    pub owner: Address,
+    pub amount: U512,
+    pub nonce: u64,
+}
+
+/// Emitted when a rule's payment is held in the vault for its recipient to claim
+#[odra::event]
+pub struct ClaimableTransferHeld {
+    pub transfer_id: u64,
+    pub rule_id: u64,
+    /// Engine execution the payment belongs to
+    pub execution_id: u64,
+    pub owner: Address,
+    pub recipient: Address,
+    pub amount: U512,
+    pub expires_at: u64,
+    pub nonce: u64,
+}
+
+/// Emitted when a recipient claims their held transfers
+#[odra::event]
+pub struct TransfersClaimed {
+    pub recipient: Address,
+    pub transfer_ids: Vec<u64>,
+    pub amount: U512,
+    pub nonce: u64,
+}
+
+/// Emitted when the owner reclaims a transfer that expired unclaimed
+#[odra::event]
+pub struct ClaimableTransferRefunded {
+    pub transfer_id: u64,
+    pub owner: Address,
+    pub amount: U512,
+    pub nonce: u64,
+}
+
+/// Emitted when a payment stream is opened in the vault
+#[odra::event]
+pub struct StreamCreated {
+    pub stream_id: u64,
+    pub owner: Address,
+    pub recipient: Address,
+    pub rate_per_second: U512,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub nonce: u64,
+}
+
+/// Emitted when accrued stream funds are paid out to the recipient
+#[odra::event]
+pub struct StreamClaimed {
+    pub stream_id: u64,
+    pub recipient: Address,
+    pub amount: U512,
+    pub nonce: u64,
+}
+
+/// Emitted when a stream is cancelled and the remainder refunded
+#[odra::event]
+pub struct StreamCancelled {
+    pub stream_id: u64,
+    pub owner: Address,
+    pub paid_to_recipient: U512,
+    pub refunded: U512,
+    pub nonce: u64,
+}
+
+/// Emitted when a vesting schedule is created in the vault
+#[odra::event]
+pub struct VestingCreated {
+    pub vesting_id: u64,
+    pub owner: Address,
+    pub beneficiary: Address,
+    pub amount: U512,
+    pub start_time: u64,
+    pub cliff_time: u64,
+    pub end_time: u64,
+    pub nonce: u64,
+}
+
+/// Emitted when vested funds are paid out to the beneficiary
+#[odra::event]
+pub struct VestingClaimed {
+    pub vesting_id: u64,
+    pub beneficiary: Address,
+    pub amount: U512,
+    pub nonce: u64,
+}
+
+/// Emitted when the owner revokes the unvested remainder
+#[odra::event]
+pub struct VestingRevoked {
+    pub vesting_id: u64,
+    pub owner: Address,
+    pub paid_to_beneficiary: U512,
+    pub refunded: U512,
+    pub nonce: u64,
+}
+
+/// Emitted when a protocol fee is charged on an automated transfer
+#[odra::event]
+pub struct FeeCharged {
+    pub owner: Address,
+    pub rule_id: u64,
+    pub amount: U512,
+    pub nonce: u64,
+}
+
+/// Emitted when accrued protocol fees are paid out to the treasury
+#[odra::event]
+pub struct FeesDistributed {
+    pub treasury: Address,
+    pub amount: U512,
+    pub nonce: u64,
+}
+
+/// Emitted when the admin withdraws accrued protocol fees
+#[odra::event]
+pub struct FeesWithdrawn {
     pub recipient: Address,
     pub amount: U512,
+    pub nonce: u64,
+}
+
+/// Emitted when unbonded unstake proceeds are credited to a vault balance
+#[odra::event]
+pub struct UnstakeProceedsReceived {
+    pub owner: Address,
+    pub amount: U512,
+    pub new_balance: U512,
+    pub nonce: u64,
+}
+
+/// Emitted when a withdrawal queues an unstake for the part the vault balance cannot cover
+#[odra::event]
+pub struct UnstakeWithdrawalQueued {
+    pub owner: Address,
+    pub amount: U512,
+    /// Pending unstake whose proceeds are paid out to the owner once unbonded
+    pub unstake_id: u64,
+    pub nonce: u64,
+}
+
+/// Emitted when unbonded proceeds are paid out to fulfil a queued withdrawal
+#[odra::event]
+pub struct UnstakeWithdrawalFulfilled {
+    pub owner: Address,
+    pub amount: U512,
+    pub nonce: u64,
+}
+
+/// Emitted when the admin adds or removes a hook contract from the allowlist
+#[odra::event]
+pub struct HookAllowlistUpdated {
+    pub hook: Address,
+    pub allowed: bool,
+    pub nonce: u64,
+}
+
+/// Emitted when the admin allows or disallows paying a contract through an entrypoint
+#[odra::event]
+pub struct ContractRecipientAllowlistUpdated {
+    pub contract: Address,
+    pub entry_point: String,
+    pub allowed: bool,
+    pub nonce: u64,
+}
+
+/// Emitted when the admin blocks or unblocks a recipient
+#[odra::event]
+pub struct RecipientBlocklistUpdated {
+    pub recipient: Address,
+    pub blocked: bool,
+    pub nonce: u64,
+}
+
+/// Emitted when the owner grants a role to an account
+#[odra::event]
+pub struct RoleGranted {
+    pub role: Role,
+    pub account: Address,
+    pub nonce: u64,
+}
+
+/// Emitted when the owner revokes a role from an account
+#[odra::event]
+pub struct RoleRevoked {
+    pub role: Role,
+    pub account: Address,
+    pub nonce: u64,
+}
+
+/// Emitted when a sensitive admin change is queued in the timelock
+#[odra::event]
+pub struct AdminActionQueued {
+    pub action_id: u64,
+    pub action: AdminAction,
+    pub nonce: u64,
+}
+
+/// Emitted when a queued admin change takes effect
+#[odra::event]
+pub struct AdminActionExecuted {
+    pub action_id: u64,
+    pub kind: AdminActionKind,
+    pub nonce: u64,
+}
+
+/// Emitted when the admin cancels a queued change
+#[odra::event]
+pub struct AdminActionCancelled {
+    pub action_id: u64,
+    pub kind: AdminActionKind,
+    pub nonce: u64,
+}
+
+/// Emitted when a pauser halts or resumes rule execution
+#[odra::event]
+pub struct ExecutionPauseUpdated {
+    pub paused: bool,
+    pub by: Address,
+    pub nonce: u64,
+}
+
+/// Emitted when a rule is auto-paused after spending its execution budget
+#[odra::event]
+pub struct BudgetExhausted {
+    pub rule_id: u64,
+    pub owner: Address,
+    pub budget: U512,
+    pub spent: U512,
+    pub nonce: u64,
+}
+
+/// Emitted when a keeper is paid a rule's bounty from the owner's vault
+#[odra::event]
+pub struct KeeperPaid {
+    pub rule_id: u64,
+    pub owner: Address,
+    pub keeper: Address,
+    pub amount: U512,
+    pub nonce: u64,
+}
+
+/// Emitted when the vault stakes an owner's CSPR through the staking adapter
+#[odra::event]
+pub struct VaultStaked {
+    pub owner: Address,
+    pub rule_id: u64,
+    pub validator: PublicKey,
+    pub amount: U512,
+    pub nonce: u64,
+}
+
+/// Emitted when an owner opts into or out of auto-staking idle vault funds
+#[odra::event]
+pub struct AutoStakeConfigured {
+    pub owner: Address,
+    /// New settings (None when auto-staking is turned off)
+    pub config: Option<AutoStakeConfig>,
+    pub nonce: u64,
+}
+
+/// Emitted when the vault stakes an owner's idle balance above their floor
+#[odra::event]
+pub struct AutoStaked {
+    pub owner: Address,
+    pub validator: PublicKey,
+    pub amount: U512,
+    /// Total the vault has staked for the owner and not yet unstaked
+    pub custodial_stake: U512,
+    pub nonce: u64,
+}
+
+/// Emitted when an owner sets or clears how their deposits are split
+#[odra::event]
+pub struct DepositRoutingConfigured {
+    pub owner: Address,
+    /// New split (None when deposits stay fully liquid)
+    pub routing: Option<DepositRouting>,
+    pub nonce: u64,
+}
+
+/// Emitted when a deposit is split according to the owner's routing
+#[odra::event]
+pub struct DepositRouted {
+    pub owner: Address,
+    pub amount: U512,
+    /// Part staked through the staking adapter
+    pub staked: U512,
+    /// Part earmarked for the savings envelope
+    pub saved: U512,
+    /// Part left in the spendable balance
+    pub liquid: U512,
+    pub nonce: u64,
+}
+
+/// Emitted when a withdrawal unstakes auto-staked funds to cover a shortfall
+#[odra::event]
+pub struct AutoStakeUnwound {
+    pub owner: Address,
+    pub amount: U512,
+    /// Pending unstake whose proceeds return to the owner's vault balance
+    pub unstake_id: u64,
+    pub nonce: u64,
+}
+
+/// Emitted when the vault swaps an owner's balance through the swap adapter
+#[odra::event]
+pub struct VaultSwapped {
+    pub owner: Address,
+    pub rule_id: u64,
+    pub token: Address,
+    pub direction: SwapDirection,
+    pub amount_in: U512,
+    pub amount_out: U512,
+    pub nonce: u64,
+}
+
+/// Emitted when the balance earmarked for one of an owner's envelopes changes
+#[odra::event]
+pub struct EnvelopeUpdated {
+    pub owner: Address,
+    pub name: String,
+    pub balance: U512,
+    pub nonce: u64,
+}
+
+/// Emitted when the part of an owner's balance reserved for a rule changes
+#[odra::event]
+pub struct ReservationUpdated {
+    pub owner: Address,
+    pub rule_id: u64,
+    pub amount: U512,
+    /// Everything now reserved across the owner's rules
+    pub total_reserved: U512,
+    pub nonce: u64,
 }
 
 // ============================================================================
@@ -44,6 +524,7 @@ pub struct RuleCreated {
     pub rule_id: u64,
     pub owner: Address,
     pub template_type: String,
+    pub nonce: u64,
 }
 
 /// Emitted when a rule is paused
@@ -51,6 +532,7 @@ pub struct RuleCreated {
 pub struct RulePaused {
     pub rule_id: u64,
     pub owner: Address,
+    pub nonce: u64,
 }
 
 /// Emitted when a rule is resumed
@@ -58,6 +540,15 @@ pub struct RulePaused {
 pub struct RuleResumed {
     pub rule_id: u64,
     pub owner: Address,
+    pub nonce: u64,
+}
+
+/// Emitted when the owner updates a rule's editable fields
+#[odra::event]
+pub struct RuleUpdated {
+    pub rule_id: u64,
+    pub owner: Address,
+    pub nonce: u64,
 }
 
 /// Emitted when a rule is deleted
@@ -65,6 +556,35 @@ pub struct RuleResumed {
 pub struct RuleDeleted {
     pub rule_id: u64,
     pub owner: Address,
+    pub nonce: u64,
+}
+
+/// Emitted when a deleted rule is restored within the grace period
+#[odra::event]
+pub struct RuleRestored {
+    pub rule_id: u64,
+    pub owner: Address,
+    /// Status the rule had before it was deleted
+    pub status: RuleStatus,
+    pub nonce: u64,
+}
+
+/// Emitted when a one-shot rule completes after its execution
+#[odra::event]
+pub struct RuleCompleted {
+    pub rule_id: u64,
+    pub owner: Address,
+    pub nonce: u64,
+}
+
+/// Emitted when a deleted rule is purged from storage, as its last record
+#[odra::event]
+pub struct RulePurged {
+    pub rule_id: u64,
+    pub owner: Address,
+    pub execution_count: u32,
+    pub last_executed: u64,
+    pub nonce: u64,
 }
 
 /// Emitted when a rule is successfully executed
@@ -73,6 +593,27 @@ pub struct RuleExecuted {
     pub rule_id: u64,
     pub owner: Address,
     pub executed_at: u64,
+    /// Resolve with the engine's `get_template_name`
+    pub template_name_id: u32,
+    pub amount: U512,
+    pub execution_count: u32,
+    /// Unique across all rules; matches the vault's payment events
+    pub execution_id: u64,
+    pub nonce: u64,
+}
+
+/// Emitted after a successful execution of a rule that asked to be notified
+/// 
+/// The off-chain notification service matches `payload_hash` against the
+/// notification settings the owner registered with it.
+#[odra::event]
+pub struct NotificationRequested {
+    pub rule_id: u64,
+    pub owner: Address,
+    pub execution_count: u32,
+    /// Opaque hash supplied by the owner when enabling notifications
+    pub payload_hash: [u8; 32],
+    pub nonce: u64,
 }
 
 /// Emitted when a rule execution fails
@@ -81,17 +622,261 @@ pub struct RuleExecutionFailed {
     pub rule_id: u64,
     pub owner: Address,
     pub error_code: u32,
+    /// Resolve with the engine's `get_template_name`
+    pub template_name_id: u32,
+    /// Funding check the execution failed at
+    pub step: ExecutionStep,
+    /// How much more the owner needed for that check
+    pub shortfall: U512,
+    pub nonce: u64,
+}
+
+/// Emitted after an execution when the owner's funds will not cover the rule's upcoming runs
+/// 
+/// `first_failing_at` is the scheduled time of the first execution the
+/// current balance cannot pay for, so bots can warn users ahead of it.
+#[odra::event]
+pub struct VaultUnderfunded {
+    pub rule_id: u64,
+    pub owner: Address,
+    pub balance: U512,
+    pub required: U512,
+    pub shortfall: U512,
+    pub first_failing_at: u64,
+    pub nonce: u64,
+}
+
+/// Emitted when a savings goal rule reaches its target and is paused
+#[odra::event]
+pub struct GoalReached {
+    pub rule_id: u64,
+    pub owner: Address,
+    pub target: U512,
+    pub saved: U512,
+    pub nonce: u64,
+}
+
+/// Emitted when a payroll execution skips an entry the owner's funds no longer cover
+#[odra::event]
+pub struct PayrollEntrySkipped {
+    pub rule_id: u64,
+    pub owner: Address,
+    pub recipient: Address,
+    pub amount: U512,
+    pub nonce: u64,
+}
+
+/// Emitted when a split execution's percentages leave a rounding remainder
+#[odra::event]
+pub struct SplitDustHandled {
+    pub rule_id: u64,
+    pub owner: Address,
+    pub dust: U512,
+    pub dust_policy: DustPolicy,
+    pub nonce: u64,
+}
+
+/// Emitted when a scheduled rule is run after its grace period
+#[odra::event]
+pub struct MissedExecution {
+    pub rule_id: u64,
+    pub owner: Address,
+    pub policy: MissedExecutionPolicy,
+    /// Slots that passed without an execution and were dropped
+    pub missed: u64,
+    /// Executions performed by this run
+    pub executed: u64,
+    pub next_execution: u64,
+    pub nonce: u64,
+}
+
+/// Emitted when a rule's price condition is satisfied at execution
+#[odra::event]
+pub struct PriceConditionMet {
+    pub rule_id: u64,
+    pub price: U512,
+    pub threshold: U512,
+    pub nonce: u64,
+}
+
+/// Emitted when a rule's attestation condition is satisfied at execution
+#[odra::event]
+pub struct AttestationConditionMet {
+    pub rule_id: u64,
+    pub key: String,
+    /// Attested value the condition held against
+    pub value: U512,
+    pub threshold: U512,
+    pub nonce: u64,
+}
+
+/// Emitted when a rule's staking rewards condition is satisfied at execution
+#[odra::event]
+pub struct RewardsConditionMet {
+    pub rule_id: u64,
+    /// Uncompounded rewards of the rule's validator delegation
+    pub rewards: U512,
+    pub threshold: U512,
+    pub nonce: u64,
+}
+
+/// Emitted when a rule and its follow-up rules execute as one workflow
+#[odra::event]
+pub struct WorkflowExecuted {
+    pub root_rule_id: u64,
+    pub owner: Address,
+    pub executed_rule_ids: Vec<u64>,
+    pub nonce: u64,
+}
+
+/// Emitted when the admin registers a new rule template
+#[odra::event]
+pub struct TemplateRegistered {
+    pub template_id: u32,
+    pub name: String,
+    pub nonce: u64,
+}
+
+/// Emitted when the admin updates the protocol fee configuration
+#[odra::event]
+pub struct FeeConfigUpdated {
+    pub fee_bps: u16,
+    pub flat_fee: U512,
+    pub nonce: u64,
+}
+
+/// Emitted when the admin points the engine at a different staking adapter
+#[odra::event]
+pub struct StakingAdapterUpdated {
+    pub adapter: Address,
+    pub nonce: u64,
+}
+
+/// Emitted when the admin configures or clears membership NFT perks
+#[odra::event]
+pub struct NftPerksUpdated {
+    /// New perks (None when cleared)
+    pub perks: Option<NftPerks>,
+    pub nonce: u64,
+}
+
+/// Emitted when a high-value rule is created and awaits confirmation
+#[odra::event]
+pub struct RulePendingApproval {
+    pub rule_id: u64,
+    pub owner: Address,
+    pub amount: U512,
+    pub confirmable_at: u64,
+    pub nonce: u64,
+}
+
+/// Emitted when the owner confirms a pending rule
+#[odra::event]
+pub struct RuleConfirmed {
+    pub rule_id: u64,
+    pub owner: Address,
+    pub nonce: u64,
+}
+
+/// Emitted when an owner checks in to keep their dead-man switch rules from firing
+#[odra::event]
+pub struct Heartbeat {
+    pub owner: Address,
+    pub timestamp: u64,
+    pub nonce: u64,
+}
+
+/// Emitted when a dead-man switch rule sends an inactive owner's funds to the beneficiary
+#[odra::event]
+pub struct InheritanceTriggered {
+    pub rule_id: u64,
+    pub owner: Address,
+    pub beneficiary: Address,
+    pub amount: U512,
+    pub last_activity: u64,
+    pub nonce: u64,
+}
+
+/// Emitted when an owner adds or relabels an address book entry
+#[odra::event]
+pub struct AddressBookEntryAdded {
+    pub owner: Address,
+    pub recipient: Address,
+    pub label: String,
+    pub active_at: u64,
+    pub nonce: u64,
+}
+
+/// Emitted when an owner removes an address book entry
+#[odra::event]
+pub struct AddressBookEntryRemoved {
+    pub owner: Address,
+    pub recipient: Address,
+    pub nonce: u64,
+}
+
+/// Emitted when an owner turns the address book restriction on or off
+#[odra::event]
+pub struct AddressBookRestrictionUpdated {
+    pub owner: Address,
+    pub enabled: bool,
+    pub effective_at: u64,
+    pub nonce: u64,
+}
+
+/// Emitted when an owner approves a manager to handle rules on their behalf
+#[odra::event]
+pub struct ManagerApproved {
+    pub owner: Address,
+    pub manager: Address,
+    pub spend_ceiling: U512,
+    pub nonce: u64,
+}
+
+/// Emitted when an owner revokes a manager
+#[odra::event]
+pub struct ManagerRevoked {
+    pub owner: Address,
+    pub manager: Address,
+    pub nonce: u64,
+}
+
+/// Emitted when an owner registers or replaces a session key
+#[odra::event]
+pub struct SessionKeyAdded {
+    pub owner: Address,
+    pub public_key: PublicKey,
+    pub scope: SessionKeyScope,
+    pub nonce: u64,
+}
+
+/// Emitted when an owner revokes a session key
+#[odra::event]
+pub struct SessionKeyRevoked {
+    pub owner: Address,
+    pub public_key: PublicKey,
+    pub nonce: u64,
+}
+
+/// Emitted when the engine's storage finishes migrating to a new version
+#[odra::event]
+pub struct StorageMigrated {
+    pub from_version: u32,
+    pub to_version: u32,
+    pub nonce: u64,
 }
 
 // ============================================================================
 // Staking Events
 // ============================================================================
 
-/// Emitted when rewards are compounded
+/// Emitted when validator rewards are compounded into the staking pool
 #[odra::event]
 pub struct RewardsCompounded {
-    pub owner: Address,
+    pub validator: PublicKey,
     pub amount: U512,
+    pub total_pooled: U512,
+    pub nonce: u64,
 }
 
 /// Emitted when tokens are unstaked
@@ -99,4 +884,289 @@ pub struct RewardsCompounded {
 pub struct Unstaked {
     pub owner: Address,
     pub amount: U512,
+    pub nonce: u64,
+}
+
+/// Emitted when an unstake is queued to return to the owner's vault after unbonding
+#[odra::event]
+pub struct UnstakeQueued {
+    pub unstake_id: u64,
+    pub owner: Address,
+    pub amount: U512,
+    pub release_time: u64,
+    pub nonce: u64,
+}
+
+/// Emitted when unbonded proceeds of a queued unstake are deposited into the vault
+#[odra::event]
+pub struct UnstakeCompleted {
+    pub unstake_id: u64,
+    pub owner: Address,
+    pub amount: U512,
+    pub nonce: u64,
+}
+
+/// Emitted when the admin adds or removes a validator from the allowlist
+#[odra::event]
+pub struct ValidatorAllowlistUpdated {
+    pub validator: PublicKey,
+    pub allowed: bool,
+    pub nonce: u64,
+}
+
+/// Emitted when the admin posts performance data for a validator
+#[odra::event]
+pub struct ValidatorMetricsUpdated {
+    pub validator: PublicKey,
+    pub performance_bps: u16,
+    pub commission_bps: u16,
+    pub nonce: u64,
+}
+
+/// Emitted when an automated delegation skips a validator at its delegation cap
+#[odra::event]
+pub struct ValidatorSaturated {
+    pub validator: PublicKey,
+    pub cap: U512,
+    pub delegated: U512,
+    pub fallback: Option<PublicKey>,
+    pub nonce: u64,
+}
+
+/// Emitted when stake is moved away from validators that miss a rule's constraints
+#[odra::event]
+pub struct RebalanceExecuted {
+    pub owner: Address,
+    pub target: PublicKey,
+    pub moves: Vec<RebalanceMove>,
+    pub total_moved: U512,
+    pub redelegation_id: u64,
+    pub nonce: u64,
+}
+
+/// Emitted when rewards are harvested from one validator to be delegated to another
+#[odra::event]
+pub struct RewardsRestaked {
+    pub from_validator: PublicKey,
+    pub to_validator: PublicKey,
+    pub amount: U512,
+    pub redelegation_id: u64,
+    pub nonce: u64,
+}
+
+/// Emitted when an owner moves stake from one validator to another
+#[odra::event]
+pub struct StakeRedelegated {
+    pub owner: Address,
+    pub from_validator: PublicKey,
+    pub to_validator: PublicKey,
+    pub amount: U512,
+    pub redelegation_id: u64,
+    pub nonce: u64,
+}
+
+/// Emitted when an owner's rewards are unstaked for delivery to their vault
+#[odra::event]
+pub struct RewardsSentToVault {
+    pub owner: Address,
+    pub amount: U512,
+    pub unstake_id: u64,
+    pub nonce: u64,
+}
+
+/// Emitted when an owner's rewards are unstaked for delivery to an external address
+#[odra::event]
+pub struct RewardsSentToAddress {
+    pub owner: Address,
+    pub recipient: Address,
+    pub amount: U512,
+    pub unstake_id: u64,
+    pub nonce: u64,
+}
+
+/// Emitted when part of an owner's rewards is donated and the rest restaked
+#[odra::event]
+pub struct RewardsDonated {
+    pub owner: Address,
+    pub recipient: Address,
+    /// Rewards unstaked for the recipient
+    pub donated: U512,
+    /// Rewards kept delegated and added to the owner's principal
+    pub restaked: U512,
+    pub unstake_id: u64,
+    pub nonce: u64,
+}
+
+/// Emitted when unbonded stake from a rebalance or restake is delegated to its target
+#[odra::event]
+pub struct RedelegationCompleted {
+    pub redelegation_id: u64,
+    pub validator: PublicKey,
+    pub amount: U512,
+    pub nonce: u64,
+}
+
+/// Emitted when a user grants or revokes the engine's permission to manage their stake
+#[odra::event]
+pub struct EnginePermissionUpdated {
+    pub owner: Address,
+    pub allowed: bool,
+    pub nonce: u64,
+}
+
+/// Emitted when sCSPR is minted for a stake
+#[odra::event]
+pub struct ScsprMinted {
+    pub owner: Address,
+    pub staked: U512,
+    pub shares: U256,
+    pub nonce: u64,
+}
+
+/// Emitted when sCSPR is burned for an unstake
+#[odra::event]
+pub struct ScsprBurned {
+    pub owner: Address,
+    pub unstaked: U512,
+    pub shares: U256,
+    pub nonce: u64,
+}
+
+// ============================================================================
+// Swap Events
+// ============================================================================
+
+/// Emitted when the swap adapter completes a swap
+#[odra::event]
+pub struct SwapExecuted {
+    pub token: Address,
+    pub direction: SwapDirection,
+    pub amount_in: U512,
+    pub amount_out: U512,
+    pub recipient: Address,
+    pub nonce: u64,
+}
+
+/// Emitted when the swap adapter is pointed at a different DEX router
+#[odra::event]
+pub struct SwapRouterUpdated {
+    pub router: Address,
+    pub nonce: u64,
+}
+
+// ============================================================================
+// Multisig Events
+// ============================================================================
+
+/// Emitted when CSPR is deposited into a multisig account's shared vault balance
+#[odra::event]
+pub struct MultisigDeposited {
+    pub depositor: Address,
+    pub amount: U512,
+    pub nonce: u64,
+}
+
+/// Emitted when a signer creates a proposal
+#[odra::event]
+pub struct ProposalCreated {
+    pub proposal_id: u64,
+    pub proposer: Address,
+    pub kind: ProposalKind,
+    pub nonce: u64,
+}
+
+/// Emitted when a signer approves a proposal
+#[odra::event]
+pub struct ProposalApproved {
+    pub proposal_id: u64,
+    pub signer: Address,
+    pub approvals: u32,
+    pub nonce: u64,
+}
+
+/// Emitted when an approved proposal is executed
+#[odra::event]
+pub struct ProposalExecuted {
+    pub proposal_id: u64,
+    pub executor: Address,
+    pub nonce: u64,
+}
+
+/// Emitted when a signer is added to a multisig account
+#[odra::event]
+pub struct SignerAdded {
+    pub signer: Address,
+    pub nonce: u64,
+}
+
+/// Emitted when a signer is removed from a multisig account
+#[odra::event]
+pub struct SignerRemoved {
+    pub signer: Address,
+    pub nonce: u64,
+}
+
+/// Emitted when the number of approvals required changes
+#[odra::event]
+pub struct ThresholdChanged {
+    pub threshold: u32,
+    pub nonce: u64,
+}
+
+// ============================================================================
+// Liquid Staking Token Events
+// ============================================================================
+
+/// Emitted when sCSPR changes hands
+#[odra::event]
+pub struct TokenTransfer {
+    pub sender: Address,
+    pub recipient: Address,
+    pub amount: U256,
+    pub nonce: u64,
+}
+
+/// Emitted when an sCSPR allowance is set
+#[odra::event]
+pub struct TokenApproval {
+    pub owner: Address,
+    pub spender: Address,
+    pub amount: U256,
+    pub nonce: u64,
+}
+
+// ============================================================================
+// Attestation Oracle Events
+// ============================================================================
+
+/// Emitted when an account is allowed or disallowed to post attestations
+#[odra::event]
+pub struct AttesterUpdated {
+    pub attester: Address,
+    pub allowed: bool,
+    pub nonce: u64,
+}
+
+/// Emitted when an attester posts a new value for a key
+#[odra::event]
+pub struct AttestationPosted {
+    pub key: String,
+    pub value: U512,
+    /// Time the attester observed the value off-chain
+    pub timestamp: u64,
+    pub attester: Address,
+    pub nonce: u64,
+}
+
+// ============================================================================
+// Registry Events
+// ============================================================================
+
+/// Emitted when the registry's address for a contract changes
+#[odra::event]
+pub struct ContractAddressSet {
+    pub kind: ContractKind,
+    /// New address (None when the entry is cleared)
+    pub address: Option<Address>,
+    pub nonce: u64,
 }
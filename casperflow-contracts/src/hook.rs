@@ -0,0 +1,53 @@
+//! Execution Hook Interface
+//!
+//! External contract definition for integrator contracts (notification
+//! relays, accounting ledgers) that react on-chain to rule executions.
+
+use odra::prelude::*;
+use odra::casper_types::U512;
+
+/// Callbacks a rule can register to run around its executions
+///
+/// A hook that reverts reverts the execution with it, so hooks are only
+/// called once the admin has allowlisted them.
+#[odra::external_contract]
+pub trait ExecutionHook {
+    /// Called before the rule's action runs, with the amount it is about to move
+    fn before_execution(&mut self, rule_id: u64, owner: Address, amount: U512);
+    /// Called after the rule's action has run, with the amount it moved
+    fn after_execution(&mut self, rule_id: u64, owner: Address, amount: U512);
+}
+
+/// Hook that counts its calls, used by the unit tests
+#[cfg(test)]
+pub mod test_hook {
+    use odra::prelude::*;
+    use odra::casper_types::U512;
+
+    #[odra::module]
+    pub struct TestHook {
+        before_calls: Var<u32>,
+        after_calls: Var<u32>,
+        last_amount: Var<U512>,
+    }
+
+    #[odra::module]
+    impl TestHook {
+        pub fn before_execution(&mut self, _rule_id: u64, _owner: Address, _amount: U512) {
+            self.before_calls.set(self.before_calls.get_or_default() + 1);
+        }
+
+        pub fn after_execution(&mut self, _rule_id: u64, _owner: Address, amount: U512) {
+            self.after_calls.set(self.after_calls.get_or_default() + 1);
+            self.last_amount.set(amount);
+        }
+
+        pub fn calls(&self) -> (u32, u32) {
+            (self.before_calls.get_or_default(), self.after_calls.get_or_default())
+        }
+
+        pub fn last_amount(&self) -> U512 {
+            self.last_amount.get_or_default()
+        }
+    }
+}
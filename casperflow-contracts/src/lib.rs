@@ -6,6 +6,10 @@
 //! - **AutomationVault**: Holds user funds for automated operations
 //! - **AutomationEngine**: Manages automation rules and execution
 //! - **StakingAdapter**: Interfaces with Casper's native liquid staking
+//! - **SwapAdapter**: Swaps between CSPR and CEP-18 tokens through a DEX router
+//! - **MultisigAccount**: Shared vault account controlled by M-of-N signers
+//! - **ContractRegistry**: Canonical addresses the other contracts resolve each other from
+//! - **AttestationOracle**: Off-chain facts posted by allowlisted attesters for condition rules
 
 #![cfg_attr(not(test), no_std)]
 #![cfg_attr(not(test), no_main)]
@@ -13,13 +17,42 @@ extern crate alloc;
 
 pub mod errors;
 pub mod events;
+pub mod event_nonce;
 pub mod types;
+pub mod cep18;
+pub mod cep78;
+pub mod hook;
+pub mod oracle;
+pub mod reentrancy_guard;
+pub mod access_control;
+pub mod timelock;
+pub mod safe_math;
 
 pub mod vault;
 pub mod automation_engine;
+pub mod template_registry;
 pub mod staking_adapter;
+pub mod swap_adapter;
+pub mod liquid_token;
+pub mod multisig;
+pub mod registry;
+pub mod attestation_oracle;
+
+#[cfg(any(test, feature = "test-utils"))]
+pub mod testing;
+
+#[cfg(test)]
+mod benchmarks;
+#[cfg(test)]
+mod lifecycle_proptest;
+#[cfg(test)]
+mod storage_upgrades;
 
 // Re-export main contracts for convenience
 pub use vault::AutomationVault;
 pub use automation_engine::AutomationEngine;
 pub use staking_adapter::StakingAdapter;
+pub use swap_adapter::SwapAdapter;
+pub use multisig::MultisigAccount;
+pub use registry::ContractRegistry;
+pub use attestation_oracle::AttestationOracle;
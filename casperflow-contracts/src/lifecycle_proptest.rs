@@ -0,0 +1,179 @@
+//! Rule Lifecycle Property Tests
+//!
+//! Drives the vault and engine through random sequences of user actions
+//! (create, pause, resume, delete, execute, deposit, withdraw, time passing)
+//! and checks invariants that must hold after every step, whichever calls
+//! succeeded or reverted along the way.
+
+use std::collections::BTreeMap;
+
+use odra::casper_types::U512;
+use odra::host::{HostEnv, HostRef};
+use odra::prelude::*;
+use proptest::prelude::*;
+
+use crate::automation_engine::AutomationEngineHostRef;
+use crate::testing::deploy_core;
+use crate::types::{ActionType, RuleStatus, Schedule, TriggerType};
+use crate::vault::AutomationVaultHostRef;
+
+/// Seconds in a day
+const DAY: u64 = 86_400;
+/// Accounts that own vaults and rules (account 0 is the admin)
+const USERS: [usize; 3] = [1, 2, 3];
+/// Account every generated rule pays
+const RECIPIENT: usize = 4;
+
+/// One user action, with rules picked by index into those created so far
+#[derive(Clone, Debug)]
+enum Op {
+    Create { user: usize, schedule: u8, amount: u64 },
+    Pause(usize),
+    Resume(usize),
+    Delete(usize),
+    Execute(usize),
+    Deposit { user: usize, amount: u64 },
+    Withdraw { user: usize, amount: u64 },
+    Advance(u64),
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    let user = 0..USERS.len();
+    prop_oneof![
+        (user.clone(), 0..3u8, 1..500u64)
+            .prop_map(|(user, schedule, amount)| Op::Create { user, schedule, amount }),
+        any::<usize>().prop_map(Op::Pause),
+        any::<usize>().prop_map(Op::Resume),
+        any::<usize>().prop_map(Op::Delete),
+        any::<usize>().prop_map(Op::Execute),
+        (user.clone(), 1..1_000u64).prop_map(|(user, amount)| Op::Deposit { user, amount }),
+        (user, 1..1_000u64).prop_map(|(user, amount)| Op::Withdraw { user, amount }),
+        (0..3 * DAY).prop_map(Op::Advance),
+    ]
+}
+
+/// Run one action as the user it belongs to; reverts are expected and ignored
+fn apply(
+    env: &HostEnv,
+    vault: &mut AutomationVaultHostRef,
+    engine: &mut AutomationEngineHostRef,
+    rules: &mut Vec<u64>,
+    op: Op,
+) {
+    // The rule an index refers to, with its owner as the caller
+    let pick = |index: usize| {
+        let rule_id = *rules.get(index % rules.len().max(1))?;
+        env.set_caller(engine.get_rule(rule_id)?.owner);
+        Some(rule_id)
+    };
+    match op {
+        Op::Create { user, schedule, amount } => {
+            env.set_caller(env.get_account(USERS[user]));
+            let schedule = match schedule {
+                0 => Schedule::Daily,
+                1 => Schedule::Weekly,
+                _ => Schedule::Monthly,
+            };
+            let created = engine.try_create_rule(
+                "generated".to_string(),
+                TriggerType::Time,
+                schedule,
+                ActionType::Transfer,
+                Some(env.get_account(RECIPIENT)),
+                U512::from(amount),
+                None,
+                None,
+                None,
+                None,
+            );
+            if let Ok(rule_id) = created {
+                rules.push(rule_id);
+            }
+        }
+        Op::Pause(index) => {
+            if let Some(rule_id) = pick(index) {
+                let _ = engine.try_pause_rule(rule_id);
+            }
+        }
+        Op::Resume(index) => {
+            if let Some(rule_id) = pick(index) {
+                let _ = engine.try_resume_rule(rule_id);
+            }
+        }
+        Op::Delete(index) => {
+            if let Some(rule_id) = pick(index) {
+                let _ = engine.try_delete_rule(rule_id);
+            }
+        }
+        Op::Execute(index) => {
+            if let Some(rule_id) = pick(index) {
+                let _ = engine.try_execute_rule(rule_id);
+            }
+        }
+        Op::Deposit { user, amount } => {
+            env.set_caller(env.get_account(USERS[user]));
+            let _ = vault.with_tokens(U512::from(amount)).try_deposit();
+        }
+        Op::Withdraw { user, amount } => {
+            env.set_caller(env.get_account(USERS[user]));
+            let _ = vault.try_withdraw(U512::from(amount));
+        }
+        Op::Advance(seconds) => env.advance_block_time(seconds),
+    }
+}
+
+/// Assert the invariants, remembering each rule's next execution for the next check
+fn check_invariants(
+    env: &HostEnv,
+    vault: &AutomationVaultHostRef,
+    engine: &AutomationEngineHostRef,
+    rules: &[u64],
+    next_executions: &mut BTreeMap<u64, u64>,
+) {
+    // The vault holds at least what it owes its users
+    let owed = USERS
+        .iter()
+        .fold(U512::zero(), |owed, user| owed + vault.get_balance(env.get_account(*user)));
+    assert!(env.balance_of(vault.address()) >= owed, "vault owes more than it holds");
+    
+    // Each user's rule counts match the statuses of their rules
+    for user in USERS.map(|user| env.get_account(user)) {
+        let owned: Vec<RuleStatus> = rules
+            .iter()
+            .filter_map(|rule_id| engine.get_rule(*rule_id))
+            .filter(|rule| rule.owner == user)
+            .map(|rule| rule.status)
+            .collect();
+        let count = |status: RuleStatus| owned.iter().filter(|s| **s == status).count() as u32;
+        let expected = (
+            count(RuleStatus::Active) + count(RuleStatus::PendingApproval),
+            count(RuleStatus::Paused),
+            count(RuleStatus::Deleted),
+        );
+        assert_eq!(engine.get_user_rule_counts(user), expected, "rule counts drifted");
+        assert_eq!(engine.get_user_rule_count(user), expected.0 + expected.1);
+    }
+    
+    // A rule's next execution never moves backwards
+    for rule_id in rules {
+        let next_execution = engine.get_rule(*rule_id).unwrap().next_execution;
+        let previous = next_executions.insert(*rule_id, next_execution).unwrap_or_default();
+        assert!(next_execution >= previous, "rule {rule_id} was rescheduled earlier");
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn rule_lifecycle_invariants(ops in proptest::collection::vec(op(), 1..40)) {
+        let env = odra_test::env();
+        let (mut vault, mut engine) = deploy_core(&env);
+        let mut rules = Vec::new();
+        let mut next_executions = BTreeMap::new();
+        for op in ops {
+            apply(&env, &mut vault, &mut engine, &mut rules, op);
+            check_invariants(&env, &vault, &engine, &rules, &mut next_executions);
+        }
+    }
+}
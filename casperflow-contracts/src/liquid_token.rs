@@ -0,0 +1,137 @@
+//! CasperFlow Liquid Staking Token
+//!
+//! A minimal CEP-18 token embedded in the staking adapter. sCSPR is minted
+//! when users stake and burned when they unstake, so staking positions are
+//! transferable and can be read by other contracts.
+
+use odra::prelude::*;
+use odra::casper_types::U256;
+
+use crate::errors::Error;
+use crate::events::{TokenTransfer, TokenApproval};
+
+/// CEP-18 token state and logic, embedded as a submodule
+#[odra::module(events = [TokenTransfer, TokenApproval], errors = Error)]
+pub struct LiquidToken {
+    name: Var<String>,
+    symbol: Var<String>,
+    decimals: Var<u8>,
+    total_supply: Var<U256>,
+    balances: Mapping<Address, U256>,
+    allowances: Mapping<(Address, Address), U256>,
+}
+
+#[odra::module]
+impl LiquidToken {
+    /// Set the token metadata
+    pub fn init(&mut self, name: String, symbol: String, decimals: u8) {
+        self.name.set(name);
+        self.symbol.set(symbol);
+        self.decimals.set(decimals);
+    }
+
+    /// Get the token name
+    pub fn name(&self) -> String {
+        self.name.get_or_default()
+    }
+
+    /// Get the token symbol
+    pub fn symbol(&self) -> String {
+        self.symbol.get_or_default()
+    }
+
+    /// Get the token decimals
+    pub fn decimals(&self) -> u8 {
+        self.decimals.get_or_default()
+    }
+
+    /// Get the total token supply
+    pub fn total_supply(&self) -> U256 {
+        self.total_supply.get_or_default()
+    }
+
+    /// Get the token balance of an address
+    pub fn balance_of(&self, address: &Address) -> U256 {
+        self.balances.get_or_default(address)
+    }
+
+    /// Get the amount `spender` may transfer on behalf of `owner`
+    pub fn allowance(&self, owner: &Address, spender: &Address) -> U256 {
+        self.allowances.get_or_default(&(*owner, *spender))
+    }
+
+    /// Allow `spender` to transfer up to `amount` of the caller's tokens
+    /// 
+    /// `nonce` is the host contract's sequence number for the emitted event;
+    /// the same applies to the transfer functions below.
+    pub fn approve(&mut self, spender: &Address, amount: &U256, nonce: u64) {
+        let owner = self.env().caller();
+        self.allowances.set(&(owner, *spender), *amount);
+
+        self.env().emit_event(TokenApproval {
+            owner,
+            spender: *spender,
+            amount: *amount,
+            nonce,
+        });
+    }
+
+    /// Transfer tokens from the caller to a recipient
+    pub fn transfer(&mut self, recipient: &Address, amount: &U256, nonce: u64) {
+        let sender = self.env().caller();
+        self.move_tokens(&sender, recipient, amount, nonce);
+    }
+
+    /// Transfer tokens from `owner` to a recipient using the caller's allowance
+    pub fn transfer_from(
+        &mut self,
+        owner: &Address,
+        recipient: &Address,
+        amount: &U256,
+        nonce: u64,
+    ) {
+        let spender = self.env().caller();
+        let allowance = self.allowance(owner, &spender);
+        if allowance < *amount {
+            self.env().revert(Error::InsufficientAllowance);
+        }
+        self.allowances.set(&(*owner, spender), allowance - *amount);
+        self.move_tokens(owner, recipient, amount, nonce);
+    }
+}
+
+impl LiquidToken {
+    /// Create new tokens for `owner`
+    pub fn mint(&mut self, owner: &Address, amount: &U256) {
+        let balance = self.balances.get_or_default(owner);
+        self.balances.set(owner, balance + *amount);
+        self.total_supply.set(self.total_supply() + *amount);
+    }
+
+    /// Destroy tokens held by `owner`
+    pub fn burn(&mut self, owner: &Address, amount: &U256) {
+        let balance = self.balances.get_or_default(owner);
+        if balance < *amount {
+            self.env().revert(Error::InsufficientStakingBalance);
+        }
+        self.balances.set(owner, balance - *amount);
+        self.total_supply.set(self.total_supply() - *amount);
+    }
+
+    fn move_tokens(&mut self, sender: &Address, recipient: &Address, amount: &U256, nonce: u64) {
+        let sender_balance = self.balances.get_or_default(sender);
+        if sender_balance < *amount {
+            self.env().revert(Error::InsufficientStakingBalance);
+        }
+        self.balances.set(sender, sender_balance - *amount);
+        let recipient_balance = self.balances.get_or_default(recipient);
+        self.balances.set(recipient, recipient_balance + *amount);
+
+        self.env().emit_event(TokenTransfer {
+            sender: *sender,
+            recipient: *recipient,
+            amount: *amount,
+            nonce,
+        });
+    }
+}
@@ -0,0 +1,475 @@
+//! CasperFlow Multisig Account
+//!
+//! A shared vault account controlled by M-of-N signers. Deposits are pooled
+//! into the account's vault balance, and withdrawals, rule creation and
+//! signer changes only happen once enough signers approve a proposal. The
+//! account itself owns the vault balance and the rules it creates.
+
+use odra::prelude::*;
+use odra::casper_types::U512;
+use odra::ContractRef;
+
+use crate::automation_engine::AutomationEngineContractRef;
+use crate::errors::Error;
+use crate::event_nonce::EventNonce;
+use crate::events::{
+    MultisigDeposited, ProposalCreated, ProposalApproved, ProposalExecuted, SignerAdded,
+    SignerRemoved, ThresholdChanged,
+};
+use crate::types::{ContractKind, ContractVersion, Proposal, ProposalKind, RuleParams};
+use crate::vault::AutomationVaultContractRef;
+
+/// Entrypoint interface version reported by `get_version`; bump on breaking changes
+pub const INTERFACE_VERSION: u32 = 1;
+
+/// The Multisig Account contract
+#[odra::module(
+    events = [
+        MultisigDeposited, ProposalCreated, ProposalApproved, ProposalExecuted, SignerAdded,
+        SignerRemoved, ThresholdChanged
+    ],
+    errors = Error
+)]
+pub struct MultisigAccount {
+    /// Addresses allowed to propose and approve
+    signers: Var<Vec<Address>>,
+    /// Number of approvals a proposal needs before it can be executed
+    threshold: Var<u32>,
+    /// The vault holding the shared balance
+    vault_address: Var<Address>,
+    /// The engine that runs the account's rules
+    engine_address: Var<Address>,
+    /// Counter for generating unique proposal IDs
+    next_proposal_id: Var<u64>,
+    /// Mapping of proposal ID to proposal
+    proposals: Mapping<u64, Proposal>,
+    /// Mapping of (proposal ID, signer) to whether the signer approved
+    approvals: Mapping<(u64, Address), bool>,
+    /// Sequence number stamped on emitted events
+    event_nonce: SubModule<EventNonce>,
+}
+
+#[odra::module]
+impl MultisigAccount {
+    /// Initialize the account with its signers and approval threshold
+    pub fn init(&mut self, signers: Vec<Address>, threshold: u32, vault: Address, engine: Address) {
+        let mut unique: Vec<Address> = Vec::new();
+        for signer in signers {
+            if unique.contains(&signer) {
+                self.env().revert(Error::SignerAlreadyExists);
+            }
+            unique.push(signer);
+        }
+        if threshold == 0 || threshold as usize > unique.len() {
+            self.env().revert(Error::InvalidThreshold);
+        }
+
+        self.signers.set(unique);
+        self.threshold.set(threshold);
+        self.vault_address.set(vault);
+        self.engine_address.set(engine);
+    }
+
+    /// Deposit CSPR into the account's shared vault balance
+    ///
+    /// Anyone may deposit. This is a payable function - attach CSPR when calling.
+    #[odra(payable)]
+    pub fn deposit(&mut self) {
+        let amount = self.env().attached_value();
+        if amount.is_zero() {
+            self.env().revert(Error::ZeroAmount);
+        }
+
+        self.vault().with_tokens(amount).deposit();
+
+        self.env().emit_event(MultisigDeposited {
+            depositor: self.env().caller(),
+            amount,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Propose withdrawing `amount` from the shared balance to `recipient`
+    pub fn propose_withdrawal(&mut self, recipient: Address, amount: U512) -> u64 {
+        if amount.is_zero() {
+            self.env().revert(Error::ZeroAmount);
+        }
+        self.create_proposal(ProposalKind::Withdraw, Some(recipient), amount, 0, None)
+    }
+
+    /// Propose creating a rule owned by the account
+    pub fn propose_rule(&mut self, rule: RuleParams) -> u64 {
+        self.create_proposal(ProposalKind::CreateRule, None, U512::zero(), 0, Some(rule))
+    }
+
+    /// Propose adding a signer
+    pub fn propose_add_signer(&mut self, signer: Address) -> u64 {
+        if self.is_signer(signer) {
+            self.env().revert(Error::SignerAlreadyExists);
+        }
+        self.create_proposal(ProposalKind::AddSigner, Some(signer), U512::zero(), 0, None)
+    }
+
+    /// Propose removing a signer
+    pub fn propose_remove_signer(&mut self, signer: Address) -> u64 {
+        if !self.is_signer(signer) {
+            self.env().revert(Error::NotSigner);
+        }
+        self.create_proposal(ProposalKind::RemoveSigner, Some(signer), U512::zero(), 0, None)
+    }
+
+    /// Propose changing the number of approvals required
+    pub fn propose_threshold(&mut self, threshold: u32) -> u64 {
+        self.create_proposal(ProposalKind::ChangeThreshold, None, U512::zero(), threshold, None)
+    }
+
+    /// Approve a proposal as the calling signer
+    pub fn approve(&mut self, proposal_id: u64) {
+        let signer = self.env().caller();
+        self.assert_signer(signer);
+        let mut proposal = self.get_open_proposal_or_revert(proposal_id);
+
+        if self.approvals.get_or_default(&(proposal_id, signer)) {
+            self.env().revert(Error::AlreadyApproved);
+        }
+        self.approvals.set(&(proposal_id, signer), true);
+        proposal.approvals += 1;
+        self.proposals.set(&proposal_id, proposal.clone());
+
+        self.env().emit_event(ProposalApproved {
+            proposal_id,
+            signer,
+            approvals: proposal.approvals,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Execute a proposal that has enough approvals
+    ///
+    /// Only approvals from current signers count, so a removed signer's
+    /// approval no longer helps a proposal through.
+    pub fn execute(&mut self, proposal_id: u64) {
+        let executor = self.env().caller();
+        self.assert_signer(executor);
+        let mut proposal = self.get_open_proposal_or_revert(proposal_id);
+
+        if self.current_approvals(proposal_id) < self.threshold.get_or_default() {
+            self.env().revert(Error::ThresholdNotMet);
+        }
+        proposal.executed = true;
+        self.proposals.set(&proposal_id, proposal.clone());
+
+        match proposal.kind {
+            ProposalKind::Withdraw => {
+                let recipient = self.target_or_revert(&proposal);
+                self.vault().withdraw(proposal.amount);
+                self.env().transfer_tokens(&recipient, &proposal.amount);
+            }
+            ProposalKind::CreateRule => {
+                let rule = match proposal.rule {
+                    Some(rule) => rule,
+                    None => self.env().revert(Error::InvalidProposal),
+                };
+                self.engine().create_rule(
+                    rule.template_name,
+                    rule.trigger_type,
+                    rule.schedule,
+                    rule.action_type,
+                    rule.recipient,
+                    rule.amount,
+                    rule.token,
+                    rule.validator,
+                    rule.metadata,
+                    rule.first_execution,
+                );
+            }
+            ProposalKind::AddSigner => {
+                let signer = self.target_or_revert(&proposal);
+                if self.is_signer(signer) {
+                    self.env().revert(Error::SignerAlreadyExists);
+                }
+                let mut signers = self.signers.get_or_default();
+                signers.push(signer);
+                self.signers.set(signers);
+                self.env().emit_event(SignerAdded { signer, nonce: self.event_nonce.next() });
+            }
+            ProposalKind::RemoveSigner => {
+                let signer = self.target_or_revert(&proposal);
+                let mut signers = self.signers.get_or_default();
+                signers.retain(|s| *s != signer);
+                if (self.threshold.get_or_default() as usize) > signers.len() {
+                    self.env().revert(Error::InvalidThreshold);
+                }
+                self.signers.set(signers);
+                self.env().emit_event(SignerRemoved { signer, nonce: self.event_nonce.next() });
+            }
+            ProposalKind::ChangeThreshold => {
+                // Signers may have been removed since the proposal was made
+                if proposal.threshold as usize > self.signers.get_or_default().len() {
+                    self.env().revert(Error::InvalidThreshold);
+                }
+                self.threshold.set(proposal.threshold);
+                self.env().emit_event(ThresholdChanged {
+                    threshold: proposal.threshold,
+                    nonce: self.event_nonce.next(),
+                });
+            }
+        }
+
+        self.env().emit_event(ProposalExecuted {
+            proposal_id,
+            executor,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    // ========================================================================
+    // View Functions
+    // ========================================================================
+
+    /// Get the current signers
+    pub fn get_signers(&self) -> Vec<Address> {
+        self.signers.get_or_default()
+    }
+
+    /// Get the kind and interface version of this contract
+    pub fn get_version(&self) -> ContractVersion {
+        ContractVersion { kind: ContractKind::Multisig, version: INTERFACE_VERSION }
+    }
+
+    /// Get the nonce of the most recently emitted event
+    pub fn get_event_nonce(&self) -> u64 {
+        self.event_nonce.current()
+    }
+
+    /// Get the number of approvals a proposal needs
+    pub fn get_threshold(&self) -> u32 {
+        self.threshold.get_or_default()
+    }
+
+    /// Get a proposal by ID
+    pub fn get_proposal(&self, proposal_id: u64) -> Option<Proposal> {
+        self.proposals.get(&proposal_id)
+    }
+
+    /// Check whether a signer has approved a proposal
+    pub fn has_approved(&self, proposal_id: u64, signer: Address) -> bool {
+        self.approvals.get_or_default(&(proposal_id, signer))
+    }
+
+    /// Get the account's shared vault balance
+    pub fn get_balance(&self) -> U512 {
+        self.vault().get_balance(self.env().self_address())
+    }
+}
+
+impl MultisigAccount {
+    /// Store a proposal from the calling signer, counting their approval
+    fn create_proposal(
+        &mut self,
+        kind: ProposalKind,
+        target: Option<Address>,
+        amount: U512,
+        threshold: u32,
+        rule: Option<RuleParams>,
+    ) -> u64 {
+        let proposer = self.env().caller();
+        self.assert_signer(proposer);
+        if matches!(kind, ProposalKind::ChangeThreshold)
+            && (threshold == 0 || threshold as usize > self.signers.get_or_default().len())
+        {
+            self.env().revert(Error::InvalidThreshold);
+        }
+
+        let proposal_id = self.next_proposal_id.get_or_default() + 1;
+        self.next_proposal_id.set(proposal_id);
+        self.proposals.set(&proposal_id, Proposal {
+            kind: kind.clone(),
+            proposer,
+            target,
+            amount,
+            threshold,
+            rule,
+            approvals: 1,
+            executed: false,
+        });
+        self.approvals.set(&(proposal_id, proposer), true);
+
+        self.env().emit_event(ProposalCreated {
+            proposal_id,
+            proposer,
+            kind,
+            nonce: self.event_nonce.next(),
+        });
+        self.env().emit_event(ProposalApproved {
+            proposal_id,
+            signer: proposer,
+            approvals: 1,
+            nonce: self.event_nonce.next(),
+        });
+        proposal_id
+    }
+
+    /// Whether an address is one of the signers
+    fn is_signer(&self, address: Address) -> bool {
+        self.signers.get_or_default().contains(&address)
+    }
+
+    /// Number of current signers who have approved a proposal
+    fn current_approvals(&self, proposal_id: u64) -> u32 {
+        self.signers
+            .get_or_default()
+            .into_iter()
+            .filter(|signer| self.approvals.get_or_default(&(proposal_id, *signer)))
+            .count() as u32
+    }
+
+    /// Revert unless the address is one of the signers
+    fn assert_signer(&self, address: Address) {
+        if !self.is_signer(address) {
+            self.env().revert(Error::NotSigner);
+        }
+    }
+
+    /// Get a proposal that has not been executed yet or revert
+    fn get_open_proposal_or_revert(&self, proposal_id: u64) -> Proposal {
+        match self.proposals.get(&proposal_id) {
+            Some(proposal) if proposal.executed => {
+                self.env().revert(Error::ProposalAlreadyExecuted)
+            }
+            Some(proposal) => proposal,
+            None => self.env().revert(Error::ProposalNotFound),
+        }
+    }
+
+    /// The proposal's target address or revert if it has none
+    fn target_or_revert(&self, proposal: &Proposal) -> Address {
+        match proposal.target {
+            Some(target) => target,
+            None => self.env().revert(Error::InvalidProposal),
+        }
+    }
+
+    /// Reference to the vault holding the shared balance
+    fn vault(&self) -> AutomationVaultContractRef {
+        match self.vault_address.get() {
+            Some(addr) => AutomationVaultContractRef::new(self.env(), addr),
+            None => self.env().revert(Error::VaultNotSet),
+        }
+    }
+
+    /// Reference to the engine running the account's rules
+    fn engine(&self) -> AutomationEngineContractRef {
+        match self.engine_address.get() {
+            Some(addr) => AutomationEngineContractRef::new(self.env(), addr),
+            None => self.env().revert(Error::InvalidRuleConfig),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use odra::host::{Deployer, HostRef};
+    use crate::automation_engine::{AutomationEngine, AutomationEngineInitArgs};
+    use crate::types::{ActionType, Schedule, TriggerType};
+    use crate::vault::{AutomationVault, AutomationVaultInitArgs};
+
+    fn setup() -> (odra::host::HostEnv, MultisigAccountHostRef) {
+        let env = odra_test::env();
+        let mut vault = AutomationVault::deploy(&env, AutomationVaultInitArgs {
+            automation_engine: None,
+        });
+        let engine = AutomationEngine::deploy(&env, AutomationEngineInitArgs {
+            vault_address: Some(*vault.address()),
+        });
+        vault.set_automation_engine(*engine.address());
+
+        let multisig = MultisigAccount::deploy(&env, MultisigAccountInitArgs {
+            signers: vec![env.get_account(0), env.get_account(1), env.get_account(2)],
+            threshold: 2,
+            vault: *vault.address(),
+            engine: *engine.address(),
+        });
+        (env, multisig)
+    }
+
+    #[test]
+    fn test_withdrawal_needs_threshold() {
+        let (env, mut multisig) = setup();
+        let recipient = env.get_account(5);
+        let amount = U512::from(1_000_000_000u64);
+
+        env.set_caller(env.get_account(0));
+        multisig.with_tokens(amount).deposit();
+        assert_eq!(multisig.get_balance(), amount);
+
+        let proposal_id = multisig.propose_withdrawal(recipient, amount);
+        let result = multisig.try_execute(proposal_id);
+        assert_eq!(result, Err(Error::ThresholdNotMet.into()));
+
+        // Outsiders cannot approve
+        env.set_caller(env.get_account(3));
+        let result = multisig.try_approve(proposal_id);
+        assert_eq!(result, Err(Error::NotSigner.into()));
+
+        let recipient_before = env.balance_of(&recipient);
+        env.set_caller(env.get_account(1));
+        multisig.approve(proposal_id);
+        multisig.execute(proposal_id);
+
+        assert_eq!(multisig.get_balance(), U512::zero());
+        assert_eq!(env.balance_of(&recipient), recipient_before + amount);
+        let result = multisig.try_execute(proposal_id);
+        assert_eq!(result, Err(Error::ProposalAlreadyExecuted.into()));
+    }
+
+    #[test]
+    fn test_removed_signer_approval_does_not_count() {
+        let (env, mut multisig) = setup();
+        let recipient = env.get_account(5);
+        let amount = U512::from(1_000_000_000u64);
+        env.set_caller(env.get_account(0));
+        multisig.with_tokens(amount).deposit();
+        let withdrawal = multisig.propose_withdrawal(recipient, amount);
+        env.set_caller(env.get_account(1));
+        multisig.approve(withdrawal);
+
+        // Signer 1 is removed before the withdrawal runs
+        env.set_caller(env.get_account(0));
+        let removal = multisig.propose_remove_signer(env.get_account(1));
+        env.set_caller(env.get_account(2));
+        multisig.approve(removal);
+        multisig.execute(removal);
+        let result = multisig.try_execute(withdrawal);
+        assert_eq!(result, Err(Error::ThresholdNotMet.into()));
+
+        multisig.approve(withdrawal);
+        multisig.execute(withdrawal);
+        assert_eq!(multisig.get_balance(), U512::zero());
+    }
+
+    #[test]
+    fn test_rule_proposal() {
+        let (env, mut multisig) = setup();
+        env.set_caller(env.get_account(0));
+
+        let proposal_id = multisig.propose_rule(RuleParams {
+            template_name: "team_payroll".to_string(),
+            trigger_type: TriggerType::Time,
+            schedule: Schedule::Monthly,
+            action_type: ActionType::Transfer,
+            recipient: Some(env.get_account(4)),
+            amount: U512::from(100u64),
+            token: None,
+            validator: None,
+            metadata: None,
+            first_execution: None,
+        });
+        env.set_caller(env.get_account(2));
+        multisig.approve(proposal_id);
+        multisig.execute(proposal_id);
+
+        assert!(multisig.get_proposal(proposal_id).unwrap().executed);
+    }
+}
@@ -0,0 +1,43 @@
+//! Price Oracle Interface
+//!
+//! External contract definition for the CSPR/USD price feed used by
+//! condition-triggered rules.
+
+use odra::prelude::*;
+
+use crate::types::PriceData;
+
+/// Price feed consumed by the automation engine
+/// 
+/// Prices are USD with 8 decimals, e.g. $0.10 is reported as 10_000_000.
+#[odra::external_contract]
+pub trait PriceOracle {
+    /// Get the latest CSPR/USD price and the time it was last updated
+    fn latest_price(&self) -> PriceData;
+}
+
+/// Settable price feed used by the unit tests
+#[cfg(test)]
+pub mod test_oracle {
+    use odra::prelude::*;
+    use odra::casper_types::U512;
+
+    use crate::errors::Error;
+    use crate::types::PriceData;
+
+    #[odra::module(errors = Error)]
+    pub struct TestOracle {
+        price: Var<PriceData>,
+    }
+
+    #[odra::module]
+    impl TestOracle {
+        pub fn set_price(&mut self, price: U512, updated_at: u64) {
+            self.price.set(PriceData { price, updated_at });
+        }
+
+        pub fn latest_price(&self) -> PriceData {
+            self.price.get_or_revert_with(Error::OracleNotSet)
+        }
+    }
+}
@@ -0,0 +1,60 @@
+//! CasperFlow Reentrancy Guard
+//!
+//! A storage lock embedded in contracts that pay out to arbitrary
+//! recipients, so a payout cannot call back into another guarded entrypoint
+//! before the first one has finished updating balances.
+
+use odra::prelude::*;
+
+use crate::errors::Error;
+
+/// Non-reentrant lock, embedded as a submodule
+#[odra::module(errors = Error)]
+pub struct ReentrancyGuard {
+    /// Set while a guarded entrypoint is running
+    locked: Var<bool>,
+}
+
+#[odra::module]
+impl ReentrancyGuard {
+    /// Take the lock, reverting if a guarded call is already in progress
+    pub fn enter(&mut self) {
+        if self.locked.get_or_default() {
+            self.env().revert(Error::ReentrancyDetected);
+        }
+        self.locked.set(true);
+    }
+
+    /// Release the lock
+    /// 
+    /// Only needed on successful paths; a revert rolls the lock back with
+    /// everything else.
+    pub fn exit(&mut self) {
+        self.locked.set(false);
+    }
+
+    /// Whether a guarded call is in progress
+    pub fn is_locked(&self) -> bool {
+        self.locked.get_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use odra::host::{Deployer, NoArgs};
+
+    #[test]
+    fn test_reentry_is_rejected() {
+        let env = odra_test::env();
+        let mut guard = ReentrancyGuard::deploy(&env, NoArgs);
+        
+        guard.enter();
+        assert!(guard.is_locked());
+        assert_eq!(guard.try_enter(), Err(Error::ReentrancyDetected.into()));
+        
+        guard.exit();
+        assert!(!guard.is_locked());
+        guard.enter();
+    }
+}
@@ -0,0 +1,117 @@
+//! CasperFlow Contract Registry
+//!
+//! Stores the canonical addresses of the vault, engine, adapters, price
+//! oracle and fee treasury. Contracts pointed at the registry look their
+//! peers up here, so replacing a contract only means updating its entry
+//! instead of re-linking every contract that calls it.
+
+use odra::prelude::*;
+
+use crate::errors::Error;
+use crate::event_nonce::EventNonce;
+use crate::events::ContractAddressSet;
+use crate::types::{ContractKind, ContractVersion};
+
+/// Any CasperFlow contract, as seen by a peer checking what it links to
+#[odra::external_contract]
+pub trait VersionedContract {
+    /// Get the kind and interface version of the contract
+    fn get_version(&self) -> ContractVersion;
+}
+
+/// Entrypoint interface version reported by `get_version`; bump on breaking changes
+pub const INTERFACE_VERSION: u32 = 1;
+
+/// The Contract Registry contract
+#[odra::module(events = [ContractAddressSet], errors = Error)]
+pub struct ContractRegistry {
+    /// The admin allowed to change entries
+    admin: Var<Address>,
+    /// Mapping of contract kind to its canonical address
+    addresses: Mapping<ContractKind, Option<Address>>,
+    /// Sequence number stamped on emitted events
+    event_nonce: SubModule<EventNonce>,
+}
+
+#[odra::module]
+impl ContractRegistry {
+    /// Initialize the registry with the deployer as admin
+    pub fn init(&mut self) {
+        self.admin.set(self.env().caller());
+    }
+
+    /// Set or clear the canonical address of a contract (admin only)
+    pub fn set_address(&mut self, kind: ContractKind, address: Option<Address>) {
+        self.assert_admin();
+        self.addresses.set(&kind, address);
+        self.env().emit_event(ContractAddressSet {
+            kind,
+            address,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Hand the registry over to a new admin (admin only)
+    pub fn transfer_admin(&mut self, new_admin: Address) {
+        self.assert_admin();
+        self.admin.set(new_admin);
+    }
+
+    /// Get the canonical address of a contract
+    pub fn get_address(&self, kind: ContractKind) -> Option<Address> {
+        self.addresses.get_or_default(&kind)
+    }
+
+    /// Get the admin address
+    pub fn get_admin(&self) -> Option<Address> {
+        self.admin.get()
+    }
+
+    /// Get the kind and interface version of this contract
+    pub fn get_version(&self) -> ContractVersion {
+        ContractVersion { kind: ContractKind::Registry, version: INTERFACE_VERSION }
+    }
+
+    /// Get the nonce of the most recently emitted event
+    pub fn get_event_nonce(&self) -> u64 {
+        self.event_nonce.current()
+    }
+}
+
+impl ContractRegistry {
+    /// Revert unless the caller is the admin
+    fn assert_admin(&self) {
+        if self.admin.get() != Some(self.env().caller()) {
+            self.env().revert(Error::NotAdmin);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use odra::host::{Deployer, HostRef, NoArgs};
+
+    #[test]
+    fn test_set_address() {
+        let env = odra_test::env();
+        let mut registry = ContractRegistry::deploy(&env, NoArgs);
+        let vault = env.get_account(1);
+        
+        registry.set_address(ContractKind::Vault, Some(vault));
+        assert_eq!(registry.get_address(ContractKind::Vault), Some(vault));
+        assert_eq!(registry.get_address(ContractKind::Engine), None);
+        assert!(env.emitted_event(
+            registry.address(),
+            ContractAddressSet {
+                kind: ContractKind::Vault,
+                address: Some(vault),
+                nonce: registry.get_event_nonce(),
+            }
+        ));
+        
+        env.set_caller(env.get_account(2));
+        let result = registry.try_set_address(ContractKind::Vault, None);
+        assert_eq!(result, Err(Error::NotAdmin.into()));
+    }
+}
@@ -0,0 +1,81 @@
+//! CasperFlow Safe Math
+//!
+//! Checked arithmetic for balance, stake and reward bookkeeping. Every
+//! helper reverts with an explicit `Overflow` or `Underflow` error instead
+//! of wrapping or panicking, so audits only need to look for raw `+`/`-`.
+
+use odra::casper_types::{U256, U512};
+use odra::ContractEnv;
+
+use crate::errors::Error;
+
+/// Integer types supported by the safe math helpers
+pub trait CheckedInt: Copy {
+    /// `self + rhs`, or `None` on overflow
+    fn add_checked(self, rhs: Self) -> Option<Self>;
+    /// `self - rhs`, or `None` on underflow
+    fn sub_checked(self, rhs: Self) -> Option<Self>;
+    /// `self * rhs`, or `None` on overflow
+    fn mul_checked(self, rhs: Self) -> Option<Self>;
+}
+
+macro_rules! impl_checked_int {
+    ($($ty:ty),*) => {
+        $(
+            impl CheckedInt for $ty {
+                fn add_checked(self, rhs: Self) -> Option<Self> {
+                    self.checked_add(rhs)
+                }
+
+                fn sub_checked(self, rhs: Self) -> Option<Self> {
+                    self.checked_sub(rhs)
+                }
+
+                fn mul_checked(self, rhs: Self) -> Option<Self> {
+                    self.checked_mul(rhs)
+                }
+            }
+        )*
+    };
+}
+
+impl_checked_int!(U256, U512, u64);
+
+/// Add two values, reverting with `Error::Overflow` if the sum does not fit
+pub fn checked_add<T: CheckedInt>(env: &ContractEnv, a: T, b: T) -> T {
+    match a.add_checked(b) {
+        Some(sum) => sum,
+        None => env.revert(Error::Overflow),
+    }
+}
+
+/// Multiply two values, reverting with `Error::Overflow` if the product does not fit
+pub fn checked_mul<T: CheckedInt>(env: &ContractEnv, a: T, b: T) -> T {
+    match a.mul_checked(b) {
+        Some(product) => product,
+        None => env.revert(Error::Overflow),
+    }
+}
+
+/// Subtract `b` from `a`, reverting with `Error::Underflow` if `b` is larger
+pub fn checked_sub<T: CheckedInt>(env: &ContractEnv, a: T, b: T) -> T {
+    match a.sub_checked(b) {
+        Some(difference) => difference,
+        None => env.revert(Error::Underflow),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checked_int_bounds() {
+        assert_eq!(U512::from(2u64).add_checked(U512::from(3u64)), Some(U512::from(5u64)));
+        assert_eq!(U512::MAX.add_checked(U512::one()), None);
+        assert_eq!(U256::zero().sub_checked(U256::one()), None);
+        assert_eq!(5u64.sub_checked(3), Some(2));
+        assert_eq!(U512::MAX.mul_checked(U512::from(2u64)), None);
+        assert_eq!(u64::MAX.mul_checked(1), Some(u64::MAX));
+    }
+}
@@ -1,29 +1,116 @@
 //! CasperFlow Staking Adapter
 //!
 //! Interfaces with Casper's native liquid staking to enable auto-compound
-//! and scheduled unstaking features.
+//! and scheduled unstaking features. Stakers receive sCSPR, a CEP-18
+//! receipt token served by this contract, at the pool's exchange rate.
 
 use odra::prelude::*;
-use odra::casper_types::{PublicKey, U512};
+use odra::casper_types::{PublicKey, U256, U512};
+use odra::ContractRef;
 
+use crate::cep18::{from_token_amount, to_token_amount};
 use crate::errors::Error;
-use crate::events::{RewardsCompounded, Unstaked};
+use crate::event_nonce::EventNonce;
+use crate::events::{
+    RewardsCompounded, Unstaked, ScsprMinted, ScsprBurned, TokenTransfer, TokenApproval,
+    EnginePermissionUpdated, UnstakeQueued, UnstakeCompleted, ValidatorAllowlistUpdated,
+    ValidatorMetricsUpdated, RebalanceExecuted, RedelegationCompleted, RewardsRestaked,
+    ValidatorSaturated, RewardsSentToVault, RewardsSentToAddress, RewardsDonated,
+    StakeRedelegated,
+};
+use crate::liquid_token::LiquidToken;
+use crate::registry::ContractRegistryContractRef;
+use crate::safe_math::{checked_add, checked_sub};
+use crate::types::{
+    ContractKind, ContractVersion, PendingUnstake, PendingRedelegation, RebalanceConstraints,
+    RebalanceMove, RewardDestination, RewardHistory, ValidatorMetrics,
+};
+use crate::vault::AutomationVaultContractRef;
+
+/// Time for undelegated CSPR to unbond (7 eras of roughly 2 hours)
+const UNBONDING_PERIOD: u64 = 50_400;
+/// Minimum time between two rebalances of the same owner's stake
+const REBALANCE_COOLDOWN: u64 = UNBONDING_PERIOD;
+/// Seconds in a 365-day year, for annualizing yields
+const SECONDS_PER_YEAR: u64 = 31_536_000;
+/// Casper's minimum delegation to a validator (500 CSPR)
+const DEFAULT_MINIMUM_DELEGATION: u64 = 500_000_000_000;
+/// Entrypoint interface version reported by `get_version`; bump on breaking changes
+pub const INTERFACE_VERSION: u32 = 1;
 
 /// The Staking Adapter contract
 /// 
 /// Provides staking operations for automation rules. Uses Casper 2.0's
 /// native delegate/undelegate functionality.
 #[odra::module(
-    events = [RewardsCompounded, Unstaked],
+    events = [
+        RewardsCompounded, Unstaked, ScsprMinted, ScsprBurned, TokenTransfer, TokenApproval,
+        EnginePermissionUpdated, UnstakeQueued, UnstakeCompleted, ValidatorAllowlistUpdated,
+        ValidatorMetricsUpdated, RebalanceExecuted, RedelegationCompleted, RewardsRestaked,
+        ValidatorSaturated, RewardsSentToVault, RewardsSentToAddress, RewardsDonated,
+        StakeRedelegated
+    ],
     errors = Error
 )]
 pub struct StakingAdapter {
     /// The default validator public key for staking operations
     default_validator: Var<Option<PublicKey>>,
-    /// Mapping of user address to their total staked amount (for tracking)
+    /// Mapping of user address to the principal they have staked (cost basis)
     user_stakes: Mapping<Address, U512>,
+    /// Mapping of (owner, validator) to the principal the owner placed with that validator
+    user_delegations: Mapping<(Address, PublicKey), U512>,
+    /// Mapping of (owner, validator) to the rewards recorded for the owner with that validator
+    reward_histories: Mapping<(Address, PublicKey), RewardHistory>,
+    /// Mapping of owner to recorded rewards still held as stake value above their principal
+    recorded_rewards: Mapping<Address, U512>,
+    /// Mapping of owner to their cumulative recorded rewards across validators
+    total_rewards: Mapping<Address, U512>,
     /// The automation engine authorized to call staking operations
     authorized_engine: Var<Option<Address>>,
+    /// sCSPR receipt token
+    token: SubModule<LiquidToken>,
+    /// Total CSPR backing sCSPR, including compounded rewards
+    total_pooled: Var<U512>,
+    /// Mapping of validator to the delegation already accounted for in the pool
+    validator_delegations: Mapping<PublicKey, U512>,
+    /// The admin allowed to configure the adapter
+    admin: Var<Address>,
+    /// Users who allow the authorized engine to manage their stake
+    engine_permissions: Mapping<Address, bool>,
+    /// The vault that receives the proceeds of queued unstakes
+    vault_address: Var<Option<Address>>,
+    /// Counter for generating unique pending unstake IDs
+    next_unstake_id: Var<u64>,
+    /// Mapping of pending unstake ID to the queued undelegation
+    pending_unstakes: Mapping<u64, PendingUnstake>,
+    /// Mapping of owner to the IDs of their queued unstakes
+    user_unstakes: Mapping<Address, Vec<u64>>,
+    /// Mapping of pending unstake ID to the address paid instead of the owner's vault
+    unstake_recipients: Mapping<u64, Address>,
+    /// Validators users and rules may choose to delegate to
+    allowed_validators: Mapping<PublicKey, bool>,
+    /// Admin-posted performance data per validator
+    validator_metrics: Mapping<PublicKey, ValidatorMetrics>,
+    /// Validators with posted metrics, considered by rebalances
+    rated_validators: Var<Vec<PublicKey>>,
+    /// Counter for generating unique pending redelegation IDs
+    next_redelegation_id: Var<u64>,
+    /// Mapping of pending redelegation ID to stake waiting to unbond
+    pending_redelegations: Mapping<u64, PendingRedelegation>,
+    /// Smallest delegation the network accepts for a validator
+    minimum_delegation: Var<U512>,
+    /// Most the adapter will delegate to each validator (absent = uncapped)
+    delegation_caps: Mapping<PublicKey, U512>,
+    /// Account allowed to feed delegation caps alongside the admin
+    cap_feeder: Var<Option<Address>>,
+    /// Validators automated delegations fall back to, in order of preference
+    fallback_validators: Var<Vec<PublicKey>>,
+    /// Contract registry whose entries override the addresses set here
+    registry: Var<Option<Address>>,
+    /// Sequence number stamped on emitted events
+    event_nonce: SubModule<EventNonce>,
+    /// Mapping of owner to when their stake was last rebalanced
+    last_rebalances: Mapping<Address, u64>,
 }
 
 #[odra::module]
@@ -31,6 +118,9 @@ impl StakingAdapter {
     /// Initialize the staking adapter with a default validator
     pub fn init(&mut self, default_validator: Option<PublicKey>) {
         self.default_validator.set(default_validator);
+        self.admin.set(self.env().caller());
+        self.token.init(String::from("Staked CSPR"), String::from("sCSPR"), 9);
+        self.minimum_delegation.set(U512::from(DEFAULT_MINIMUM_DELEGATION));
     }
 
     /// Stake CSPR to the default validator
@@ -49,96 +139,586 @@ impl StakingAdapter {
             Some(v) => v,
             None => self.env().revert(Error::InvalidValidator),
         };
+        self.assert_minimum_delegation(&validator, amount);
+        self.assert_capacity(&validator, amount);
         
         // Delegate to validator using Casper 2.0 API
-        self.env().delegate(validator, amount);
+        self.env().delegate(validator.clone(), amount);
+        self.add_delegation(&validator, amount);
         
-        // Track user's stake
+        // Track user's stake and mint sCSPR
         let current_stake = self.user_stakes.get_or_default(&caller);
-        self.user_stakes.set(&caller, current_stake + amount);
+        self.user_stakes.set(&caller, checked_add(&self.env(), current_stake, amount));
+        self.mint_shares(caller, amount);
     }
 
-    /// Stake CSPR to a specific validator
+    /// Stake CSPR to a specific allowlisted validator
     #[odra(payable)]
     pub fn stake_to_validator(&mut self, validator: PublicKey) {
         let caller = self.env().caller();
         let amount = self.env().attached_value();
-        
-        if amount.is_zero() {
-            self.env().revert(Error::ZeroAmount);
-        }
-        
-        // Delegate to specified validator
-        self.env().delegate(validator, amount);
-        
-        // Track user's stake
-        let current_stake = self.user_stakes.get_or_default(&caller);
-        self.user_stakes.set(&caller, current_stake + amount);
+        self.assert_validator_allowed(&validator);
+        self.stake_as(caller, validator, amount);
+    }
+
+    /// Stake the attached CSPR to a validator and credit the sCSPR to `owner`
+    /// 
+    /// Used by the vault to stake on behalf of its users. The caller pays, so
+    /// no authorization is needed to credit someone else. If `validator` is
+    /// at its delegation cap the stake goes to the first fallback validator
+    /// with room instead.
+    #[odra(payable)]
+    pub fn stake_for(&mut self, owner: Address, validator: PublicKey) {
+        let amount = self.env().attached_value();
+        self.assert_validator_allowed(&validator);
+        let validator = self.route_delegation(validator, amount);
+        self.stake_as(owner, validator, amount);
     }
 
     /// Unstake CSPR from the default validator
     pub fn unstake(&mut self, amount: U512) {
         let caller = self.env().caller();
+        self.unstake_from(caller, amount);
+    }
+
+    /// Unstake CSPR on behalf of `owner` from the default validator
+    /// 
+    /// Callable by the owner, or by the authorized engine if the owner has
+    /// granted it permission via `set_engine_permission`.
+    pub fn unstake_for(&mut self, owner: Address, amount: U512) {
+        self.assert_can_act_for(owner);
+        self.unstake_from(owner, amount);
+    }
+
+    /// Unstake on behalf of `owner` and queue the proceeds for their vault
+    /// 
+    /// Same permissions as `unstake_for`. Once the undelegation has unbonded,
+    /// anyone can call `complete_unstake` to deposit the CSPR into the
+    /// owner's vault balance. Returns the pending unstake ID.
+    pub fn queue_unstake(&mut self, owner: Address, amount: U512) -> u64 {
+        self.assert_can_act_for(owner);
+        if self.resolved_vault().is_none() {
+            self.env().revert(Error::VaultNotSet);
+        }
+        self.unstake_from(owner, amount);
+        self.queue_proceeds(owner, amount)
+    }
+
+    /// Unstake on behalf of `owner` and queue the proceeds for their vault
+    /// 
+    /// Only callable by the vault, which does so for stake it auto-staked
+    /// from the owner's idle balance or when the owner withdraws more than
+    /// their vault balance. Returns the pending unstake ID.
+    pub fn queue_vault_unstake(&mut self, owner: Address, amount: U512) -> u64 {
+        if self.resolved_vault() != Some(self.env().caller()) {
+            self.env().revert(Error::UnauthorizedExecutor);
+        }
+        self.unstake_from(owner, amount);
+        self.queue_proceeds(owner, amount)
+    }
+
+    /// Deposit the unbonded proceeds of a queued unstake into the owner's vault
+    /// 
+    /// Rewards routed to an external address are sent there instead. Anyone
+    /// may trigger this since the destination is fixed.
+    pub fn complete_unstake(&mut self, unstake_id: u64) {
+        let mut pending = match self.pending_unstakes.get(&unstake_id) {
+            Some(pending) if !pending.completed => pending,
+            _ => self.env().revert(Error::UnstakeNotFound),
+        };
+        if self.env().get_block_time() < pending.release_time
+            || self.env().self_balance() < pending.amount
+        {
+            self.env().revert(Error::UnbondingNotComplete);
+        }
+        pending.completed = true;
+        self.pending_unstakes.set(&unstake_id, pending.clone());
         
-        if amount.is_zero() {
-            self.env().revert(Error::ZeroAmount);
+        match self.unstake_recipients.get(&unstake_id) {
+            Some(recipient) => self.env().transfer_tokens(&recipient, &pending.amount),
+            None => {
+                let vault = match self.resolved_vault() {
+                    Some(vault) => vault,
+                    None => self.env().revert(Error::VaultNotSet),
+                };
+                AutomationVaultContractRef::new(self.env(), vault)
+                    .with_tokens(pending.amount)
+                    .receive_unstaked(pending.owner);
+            }
         }
         
-        let validator = match self.default_validator.get_or_default() {
-            Some(v) => v,
-            None => self.env().revert(Error::InvalidValidator),
+        self.env().emit_event(UnstakeCompleted {
+            unstake_id,
+            owner: pending.owner,
+            amount: pending.amount,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Move stake away from validators that miss `constraints`
+    /// 
+    /// Same permissions as `unstake_for`. Undelegates up to
+    /// `constraints.max_amount` of the owner's own delegations with rated
+    /// validators that miss the constraints and queues it for delegation to
+    /// the best-scoring allowlisted validator that meets them. The stake keeps
+    /// backing sCSPR while it unbonds; `complete_redelegation` delegates it to
+    /// the target afterwards. An owner's stake can be rebalanced once per
+    /// unbonding period. Returns the total amount moved.
+    pub fn rebalance_for(&mut self, owner: Address, constraints: RebalanceConstraints) -> U512 {
+        self.assert_can_act_for(owner);
+        let now = self.env().get_block_time();
+        let last = self.last_rebalances.get(&owner);
+        if last.is_some_and(|last| now < last + REBALANCE_COOLDOWN) {
+            self.env().revert(Error::RebalanceCooldown);
+        }
+        
+        let target = match self.best_validator(&constraints) {
+            Some(target) => target,
+            None => self.env().revert(Error::NoRebalanceTarget),
         };
+        let mut budget = constraints.max_amount.min(self.get_user_stake(owner));
         
-        // Check tracked stake
-        let current_stake = self.user_stakes.get_or_default(&caller);
-        if current_stake < amount {
-            self.env().revert(Error::InsufficientStakingBalance);
+        // Undelegate from every rated validator that misses the constraints
+        let mut moves = Vec::new();
+        let mut total_moved = U512::zero();
+        for validator in self.rated_validators.get_or_default() {
+            if budget.is_zero() {
+                break;
+            }
+            if validator == target || self.validator_meets(&validator, &constraints) {
+                continue;
+            }
+            let delegated = self.validator_delegations.get_or_default(&validator);
+            let placed = self.user_delegations.get_or_default(&(owner, validator.clone()));
+            let amount = placed.min(delegated).min(budget);
+            if amount.is_zero() {
+                continue;
+            }
+            
+            self.env().undelegate(validator.clone(), amount);
+            self.validator_delegations.set(&validator, checked_sub(&self.env(), delegated, amount));
+            self.user_delegations.set(
+                &(owner, validator.clone()),
+                checked_sub(&self.env(), placed, amount),
+            );
+            budget = checked_sub(&self.env(), budget, amount);
+            total_moved = checked_add(&self.env(), total_moved, amount);
+            moves.push(RebalanceMove { from: validator, amount });
         }
+        if total_moved.is_zero() {
+            return total_moved;
+        }
+        self.assert_minimum_delegation(&target, total_moved);
+        let placed = self.user_delegations.get_or_default(&(owner, target.clone()));
+        self.user_delegations.set(
+            &(owner, target.clone()),
+            checked_add(&self.env(), placed, total_moved),
+        );
+        self.last_rebalances.set(&owner, now);
         
-        // Undelegate from validator
-        self.env().undelegate(validator, amount);
+        // Queue the unbonding stake for the target
+        let redelegation_id = self.next_redelegation_id.get_or_default() + 1;
+        self.next_redelegation_id.set(redelegation_id);
+        self.pending_redelegations.set(&redelegation_id, PendingRedelegation {
+            validator: target.clone(),
+            amount: total_moved,
+            release_time: self.env().get_block_time() + UNBONDING_PERIOD,
+            completed: false,
+        });
         
-        // Update tracked stake
-        self.user_stakes.set(&caller, current_stake - amount);
+        self.env().emit_event(RebalanceExecuted {
+            owner,
+            target,
+            moves,
+            total_moved,
+            redelegation_id,
+            nonce: self.event_nonce.next(),
+        });
         
-        // Emit event
-        self.env().emit_event(Unstaked {
-            owner: caller,
-            amount,
+        total_moved
+    }
+
+    /// Harvest the rewards earned with `from` and queue them for delegation to `to`
+    /// 
+    /// Rewards are the growth of the contract's delegation to `from` beyond
+    /// what the pool has accounted for. They are added to the pool, as when
+    /// compounding, then undelegated and queued like a rebalance so that
+    /// `complete_redelegation` delegates them to `to` once unbonded. Only the
+    /// authorized engine may restake, on behalf of its admin or operators, and
+    /// `to` must be allowlisted. Returns the rewards moved.
+    pub fn restake_rewards(&mut self, from: PublicKey, to: PublicKey) -> U512 {
+        self.assert_authorized_engine();
+        self.assert_validator_allowed(&to);
+        if from == to {
+            self.env().revert(Error::InvalidValidator);
+        }
+        
+        let delegated = self.env().delegated_amount(from.clone());
+        let tracked = self.validator_delegations.get_or_default(&from);
+        if delegated <= tracked {
+            return U512::zero();
+        }
+        let rewards = checked_sub(&self.env(), delegated, tracked);
+        
+        // Too little to open a delegation with `to`; let rewards keep accruing with `from`
+        if !self.meets_minimum_delegation(&to, rewards) {
+            return U512::zero();
+        }
+        
+        // Rewards back existing sCSPR wherever they end up delegated
+        let total_pooled = checked_add(&self.env(), self.total_pooled.get_or_default(), rewards);
+        self.total_pooled.set(total_pooled);
+        self.env().undelegate(from.clone(), rewards);
+        
+        let redelegation_id = self.next_redelegation_id.get_or_default() + 1;
+        self.next_redelegation_id.set(redelegation_id);
+        self.pending_redelegations.set(&redelegation_id, PendingRedelegation {
+            validator: to.clone(),
+            amount: rewards,
+            release_time: self.env().get_block_time() + UNBONDING_PERIOD,
+            completed: false,
+        });
+        
+        self.env().emit_event(RewardsRestaked {
+            from_validator: from,
+            to_validator: to,
+            amount: rewards,
+            redelegation_id,
+            nonce: self.event_nonce.next(),
+        });
+        
+        rewards
+    }
+
+    /// Delegate the unbonded stake of a rebalance, restake or redelegation to its target validator
+    /// 
+    /// Anyone may trigger this since the target is fixed when it is queued.
+    pub fn complete_redelegation(&mut self, redelegation_id: u64) {
+        let mut pending = match self.pending_redelegations.get(&redelegation_id) {
+            Some(pending) if !pending.completed => pending,
+            _ => self.env().revert(Error::RedelegationNotFound),
+        };
+        if self.env().get_block_time() < pending.release_time
+            || self.env().self_balance() < pending.amount
+        {
+            self.env().revert(Error::UnbondingNotComplete);
+        }
+        
+        // The target may have filled up while the stake was unbonding
+        pending.validator = self.route_delegation(pending.validator, pending.amount);
+        pending.completed = true;
+        self.pending_redelegations.set(&redelegation_id, pending.clone());
+        
+        self.env().delegate(pending.validator.clone(), pending.amount);
+        self.add_delegation(&pending.validator, pending.amount);
+        
+        self.env().emit_event(RedelegationCompleted {
+            redelegation_id,
+            validator: pending.validator,
+            amount: pending.amount,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Move `amount` of the caller's stake from one validator to another
+    /// 
+    /// The contract env only exposes delegate and undelegate, so the stake
+    /// unbonds from `from_validator` and `complete_redelegation` delegates
+    /// it to `to_validator` afterwards. The caller's sCSPR keeps backing the
+    /// stake throughout: nothing is burned, minted or sent back to a vault.
+    /// Returns the pending redelegation ID.
+    pub fn redelegate(
+        &mut self,
+        from_validator: PublicKey,
+        to_validator: PublicKey,
+        amount: U512,
+    ) -> u64 {
+        let caller = self.env().caller();
+        self.redelegate_as(caller, from_validator, to_validator, amount)
+    }
+
+    /// Move `amount` of `owner`'s stake from one validator to another
+    /// 
+    /// Same permissions as `unstake_for`; otherwise as `redelegate`.
+    pub fn redelegate_for(
+        &mut self,
+        owner: Address,
+        from_validator: PublicKey,
+        to_validator: PublicKey,
+        amount: U512,
+    ) -> u64 {
+        self.assert_can_act_for(owner);
+        self.redelegate_as(owner, from_validator, to_validator, amount)
+    }
+
+    /// Allow or forbid the authorized engine to manage the caller's stake
+    pub fn set_engine_permission(&mut self, allowed: bool) {
+        let owner = self.env().caller();
+        self.engine_permissions.set(&owner, allowed);
+        
+        self.env().emit_event(EnginePermissionUpdated {
+            owner,
+            allowed,
+            nonce: self.event_nonce.next(),
         });
     }
 
-    /// Compound staking rewards
+    /// Compound staking rewards earned by a validator delegation
     /// 
-    /// This function claims pending rewards and re-stakes them.
-    /// Called by the automation engine for auto-compound rules.
-    pub fn compound_rewards(&mut self, owner: Address, validator: PublicKey) {
+    /// Rewards are the growth of the contract's delegation to `validator`
+    /// beyond what the pool has already accounted for. They are added to the
+    /// pool, raising the sCSPR exchange rate, so every holder earns in
+    /// proportion to their shares. Only the authorized engine may compound.
+    /// Returns the rewards compounded.
+    pub fn compound_rewards(&mut self, validator: PublicKey) -> U512 {
+        self.assert_authorized_engine();
+        
         // Get current delegated amount (includes rewards)
         let delegated = self.env().delegated_amount(validator.clone());
-        let tracked = self.user_stakes.get_or_default(&owner);
+        let tracked = self.validator_delegations.get_or_default(&validator);
         
-        // Rewards = delegated - tracked (simplified)
-        if delegated > tracked {
-            let rewards = delegated - tracked;
-            
-            // Update tracked stake to include compounded rewards
-            self.user_stakes.set(&owner, delegated);
-            
-            // Emit event
-            self.env().emit_event(RewardsCompounded {
-                owner,
-                amount: rewards,
-            });
+        if delegated <= tracked {
+            return U512::zero();
+        }
+        let rewards = checked_sub(&self.env(), delegated, tracked);
+        
+        // Rewards stay delegated and back existing sCSPR
+        self.validator_delegations.set(&validator, delegated);
+        let total_pooled = checked_add(&self.env(), self.total_pooled.get_or_default(), rewards);
+        self.total_pooled.set(total_pooled);
+        
+        // Emit event
+        self.env().emit_event(RewardsCompounded {
+            validator,
+            amount: rewards,
+            total_pooled,
+            nonce: self.event_nonce.next(),
+        });
+        
+        rewards
+    }
+
+    /// Compound a validator's rewards, then send the owner's share where they chose
+    /// 
+    /// `Restake` leaves the rewards delegated, as `compound_rewards` does.
+    /// `Vault` and `External` unstake the owner's rewards (their sCSPR value
+    /// above principal) from the default validator and queue the proceeds
+    /// for their vault or `recipient` once unbonded; these need the owner's
+    /// engine permission. `validator` defaults to the default validator. Only
+    /// the authorized engine may route rewards. Returns the rewards compounded
+    /// or unstaked.
+    pub fn route_rewards(
+        &mut self,
+        owner: Address,
+        validator: Option<PublicKey>,
+        destination: RewardDestination,
+        recipient: Option<Address>,
+    ) -> U512 {
+        self.assert_authorized_engine();
+        let validator = match validator.or(self.default_validator.get_or_default()) {
+            Some(validator) => validator,
+            None => self.env().revert(Error::InvalidValidator),
+        };
+        let compounded = self.compound_rewards(validator.clone());
+        self.record_rewards(owner, validator);
+        if destination == RewardDestination::Restake {
+            return compounded;
+        }
+        
+        self.assert_can_act_for(owner);
+        let recipient = match (destination, recipient) {
+            (RewardDestination::External, None) => self.env().revert(Error::RewardRecipientNotSet),
+            (RewardDestination::External, recipient) => recipient,
+            _ if self.resolved_vault().is_none() => {
+                self.env().revert(Error::VaultNotSet)
+            }
+            _ => None,
+        };
+        let rewards = self.get_user_rewards(owner);
+        if rewards.is_zero() {
+            return rewards;
+        }
+        
+        // Principal stays put; only the shares backing the rewards are burned
+        self.withdraw_stake(owner, rewards);
+        self.recorded_rewards.set(&owner, U512::zero());
+        let unstake_id = self.queue_proceeds(owner, rewards);
+        match recipient {
+            Some(recipient) => {
+                self.unstake_recipients.set(&unstake_id, recipient);
+                self.env().emit_event(RewardsSentToAddress {
+                    owner,
+                    recipient,
+                    amount: rewards,
+                    unstake_id,
+                    nonce: self.event_nonce.next(),
+                });
+            }
+            None => {
+                self.env().emit_event(RewardsSentToVault {
+                    owner,
+                    amount: rewards,
+                    unstake_id,
+                    nonce: self.event_nonce.next(),
+                });
+            }
+        }
+        rewards
+    }
+
+    /// Compound a validator's rewards, then donate `share_bps` of the owner's to `recipient`
+    /// 
+    /// The donated share is unstaked from the default validator and queued
+    /// for `recipient` once unbonded. The rest stays delegated and is added
+    /// to the owner's principal, so it is not counted as rewards again by
+    /// the next donation. Needs the owner's engine permission; only the
+    /// authorized engine may call it. Returns the amount donated.
+    pub fn donate_rewards(
+        &mut self,
+        owner: Address,
+        validator: Option<PublicKey>,
+        recipient: Address,
+        share_bps: u16,
+    ) -> U512 {
+        self.assert_authorized_engine();
+        if share_bps == 0 || share_bps > 10_000 {
+            self.env().revert(Error::InvalidRewardSplit);
+        }
+        let validator = match validator.or(self.default_validator.get_or_default()) {
+            Some(validator) => validator,
+            None => self.env().revert(Error::InvalidValidator),
+        };
+        self.compound_rewards(validator.clone());
+        self.assert_can_act_for(owner);
+        self.record_rewards(owner, validator);
+        
+        let rewards = self.get_user_rewards(owner);
+        let donated = rewards * U512::from(share_bps) / U512::from(10_000u64);
+        if donated.is_zero() {
+            return donated;
         }
+        let restaked = rewards - donated;
+        self.withdraw_stake(owner, donated);
+        let principal = checked_add(&self.env(), self.user_stakes.get_or_default(&owner), restaked);
+        self.user_stakes.set(&owner, principal);
+        self.recorded_rewards.set(&owner, U512::zero());
+        
+        let unstake_id = self.queue_proceeds(owner, donated);
+        self.unstake_recipients.set(&unstake_id, recipient);
+        self.env().emit_event(RewardsDonated {
+            owner,
+            recipient,
+            donated,
+            restaked,
+            unstake_id,
+            nonce: self.event_nonce.next(),
+        });
+        donated
     }
 
-    /// Set the authorized automation engine
+    /// Set the authorized automation engine (admin only)
     pub fn set_automation_engine(&mut self, engine: Address) {
+        self.assert_admin();
         self.authorized_engine.set(Some(engine));
     }
 
-    /// Set the default validator
+    /// Set the vault that receives queued unstake proceeds (admin only)
+    pub fn set_vault_address(&mut self, vault: Address) {
+        self.assert_admin();
+        self.vault_address.set(Some(vault));
+    }
+
+    /// Point the adapter at a contract registry, or detach it with `None` (admin only)
+    /// 
+    /// While set, the registry's engine and vault entries take precedence
+    /// over the addresses set on the adapter.
+    pub fn set_registry(&mut self, registry: Option<Address>) {
+        self.assert_admin();
+        self.registry.set(registry);
+    }
+
+    /// Add or remove a validator from the allowlist (admin only)
+    pub fn set_validator_allowed(&mut self, validator: PublicKey, allowed: bool) {
+        self.assert_admin();
+        self.allowed_validators.set(&validator, allowed);
+        
+        self.env().emit_event(ValidatorAllowlistUpdated {
+            validator,
+            allowed,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Post performance and commission data for a validator (admin only)
+    pub fn set_validator_metrics(
+        &mut self,
+        validator: PublicKey,
+        performance_bps: u16,
+        commission_bps: u16,
+    ) {
+        self.assert_admin();
+        if performance_bps > 10_000 || commission_bps > 10_000 {
+            self.env().revert(Error::InvalidValidator);
+        }
+        
+        if self.validator_metrics.get(&validator).is_none() {
+            let mut rated = self.rated_validators.get_or_default();
+            rated.push(validator.clone());
+            self.rated_validators.set(rated);
+        }
+        self.validator_metrics.set(&validator, ValidatorMetrics {
+            performance_bps,
+            commission_bps,
+            updated_at: self.env().get_block_time(),
+        });
+        
+        self.env().emit_event(ValidatorMetricsUpdated {
+            validator,
+            performance_bps,
+            commission_bps,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Set the minimum delegation the network accepts (admin only)
+    /// 
+    /// Stakes, rebalances and restakes are rejected when they would leave
+    /// the adapter's delegation to a validator below this amount.
+    pub fn set_minimum_delegation(&mut self, amount: U512) {
+        self.assert_admin();
+        self.minimum_delegation.set(amount);
+    }
+
+    /// Cap how much the adapter delegates to a validator (admin or cap feeder)
+    /// 
+    /// Lets the admin or an oracle keep automated stake away from validators
+    /// nearing the network's saturation point. Zero removes the cap.
+    pub fn set_delegation_cap(&mut self, validator: PublicKey, cap: U512) {
+        let caller = self.env().caller();
+        if self.admin.get() != Some(caller) && self.cap_feeder.get_or_default() != Some(caller) {
+            self.env().revert(Error::NotAdmin);
+        }
+        self.delegation_caps.set(&validator, cap);
+    }
+
+    /// Set the account allowed to feed delegation caps (admin only)
+    pub fn set_cap_feeder(&mut self, feeder: Address) {
+        self.assert_admin();
+        self.cap_feeder.set(Some(feeder));
+    }
+
+    /// Set the validators automated delegations fall back to, in order (admin only)
+    pub fn set_fallback_validators(&mut self, validators: Vec<PublicKey>) {
+        self.assert_admin();
+        for validator in &validators {
+            self.assert_validator_allowed(validator);
+        }
+        self.fallback_validators.set(validators);
+    }
+
+    /// Set the default validator (admin only)
     pub fn set_default_validator(&mut self, validator: PublicKey) {
+        self.assert_admin();
         self.default_validator.set(Some(validator));
     }
 
@@ -146,11 +726,155 @@ impl StakingAdapter {
     // View Functions
     // ========================================================================
 
-    /// Get the tracked stake for a user
+    /// Get the current CSPR value of a user's sCSPR, including their share of rewards
     pub fn get_user_stake(&self, owner: Address) -> U512 {
+        self.get_cspr_for_shares(self.token.balance_of(&owner))
+    }
+
+    /// Get the principal a user has staked
+    pub fn get_user_principal(&self, owner: Address) -> U512 {
         self.user_stakes.get_or_default(&owner)
     }
 
+    /// Get the rewards earned on a user's stake beyond their principal
+    pub fn get_user_rewards(&self, owner: Address) -> U512 {
+        self.get_user_stake(owner).saturating_sub(self.get_user_principal(owner))
+    }
+
+    /// Get the kind and interface version of this contract
+    pub fn get_version(&self) -> ContractVersion {
+        ContractVersion { kind: ContractKind::StakingAdapter, version: INTERFACE_VERSION }
+    }
+
+    /// Get the nonce of the most recently emitted event
+    pub fn get_event_nonce(&self) -> u64 {
+        self.event_nonce.current()
+    }
+
+    /// Get the authorized automation engine
+    pub fn get_automation_engine(&self) -> Option<Address> {
+        self.resolved_engine()
+    }
+
+    /// Get the vault that receives queued unstake proceeds
+    pub fn get_vault_address(&self) -> Option<Address> {
+        self.resolved_vault()
+    }
+
+    /// Get the contract registry the adapter resolves its peers from
+    pub fn get_registry(&self) -> Option<Address> {
+        self.registry.get_or_default()
+    }
+
+    /// Get a pending unstake by ID
+    pub fn get_pending_unstake(&self, unstake_id: u64) -> Option<PendingUnstake> {
+        self.pending_unstakes.get(&unstake_id)
+    }
+
+    /// Get an owner's queued unstakes that have not been completed, by unstake ID
+    pub fn get_user_pending_unstakes(&self, owner: Address) -> Vec<(u64, PendingUnstake)> {
+        self.user_unstakes
+            .get_or_default(&owner)
+            .into_iter()
+            .filter_map(|id| self.pending_unstakes.get(&id).map(|pending| (id, pending)))
+            .filter(|(_, pending)| !pending.completed)
+            .collect()
+    }
+
+    /// Check whether a user allows the engine to manage their stake
+    pub fn has_engine_permission(&self, owner: Address) -> bool {
+        self.engine_permissions.get_or_default(&owner)
+    }
+
+    /// Get the delegation to a validator already accounted for in the pool
+    pub fn get_tracked_delegation(&self, validator: PublicKey) -> U512 {
+        self.validator_delegations.get_or_default(&validator)
+    }
+
+    /// Get the rewards a validator delegation has earned but not yet compounded
+    /// 
+    /// This is what the next `compound_rewards` for the validator would add
+    /// to the pool. `validator` defaults to the default validator.
+    pub fn get_pending_rewards(&self, validator: Option<PublicKey>) -> U512 {
+        match validator.or(self.default_validator.get_or_default()) {
+            Some(validator) => self
+                .env()
+                .delegated_amount(validator.clone())
+                .saturating_sub(self.validator_delegations.get_or_default(&validator)),
+            None => U512::zero(),
+        }
+    }
+
+    /// Check whether a validator is on the allowlist
+    pub fn is_validator_allowed(&self, validator: PublicKey) -> bool {
+        self.allowed_validators.get_or_default(&validator)
+    }
+
+    /// Get the posted performance data for a validator
+    pub fn get_validator_metrics(&self, validator: PublicKey) -> Option<ValidatorMetrics> {
+        self.validator_metrics.get(&validator)
+    }
+
+    /// Get a pending redelegation by ID
+    pub fn get_pending_redelegation(&self, redelegation_id: u64) -> Option<PendingRedelegation> {
+        self.pending_redelegations.get(&redelegation_id)
+    }
+
+    /// Get the cumulative rewards recorded for an owner across validators
+    pub fn get_total_rewards(&self, owner: Address) -> U512 {
+        self.total_rewards.get_or_default(&owner)
+    }
+
+    /// Get the rewards recorded for an owner with a validator
+    pub fn get_reward_history(
+        &self,
+        owner: Address,
+        validator: PublicKey,
+    ) -> Option<RewardHistory> {
+        self.reward_histories.get(&(owner, validator))
+    }
+
+    /// Estimate an owner's annual yield with a validator in basis points
+    /// 
+    /// Annualizes the rewards recorded since the owner's history with the
+    /// validator began against the principal they currently have placed
+    /// with it, so it is only as fresh as the last compounding and assumes
+    /// that principal was constant. Zero without history or principal.
+    pub fn estimate_apy(&self, owner: Address, validator: PublicKey) -> u32 {
+        let history = match self.reward_histories.get(&(owner, validator.clone())) {
+            Some(history) => history,
+            None => return 0,
+        };
+        let principal = self.user_delegations.get_or_default(&(owner, validator));
+        let elapsed = history.last_recorded_at.saturating_sub(history.since);
+        if principal.is_zero() || elapsed == 0 {
+            return 0;
+        }
+        let apy = history.total * U512::from(10_000u64) * U512::from(SECONDS_PER_YEAR)
+            / (principal * U512::from(elapsed));
+        apy.min(U512::from(u32::MAX)).as_u32()
+    }
+
+    /// Get the principal an owner has placed with a validator
+    pub fn get_user_delegation(&self, owner: Address, validator: PublicKey) -> U512 {
+        self.user_delegations.get_or_default(&(owner, validator))
+    }
+
+    /// Get the minimum delegation the network accepts for a validator
+    pub fn get_minimum_delegation(&self) -> U512 {
+        self.minimum_delegation.get_or_default()
+    }
+
+    /// Get the most the adapter will delegate to a validator (zero for uncapped)
+    pub fn get_delegation_cap(&self, validator: PublicKey) -> U512 {
+        self.delegation_caps.get_or_default(&validator)
+    }
+
+    /// Get the validators automated delegations fall back to
+    pub fn get_fallback_validators(&self) -> Vec<PublicKey> {
+        self.fallback_validators.get_or_default()
+    }
+
     /// Get the default validator
     pub fn get_default_validator(&self) -> Option<PublicKey> {
         self.default_validator.get_or_default()
@@ -160,6 +884,432 @@ impl StakingAdapter {
     pub fn get_delegated_amount(&self, validator: PublicKey) -> U512 {
         self.env().delegated_amount(validator)
     }
+
+    /// Get the total CSPR backing sCSPR
+    pub fn get_total_pooled(&self) -> U512 {
+        self.total_pooled.get_or_default()
+    }
+
+    /// Get the CSPR value of an amount of sCSPR at the current exchange rate
+    pub fn get_cspr_for_shares(&self, shares: U256) -> U512 {
+        let supply = from_token_amount(self.token.total_supply());
+        if supply.is_zero() {
+            return from_token_amount(shares);
+        }
+        from_token_amount(shares) * self.total_pooled.get_or_default() / supply
+    }
+
+    // ========================================================================
+    // sCSPR (CEP-18) Functions
+    // ========================================================================
+
+    /// Get the sCSPR token name
+    pub fn name(&self) -> String {
+        self.token.name()
+    }
+
+    /// Get the sCSPR token symbol
+    pub fn symbol(&self) -> String {
+        self.token.symbol()
+    }
+
+    /// Get the sCSPR token decimals
+    pub fn decimals(&self) -> u8 {
+        self.token.decimals()
+    }
+
+    /// Get the total sCSPR supply
+    pub fn total_supply(&self) -> U256 {
+        self.token.total_supply()
+    }
+
+    /// Get the sCSPR balance of an address
+    pub fn balance_of(&self, address: &Address) -> U256 {
+        self.token.balance_of(address)
+    }
+
+    /// Get the sCSPR amount `spender` may transfer on behalf of `owner`
+    pub fn allowance(&self, owner: &Address, spender: &Address) -> U256 {
+        self.token.allowance(owner, spender)
+    }
+
+    /// Allow `spender` to transfer up to `amount` of the caller's sCSPR
+    pub fn approve(&mut self, spender: &Address, amount: &U256) {
+        let nonce = self.event_nonce.next();
+        self.token.approve(spender, amount, nonce);
+    }
+
+    /// Transfer sCSPR from the caller to a recipient
+    pub fn transfer(&mut self, recipient: &Address, amount: &U256) {
+        let nonce = self.event_nonce.next();
+        self.token.transfer(recipient, amount, nonce);
+    }
+
+    /// Transfer sCSPR from `owner` to a recipient using the caller's allowance
+    pub fn transfer_from(&mut self, owner: &Address, recipient: &Address, amount: &U256) {
+        let nonce = self.event_nonce.next();
+        self.token.transfer_from(owner, recipient, amount, nonce);
+    }
+}
+
+impl StakingAdapter {
+    /// Look a contract up in the registry, falling back to the address set on the adapter
+    fn resolve_address(&self, kind: ContractKind, local: Option<Address>) -> Option<Address> {
+        match self.registry.get_or_default() {
+            Some(registry) => ContractRegistryContractRef::new(self.env(), registry)
+                .get_address(kind)
+                .or(local),
+            None => local,
+        }
+    }
+
+    /// Get the automation engine address, from the registry if it has one
+    fn resolved_engine(&self) -> Option<Address> {
+        self.resolve_address(ContractKind::Engine, self.authorized_engine.get_or_default())
+    }
+
+    /// Get the vault address, from the registry if it has one
+    fn resolved_vault(&self) -> Option<Address> {
+        self.resolve_address(ContractKind::Vault, self.vault_address.get_or_default())
+    }
+
+    /// Delegate `amount` to a validator and mint the matching sCSPR to `owner`
+    fn stake_as(&mut self, owner: Address, validator: PublicKey, amount: U512) {
+        if amount.is_zero() {
+            self.env().revert(Error::ZeroAmount);
+        }
+        self.assert_minimum_delegation(&validator, amount);
+        self.assert_capacity(&validator, amount);
+        
+        // Delegate to specified validator
+        self.env().delegate(validator.clone(), amount);
+        self.add_delegation(&validator, amount);
+        
+        // Track user's stake and mint sCSPR
+        let current_stake = self.user_stakes.get_or_default(&owner);
+        self.user_stakes.set(&owner, checked_add(&self.env(), current_stake, amount));
+        let placed = self.user_delegations.get_or_default(&(owner, validator.clone()));
+        let placed = checked_add(&self.env(), placed, amount);
+        self.user_delegations.set(&(owner, validator.clone()), placed);
+        if self.reward_histories.get(&(owner, validator.clone())).is_none() {
+            let now = self.env().get_block_time();
+            self.reward_histories.set(&(owner, validator), RewardHistory {
+                total: U512::zero(),
+                since: now,
+                last_recorded_at: now,
+            });
+        }
+        self.mint_shares(owner, amount);
+    }
+
+    /// Add the rewards `owner` earned since the last record to their history with `validator`
+    /// 
+    /// Rewards are the owner's sCSPR value above principal. Those recorded
+    /// are remembered until they leave the stake value, so compounding
+    /// again does not count them twice.
+    fn record_rewards(&mut self, owner: Address, validator: PublicKey) {
+        let rewards = self.get_user_rewards(owner);
+        let earned = rewards.saturating_sub(self.recorded_rewards.get_or_default(&owner));
+        self.recorded_rewards.set(&owner, rewards);
+        if earned.is_zero() {
+            return;
+        }
+        
+        let now = self.env().get_block_time();
+        let key = (owner, validator);
+        let mut history = self.reward_histories.get(&key).unwrap_or(RewardHistory {
+            total: U512::zero(),
+            since: now,
+            last_recorded_at: now,
+        });
+        history.total = checked_add(&self.env(), history.total, earned);
+        history.last_recorded_at = now;
+        self.reward_histories.set(&key, history);
+        let total = checked_add(&self.env(), self.total_rewards.get_or_default(&owner), earned);
+        self.total_rewards.set(&owner, total);
+    }
+
+    /// Undelegate `owner`'s stake from one validator and queue it for another
+    fn redelegate_as(
+        &mut self,
+        owner: Address,
+        from_validator: PublicKey,
+        to_validator: PublicKey,
+        amount: U512,
+    ) -> u64 {
+        if amount.is_zero() {
+            self.env().revert(Error::ZeroAmount);
+        }
+        if from_validator == to_validator {
+            self.env().revert(Error::InvalidValidator);
+        }
+        self.assert_validator_allowed(&to_validator);
+        self.assert_minimum_delegation(&to_validator, amount);
+        
+        let placed = self.user_delegations.get_or_default(&(owner, from_validator.clone()));
+        let delegated = self.validator_delegations.get_or_default(&from_validator);
+        if placed < amount || delegated < amount {
+            self.env().revert(Error::InsufficientStakingBalance);
+        }
+        
+        self.env().undelegate(from_validator.clone(), amount);
+        self.validator_delegations.set(&from_validator, delegated - amount);
+        self.user_delegations.set(&(owner, from_validator.clone()), placed - amount);
+        let placed_to = self.user_delegations.get_or_default(&(owner, to_validator.clone()));
+        self.user_delegations.set(
+            &(owner, to_validator.clone()),
+            checked_add(&self.env(), placed_to, amount),
+        );
+        
+        // Queue the unbonding stake for the new validator
+        let redelegation_id = self.next_redelegation_id.get_or_default() + 1;
+        self.next_redelegation_id.set(redelegation_id);
+        self.pending_redelegations.set(&redelegation_id, PendingRedelegation {
+            validator: to_validator.clone(),
+            amount,
+            release_time: self.env().get_block_time() + UNBONDING_PERIOD,
+            completed: false,
+        });
+        
+        self.env().emit_event(StakeRedelegated {
+            owner,
+            from_validator,
+            to_validator,
+            amount,
+            redelegation_id,
+            nonce: self.event_nonce.next(),
+        });
+        redelegation_id
+    }
+
+    /// Burn the owner's sCSPR and undelegate `amount` from the default validator
+    fn unstake_from(&mut self, caller: Address, amount: U512) {
+        if amount.is_zero() {
+            self.env().revert(Error::ZeroAmount);
+        }
+        
+        self.withdraw_stake(caller, amount);
+        
+        // Update tracked stake; sCSPR may have been received by transfer, so
+        // the tracked principal can be lower than the amount unstaked
+        let current_stake = self.user_stakes.get_or_default(&caller);
+        self.user_stakes.set(&caller, current_stake.saturating_sub(amount));
+        
+        // Emit event
+        self.env().emit_event(Unstaked {
+            owner: caller,
+            amount,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Burn `owner`'s sCSPR backing `amount` and undelegate it from the default validator
+    fn withdraw_stake(&mut self, owner: Address, amount: U512) {
+        let validator = match self.default_validator.get_or_default() {
+            Some(v) => v,
+            None => self.env().revert(Error::InvalidValidator),
+        };
+        
+        // Burn the sCSPR backing the amount (reverts if the owner holds too little)
+        self.burn_shares(owner, amount);
+        let placed = self.user_delegations.get_or_default(&(owner, validator.clone()));
+        self.user_delegations.set(&(owner, validator.clone()), placed.saturating_sub(amount));
+        
+        // Undelegate from validator
+        self.env().undelegate(validator.clone(), amount);
+        let delegated = self.validator_delegations.get_or_default(&validator);
+        self.validator_delegations.set(&validator, delegated.saturating_sub(amount));
+    }
+
+    /// Record undelegated CSPR as a pending unstake for `owner`, returning its ID
+    fn queue_proceeds(&mut self, owner: Address, amount: U512) -> u64 {
+        let unstake_id = self.next_unstake_id.get_or_default() + 1;
+        self.next_unstake_id.set(unstake_id);
+        let release_time = self.env().get_block_time() + UNBONDING_PERIOD;
+        self.pending_unstakes.set(&unstake_id, PendingUnstake {
+            owner,
+            amount,
+            release_time,
+            completed: false,
+        });
+        let mut unstake_ids = self.user_unstakes.get_or_default(&owner);
+        unstake_ids.push(unstake_id);
+        self.user_unstakes.set(&owner, unstake_ids);
+        
+        self.env().emit_event(UnstakeQueued {
+            unstake_id,
+            owner,
+            amount,
+            release_time,
+            nonce: self.event_nonce.next(),
+        });
+        
+        unstake_id
+    }
+
+    /// Revert unless the caller is the authorized automation engine
+    fn assert_authorized_engine(&self) {
+        let caller = self.env().caller();
+        match self.resolved_engine() {
+            Some(engine_addr) if caller == engine_addr => {}
+            _ => self.env().revert(Error::UnauthorizedExecutor),
+        }
+    }
+
+    /// Revert unless the caller is `owner` or the engine acting with the owner's permission
+    fn assert_can_act_for(&self, owner: Address) {
+        if self.env().caller() == owner {
+            return;
+        }
+        self.assert_authorized_engine();
+        if !self.engine_permissions.get_or_default(&owner) {
+            self.env().revert(Error::UnauthorizedExecutor);
+        }
+    }
+
+    /// Whether a validator has posted metrics that meet the constraints
+    fn validator_meets(&self, validator: &PublicKey, constraints: &RebalanceConstraints) -> bool {
+        match self.validator_metrics.get(validator) {
+            Some(metrics) => metrics.meets(constraints),
+            None => false,
+        }
+    }
+
+    /// The best-scoring allowlisted validator that meets the constraints
+    fn best_validator(&self, constraints: &RebalanceConstraints) -> Option<PublicKey> {
+        self.rated_validators
+            .get_or_default()
+            .into_iter()
+            .filter(|v| self.allowed_validators.get_or_default(v))
+            .filter_map(|v| self.validator_metrics.get(&v).map(|metrics| (v, metrics)))
+            .filter(|(_, metrics)| metrics.meets(constraints))
+            .max_by_key(|(_, metrics)| metrics.score())
+            .map(|(v, _)| v)
+    }
+
+    /// Revert unless the validator is on the allowlist
+    fn assert_validator_allowed(&self, validator: &PublicKey) {
+        if !self.allowed_validators.get_or_default(validator) {
+            self.env().revert(Error::InvalidValidator);
+        }
+    }
+
+    /// Revert unless the caller is the admin
+    fn assert_admin(&self) {
+        if self.admin.get() != Some(self.env().caller()) {
+            self.env().revert(Error::NotAdmin);
+        }
+    }
+
+    /// Whether delegating `amount` more leaves the delegation to `validator` at or above the minimum
+    fn meets_minimum_delegation(&self, validator: &PublicKey, amount: U512) -> bool {
+        let delegated = self.validator_delegations.get_or_default(validator);
+        checked_add(&self.env(), delegated, amount) >= self.minimum_delegation.get_or_default()
+    }
+
+    /// Revert unless delegating `amount` more meets the network's minimum delegation
+    fn assert_minimum_delegation(&self, validator: &PublicKey, amount: U512) {
+        if !self.meets_minimum_delegation(validator, amount) {
+            self.env().revert(Error::MinimumStakeNotMet);
+        }
+    }
+
+    /// Whether delegating `amount` more to `validator` stays within its cap
+    fn has_capacity(&self, validator: &PublicKey, amount: U512) -> bool {
+        let cap = self.delegation_caps.get_or_default(validator);
+        let delegated = self.validator_delegations.get_or_default(validator);
+        cap.is_zero() || checked_add(&self.env(), delegated, amount) <= cap
+    }
+
+    /// Revert if delegating `amount` more would take `validator` over its cap
+    fn assert_capacity(&self, validator: &PublicKey, amount: U512) {
+        if !self.has_capacity(validator, amount) {
+            self.env().revert(Error::ValidatorSaturated);
+        }
+    }
+
+    /// The validator an automated delegation of `amount` should go to
+    /// 
+    /// `preferred` while it has room under its cap, otherwise the first
+    /// allowlisted fallback validator that can take the whole amount.
+    fn route_delegation(&mut self, preferred: PublicKey, amount: U512) -> PublicKey {
+        if self.has_capacity(&preferred, amount) {
+            return preferred;
+        }
+        let fallback = self.fallback_validators.get_or_default().into_iter().find(|validator| {
+            *validator != preferred
+                && self.allowed_validators.get_or_default(validator)
+                && self.has_capacity(validator, amount)
+                && self.meets_minimum_delegation(validator, amount)
+        });
+        
+        self.env().emit_event(ValidatorSaturated {
+            validator: preferred.clone(),
+            cap: self.delegation_caps.get_or_default(&preferred),
+            delegated: self.validator_delegations.get_or_default(&preferred),
+            fallback: fallback.clone(),
+            nonce: self.event_nonce.next(),
+        });
+        match fallback {
+            Some(validator) => validator,
+            None => self.env().revert(Error::ValidatorSaturated),
+        }
+    }
+
+    /// Record new principal delegated to a validator
+    fn add_delegation(&mut self, validator: &PublicKey, amount: U512) {
+        let delegated = self.validator_delegations.get_or_default(validator);
+        self.validator_delegations.set(validator, checked_add(&self.env(), delegated, amount));
+    }
+
+    /// Mint sCSPR for `amount` of newly staked CSPR at the current exchange rate
+    fn mint_shares(&mut self, owner: Address, amount: U512) {
+        let pooled = self.total_pooled.get_or_default();
+        let supply = from_token_amount(self.token.total_supply());
+        let shares = if supply.is_zero() || pooled.is_zero() {
+            amount
+        } else {
+            amount * supply / pooled
+        };
+        let shares = match to_token_amount(shares) {
+            Some(shares) => shares,
+            None => self.env().revert(Error::InvalidRuleConfig),
+        };
+        
+        self.total_pooled.set(checked_add(&self.env(), pooled, amount));
+        self.token.mint(&owner, &shares);
+        
+        self.env().emit_event(ScsprMinted {
+            owner,
+            staked: amount,
+            shares,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Burn the sCSPR backing `amount` of CSPR, rounding the shares up
+    fn burn_shares(&mut self, owner: Address, amount: U512) {
+        let pooled = self.total_pooled.get_or_default();
+        let supply = from_token_amount(self.token.total_supply());
+        if pooled < amount {
+            self.env().revert(Error::InsufficientStakingBalance);
+        }
+        let shares = (amount * supply + pooled - 1) / pooled;
+        let shares = match to_token_amount(shares) {
+            Some(shares) => shares,
+            None => self.env().revert(Error::InsufficientStakingBalance),
+        };
+        
+        self.token.burn(&owner, &shares);
+        self.total_pooled.set(checked_sub(&self.env(), pooled, amount));
+        
+        self.env().emit_event(ScsprBurned {
+            owner,
+            unstaked: amount,
+            shares,
+            nonce: self.event_nonce.next(),
+        });
+    }
 }
 
 #[cfg(test)]
@@ -184,8 +1334,359 @@ mod tests {
         adapter.with_tokens(stake_amount).stake();
         
         assert_eq!(adapter.get_user_stake(staker), stake_amount);
+        assert_eq!(adapter.balance_of(&staker), U256::from(1_000_000_000_000u64));
         
         // Note: In testnet, delegation takes time to process
         // For unit tests, we just verify the tracked stake
     }
+
+    #[test]
+    fn test_scspr_is_transferable() {
+        let env = odra_test::env();
+        let validator = env.get_validator(0);
+        
+        let mut adapter = StakingAdapter::deploy(&env, StakingAdapterInitArgs {
+            default_validator: Some(validator),
+        });
+        assert_eq!(adapter.symbol(), "sCSPR");
+        
+        let staker = env.get_account(0);
+        let friend = env.get_account(1);
+        let stake_amount = U512::from(1_000_000_000_000u64);
+        env.set_caller(staker);
+        adapter.with_tokens(stake_amount).stake();
+        
+        let half = U256::from(500_000_000_000u64);
+        adapter.transfer(&friend, &half);
+        assert_eq!(adapter.balance_of(&staker), half);
+        assert_eq!(adapter.balance_of(&friend), half);
+        assert_eq!(adapter.get_cspr_for_shares(half), U512::from(500_000_000_000u64));
+        assert_eq!(adapter.total_supply(), U256::from(1_000_000_000_000u64));
+    }
+
+    #[test]
+    fn test_stakers_share_pool_proportionally() {
+        let env = odra_test::env();
+        let validator = env.get_validator(0);
+        
+        let mut adapter = StakingAdapter::deploy(&env, StakingAdapterInitArgs {
+            default_validator: Some(validator.clone()),
+        });
+        
+        let alice = env.get_account(0);
+        let bob = env.get_account(1);
+        env.set_caller(alice);
+        adapter.with_tokens(U512::from(3_000_000_000_000u64)).stake();
+        env.set_caller(bob);
+        adapter.with_tokens(U512::from(1_000_000_000_000u64)).stake();
+        
+        assert_eq!(adapter.get_total_pooled(), U512::from(4_000_000_000_000u64));
+        assert_eq!(adapter.get_tracked_delegation(validator.clone()), U512::from(4_000_000_000_000u64));
+        
+        // Compounding only recognises growth of the shared delegation, so
+        // one staker cannot claim another's principal as rewards
+        let engine = env.get_account(2);
+        env.set_caller(alice);
+        adapter.set_automation_engine(engine);
+        env.set_caller(engine);
+        adapter.compound_rewards(validator);
+        assert_eq!(adapter.get_user_rewards(bob), U512::zero());
+        assert_eq!(adapter.get_user_stake(alice), U512::from(3_000_000_000_000u64));
+    }
+
+    #[test]
+    fn test_engine_authorization() {
+        let env = odra_test::env();
+        let validator = env.get_validator(0);
+        let admin = env.get_account(0);
+        let user = env.get_account(1);
+        let engine = env.get_account(2);
+        
+        env.set_caller(admin);
+        let mut adapter = StakingAdapter::deploy(&env, StakingAdapterInitArgs {
+            default_validator: Some(validator.clone()),
+        });
+        
+        // Only the admin can configure the engine
+        env.set_caller(user);
+        let result = adapter.try_set_automation_engine(user);
+        assert_eq!(result, Err(Error::NotAdmin.into()));
+        env.set_caller(admin);
+        adapter.set_automation_engine(engine);
+        
+        // Only the engine can compound
+        env.set_caller(user);
+        let result = adapter.try_compound_rewards(validator);
+        assert_eq!(result, Err(Error::UnauthorizedExecutor.into()));
+        
+        // The engine needs the owner's permission to unstake for them
+        adapter.with_tokens(U512::from(1_000_000_000_000u64)).stake();
+        env.set_caller(engine);
+        let result = adapter.try_unstake_for(user, U512::from(1_000_000_000u64));
+        assert_eq!(result, Err(Error::UnauthorizedExecutor.into()));
+        env.set_caller(user);
+        adapter.set_engine_permission(true);
+        assert!(adapter.has_engine_permission(user));
+    }
+
+    #[test]
+    fn test_rebalance_moves_stake_to_better_validator() {
+        let env = odra_test::env();
+        let weak = env.get_validator(0);
+        let strong = env.get_validator(1);
+        let user = env.get_account(0);
+        env.set_caller(user);
+        
+        let mut adapter = StakingAdapter::deploy(&env, StakingAdapterInitArgs {
+            default_validator: Some(weak.clone()),
+        });
+        adapter.set_validator_allowed(strong.clone(), true);
+        adapter.set_validator_metrics(weak.clone(), 9_000, 2_000);
+        adapter.set_validator_metrics(strong.clone(), 9_800, 500);
+        
+        let amount = U512::from(1_000_000_000_000u64);
+        adapter.with_tokens(amount).stake();
+        
+        let constraints = RebalanceConstraints {
+            max_commission_bps: 1_000,
+            min_performance_bps: 9_500,
+            max_amount: amount * 2,
+        };
+        let moved = adapter.rebalance_for(user, constraints.clone());
+        
+        assert_eq!(moved, amount);
+        assert_eq!(adapter.get_tracked_delegation(weak.clone()), U512::zero());
+        let pending = adapter.get_pending_redelegation(1).unwrap();
+        assert_eq!(pending.validator, strong);
+        assert_eq!(pending.amount, amount);
+        
+        // Unbonding stake keeps backing sCSPR
+        assert_eq!(adapter.get_user_stake(user), amount);
+        assert_eq!(adapter.get_user_delegation(user, strong), amount);
+        let result = adapter.try_complete_redelegation(1);
+        assert_eq!(result, Err(Error::UnbondingNotComplete.into()));
+        
+        // Another owner's stake is not theirs to move, and rebalances are rate limited
+        let other = env.get_account(1);
+        env.set_caller(other);
+        adapter.with_tokens(amount).stake();
+        env.set_caller(user);
+        let result = adapter.try_rebalance_for(user, constraints.clone());
+        assert_eq!(result, Err(Error::RebalanceCooldown.into()));
+        env.advance_block_time(UNBONDING_PERIOD);
+        assert_eq!(adapter.rebalance_for(user, constraints), U512::zero());
+        assert_eq!(adapter.get_tracked_delegation(weak), amount);
+    }
+
+    #[test]
+    fn test_restake_rewards_needs_distinct_allowlisted_target() {
+        let env = odra_test::env();
+        let source = env.get_validator(0);
+        let target = env.get_validator(1);
+        let admin = env.get_account(0);
+        let engine = env.get_account(1);
+        env.set_caller(admin);
+        
+        let mut adapter = StakingAdapter::deploy(&env, StakingAdapterInitArgs {
+            default_validator: Some(source.clone()),
+        });
+        adapter.set_automation_engine(engine);
+        adapter.with_tokens(U512::from(1_000_000_000_000u64)).stake();
+        
+        env.set_caller(engine);
+        let result = adapter.try_restake_rewards(source.clone(), target.clone());
+        assert_eq!(result, Err(Error::InvalidValidator.into()));
+        env.set_caller(admin);
+        adapter.set_validator_allowed(target.clone(), true);
+        adapter.set_validator_allowed(source.clone(), true);
+        
+        env.set_caller(engine);
+        let result = adapter.try_restake_rewards(source.clone(), source.clone());
+        assert_eq!(result, Err(Error::InvalidValidator.into()));
+        
+        // Principal is never treated as rewards, so nothing moves yet
+        assert_eq!(adapter.restake_rewards(source.clone(), target), U512::zero());
+        assert!(adapter.get_pending_redelegation(1).is_none());
+        assert_eq!(adapter.get_tracked_delegation(source), U512::from(1_000_000_000_000u64));
+    }
+
+    #[test]
+    fn test_minimum_delegation() {
+        let env = odra_test::env();
+        let validator = env.get_validator(0);
+        let admin = env.get_account(0);
+        env.set_caller(admin);
+        
+        let mut adapter = StakingAdapter::deploy(&env, StakingAdapterInitArgs {
+            default_validator: Some(validator),
+        });
+        let minimum = U512::from(500_000_000_000u64);
+        assert_eq!(adapter.get_minimum_delegation(), minimum);
+        
+        // The first delegation must reach the minimum; top-ups may be smaller
+        let result = adapter.with_tokens(minimum - 1).try_stake();
+        assert_eq!(result, Err(Error::MinimumStakeNotMet.into()));
+        adapter.with_tokens(minimum).stake();
+        adapter.with_tokens(U512::from(1_000_000_000u64)).stake();
+        
+        env.set_caller(env.get_account(1));
+        let result = adapter.try_set_minimum_delegation(U512::zero());
+        assert_eq!(result, Err(Error::NotAdmin.into()));
+    }
+
+    #[test]
+    fn test_saturated_validator_falls_back() {
+        let env = odra_test::env();
+        let primary = env.get_validator(0);
+        let secondary = env.get_validator(1);
+        let admin = env.get_account(0);
+        let vault = env.get_account(1);
+        let owner = env.get_account(2);
+        env.set_caller(admin);
+        
+        let mut adapter = StakingAdapter::deploy(&env, StakingAdapterInitArgs {
+            default_validator: Some(primary.clone()),
+        });
+        adapter.set_validator_allowed(primary.clone(), true);
+        adapter.set_validator_allowed(secondary.clone(), true);
+        adapter.set_fallback_validators(vec![secondary.clone()]);
+        let amount = U512::from(1_000_000_000_000u64);
+        adapter.set_delegation_cap(primary.clone(), amount);
+        
+        // The first stake fills the primary validator; the next goes to the fallback
+        env.set_caller(vault);
+        adapter.with_tokens(amount).stake_for(owner, primary.clone());
+        adapter.with_tokens(amount).stake_for(owner, primary.clone());
+        assert_eq!(adapter.get_tracked_delegation(primary.clone()), amount);
+        assert_eq!(adapter.get_tracked_delegation(secondary.clone()), amount);
+        assert!(env.emitted_event(
+            adapter.address(),
+            ValidatorSaturated {
+                validator: primary.clone(),
+                cap: amount,
+                delegated: amount,
+                fallback: Some(secondary),
+                nonce: adapter.get_event_nonce() - 1,
+            }
+        ));
+        
+        // Explicit choices are never redirected
+        let result = adapter.with_tokens(amount).try_stake_to_validator(primary);
+        assert_eq!(result, Err(Error::ValidatorSaturated.into()));
+    }
+
+    #[test]
+    fn test_route_rewards_to_external_address() {
+        let env = odra_test::env();
+        let validator = env.get_validator(0);
+        let (alice, bob, engine, carol) =
+            (env.get_account(0), env.get_account(1), env.get_account(2), env.get_account(3));
+        env.set_caller(alice);
+        let mut adapter = StakingAdapter::deploy(&env, StakingAdapterInitArgs {
+            default_validator: Some(validator.clone()),
+        });
+        adapter.set_automation_engine(engine);
+        adapter.with_tokens(U512::from(1_000_000_000_000u64)).stake();
+        
+        // sCSPR received by transfer counts as rewards on top of bob's principal
+        adapter.transfer(&bob, &U256::from(200_000_000_000u64));
+        env.set_caller(bob);
+        adapter.set_engine_permission(true);
+        
+        env.set_caller(engine);
+        let result = adapter.try_route_rewards(bob, None, RewardDestination::External, None);
+        assert_eq!(result, Err(Error::RewardRecipientNotSet.into()));
+        let moved = adapter.route_rewards(bob, None, RewardDestination::External, Some(carol));
+        assert_eq!(moved, U512::from(200_000_000_000u64));
+        
+        assert_eq!(adapter.get_user_stake(bob), U512::zero());
+        assert_eq!(adapter.get_pending_unstake(1).unwrap().amount, moved);
+        assert!(env.emitted_event(
+            adapter.address(),
+            RewardsSentToAddress {
+                owner: bob,
+                recipient: carol,
+                amount: moved,
+                unstake_id: 1,
+                nonce: adapter.get_event_nonce(),
+            }
+        ));
+    }
+
+    #[test]
+    fn test_donate_rewards() {
+        let env = odra_test::env();
+        let validator = env.get_validator(0);
+        let (alice, bob, engine, charity) =
+            (env.get_account(0), env.get_account(1), env.get_account(2), env.get_account(3));
+        env.set_caller(alice);
+        let mut adapter = StakingAdapter::deploy(&env, StakingAdapterInitArgs {
+            default_validator: Some(validator.clone()),
+        });
+        adapter.set_automation_engine(engine);
+        adapter.with_tokens(U512::from(1_000_000_000_000u64)).stake();
+        
+        // sCSPR received by transfer counts as rewards on top of bob's principal
+        adapter.transfer(&bob, &U256::from(200_000_000_000u64));
+        env.set_caller(bob);
+        adapter.set_engine_permission(true);
+        
+        // A quarter goes to the charity, the rest becomes bob's principal
+        env.set_caller(engine);
+        let result = adapter.try_donate_rewards(bob, None, charity, 10_001);
+        assert_eq!(result, Err(Error::InvalidRewardSplit.into()));
+        let donated = adapter.donate_rewards(bob, None, charity, 2_500);
+        assert_eq!(donated, U512::from(50_000_000_000u64));
+        assert_eq!(adapter.get_user_principal(bob), U512::from(150_000_000_000u64));
+        assert_eq!(adapter.get_user_rewards(bob), U512::zero());
+        assert_eq!(adapter.get_pending_unstake(1).unwrap().amount, donated);
+        assert!(env.emitted_event(
+            adapter.address(),
+            RewardsDonated {
+                owner: bob,
+                recipient: charity,
+                donated,
+                restaked: U512::from(150_000_000_000u64),
+                unstake_id: 1,
+                nonce: adapter.get_event_nonce(),
+            }
+        ));
+        
+        // Restaked rewards are not donated a second time
+        assert_eq!(adapter.donate_rewards(bob, None, charity, 2_500), U512::zero());
+    }
+
+    #[test]
+    fn test_reward_history_and_apy() {
+        let env = odra_test::env();
+        let validator = env.get_validator(0);
+        let (alice, bob, engine) = (env.get_account(0), env.get_account(1), env.get_account(2));
+        env.set_caller(alice);
+        let mut adapter = StakingAdapter::deploy(&env, StakingAdapterInitArgs {
+            default_validator: Some(validator.clone()),
+        });
+        adapter.set_automation_engine(engine);
+        adapter.with_tokens(U512::from(1_000_000_000_000u64)).stake();
+        env.set_caller(bob);
+        adapter.with_tokens(U512::from(1_000_000_000_000u64)).stake();
+        assert_eq!(adapter.estimate_apy(bob, validator.clone()), 0);
+        
+        // sCSPR received by transfer counts as rewards on top of bob's principal
+        env.set_caller(alice);
+        adapter.transfer(&bob, &U256::from(100_000_000_000u64));
+        env.advance_block_time(SECONDS_PER_YEAR / 2);
+        env.set_caller(engine);
+        adapter.route_rewards(bob, None, RewardDestination::Restake, None);
+        assert_eq!(adapter.get_total_rewards(bob), U512::from(100_000_000_000u64));
+        let history = adapter.get_reward_history(bob, validator.clone()).unwrap();
+        assert_eq!(history.last_recorded_at - history.since, SECONDS_PER_YEAR / 2);
+        
+        // 10% of principal in half a year annualizes to 20%
+        assert_eq!(adapter.estimate_apy(bob, validator.clone()), 2_000);
+        
+        // Compounding again does not record the same rewards twice
+        adapter.route_rewards(bob, None, RewardDestination::Restake, None);
+        assert_eq!(adapter.get_total_rewards(bob), U512::from(100_000_000_000u64));
+        assert_eq!(adapter.get_total_rewards(alice), U512::zero());
+    }
 }
@@ -0,0 +1,368 @@
+//! Storage Upgrade Tests
+//!
+//! Odra keys each module field by its position, so upgraded code must keep
+//! the fields of every released layout where they were. These tests deploy
+//! a contract with an old engine layout, upgrade it to the current engine
+//! and run `migrate`, checking rules come through intact, and do the same
+//! for the vault's balances and accounting.
+
+use odra::casper_types::U512;
+use odra::host::{Deployer, HostEnv, HostRef, InstallConfig, NoArgs, Upgrader};
+use odra::prelude::*;
+
+use crate::automation_engine::{AutomationEngine, AutomationEngineHostRef};
+use crate::vault::AutomationVault;
+use crate::types::{
+    ActionType, BaselineRule, LegacyRuleConfig, RuleState, RuleStatus, Schedule, TriggerType,
+};
+
+/// Seconds in a day
+const DAY: u64 = 86_400;
+
+/// Engine and vault layouts of released storage versions, as far as the tests use them
+#[allow(dead_code)]
+mod layouts {
+    use super::*;
+
+    /// The first released engine, which predates storage versioning
+    #[odra::module]
+    pub struct BaselineEngine {
+        next_rule_id: Var<u64>,
+        rules: Mapping<u64, BaselineRule>,
+        user_rules: Mapping<Address, Vec<u64>>,
+        user_rule_count: Mapping<Address, u32>,
+        vault_address: Var<Option<Address>>,
+    }
+
+    #[odra::module]
+    impl BaselineEngine {
+        pub fn init(&mut self, vault_address: Address) {
+            self.next_rule_id.set(1);
+            self.vault_address.set(Some(vault_address));
+        }
+
+        /// Store a rule the way the first release did
+        pub fn store_rule(&mut self, rule: BaselineRule) {
+            let rule_id = self.next_rule_id.get_or_default();
+            let owner = rule.owner;
+            self.next_rule_id.set(rule_id + 1);
+            self.rules.set(&rule_id, BaselineRule { id: rule_id, ..rule });
+            
+            let mut user_rule_ids = self.user_rules.get_or_default(&owner);
+            user_rule_ids.push(rule_id);
+            self.user_rules.set(&owner, user_rule_ids);
+            let count = self.user_rule_count.get_or_default(&owner);
+            self.user_rule_count.set(&owner, count + 1);
+        }
+    }
+
+    /// The first released vault
+    #[odra::module]
+    pub struct BaselineVault {
+        balances: Mapping<Address, U512>,
+        authorized_engine: Var<Option<Address>>,
+    }
+
+    #[odra::module]
+    impl BaselineVault {
+        pub fn init(&mut self, automation_engine: Option<Address>) {
+            self.authorized_engine.set(automation_engine);
+        }
+
+        /// Deposit the way the first release did
+        #[odra(payable)]
+        pub fn deposit(&mut self) {
+            let caller = self.env().caller();
+            let balance = self.balances.get_or_default(&caller) + self.env().attached_value();
+            self.balances.set(&caller, balance);
+        }
+    }
+
+    /// The engine as laid out at storage version 1
+    /// 
+    /// Fields up to `storage_version` are declared in their original order;
+    /// those the tests do not touch are `Var<()>`, since only their position
+    /// matters.
+    #[odra::module]
+    pub struct V1Engine {
+        next_rule_id: Var<u64>,
+        last_execution_id: Var<()>,
+        rule_configs: Mapping<u64, LegacyRuleConfig>,
+        rule_states: Mapping<u64, RuleState>,
+        rule_metadata: Var<()>,
+        user_rules: Mapping<Address, Vec<u64>>,
+        user_rule_count: Mapping<Address, u32>,
+        local_rule_ids: Mapping<(Address, u32), u64>,
+        rule_local_indices: Mapping<u64, u32>,
+        next_local_indices: Mapping<Address, u32>,
+        purged_rules: Var<()>,
+        registry: Var<()>,
+        payroll_configs: Var<()>,
+        savings_goals: Var<()>,
+        savings_progress: Var<()>,
+        reward_splits: Var<()>,
+        split_configs: Var<()>,
+        signature_nonces: Var<()>,
+        escrow_windows: Var<()>,
+        vault_address: Var<Option<Address>>,
+        admin: Var<Address>,
+        fee_config: Var<()>,
+        fee_exempt_tiers: Var<()>,
+        nft_perks: Var<()>,
+        rule_notifications: Var<()>,
+        rule_priorities: Var<()>,
+        execution_history: Var<()>,
+        execution_history_len: Var<()>,
+        templates: Var<()>,
+        follow_up_rules: Var<()>,
+        price_oracle: Var<()>,
+        max_price_age: Var<()>,
+        price_conditions: Var<()>,
+        rewards_conditions: Var<()>,
+        attestation_oracle: Var<()>,
+        attestation_conditions: Var<()>,
+        staking_adapter: Var<()>,
+        rebalance_constraints: Var<()>,
+        managers: Var<()>,
+        session_keys: Var<()>,
+        approval_thresholds: Var<()>,
+        confirmable_at: Var<()>,
+        last_activity: Var<()>,
+        inheritance_configs: Var<()>,
+        amount_specs: Var<()>,
+        top_up_targets: Var<()>,
+        swap_configs: Var<()>,
+        restake_destinations: Var<()>,
+        address_book: Var<()>,
+        address_book_index: Var<()>,
+        address_book_only: Var<()>,
+        address_book_release: Var<()>,
+        global_stats: Var<()>,
+        user_stats: Var<()>,
+        max_executions_per_hour: Var<()>,
+        hourly_executions: Var<()>,
+        executor_policies: Var<()>,
+        allowed_executors: Var<()>,
+        keeper_bounties: Var<()>,
+        min_keeper_bounty: Var<()>,
+        max_keeper_bounty: Var<()>,
+        min_schedule_interval: Var<()>,
+        max_rule_amount: Var<()>,
+        max_rules_per_batch: Var<()>,
+        time_config: Var<()>,
+        storage_version: Var<u32>,
+    }
+
+    #[odra::module]
+    impl V1Engine {
+        pub fn init(&mut self) {
+            self.next_rule_id.set(1);
+            self.admin.set(self.env().caller());
+            self.storage_version.set(1);
+        }
+
+        /// Store a rule the way version 1 code did
+        pub fn store_rule(&mut self, config: LegacyRuleConfig, state: RuleState) {
+            let rule_id = self.next_rule_id.get_or_default();
+            let owner = config.owner;
+            self.next_rule_id.set(rule_id + 1);
+            self.rule_configs.set(&rule_id, LegacyRuleConfig { id: rule_id, ..config });
+            self.rule_states.set(&rule_id, state);
+            
+            let mut user_rule_ids = self.user_rules.get_or_default(&owner);
+            let local_index = user_rule_ids.len() as u32;
+            self.local_rule_ids.set(&(owner, local_index), rule_id);
+            self.rule_local_indices.set(&rule_id, local_index);
+            self.next_local_indices.set(&owner, local_index + 1);
+            user_rule_ids.push(rule_id);
+            self.user_rules.set(&owner, user_rule_ids);
+            let count = self.user_rule_count.get_or_default(&owner);
+            self.user_rule_count.set(&owner, count + 1);
+        }
+    }
+}
+
+use layouts::{
+    BaselineEngine, BaselineEngineHostRef, BaselineEngineInitArgs, BaselineVault,
+    BaselineVaultInitArgs, V1Engine, V1EngineHostRef,
+};
+
+/// Deploy an upgradable first-release engine holding `rules` of (owner, template name, status)
+fn deploy_baseline(
+    env: &HostEnv,
+    vault_address: Address,
+    rules: &[(Address, &str, RuleStatus)],
+) -> BaselineEngineHostRef {
+    let config = InstallConfig::upgradable::<BaselineEngine>();
+    let mut legacy =
+        BaselineEngine::deploy_with_cfg(env, BaselineEngineInitArgs { vault_address }, config);
+    for (owner, template_name, status) in rules {
+        legacy.store_rule(BaselineRule {
+            id: 0,
+            owner: *owner,
+            trigger_type: TriggerType::Time,
+            schedule: Schedule::Daily,
+            action_type: ActionType::Transfer,
+            status: status.clone(),
+            template_name: template_name.to_string(),
+            recipient: Some(env.get_account(4)),
+            amount: U512::from(1_000u64),
+            last_executed: 0,
+            next_execution: DAY,
+            execution_count: 0,
+        });
+    }
+    legacy
+}
+
+/// Deploy an upgradable version 1 engine holding `rules` of (owner, template name, status)
+fn deploy_v1(env: &HostEnv, rules: &[(Address, &str, RuleStatus)]) -> V1EngineHostRef {
+    let config = InstallConfig::upgradable::<V1Engine>();
+    let mut legacy = V1Engine::deploy_with_cfg(env, NoArgs, config);
+    for (owner, template_name, status) in rules {
+        legacy.store_rule(
+            LegacyRuleConfig {
+                id: 0,
+                owner: *owner,
+                trigger_type: TriggerType::Time,
+                schedule: Schedule::Daily,
+                action_type: ActionType::Transfer,
+                template_name: template_name.to_string(),
+                recipient: Some(env.get_account(4)),
+                amount: U512::from(1_000u64),
+                token: None,
+                validator: None,
+            },
+            RuleState {
+                status: status.clone(),
+                last_executed: 0,
+                next_execution: DAY,
+                execution_count: 0,
+            },
+        );
+    }
+    legacy
+}
+
+/// Install the current engine code over an old one
+fn upgrade(env: &HostEnv, address: Address) -> AutomationEngineHostRef {
+    AutomationEngine::try_upgrade(env, address, NoArgs).expect("engine upgrade failed")
+}
+
+#[test]
+fn test_upgrade_from_baseline() {
+    let env = odra_test::env();
+    let (alice, bob, vault) = (env.get_account(1), env.get_account(2), env.get_account(5));
+    let legacy = deploy_baseline(&env, vault, &[
+        (alice, "allowance", RuleStatus::Active),
+        (bob, "payroll", RuleStatus::Paused),
+        (alice, "rent", RuleStatus::Active),
+    ]);
+    let mut engine = upgrade(&env, *legacy.address());
+    assert_eq!(engine.get_storage_version(), 0);
+    assert_eq!(engine.get_admin(), Some(env.get_account(0)));
+    assert_eq!(engine.get_vault_address(), Some(vault));
+    
+    // Version 1 splits each whole rule into config and state
+    assert!(!engine.migrate(10));
+    assert_eq!(engine.get_storage_version(), 1);
+    assert!(!engine.migrate(10));
+    assert!(engine.migrate(10));
+    let rule = engine.get_rule(1).unwrap();
+    assert_eq!(rule.owner, alice);
+    assert_eq!(rule.template_name, "allowance".to_string());
+    assert_eq!(rule.recipient, Some(env.get_account(4)));
+    assert_eq!(rule.next_execution, DAY);
+    let rule = engine.get_rule(2).unwrap();
+    assert_eq!((rule.template_name, rule.status), ("payroll".to_string(), RuleStatus::Paused));
+    assert_eq!(engine.get_user_rule_ids(alice), vec![1, 3]);
+    assert_eq!(engine.get_user_rule_counts(alice), (2, 0, 0));
+    assert_eq!(engine.get_user_rule_counts(bob), (0, 1, 0));
+}
+
+#[test]
+fn test_vault_upgrade_from_baseline() {
+    let env = odra_test::env();
+    let (alice, bob) = (env.get_account(1), env.get_account(2));
+    let config = InstallConfig::upgradable::<BaselineVault>();
+    let init_args = BaselineVaultInitArgs { automation_engine: None };
+    let mut legacy = BaselineVault::deploy_with_cfg(&env, init_args, config);
+    env.set_caller(alice);
+    legacy.with_tokens(U512::from(3_000u64)).deposit();
+    env.set_caller(bob);
+    legacy.with_tokens(U512::from(2_000u64)).deposit();
+    
+    env.set_caller(env.get_account(0));
+    let mut vault = AutomationVault::try_upgrade(&env, *legacy.address(), NoArgs)
+        .expect("vault upgrade failed");
+    assert_eq!(vault.get_admin(), Some(env.get_account(0)));
+    assert_eq!(vault.get_balance(alice), U512::from(3_000u64));
+    let reconciliation = vault.reconcile();
+    assert_eq!(reconciliation.tracked_total, U512::from(5_000u64));
+    assert!(reconciliation.deficit.is_zero());
+    
+    // Withdrawals pass the solvency check against the seeded total
+    env.set_caller(alice);
+    vault.withdraw(U512::from(3_000u64));
+    assert_eq!(vault.get_accounting().total_user_deposits, U512::from(2_000u64));
+}
+
+#[test]
+fn test_upgrade_from_v1() {
+    let env = odra_test::env();
+    let (alice, bob) = (env.get_account(1), env.get_account(2));
+    let legacy = deploy_v1(&env, &[
+        (alice, "allowance", RuleStatus::Active),
+        (bob, "payroll", RuleStatus::Paused),
+        (alice, "allowance", RuleStatus::Active),
+    ]);
+    let mut engine = upgrade(&env, *legacy.address());
+    assert_eq!(engine.get_storage_version(), 1);
+    
+    // Version 2 interns the inlined template names
+    assert!(!engine.migrate(10));
+    assert_eq!(engine.get_storage_version(), 2);
+    let rule = engine.get_rule(1).unwrap();
+    assert_eq!(rule.owner, alice);
+    assert_eq!(rule.template_name, "allowance".to_string());
+    assert_eq!(rule.next_execution, DAY);
+    assert_eq!(rule.template_name_id, engine.get_rule(3).unwrap().template_name_id);
+    let rule = engine.get_rule(2).unwrap();
+    assert_eq!((rule.template_name, rule.status), ("payroll".to_string(), RuleStatus::Paused));
+    assert_eq!(engine.get_user_rule_ids(alice), vec![1, 3]);
+    
+    assert!(engine.migrate(10));
+    assert_eq!(engine.get_user_rule_count(alice), 2);
+}
+
+#[test]
+fn test_status_counts_migration() {
+    let env = odra_test::env();
+    let (alice, bob) = (env.get_account(1), env.get_account(2));
+    let legacy = deploy_v1(&env, &[
+        (alice, "rent", RuleStatus::Active),
+        (alice, "savings", RuleStatus::Active),
+        (alice, "gym", RuleStatus::Active),
+        (bob, "payroll", RuleStatus::Paused),
+    ]);
+    let mut engine = upgrade(&env, *legacy.address());
+    assert!(!engine.migrate(10));
+    assert_eq!(engine.get_storage_version(), 2);
+    
+    // Counting stops after the first two rules
+    assert!(!engine.migrate(2));
+    assert_eq!(engine.get_user_rule_counts(alice), (2, 0, 0));
+    
+    // A counted rule changes status, then one the migration has not reached
+    env.set_caller(alice);
+    engine.pause_rule(1);
+    engine.delete_rule(3);
+    assert_eq!(engine.get_user_rule_counts(alice), (1, 1, 0));
+    
+    // The migration counts the rest with their current status
+    env.set_caller(env.get_account(0));
+    assert!(engine.migrate(10));
+    assert_eq!(engine.get_storage_version(), 3);
+    assert_eq!(engine.get_user_rule_counts(alice), (1, 1, 1));
+    assert_eq!(engine.get_user_rule_counts(bob), (0, 1, 0));
+}
@@ -0,0 +1,282 @@
+//! CasperFlow Swap Adapter
+//!
+//! Converts between CSPR and CEP-18 tokens through a pluggable DEX router,
+//! enforcing a slippage limit on every swap. The vault routes swap rules
+//! (e.g. recurring buys) through this contract.
+
+use odra::prelude::*;
+use odra::casper_types::{U256, U512};
+use odra::ContractRef;
+
+use crate::cep18::{from_token_amount, Cep18TokenContractRef};
+use crate::errors::Error;
+use crate::event_nonce::EventNonce;
+use crate::events::{SwapExecuted, SwapRouterUpdated};
+use crate::types::{ContractKind, ContractVersion, SwapDirection};
+
+/// Basis points in 100%
+const BPS_DENOMINATOR: u64 = 10_000;
+/// Entrypoint interface version reported by `get_version`; bump on breaking changes
+/// 
+/// Version 2 added the caller's own minimum output to both swaps.
+pub const INTERFACE_VERSION: u32 = 2;
+
+/// DEX router the adapter trades through
+///
+/// Any DEX can be plugged in by deploying a router contract exposing this
+/// interface. Swaps must revert when they cannot deliver `min_out`.
+#[odra::external_contract]
+pub trait DexRouter {
+    /// Quote how many `token` units `amount_in` motes currently buy
+    fn quote_cspr_for_tokens(&self, token: Address, amount_in: U512) -> U256;
+    /// Quote how many motes `amount_in` token units currently buy
+    fn quote_tokens_for_cspr(&self, token: Address, amount_in: U256) -> U512;
+    /// Swap the attached CSPR for at least `min_out` tokens sent to `recipient`
+    fn swap_exact_cspr_for_tokens(
+        &mut self,
+        token: Address,
+        min_out: U256,
+        recipient: Address,
+    ) -> U256;
+    /// Pull `amount_in` tokens from the caller and send at least `min_out` motes to `recipient`
+    fn swap_exact_tokens_for_cspr(
+        &mut self,
+        token: Address,
+        amount_in: U256,
+        min_out: U512,
+        recipient: Address,
+    ) -> U512;
+}
+
+/// The Swap Adapter contract
+///
+/// Holds no funds between calls: each swap forwards its input to the router
+/// and the router delivers the output straight to the recipient.
+#[odra::module(events = [SwapExecuted, SwapRouterUpdated], errors = Error)]
+pub struct SwapAdapter {
+    /// Contract administrator
+    admin: Var<Address>,
+    /// DEX router swaps are executed through
+    router: Var<Option<Address>>,
+    /// Sequence number stamped on emitted events
+    event_nonce: SubModule<EventNonce>,
+}
+
+#[odra::module]
+impl SwapAdapter {
+    /// Initialize the swap adapter with an optional DEX router
+    pub fn init(&mut self, router: Option<Address>) {
+        self.admin.set(self.env().caller());
+        self.router.set(router);
+    }
+
+    /// Swap the attached CSPR for `token`, delivered to `recipient`
+    ///
+    /// The output must be within `max_slippage_bps` of the router's quote at
+    /// execution time and at least `min_out`, since a manipulated pool
+    /// quotes low too. Returns the token amount received.
+    #[odra(payable)]
+    pub fn swap_cspr_for_tokens(
+        &mut self,
+        token: Address,
+        max_slippage_bps: u16,
+        min_out: U256,
+        recipient: Address,
+    ) -> U256 {
+        let amount_in = self.env().attached_value();
+        if amount_in.is_zero() {
+            self.env().revert(Error::ZeroAmount);
+        }
+        self.assert_slippage(max_slippage_bps);
+
+        let mut router = self.router_or_revert();
+        let quote = router.quote_cspr_for_tokens(token, amount_in);
+        let min_out = (quote * U256::from(BPS_DENOMINATOR - max_slippage_bps as u64)
+            / U256::from(BPS_DENOMINATOR))
+            .max(min_out);
+        let amount_out = router
+            .with_tokens(amount_in)
+            .swap_exact_cspr_for_tokens(token, min_out, recipient);
+        if amount_out < min_out {
+            self.env().revert(Error::SlippageExceeded);
+        }
+
+        self.env().emit_event(SwapExecuted {
+            token,
+            direction: SwapDirection::CsprToToken,
+            amount_in,
+            amount_out: from_token_amount(amount_out),
+            recipient,
+            nonce: self.event_nonce.next(),
+        });
+        amount_out
+    }
+
+    /// Swap `amount_in` of `token`, already sent to the adapter, for CSPR delivered to `recipient`
+    ///
+    /// The output must be within `max_slippage_bps` of the router's quote at
+    /// execution time and at least `min_out`. Returns the CSPR amount received.
+    pub fn swap_tokens_for_cspr(
+        &mut self,
+        token: Address,
+        amount_in: U256,
+        max_slippage_bps: u16,
+        min_out: U512,
+        recipient: Address,
+    ) -> U512 {
+        if amount_in.is_zero() {
+            self.env().revert(Error::ZeroAmount);
+        }
+        self.assert_slippage(max_slippage_bps);
+
+        let router_address = match self.router.get_or_default() {
+            Some(router) => router,
+            None => self.env().revert(Error::DexRouterNotSet),
+        };
+        let mut router = DexRouterContractRef::new(self.env(), router_address);
+        let quote = router.quote_tokens_for_cspr(token, amount_in);
+        let min_out = (quote * U512::from(BPS_DENOMINATOR - max_slippage_bps as u64)
+            / U512::from(BPS_DENOMINATOR))
+            .max(min_out);
+
+        // Let the router pull the tokens it trades
+        Cep18TokenContractRef::new(self.env(), token).approve(&router_address, &amount_in);
+        let amount_out = router.swap_exact_tokens_for_cspr(token, amount_in, min_out, recipient);
+        if amount_out < min_out {
+            self.env().revert(Error::SlippageExceeded);
+        }
+
+        self.env().emit_event(SwapExecuted {
+            token,
+            direction: SwapDirection::TokenToCspr,
+            amount_in: from_token_amount(amount_in),
+            amount_out,
+            recipient,
+            nonce: self.event_nonce.next(),
+        });
+        amount_out
+    }
+
+    /// Plug in a different DEX router (admin only)
+    pub fn set_router(&mut self, router: Address) {
+        if self.admin.get() != Some(self.env().caller()) {
+            self.env().revert(Error::NotAdmin);
+        }
+        self.router.set(Some(router));
+
+        self.env().emit_event(SwapRouterUpdated {
+            router,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Get the DEX router swaps are executed through
+    pub fn get_router(&self) -> Option<Address> {
+        self.router.get_or_default()
+    }
+
+    /// Get the kind and interface version of this contract
+    pub fn get_version(&self) -> ContractVersion {
+        ContractVersion { kind: ContractKind::SwapAdapter, version: INTERFACE_VERSION }
+    }
+
+    /// Get the nonce of the last event emitted by this contract
+    pub fn get_event_nonce(&self) -> u64 {
+        self.event_nonce.current()
+    }
+}
+
+impl SwapAdapter {
+    /// Revert if a slippage limit is above 100%
+    fn assert_slippage(&self, max_slippage_bps: u16) {
+        if max_slippage_bps as u64 > BPS_DENOMINATOR {
+            self.env().revert(Error::SlippageTooHigh);
+        }
+    }
+
+    /// Get a reference to the configured router, reverting if none is set
+    fn router_or_revert(&self) -> DexRouterContractRef {
+        match self.router.get_or_default() {
+            Some(router) => DexRouterContractRef::new(self.env(), router),
+            None => self.env().revert(Error::DexRouterNotSet),
+        }
+    }
+}
+
+/// Fixed-rate DEX router used by the unit tests
+#[cfg(test)]
+pub mod test_dex {
+    use odra::prelude::*;
+    use odra::casper_types::{U256, U512};
+    use odra::ContractRef;
+
+    use crate::cep18::{from_token_amount, to_token_amount, Cep18TokenContractRef};
+
+    #[odra::module]
+    pub struct TestDex {
+        /// Token units paid per mote
+        rate: Var<u64>,
+        /// How far fills fall short of quotes, to simulate slippage
+        shortfall_bps: Var<u16>,
+    }
+
+    #[odra::module]
+    impl TestDex {
+        pub fn init(&mut self, rate: u64) {
+            self.rate.set(rate);
+        }
+
+        pub fn set_shortfall(&mut self, shortfall_bps: u16) {
+            self.shortfall_bps.set(shortfall_bps);
+        }
+
+        pub fn set_rate(&mut self, rate: u64) {
+            self.rate.set(rate);
+        }
+
+        pub fn quote_cspr_for_tokens(&self, _token: Address, amount_in: U512) -> U256 {
+            to_token_amount(amount_in * U512::from(self.rate.get_or_default())).unwrap_or_default()
+        }
+
+        pub fn quote_tokens_for_cspr(&self, _token: Address, amount_in: U256) -> U512 {
+            from_token_amount(amount_in) / U512::from(self.rate.get_or_default())
+        }
+
+        /// Pays out of tokens minted to the dex beforehand; does not enforce `min_out`
+        #[odra(payable)]
+        pub fn swap_exact_cspr_for_tokens(
+            &mut self,
+            token: Address,
+            _min_out: U256,
+            recipient: Address,
+        ) -> U256 {
+            let quote = self.quote_cspr_for_tokens(token, self.env().attached_value());
+            let amount_out = quote * U256::from(self.fill_bps()) / U256::from(10_000u64);
+            Cep18TokenContractRef::new(self.env(), token).transfer(&recipient, &amount_out);
+            amount_out
+        }
+
+        pub fn swap_exact_tokens_for_cspr(
+            &mut self,
+            token: Address,
+            amount_in: U256,
+            _min_out: U512,
+            recipient: Address,
+        ) -> U512 {
+            let caller = self.env().caller();
+            let dex = self.env().self_address();
+            Cep18TokenContractRef::new(self.env(), token).transfer_from(&caller, &dex, &amount_in);
+            let quote = self.quote_tokens_for_cspr(token, amount_in);
+            let amount_out = quote * U512::from(self.fill_bps()) / U512::from(10_000u64);
+            self.env().transfer_tokens(&recipient, &amount_out);
+            amount_out
+        }
+    }
+
+    impl TestDex {
+        /// Share of the quote actually delivered, in basis points
+        fn fill_bps(&self) -> u64 {
+            10_000 - self.shortfall_bps.get_or_default() as u64
+        }
+    }
+}
@@ -0,0 +1,87 @@
+//! CasperFlow Template Registry
+//!
+//! Stores the named rule templates offered by the no-code frontend and
+//! validates user-supplied parameters against them before a rule is created.
+
+use odra::prelude::*;
+
+use crate::errors::Error;
+use crate::types::{RuleTemplate, TemplateParams};
+
+/// Registry of rule templates, embedded in the automation engine
+#[odra::module(errors = Error)]
+pub struct TemplateRegistry {
+    /// Counter for generating unique template IDs
+    next_template_id: Var<u32>,
+    /// Mapping of template ID to template definition
+    templates: Mapping<u32, RuleTemplate>,
+}
+
+#[odra::module]
+impl TemplateRegistry {
+    /// Register a new template and return its ID
+    pub fn register(&mut self, template: RuleTemplate) -> u32 {
+        if template.allowed_action_types.is_empty() || template.min_amount > template.max_amount {
+            self.env().revert(Error::InvalidTemplateParams);
+        }
+
+        // IDs start at 1 so 0 can never refer to a template
+        let template_id = self.next_template_id.get_or_default() + 1;
+        self.next_template_id.set(template_id);
+        self.templates.set(&template_id, template);
+        template_id
+    }
+
+    /// Enable or disable an existing template
+    pub fn set_enabled(&mut self, template_id: u32, enabled: bool) {
+        let mut template = self.get_or_revert(template_id);
+        template.enabled = enabled;
+        self.templates.set(&template_id, template);
+    }
+
+    /// Get a template by ID
+    pub fn get(&self, template_id: u32) -> Option<RuleTemplate> {
+        self.templates.get(&template_id)
+    }
+
+    /// Get the number of registered templates
+    pub fn count(&self) -> u32 {
+        self.next_template_id.get_or_default()
+    }
+
+    /// Validate parameters against a template, returning the template
+    pub fn validate(&self, template_id: u32, params: &TemplateParams) -> RuleTemplate {
+        let template = self.get_or_revert(template_id);
+
+        if !template.enabled {
+            self.env().revert(Error::TemplateNotFound);
+        }
+        if !template.allowed_action_types.contains(&params.action_type) {
+            self.env().revert(Error::InvalidTemplateParams);
+        }
+        if params.amount < template.min_amount || params.amount > template.max_amount {
+            self.env().revert(Error::InvalidTemplateParams);
+        }
+        if template.requires_recipient && params.recipient.is_none() {
+            self.env().revert(Error::InvalidTemplateParams);
+        }
+        if !template.allow_token && params.token.is_some() {
+            self.env().revert(Error::InvalidTemplateParams);
+        }
+        if !template.allow_custom_schedule && params.schedule.is_some() {
+            self.env().revert(Error::InvalidTemplateParams);
+        }
+
+        template
+    }
+}
+
+impl TemplateRegistry {
+    /// Get a template or revert if not found
+    fn get_or_revert(&self, template_id: u32) -> RuleTemplate {
+        match self.templates.get(&template_id) {
+            Some(template) => template,
+            None => self.env().revert(Error::TemplateNotFound),
+        }
+    }
+}
@@ -0,0 +1,189 @@
+//! Test Utilities
+//!
+//! Deployment, funding and time-travel helpers for odra tests written
+//! against CasperFlow, so integrators do not have to copy the setup from
+//! the contracts' own tests. Enable the `test-utils` feature to use them:
+//!
+//! ```ignore
+//! let env = odra_test::env();
+//! let owner = env.get_account(0);
+//! let mut scenario = Scenario::new(&env).with_deposit(owner, cspr(100));
+//! let rule_id = scenario.transfer_rule(owner, env.get_account(1), cspr(10), Schedule::Daily);
+//! advance_time_and_execute(&env, &mut scenario.engine, rule_id, SECONDS_PER_DAY);
+//! ```
+
+use odra::casper_types::{PublicKey, U512};
+use odra::host::{Deployer, HostEnv, HostRef};
+use odra::prelude::*;
+
+use crate::automation_engine::{
+    AutomationEngine, AutomationEngineHostRef, AutomationEngineInitArgs,
+};
+use crate::staking_adapter::{StakingAdapter, StakingAdapterHostRef, StakingAdapterInitArgs};
+use crate::types::{ActionType, Schedule, TimeConfig, TriggerType};
+use crate::vault::{AutomationVault, AutomationVaultHostRef, AutomationVaultInitArgs};
+
+/// Seconds in a day with the default time config
+pub const SECONDS_PER_DAY: u64 = 86_400;
+/// Seconds in a week with the default time config
+pub const SECONDS_PER_WEEK: u64 = 604_800;
+/// Motes in one CSPR
+pub const MOTES_PER_CSPR: u64 = 1_000_000_000;
+
+/// Convert whole CSPR to motes
+pub fn cspr(amount: u64) -> U512 {
+    U512::from(amount) * U512::from(MOTES_PER_CSPR)
+}
+
+/// Deploy a vault and an engine linked to each other, with the caller as admin of both
+pub fn deploy_core(env: &HostEnv) -> (AutomationVaultHostRef, AutomationEngineHostRef) {
+    let mut vault = AutomationVault::deploy(env, AutomationVaultInitArgs {
+        automation_engine: None,
+    });
+    let engine = AutomationEngine::deploy(env, AutomationEngineInitArgs {
+        vault_address: Some(*vault.address()),
+    });
+    vault.set_automation_engine(*engine.address());
+    (vault, engine)
+}
+
+/// Deploy a staking adapter that allowlists `validator` and wire it to the vault and engine
+///
+/// The caller must be the admin of the vault and engine.
+pub fn deploy_staking(
+    env: &HostEnv,
+    vault: &mut AutomationVaultHostRef,
+    engine: &mut AutomationEngineHostRef,
+    validator: PublicKey,
+) -> StakingAdapterHostRef {
+    let mut adapter = StakingAdapter::deploy(env, StakingAdapterInitArgs {
+        default_validator: Some(validator.clone()),
+    });
+    adapter.set_validator_allowed(validator, true);
+    adapter.set_automation_engine(*engine.address());
+    adapter.set_vault_address(*vault.address());
+    vault.set_staking_adapter(*adapter.address());
+    engine.set_staking_adapter(*adapter.address());
+    adapter
+}
+
+/// Move the block time forward by `seconds`, then execute a rule as the current caller
+///
+/// Returns the execution ID, or zero if the owner could not cover the rule.
+pub fn advance_time_and_execute(
+    env: &HostEnv,
+    engine: &mut AutomationEngineHostRef,
+    rule_id: u64,
+    seconds: u64,
+) -> u64 {
+    env.advance_block_time(seconds);
+    engine.execute_rule(rule_id)
+}
+
+/// Execute a scheduled rule `runs` times, advancing one schedule interval before each
+///
+/// Intervals follow the engine's time config. Returns the execution IDs.
+pub fn run_schedule(
+    env: &HostEnv,
+    engine: &mut AutomationEngineHostRef,
+    rule_id: u64,
+    runs: u32,
+) -> Vec<u64> {
+    let rule = engine.get_rule(rule_id).expect("rule not found");
+    let interval = engine.get_time_config().interval(&rule.schedule);
+    (0..runs)
+        .map(|_| advance_time_and_execute(env, engine, rule_id, interval))
+        .collect()
+}
+
+/// Deployed and wired contracts for a test, built up step by step
+pub struct Scenario {
+    pub env: HostEnv,
+    pub vault: AutomationVaultHostRef,
+    pub engine: AutomationEngineHostRef,
+    /// Set by `with_staking`
+    pub staking: Option<StakingAdapterHostRef>,
+}
+
+impl Scenario {
+    /// Deploy a linked vault and engine, with the current caller as admin
+    pub fn new(env: &HostEnv) -> Self {
+        let (vault, engine) = deploy_core(env);
+        Scenario { env: env.clone(), vault, engine, staking: None }
+    }
+
+    /// Add a staking adapter that delegates to `validator`
+    pub fn with_staking(mut self, validator: PublicKey) -> Self {
+        let adapter = deploy_staking(&self.env, &mut self.vault, &mut self.engine, validator);
+        self.staking = Some(adapter);
+        self
+    }
+
+    /// Compress the engine's schedules, e.g. to run a month of payments quickly
+    pub fn with_time_config(mut self, config: TimeConfig) -> Self {
+        self.engine.set_time_config(config);
+        self
+    }
+
+    /// Deposit `amount` into `owner`'s vault balance
+    pub fn with_deposit(mut self, owner: Address, amount: U512) -> Self {
+        self.as_caller(owner, |scenario| scenario.vault.with_tokens(amount).deposit());
+        self
+    }
+
+    /// Create a time-triggered transfer rule for `owner`, returning its ID
+    pub fn transfer_rule(
+        &mut self,
+        owner: Address,
+        recipient: Address,
+        amount: U512,
+        schedule: Schedule,
+    ) -> u64 {
+        self.as_caller(owner, |scenario| {
+            scenario.engine.create_rule(
+                "transfer".to_string(),
+                TriggerType::Time,
+                schedule,
+                ActionType::Transfer,
+                Some(recipient),
+                amount,
+                None,
+                None,
+                None,
+                None,
+            )
+        })
+    }
+
+    /// Run `f` with `caller` as the caller, then restore the previous caller
+    pub fn as_caller<T>(&mut self, caller: Address, f: impl FnOnce(&mut Self) -> T) -> T {
+        let previous = self.env.caller();
+        self.env.set_caller(caller);
+        let result = f(self);
+        self.env.set_caller(previous);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scenario_runs_schedule() {
+        let env = odra_test::env();
+        let owner = env.get_account(1);
+        let recipient = env.get_account(2);
+        let mut scenario = Scenario::new(&env)
+            .with_time_config(TimeConfig { day: 60, ..TimeConfig::default() })
+            .with_deposit(owner, cspr(100));
+        assert_eq!(env.caller(), env.get_account(0));
+        
+        let rule_id = scenario.transfer_rule(owner, recipient, cspr(10), Schedule::Daily);
+        let before = env.balance_of(&recipient);
+        let execution_ids = run_schedule(&env, &mut scenario.engine, rule_id, 3);
+        assert_eq!(execution_ids, vec![1, 2, 3]);
+        assert_eq!(scenario.vault.get_balance(owner), cspr(70));
+        assert_eq!(env.balance_of(&recipient), before + cspr(30));
+    }
+}
@@ -0,0 +1,96 @@
+//! CasperFlow Admin Timelock
+//!
+//! A queue of sensitive admin actions embedded in the vault and engine.
+//! Once the owner opts into a delay, those actions must be queued, stay
+//! publicly visible for the delay, and only then take effect, giving users
+//! time to withdraw if they disagree with a change.
+
+use odra::prelude::*;
+
+use crate::errors::Error;
+use crate::types::AdminAction;
+
+/// Queued admin actions and the delay they wait for, embedded as a submodule
+#[odra::module(errors = Error)]
+pub struct Timelock {
+    /// Seconds a queued action waits before it can be executed (zero = off)
+    delay: Var<u64>,
+    /// Counter for generating action IDs
+    next_action_id: Var<u64>,
+    /// Mapping of action ID to the queued action (None once executed or cancelled)
+    actions: Mapping<u64, Option<AdminAction>>,
+    /// IDs of the actions still queued
+    pending: Var<Vec<u64>>,
+}
+
+#[odra::module]
+impl Timelock {
+    /// Queue an action to become executable after the current delay
+    ///
+    /// Returns the action's ID and the action with its `eta` filled in.
+    pub fn queue(&mut self, mut action: AdminAction) -> (u64, AdminAction) {
+        action.eta = self.env().get_block_time() + self.delay.get_or_default();
+        let action_id = self.next_action_id.get_or_default() + 1;
+        self.next_action_id.set(action_id);
+        self.actions.set(&action_id, Some(action.clone()));
+        let mut pending = self.pending.get_or_default();
+        pending.push(action_id);
+        self.pending.set(pending);
+        (action_id, action)
+    }
+
+    /// Remove a queued action whose delay has passed, returning it
+    pub fn take_ready(&mut self, action_id: u64) -> AdminAction {
+        let action = self.get_or_revert(action_id);
+        if self.env().get_block_time() < action.eta {
+            self.env().revert(Error::AdminActionNotReady);
+        }
+        self.remove(action_id);
+        action
+    }
+
+    /// Remove a queued action without executing it, returning it
+    pub fn cancel(&mut self, action_id: u64) -> AdminAction {
+        let action = self.get_or_revert(action_id);
+        self.remove(action_id);
+        action
+    }
+
+    /// Set the delay applied to actions queued from now on
+    pub fn set_delay(&mut self, delay: u64) {
+        self.delay.set(delay);
+    }
+
+    /// Get the delay queued actions wait for
+    pub fn delay(&self) -> u64 {
+        self.delay.get_or_default()
+    }
+
+    /// Get a queued action by ID
+    pub fn get(&self, action_id: u64) -> Option<AdminAction> {
+        self.actions.get_or_default(&action_id)
+    }
+
+    /// Get every queued action with its ID, oldest first
+    pub fn pending(&self) -> Vec<(u64, AdminAction)> {
+        self.pending
+            .get_or_default()
+            .into_iter()
+            .filter_map(|action_id| self.get(action_id).map(|action| (action_id, action)))
+            .collect()
+    }
+
+    fn get_or_revert(&self, action_id: u64) -> AdminAction {
+        match self.get(action_id) {
+            Some(action) => action,
+            None => self.env().revert(Error::AdminActionNotFound),
+        }
+    }
+
+    fn remove(&mut self, action_id: u64) {
+        self.actions.set(&action_id, None);
+        let mut pending = self.pending.get_or_default();
+        pending.retain(|id| *id != action_id);
+        self.pending.set(pending);
+    }
+}
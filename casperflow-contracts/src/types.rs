@@ -3,7 +3,8 @@
 //! Defines the data structures used for automation rules.
 
 use odra::prelude::*;
-use odra::casper_types::U512;
+use odra::casper_types::bytesrepr::Bytes;
+use odra::casper_types::{PublicKey, U256, U512};
 
 /// The type of trigger that activates a rule
 #[odra::odra_type]
@@ -25,6 +26,8 @@ pub enum Schedule {
     Weekly = 1,
     /// Execute monthly
     Monthly = 2,
+    /// Execute once at the rule's first execution time, then complete
+    Once = 3,
 }
 
 /// The type of action to perform
@@ -36,6 +39,32 @@ pub enum ActionType {
     Split = 1,
     /// Compound staking rewards
     Compound = 2,
+    /// Stream funds to a recipient at a per-second rate
+    Stream = 3,
+    /// Push vested tranches of a cliff + linear vesting schedule
+    Vesting = 4,
+    /// Stake CSPR from the owner's vault through the staking adapter
+    StakeFromVault = 5,
+    /// Unstake CSPR and return the proceeds to the owner's vault once unbonded
+    Unstake = 6,
+    /// Move stake away from validators that miss the owner's constraints
+    Rebalance = 7,
+    /// Send the whole vault balance to a beneficiary after the owner goes inactive
+    Inheritance = 8,
+    /// Send everything above a floor balance (the rule amount) to the recipient
+    Sweep = 9,
+    /// Refill the recipient's vault balance up to a target, at most the rule amount per run
+    TopUp = 10,
+    /// Convert between the owner's CSPR and CEP-18 vault balances through the swap adapter
+    Swap = 11,
+    /// Harvest rewards from the rule's validator and delegate them to another validator
+    Restake = 12,
+    /// Pay a fixed list of recipients their own amounts in one execution
+    Payroll = 13,
+    /// Hold each payment in escrow until the recipient claims it after a dispute window
+    Escrow = 14,
+    /// Move stake from one validator to another
+    Redelegate = 15,
 }
 
 /// The status of a rule
@@ -47,6 +76,123 @@ pub enum RuleStatus {
     Paused = 1,
     /// Rule has been deleted
     Deleted = 2,
+    /// High-value rule waiting for the owner's confirmation
+    PendingApproval = 3,
+    /// One-shot rule that has executed and will not run again
+    Completed = 4,
+}
+
+/// Who may execute a rule
+#[odra::odra_type]
+pub enum ExecutorPolicy {
+    /// Any keeper may execute the rule once it is due
+    Anyone = 0,
+    /// Only the rule owner may execute the rule
+    OwnerOnly = 1,
+    /// Only keepers on the owner's executor allowlist (or the owner) may execute the rule
+    AllowlistedKeepers = 2,
+}
+
+/// How a rule's amount is worked out when it executes
+#[odra::odra_type]
+pub enum AmountSpec {
+    /// The rule's fixed amount
+    Fixed = 0,
+    /// A share of the owner's vault balance in the rule's asset
+    PercentOfVault = 1,
+    /// A share of the owner's staking rewards
+    PercentOfRewards = 2,
+}
+
+/// Which way a swap converts
+#[odra::odra_type]
+pub enum SwapDirection {
+    /// Spend CSPR to buy the token
+    CsprToToken = 0,
+    /// Sell the token for CSPR
+    TokenToCspr = 1,
+}
+
+/// What kind of address a rule pays
+#[odra::odra_type]
+pub enum RecipientKind {
+    /// An account, paid with a plain transfer
+    Account = 0,
+    /// A contract, paid by calling its deposit entrypoint with the value attached
+    Contract = 1,
+    /// A vault account, credited inside the vault without a token transfer
+    Vault = 2,
+}
+
+/// How an owner's funds are paid into a contract recipient
+#[odra::odra_type]
+pub struct ContractRecipient {
+    /// Payable entrypoint called on the recipient, e.g. `deposit_for`
+    pub entry_point: String,
+    /// Passed as the `owner` argument when set, to credit someone other than the vault
+    pub beneficiary: Option<Address>,
+}
+
+/// How an observed value is compared against a threshold
+#[odra::odra_type]
+pub enum Comparison {
+    /// Condition holds when the value is strictly above the threshold
+    Above = 0,
+    /// Condition holds when the value is strictly below the threshold
+    Below = 1,
+    /// Condition holds when the value is equal to or above the threshold
+    AtLeast = 2,
+    /// Condition holds when the value is equal to or below the threshold
+    AtMost = 3,
+}
+
+impl Comparison {
+    /// Whether `value` satisfies the comparison against `threshold`
+    pub fn holds(&self, value: U512, threshold: U512) -> bool {
+        match self {
+            Comparison::Above => value > threshold,
+            Comparison::Below => value < threshold,
+            Comparison::AtLeast => value >= threshold,
+            Comparison::AtMost => value <= threshold,
+        }
+    }
+}
+
+/// A CSPR/USD price reading reported by the oracle
+#[odra::odra_type]
+pub struct PriceData {
+    /// USD price with 8 decimals
+    pub price: U512,
+    /// Timestamp of the last oracle update
+    pub updated_at: u64,
+}
+
+/// Price threshold that must hold for a condition rule to execute
+#[odra::odra_type]
+pub struct PriceCondition {
+    pub comparison: Comparison,
+    /// USD price with 8 decimals
+    pub threshold: U512,
+}
+
+/// A value posted to the attestation oracle by an allowlisted attester
+#[odra::odra_type]
+pub struct Attestation {
+    pub value: U512,
+    /// Time the attester observed the value off-chain
+    pub timestamp: u64,
+    pub attester: Address,
+}
+
+/// Attested value that must hold for a condition rule to execute
+#[odra::odra_type]
+pub struct AttestationCondition {
+    /// Attestation oracle key, e.g. "github_sponsor_count"
+    pub key: String,
+    pub comparison: Comparison,
+    pub threshold: U512,
+    /// Oldest attestation accepted, in seconds (zero accepts any age)
+    pub max_age: u64,
 }
 
 /// Configuration for a transfer action
@@ -63,7 +209,549 @@ pub struct SplitRecipient {
     pub percentage: u8, // 0-100
 }
 
-/// Complete automation rule stored on-chain
+/// Where the remainder goes when a split's percentages don't divide its amount evenly
+#[odra::odra_type]
+pub enum DustPolicy {
+    /// Added to the first recipient's share
+    FirstRecipient = 0,
+    /// Left in the owner's vault balance
+    KeepInVault = 1,
+    /// Sent to the vault's treasury
+    Treasury = 2,
+}
+
+/// Recipients of a split rule and how its rounding remainder is handled
+#[odra::odra_type]
+pub struct SplitConfig {
+    /// Percentages must add up to exactly 100
+    pub recipients: Vec<SplitRecipient>,
+    pub dust_policy: DustPolicy,
+}
+
+impl SplitConfig {
+    /// Divide `amount` among the recipients, rounding each share down
+    /// 
+    /// Returns each recipient's share in list order and the dust not given
+    /// to any of them. Under `FirstRecipient` the dust is folded into the
+    /// first share and zero is returned. The shares and dust always add up
+    /// to exactly `amount`.
+    pub fn shares(&self, amount: U512) -> (Vec<U512>, U512) {
+        let mut shares: Vec<U512> = self
+            .recipients
+            .iter()
+            .map(|split| amount * U512::from(split.percentage) / U512::from(100u64))
+            .collect();
+        let mut dust = shares.iter().fold(amount, |rest, share| rest - *share);
+        if self.dust_policy == DustPolicy::FirstRecipient {
+            if let Some(first) = shares.first_mut() {
+                *first += dust;
+                dust = U512::zero();
+            }
+        }
+        (shares, dust)
+    }
+}
+
+/// Where an escrowed payment stands
+#[odra::odra_type]
+pub enum EscrowStatus {
+    /// Held by the vault, claimable once the dispute window ends
+    Held = 0,
+    /// Paid out to the recipient
+    Claimed = 1,
+    /// Returned to the owner's vault balance during the dispute window
+    Cancelled = 2,
+}
+
+/// One payment of an escrow rule, held by the vault
+#[odra::odra_type]
+pub struct EscrowPayment {
+    /// Engine rule that funded the payment
+    pub rule_id: u64,
+    pub owner: Address,
+    pub recipient: Address,
+    pub amount: U512,
+    /// Time from which the recipient can claim and the owner can no longer cancel
+    pub release_at: u64,
+    pub status: EscrowStatus,
+}
+
+/// Lifecycle of a claimable transfer
+#[odra::odra_type]
+pub enum ClaimStatus {
+    /// Held by the vault until the recipient claims it
+    Held = 0,
+    /// Paid out to the recipient
+    Claimed = 1,
+    /// Returned to the owner's vault balance after it expired unclaimed
+    Refunded = 2,
+}
+
+/// One payment of a claimable transfer rule, held by the vault for its recipient
+#[odra::odra_type]
+pub struct ClaimableTransfer {
+    /// Engine rule that sent the payment
+    pub rule_id: u64,
+    /// Engine execution the payment belongs to
+    pub execution_id: u64,
+    pub owner: Address,
+    pub recipient: Address,
+    pub amount: U512,
+    /// Time from which the owner can reclaim the payment if it is still unclaimed
+    pub expires_at: u64,
+    pub status: ClaimStatus,
+}
+
+/// An owner's opt-in to staking idle vault funds
+#[odra::odra_type]
+pub struct AutoStakeConfig {
+    /// Spendable balance kept liquid in the vault; everything above it is staked
+    pub floor: U512,
+    pub validator: PublicKey,
+}
+
+/// An owner's standing split of each deposit between stake, savings and liquid funds
+#[odra::odra_type]
+pub struct DepositRouting {
+    /// Share of each deposit staked with `validator`, in basis points
+    pub stake_bps: u16,
+    /// Share of each deposit earmarked for `savings_envelope`, in basis points
+    pub savings_bps: u16,
+    /// Validator for the staked share (required when `stake_bps` is set)
+    pub validator: Option<PublicKey>,
+    /// Envelope holding the savings share (required when `savings_bps` is set)
+    pub savings_envelope: String,
+}
+
+impl DepositRouting {
+    /// Whether the shares fit in one deposit and each has somewhere to go
+    pub fn is_valid(&self) -> bool {
+        self.stake_bps as u32 + self.savings_bps as u32 <= 10_000
+            && (self.stake_bps == 0 || self.validator.is_some())
+            && (self.savings_bps == 0 || !self.savings_envelope.is_empty())
+    }
+
+    /// Split a deposit into its (staked, saved, liquid) parts; rounding favours liquid
+    pub fn split(&self, amount: U512) -> (U512, U512, U512) {
+        let staked = amount * U512::from(self.stake_bps) / U512::from(10_000u64);
+        let saved = amount * U512::from(self.savings_bps) / U512::from(10_000u64);
+        (staked, saved, amount - staked - saved)
+    }
+}
+
+/// A per-second payment stream escrowed in the vault
+#[odra::odra_type]
+pub struct PaymentStream {
+    /// Owner funding the stream
+    pub owner: Address,
+    /// Recipient entitled to the accrued funds
+    pub recipient: Address,
+    /// Amount accrued to the recipient per second
+    pub rate_per_second: U512,
+    /// Timestamp at which accrual starts
+    pub start_time: u64,
+    /// Timestamp at which accrual stops
+    pub end_time: u64,
+    /// Total amount already paid out to the recipient
+    pub withdrawn: U512,
+}
+
+impl PaymentStream {
+    /// Total amount locked for the full duration of the stream, or `None` if it does not fit
+    pub fn total(&self) -> Option<U512> {
+        let duration = self.end_time.checked_sub(self.start_time)?;
+        self.rate_per_second.checked_mul(U512::from(duration))
+    }
+
+    /// Amount accrued to the recipient up to `now`
+    pub fn accrued_at(&self, now: u64) -> U512 {
+        let until = now.clamp(self.start_time, self.end_time);
+        self.rate_per_second * U512::from(until - self.start_time)
+    }
+
+    /// Amount the recipient can claim at `now`
+    pub fn claimable_at(&self, now: u64) -> U512 {
+        self.accrued_at(now) - self.withdrawn
+    }
+}
+
+/// A cliff + linear vesting schedule escrowed in the vault
+#[odra::odra_type]
+pub struct VestingSchedule {
+    /// Owner funding the vesting
+    pub owner: Address,
+    /// Beneficiary entitled to the vested funds
+    pub beneficiary: Address,
+    /// Total amount locked (truncated to the vested amount on revoke)
+    pub total_amount: U512,
+    /// Timestamp at which linear vesting starts
+    pub start_time: u64,
+    /// Nothing can be claimed before this timestamp
+    pub cliff_time: u64,
+    /// Timestamp at which the full amount is vested
+    pub end_time: u64,
+    /// Total amount already paid out to the beneficiary
+    pub claimed: U512,
+    /// Whether the owner has revoked the unvested remainder
+    pub revoked: bool,
+}
+
+impl VestingSchedule {
+    /// Amount vested up to `now`
+    pub fn vested_at(&self, now: u64) -> U512 {
+        if self.revoked || now >= self.end_time {
+            self.total_amount
+        } else if now < self.cliff_time {
+            U512::zero()
+        } else {
+            self.total_amount * U512::from(now - self.start_time)
+                / U512::from(self.end_time - self.start_time)
+        }
+    }
+
+    /// Amount the beneficiary can claim at `now`
+    pub fn claimable_at(&self, now: u64) -> U512 {
+        self.vested_at(now) - self.claimed
+    }
+}
+
+/// A timelocked withdrawal from a vault with a withdrawal delay
+#[odra::odra_type]
+pub struct WithdrawalRequest {
+    /// Owner withdrawing the funds
+    pub owner: Address,
+    /// CEP-18 token withdrawn, or `None` for native CSPR
+    pub token: Option<Address>,
+    /// Amount reserved for the withdrawal
+    pub amount: U512,
+    /// Timestamp from which the withdrawal can be completed
+    pub unlock_time: u64,
+    /// Whether the request has been completed or cancelled
+    pub closed: bool,
+}
+
+/// An undelegation waiting to unbond before its proceeds return to the vault
+#[odra::odra_type]
+pub struct PendingUnstake {
+    /// Owner whose vault receives the proceeds
+    pub owner: Address,
+    /// Amount undelegated
+    pub amount: U512,
+    /// Timestamp after which the undelegated CSPR has unbonded
+    pub release_time: u64,
+    /// Whether the proceeds have been deposited into the vault
+    pub completed: bool,
+}
+
+/// Where a compounding rule sends the owner's staking rewards
+#[odra::odra_type]
+pub enum RewardDestination {
+    /// Leave rewards delegated, raising the value of the owner's sCSPR
+    Restake = 0,
+    /// Unstake rewards into the owner's vault balance
+    Vault = 1,
+    /// Unstake rewards and send them to the rule's recipient
+    External = 2,
+}
+
+/// Admin-posted performance data for a validator
+#[odra::odra_type]
+pub struct ValidatorMetrics {
+    /// Performance (uptime and reward rate) in basis points of the ideal
+    pub performance_bps: u16,
+    /// Commission charged by the validator in basis points
+    pub commission_bps: u16,
+    /// Block time the metrics were posted
+    pub updated_at: u64,
+}
+
+impl ValidatorMetrics {
+    /// Performance net of commission, in basis points
+    pub fn score(&self) -> u32 {
+        self.performance_bps as u32 * (10_000 - self.commission_bps.min(10_000)) as u32 / 10_000
+    }
+
+    /// Whether the validator satisfies a rebalance rule's constraints
+    pub fn meets(&self, constraints: &RebalanceConstraints) -> bool {
+        self.commission_bps <= constraints.max_commission_bps
+            && self.performance_bps >= constraints.min_performance_bps
+    }
+}
+
+/// A labeled recipient in an owner's address book
+#[odra::odra_type]
+pub struct AddressBookEntry {
+    pub recipient: Address,
+    /// Human-readable label shown by frontends
+    pub label: String,
+    /// Timestamp from which rules may send to the recipient
+    pub active_at: u64,
+    /// Whether the entry has been removed
+    pub removed: bool,
+}
+
+/// External contract a rule calls around its executions
+#[odra::odra_type]
+pub struct RuleHook {
+    /// Allowlisted contract implementing the execution hook interface
+    pub contract: Address,
+    /// Whether to call the hook before the action runs
+    pub before: bool,
+    /// Whether to call the hook after the action has run
+    pub after: bool,
+}
+
+/// Hours of the day, in the owner's timezone, during which a rule may execute
+#[odra::odra_type]
+pub struct ExecutionWindow {
+    /// First hour of the window (0-23), inclusive
+    pub earliest_hour: u8,
+    /// Last hour of the window (0-23), inclusive; before `earliest_hour` for overnight windows
+    pub latest_hour: u8,
+    /// Offset of the owner's timezone from UTC in minutes, e.g. -300 for UTC-5
+    pub utc_offset_minutes: i16,
+}
+
+impl ExecutionWindow {
+    /// Largest timezone offset from UTC in minutes (UTC+14)
+    pub const MAX_UTC_OFFSET_MINUTES: i16 = 14 * 60;
+
+    /// Whether the hours and offset are in range
+    pub fn is_valid(&self) -> bool {
+        self.earliest_hour < 24
+            && self.latest_hour < 24
+            && self.utc_offset_minutes.abs() <= Self::MAX_UTC_OFFSET_MINUTES
+    }
+
+    /// Whether `timestamp` (seconds since the epoch) falls inside the window
+    pub fn contains(&self, timestamp: u64) -> bool {
+        let local = timestamp as i64 + self.utc_offset_minutes as i64 * 60;
+        let hour = (local.rem_euclid(86_400) / 3_600) as u8;
+        if self.earliest_hour <= self.latest_hour {
+            hour >= self.earliest_hour && hour <= self.latest_hour
+        } else {
+            hour >= self.earliest_hour || hour <= self.latest_hour
+        }
+    }
+}
+
+/// What a scheduled rule does when keepers run it after its grace period
+#[odra::odra_type]
+pub enum MissedExecutionPolicy {
+    /// Do not execute; wait for the next slot on the rule's schedule
+    Skip = 0,
+    /// Execute once and drop the other missed slots
+    ExecuteOnceLate = 1,
+    /// Execute once for every missed slot, up to a cap per call
+    CatchUpAll = 2,
+}
+
+/// How late runs of a scheduled rule are handled
+#[odra::odra_type]
+pub struct MissedExecutionConfig {
+    pub policy: MissedExecutionPolicy,
+    /// Seconds after a slot during which a run still counts as on time
+    pub grace_period: u64,
+}
+
+/// Settings of a dead-man switch rule
+#[odra::odra_type]
+pub struct InheritanceConfig {
+    /// Seconds without owner activity after which the rule may execute
+    pub inactivity_period: u64,
+    /// Whether to also unstake the owner's sCSPR into the vault
+    pub unstake: bool,
+}
+
+/// Parameters of a swap rule
+#[odra::odra_type]
+pub struct SwapConfig {
+    /// CEP-18 token bought or sold for CSPR
+    pub token: Address,
+    /// Which way the rule converts
+    pub direction: SwapDirection,
+    /// Maximum shortfall from the router's quote, in basis points
+    pub max_slippage_bps: u16,
+}
+
+/// Share of a compounding rule's rewards donated to a recipient each execution
+#[odra::odra_type]
+pub struct RewardSplit {
+    pub recipient: Address,
+    /// Donated share of the rewards in basis points; the rest is restaked
+    pub share_bps: u16,
+}
+
+/// One recipient of a payroll rule and what they are paid each period
+#[odra::odra_type]
+pub struct PayrollEntry {
+    pub recipient: Address,
+    pub amount: U512,
+}
+
+/// What a payroll rule does when the owner cannot pay every entry
+#[odra::odra_type]
+pub enum PayrollFailurePolicy {
+    /// Pay nobody and record the execution as failed
+    AllOrNothing = 0,
+    /// Pay entries in list order while funds last and skip the rest
+    PayWhatFits = 1,
+}
+
+/// Parameters of a payroll rule
+#[odra::odra_type]
+pub struct PayrollConfig {
+    pub entries: Vec<PayrollEntry>,
+    pub failure_policy: PayrollFailurePolicy,
+}
+
+/// User-set limits for a rebalance rule
+#[odra::odra_type]
+pub struct RebalanceConstraints {
+    /// Validators charging more commission than this are moved away from
+    pub max_commission_bps: u16,
+    /// Validators performing below this are moved away from
+    pub min_performance_bps: u16,
+    /// Maximum amount moved per execution (also capped by the owner's stake)
+    pub max_amount: U512,
+}
+
+/// A single undelegation made by a rebalance
+#[odra::odra_type]
+pub struct RebalanceMove {
+    /// Validator the stake was moved away from
+    pub from: PublicKey,
+    /// Amount undelegated
+    pub amount: U512,
+}
+
+/// Stake unbonding from one validator before it is delegated to another
+#[odra::odra_type]
+pub struct PendingRedelegation {
+    /// Validator that receives the stake once unbonded
+    pub validator: PublicKey,
+    /// Amount waiting to be redelegated
+    pub amount: U512,
+    /// Timestamp after which the undelegated CSPR has unbonded
+    pub release_time: u64,
+    /// Whether the stake has been delegated to the new validator
+    pub completed: bool,
+}
+
+/// Rewards an owner has earned with one validator, as recorded by compounding rules
+#[odra::odra_type]
+pub struct RewardHistory {
+    /// Cumulative rewards recorded
+    pub total: U512,
+    /// When the owner first staked with or earned from the validator
+    pub since: u64,
+    /// When rewards were last recorded
+    pub last_recorded_at: u64,
+}
+
+/// Immutable part of a rule, written once at creation
+/// 
+/// The template name is stored once per distinct name and referenced by
+/// ID, so the record has a fixed size apart from its optional fields.
+#[odra::odra_type]
+pub struct RuleConfig {
+    pub id: u64,
+    pub owner: Address,
+    pub trigger_type: TriggerType,
+    pub schedule: Schedule,
+    pub action_type: ActionType,
+    pub template_name_id: u32,
+    pub recipient: Option<Address>,
+    pub amount: U512,
+    pub token: Option<Address>,
+    pub validator: Option<PublicKey>,
+}
+
+/// Rule config layout of storage versions 0 and 1, which inlined the template name
+#[odra::odra_type]
+pub struct LegacyRuleConfig {
+    pub id: u64,
+    pub owner: Address,
+    pub trigger_type: TriggerType,
+    pub schedule: Schedule,
+    pub action_type: ActionType,
+    pub template_name: String,
+    pub recipient: Option<Address>,
+    pub amount: U512,
+    pub token: Option<Address>,
+    pub validator: Option<PublicKey>,
+}
+
+impl LegacyRuleConfig {
+    /// Convert to the current layout, referencing the interned template name
+    pub fn into_config(self, template_name_id: u32) -> RuleConfig {
+        RuleConfig {
+            id: self.id,
+            owner: self.owner,
+            trigger_type: self.trigger_type,
+            schedule: self.schedule,
+            action_type: self.action_type,
+            template_name_id,
+            recipient: self.recipient,
+            amount: self.amount,
+            token: self.token,
+            validator: self.validator,
+        }
+    }
+}
+
+/// Rule layout of the first released engine, which stored each rule whole
+#[odra::odra_type]
+pub struct BaselineRule {
+    pub id: u64,
+    pub owner: Address,
+    pub trigger_type: TriggerType,
+    pub schedule: Schedule,
+    pub action_type: ActionType,
+    pub status: RuleStatus,
+    pub template_name: String,
+    pub recipient: Option<Address>,
+    pub amount: U512,
+    pub last_executed: u64,
+    pub next_execution: u64,
+    pub execution_count: u32,
+}
+
+impl BaselineRule {
+    /// Split into the config and state records of storage version 1
+    pub fn into_records(self) -> (LegacyRuleConfig, RuleState) {
+        let config = LegacyRuleConfig {
+            id: self.id,
+            owner: self.owner,
+            trigger_type: self.trigger_type,
+            schedule: self.schedule,
+            action_type: self.action_type,
+            template_name: self.template_name,
+            recipient: self.recipient,
+            amount: self.amount,
+            token: None,
+            validator: None,
+        };
+        let state = RuleState {
+            status: self.status,
+            last_executed: self.last_executed,
+            next_execution: self.next_execution,
+            execution_count: self.execution_count,
+        };
+        (config, state)
+    }
+}
+
+/// Mutable part of a rule, rewritten on every execution or status change
+#[odra::odra_type]
+pub struct RuleState {
+    pub status: RuleStatus,
+    pub last_executed: u64,
+    pub next_execution: u64,
+    pub execution_count: u32,
+}
+
+/// Complete automation rule, assembled from its config and state records
 #[odra::odra_type]
 pub struct AutomationRule {
     /// Unique identifier for this rule
@@ -80,16 +768,26 @@ pub struct AutomationRule {
     pub status: RuleStatus,
     /// Template name for display purposes
     pub template_name: String,
+    /// ID of the interned template name, as carried by execution events
+    pub template_name_id: u32,
     /// Recipient address (for Transfer action)
     pub recipient: Option<Address>,
-    /// Amount (for Transfer action), or minimum balance condition
+    /// Amount per execution, the floor balance kept by a Sweep, or the cap of a TopUp
     pub amount: U512,
+    /// CEP-18 token to transfer, or `None` for native CSPR
+    pub token: Option<Address>,
+    /// Validator used by staking actions (allowlisted by the staking adapter)
+    pub validator: Option<PublicKey>,
     /// Timestamp of last execution
     pub last_executed: u64,
     /// Timestamp of next scheduled execution
     pub next_execution: u64,
     /// Total number of successful executions
     pub execution_count: u32,
+    /// User-supplied label or memo, opaque to the contract
+    pub metadata: Option<Bytes>,
+    /// Whether the recipient is paid as an account or through a contract call
+    pub recipient_kind: RecipientKind,
 }
 
 impl AutomationRule {
@@ -103,6 +801,7 @@ impl AutomationRule {
         action_type: ActionType,
         recipient: Option<Address>,
         amount: U512,
+        token: Option<Address>,
         next_execution: u64,
     ) -> Self {
         Self {
@@ -113,15 +812,540 @@ impl AutomationRule {
             action_type,
             status: RuleStatus::Active,
             template_name,
+            template_name_id: 0,
             recipient,
             amount,
+            token,
+            validator: None,
             last_executed: 0,
             next_execution,
             execution_count: 0,
+            metadata: None,
+            recipient_kind: RecipientKind::Account,
+        }
+    }
+
+    /// Assemble a rule from its stored config and state records
+    /// 
+    /// The template name is left empty; the engine resolves it from
+    /// `template_name_id` only for views that return it.
+    pub fn from_parts(config: RuleConfig, state: RuleState) -> Self {
+        Self {
+            id: config.id,
+            owner: config.owner,
+            trigger_type: config.trigger_type,
+            schedule: config.schedule,
+            action_type: config.action_type,
+            status: state.status,
+            template_name: String::new(),
+            template_name_id: config.template_name_id,
+            recipient: config.recipient,
+            amount: config.amount,
+            token: config.token,
+            validator: config.validator,
+            last_executed: state.last_executed,
+            next_execution: state.next_execution,
+            execution_count: state.execution_count,
+            metadata: None,
+            recipient_kind: RecipientKind::Account,
+        }
+    }
+
+    /// The immutable config record for this rule
+    pub fn config(&self) -> RuleConfig {
+        RuleConfig {
+            id: self.id,
+            owner: self.owner,
+            trigger_type: self.trigger_type.clone(),
+            schedule: self.schedule.clone(),
+            action_type: self.action_type.clone(),
+            template_name_id: self.template_name_id,
+            recipient: self.recipient,
+            amount: self.amount,
+            token: self.token,
+            validator: self.validator.clone(),
+        }
+    }
+
+    /// The mutable execution-state record for this rule
+    pub fn state(&self) -> RuleState {
+        RuleState {
+            status: self.status.clone(),
+            last_executed: self.last_executed,
+            next_execution: self.next_execution,
+            execution_count: self.execution_count,
         }
     }
 }
 
+/// A named rule template with the parameter schema it accepts
+#[odra::odra_type]
+pub struct RuleTemplate {
+    /// Display name, copied to rules created from the template
+    pub name: String,
+    /// Trigger used by rules created from the template
+    pub trigger_type: TriggerType,
+    /// Schedule used unless the caller overrides it
+    pub default_schedule: Schedule,
+    /// Whether callers may override the default schedule
+    pub allow_custom_schedule: bool,
+    /// Action types a rule created from the template may use
+    pub allowed_action_types: Vec<ActionType>,
+    /// Minimum amount per execution
+    pub min_amount: U512,
+    /// Maximum amount per execution
+    pub max_amount: U512,
+    /// Whether a recipient must be supplied
+    pub requires_recipient: bool,
+    /// Whether the rule may move a CEP-18 token instead of CSPR
+    pub allow_token: bool,
+    /// Disabled templates cannot be used to create new rules
+    pub enabled: bool,
+}
+
+/// User-supplied parameters for creating a rule from a template
+#[odra::odra_type]
+pub struct TemplateParams {
+    /// Overrides the template's default schedule, if allowed
+    pub schedule: Option<Schedule>,
+    pub action_type: ActionType,
+    pub recipient: Option<Address>,
+    pub amount: U512,
+    pub token: Option<Address>,
+    /// Validator for staking actions
+    pub validator: Option<PublicKey>,
+}
+
+/// Parameters of a standard rule, as accepted by `create_rule`
+#[odra::odra_type]
+pub struct RuleParams {
+    pub template_name: String,
+    pub trigger_type: TriggerType,
+    pub schedule: Schedule,
+    pub action_type: ActionType,
+    pub recipient: Option<Address>,
+    pub amount: U512,
+    pub token: Option<Address>,
+    pub validator: Option<PublicKey>,
+    pub metadata: Option<Bytes>,
+    /// When the rule first becomes due (`None` = one interval after creation)
+    pub first_execution: Option<u64>,
+}
+
+/// What a session key may do with its owner's rules, and until when
+#[odra::odra_type]
+pub struct SessionKeyScope {
+    /// May pause and resume rules
+    pub can_pause_resume: bool,
+    /// May trigger Manual rules
+    pub can_trigger_manual: bool,
+    /// Block time from which the key no longer works
+    pub expires_at: u64,
+}
+
+/// Rule creation signed off-chain by the owner for a relayer to submit
+#[odra::odra_type]
+pub struct SignedRuleRequest {
+    pub params: RuleParams,
+    /// Must equal the owner's current signature nonce
+    pub nonce: u64,
+    /// Last block time at which the request may be submitted
+    pub deadline: u64,
+}
+
+/// Fields replaced when cloning a rule; `None` keeps the source rule's value
+#[odra::odra_type]
+pub struct RuleOverrides {
+    pub template_name: Option<String>,
+    pub schedule: Option<Schedule>,
+    pub recipient: Option<Address>,
+    pub amount: Option<U512>,
+    pub metadata: Option<Bytes>,
+    /// When the clone first becomes due; unlike the other fields, `None` means one interval from now
+    pub first_execution: Option<u64>,
+}
+
+/// What a multisig proposal does once approved
+#[odra::odra_type]
+pub enum ProposalKind {
+    /// Withdraw `amount` from the shared vault balance to `target`
+    Withdraw = 0,
+    /// Create the proposal's rule, owned by the multisig account
+    CreateRule = 1,
+    /// Add `target` as a signer
+    AddSigner = 2,
+    /// Remove `target` from the signers
+    RemoveSigner = 3,
+    /// Change the number of approvals required to `threshold`
+    ChangeThreshold = 4,
+}
+
+/// A pending action of a multisig account
+#[odra::odra_type]
+pub struct Proposal {
+    pub kind: ProposalKind,
+    /// Signer who created the proposal
+    pub proposer: Address,
+    /// Withdrawal recipient, or the signer added or removed
+    pub target: Option<Address>,
+    /// Amount withdrawn
+    pub amount: U512,
+    /// New approval threshold
+    pub threshold: u32,
+    /// Rule created by a `CreateRule` proposal
+    pub rule: Option<RuleParams>,
+    /// Number of signers who approved so far
+    pub approvals: u32,
+    /// Whether the proposal has been executed
+    pub executed: bool,
+}
+
+/// Engine-wide activity counters
+#[odra::odra_type]
+pub struct GlobalStats {
+    /// Rules ever created
+    pub total_rules_created: u64,
+    /// Successful rule executions
+    pub total_executions: u64,
+    /// CSPR moved out of vaults by rule executions
+    pub total_volume: U512,
+}
+
+impl Default for GlobalStats {
+    fn default() -> Self {
+        Self {
+            total_rules_created: 0,
+            total_executions: 0,
+            total_volume: U512::zero(),
+        }
+    }
+}
+
+/// Per-user activity aggregates
+#[odra::odra_type]
+pub struct UserStats {
+    /// Rules the user has created
+    pub rules_created: u64,
+    /// Successful executions of the user's rules
+    pub executions: u64,
+    /// CSPR moved out of the user's vault by rule executions
+    pub total_outflow: U512,
+    /// Rewards earned on the user's staked CSPR (read from the staking adapter)
+    pub total_compounded: U512,
+}
+
+impl Default for UserStats {
+    fn default() -> Self {
+        Self {
+            rules_created: 0,
+            executions: 0,
+            total_outflow: U512::zero(),
+            total_compounded: U512::zero(),
+        }
+    }
+}
+
+/// Outcome of executing a rule now, as predicted by `preview_execution`
+#[odra::odra_type]
+pub struct ExecutionPreview {
+    /// Whether the rule is active
+    pub is_active: bool,
+    /// Whether the rule's trigger time has been reached (always true for manual and condition rules)
+    pub is_due: bool,
+    /// Whether the rule's price condition, inactivity period and recipient restrictions pass
+    pub condition_met: bool,
+    /// Whether the owner's vault balance or stake covers the action
+    pub is_funded: bool,
+    /// Amount the execution would move
+    pub amount: U512,
+    /// Protocol fee the execution would charge
+    pub fee: U512,
+    /// Owner's vault balance in the rule's asset before execution
+    pub balance_before: U512,
+    /// Owner's vault balance in the rule's asset after execution
+    pub balance_after: U512,
+    /// Whether executing now would succeed
+    pub would_succeed: bool,
+}
+
+/// The funding check an execution failed at
+#[odra::odra_type]
+pub enum ExecutionStep {
+    /// The owner's CSPR vault balance did not cover the amount and fees
+    VaultBalance = 0,
+    /// The owner's token balance did not cover the amount
+    TokenBalance = 1,
+    /// The owner's stake did not cover the unstake
+    Stake = 2,
+    /// The owner's delegation to the source validator did not cover the redelegation
+    Delegation = 3,
+}
+
+/// Outcome of one rule's execution, as returned by `execute_rule_with_result` and `execute_rules`
+#[odra::odra_type]
+pub struct ExecutionResult {
+    pub rule_id: u64,
+    /// ID of the execution, or zero if the rule did not run
+    pub execution_id: u64,
+    /// Code of the error the rule failed with, or zero
+    pub status_code: u32,
+    /// Check the execution failed at, when it was underfunded
+    pub failed_step: Option<ExecutionStep>,
+    /// How much more the owner needed for the failed check
+    pub shortfall: U512,
+}
+
+/// Everything the dashboard shows for one user, as returned by `get_user_snapshot`
+#[odra::odra_type]
+pub struct UserSnapshot {
+    pub tier: StakingTier,
+    /// CSPR balance in the vault
+    pub vault_balance: U512,
+    /// Part of the vault balance not earmarked for envelopes
+    pub spendable_balance: U512,
+    /// Vault balance of each token that has been allowlisted
+    pub token_balances: Vec<(Address, U256)>,
+    /// All of the user's rules, deleted ones included
+    pub rules: Vec<AutomationRule>,
+    pub stats: UserStats,
+    /// Current value of the user's sCSPR; stake is pooled across the adapter's validators
+    pub staked: U512,
+    /// CSPR the user has staked, excluding rewards
+    pub staking_principal: U512,
+    /// Queued unstakes still unbonding or awaiting completion, by unstake ID
+    pub pending_unstakes: Vec<(u64, PendingUnstake)>,
+}
+
+/// A single entry in a rule's execution history
+#[odra::odra_type]
+pub struct ExecutionRecord {
+    /// Block time of the execution attempt
+    pub timestamp: u64,
+    /// Amount moved by the execution
+    pub amount: U512,
+    /// Recipient of the moved funds, if any
+    pub recipient: Option<Address>,
+    /// 0 on success, otherwise the error code that stopped the execution
+    pub status_code: u32,
+}
+
+/// Breakdown of the CSPR the vault is accountable for
+#[odra::odra_type]
+pub struct VaultAccounting {
+    /// CSPR owed to users: balances, pending withdrawals, streams and vestings
+    pub total_user_deposits: U512,
+    /// Protocol fees accrued and not yet withdrawn
+    pub total_fees: U512,
+    /// Keeper bounties ever paid out of user balances
+    pub total_keeper_bounties: U512,
+    /// CSPR actually held by the vault contract
+    pub contract_balance: U512,
+}
+
+/// Result of comparing the vault's tracked CSPR against what it actually holds
+#[odra::odra_type]
+pub struct VaultReconciliation {
+    /// Running total of CSPR owed: user deposits plus accrued fees
+    pub tracked_total: U512,
+    /// CSPR actually held by the vault contract
+    pub contract_balance: U512,
+    /// CSPR staked out of vault balances and not yet returned by the adapter
+    pub delegated: U512,
+    /// CSPR held beyond what is tracked (e.g. direct transfers)
+    pub surplus: U512,
+    /// CSPR tracked but not held; anything non-zero means accounting drift
+    pub deficit: U512,
+}
+
+/// Protocol fee configuration charged on automated transfers
+#[odra::odra_type]
+pub struct FeeConfig {
+    /// Fee in basis points of the transferred amount
+    pub fee_bps: u16,
+    /// Flat fee in motes charged per execution
+    pub flat_fee: U512,
+}
+
+impl Default for FeeConfig {
+    fn default() -> Self {
+        Self {
+            fee_bps: 0,
+            flat_fee: U512::zero(),
+        }
+    }
+}
+
+impl FeeConfig {
+    /// Maximum fee in basis points (10%)
+    pub const MAX_FEE_BPS: u16 = 1_000;
+
+    /// Compute the fee owed on a transfer of `amount`
+    pub fn fee_for(&self, amount: U512) -> U512 {
+        amount * U512::from(self.fee_bps) / U512::from(10_000u64) + self.flat_fee
+    }
+
+    /// This config with both fee components reduced by `discount_bps`
+    pub fn discounted(&self, discount_bps: u16) -> FeeConfig {
+        let keep = 10_000u64.saturating_sub(discount_bps as u64);
+        FeeConfig {
+            fee_bps: (self.fee_bps as u64 * keep / 10_000) as u16,
+            flat_fee: self.flat_fee * U512::from(keep) / U512::from(10_000u64),
+        }
+    }
+
+    /// Largest amount that can be transferred, fee included, out of `total`
+    pub fn max_amount_within(&self, total: U512) -> U512 {
+        if total <= self.flat_fee {
+            return U512::zero();
+        }
+        (total - self.flat_fee) * U512::from(10_000u64)
+            / U512::from(10_000u64 + self.fee_bps as u64)
+    }
+}
+
+/// Period lengths and delays the engine schedules with, in seconds
+/// 
+/// Mainnet uses real durations (the default); testnet deployments can
+/// compress them to exercise monthly rules in minutes.
+#[odra::odra_type]
+pub struct TimeConfig {
+    /// Interval of `Schedule::Daily` rules
+    pub day: u64,
+    /// Interval of `Schedule::Weekly` rules
+    pub week: u64,
+    /// Interval of `Schedule::Monthly` rules
+    pub month: u64,
+    /// Delay before a high-value rule can be confirmed
+    pub confirmation_delay: u64,
+    /// Delay before a new address book entry takes effect
+    pub address_book_delay: u64,
+}
+
+impl Default for TimeConfig {
+    fn default() -> Self {
+        Self {
+            day: 86_400,
+            week: 604_800,
+            month: 2_592_000,
+            confirmation_delay: 86_400,
+            address_book_delay: 86_400,
+        }
+    }
+}
+
+impl TimeConfig {
+    /// Whether every period is non-zero and no longer than the next one
+    pub fn is_valid(&self) -> bool {
+        self.day > 0 && self.day <= self.week && self.week <= self.month
+    }
+
+    /// Seconds between two executions of `schedule` (zero for `Once`)
+    pub fn interval(&self, schedule: &Schedule) -> u64 {
+        match schedule {
+            Schedule::Daily => self.day,
+            Schedule::Weekly => self.week,
+            Schedule::Monthly => self.month,
+            Schedule::Once => 0,
+        }
+    }
+}
+
+/// Perks granted to holders of a membership NFT collection
+#[odra::odra_type]
+pub struct NftPerks {
+    /// CEP-78 collection whose holders get the perks
+    pub collection: Address,
+    /// Minimum tier holders are treated as
+    pub tier: StakingTier,
+    /// Discount on protocol fees in basis points
+    pub fee_discount_bps: u16,
+}
+
+/// A user's effective tier and fee perks
+#[odra::odra_type]
+pub struct UserPerks {
+    pub tier: StakingTier,
+    /// Whether the user holds the configured membership NFT
+    pub nft_holder: bool,
+    /// Whether the user's tier is exempt from protocol fees
+    pub fee_exempt: bool,
+    /// Discount on protocol fees in basis points (0 when exempt or not a holder)
+    pub fee_discount_bps: u16,
+}
+
+/// Contracts whose canonical address the contract registry stores
+#[odra::odra_type]
+pub enum ContractKind {
+    Vault = 0,
+    Engine = 1,
+    StakingAdapter = 2,
+    SwapAdapter = 3,
+    PriceOracle = 4,
+    Treasury = 5,
+    Multisig = 6,
+    Registry = 7,
+    AttestationOracle = 8,
+}
+
+/// Roles the owner (a contract's admin) can delegate admin functions through
+/// 
+/// The owner holds every role implicitly and alone can grant and revoke them.
+#[odra::odra_type]
+pub enum Role {
+    /// The contract's admin; cannot be granted
+    Owner = 0,
+    /// Day-to-day configuration: limits, templates, allowlists and blocklists
+    Operator = 1,
+    /// Pausing and unpausing rule execution
+    Pauser = 2,
+    /// Fee settings and fee withdrawals
+    FeeManager = 3,
+}
+
+/// Sensitive admin changes that go through the timelock once it is enabled
+#[odra::odra_type]
+pub enum AdminActionKind {
+    /// Change the engine's protocol fee to `fee_bps` and `flat_fee`
+    SetFeeConfig = 0,
+    /// Point the engine at the price oracle `target`
+    SetPriceOracle = 1,
+    /// Authorize `target` as the vault's automation engine
+    SetAutomationEngine = 2,
+    /// Change the timelock's own delay to `delay`
+    SetTimelockDelay = 3,
+    /// Point the engine at the attestation oracle `target`
+    SetAttestationOracle = 4,
+    /// Point the contract at the registry `target`, or detach it with `None`
+    SetRegistry = 5,
+    /// Point the contract at the staking adapter `target`
+    SetStakingAdapter = 6,
+    /// Point the engine at the vault `target`
+    SetVaultAddress = 7,
+}
+
+/// An admin change waiting in the timelock
+#[odra::odra_type]
+pub struct AdminAction {
+    pub kind: AdminActionKind,
+    /// New oracle or engine address
+    pub target: Option<Address>,
+    /// New fee in basis points
+    pub fee_bps: u16,
+    /// New flat fee in motes
+    pub flat_fee: U512,
+    /// New timelock delay in seconds
+    pub delay: u64,
+    /// Earliest time the action can be executed
+    pub eta: u64,
+}
+
+/// Which contract an address holds and the version of its entrypoint interface
+#[odra::odra_type]
+pub struct ContractVersion {
+    pub kind: ContractKind,
+    pub version: u32,
+}
+
 /// User tier based on sCSPR holdings
 #[odra::odra_type]
 pub enum StakingTier {
@@ -5,17 +5,75 @@
 //! can execute transfers on their behalf.
 
 use odra::prelude::*;
-use odra::casper_types::U512;
+use odra::casper_types::{PublicKey, RuntimeArgs, U256, U512};
+use odra::{CallDef, ContractRef};
 
+use crate::access_control::AccessControl;
+use crate::automation_engine::AutomationEngineContractRef;
+use crate::cep18::{from_token_amount, to_token_amount, Cep18TokenContractRef};
+use crate::staking_adapter::StakingAdapterContractRef;
+use crate::swap_adapter::SwapAdapterContractRef;
 use crate::errors::Error;
-use crate::events::{Deposited, Withdrawn, AutomationExecuted};
+use crate::event_nonce::EventNonce;
+use crate::reentrancy_guard::ReentrancyGuard;
+use crate::registry::ContractRegistryContractRef;
+use crate::safe_math::{checked_add, checked_sub};
+use crate::timelock::Timelock;
+use crate::events::{
+    Deposited, DepositedFor, Withdrawn, AutomationExecuted, TokenDeposited, TokenWithdrawn,
+    TokenAutomationExecuted, TokenAllowlistUpdated, StreamCreated, StreamClaimed,
+    StreamCancelled, VestingCreated, VestingClaimed, VestingRevoked, FeeCharged,
+    FeesDistributed, VaultStaked, UnstakeProceedsReceived, WithdrawalDelayUpdated,
+    WithdrawalRequested, WithdrawalCancelled, WithdrawalCompleted, KeeperPaid, FeesWithdrawn,
+    VaultSwapped, EnvelopeUpdated, EscrowFunded, EscrowClaimed, EscrowCancelled,
+    AutoStakeConfigured, AutoStaked, AutoStakeUnwound, UnstakeWithdrawalQueued,
+    UnstakeWithdrawalFulfilled, ReservationUpdated, RoleGranted, RoleRevoked, AdminActionQueued,
+    AdminActionExecuted, AdminActionCancelled, InternalTransfer, DepositRoutingConfigured,
+    DepositRouted, ClaimableTransferHeld, TransfersClaimed, ClaimableTransferRefunded,
+    ContractRecipientAllowlistUpdated,
+};
+use crate::types::{
+    AdminAction, AdminActionKind, AutoStakeConfig, ClaimStatus, ClaimableTransfer, ContractKind,
+    ContractRecipient, ContractVersion, DepositRouting, EscrowPayment, EscrowStatus, FeeConfig,
+    PaymentStream, Role, SwapDirection, VaultAccounting, VaultReconciliation, VestingSchedule,
+    WithdrawalRequest,
+};
+
+/// Seconds in a day (the window for outflow limits)
+const SECONDS_PER_DAY: u64 = 86_400;
+/// Maximum number of envelopes per owner
+const MAX_ENVELOPES: usize = 16;
+/// Maximum length of an envelope name in bytes
+const MAX_ENVELOPE_NAME_LEN: usize = 32;
+/// Most claimable transfers one `claim` call settles or one page of IDs scans
+pub const MAX_CLAIM_BATCH: u32 = 50;
+/// Entrypoint interface version reported by `get_version`; bump on breaking changes
+/// 
+/// The engine checks it against the same constant, so both move together.
+/// Version 2 added the execution ID to the engine's payment entrypoints and
+/// version 3 added `hold_claimable`, version 4 the minimum output of
+/// `execute_swap` and version 5 `get_last_activity`.
+pub const INTERFACE_VERSION: u32 = 5;
 
 /// The Automation Vault contract
 /// 
 /// Each user has a dedicated vault balance. The vault holds CSPR tokens
-/// that can be used by automation rules to execute transfers.
+/// and allowlisted CEP-18 tokens that can be used by automation rules
+/// to execute transfers.
 #[odra::module(
-    events = [Deposited, Withdrawn, AutomationExecuted],
+    events = [
+        Deposited, DepositedFor, Withdrawn, AutomationExecuted, TokenDeposited, TokenWithdrawn,
+        TokenAutomationExecuted, TokenAllowlistUpdated, StreamCreated, StreamClaimed,
+        StreamCancelled, VestingCreated, VestingClaimed, VestingRevoked, FeeCharged,
+        FeesDistributed, VaultStaked, UnstakeProceedsReceived, WithdrawalDelayUpdated,
+        WithdrawalRequested, WithdrawalCancelled, WithdrawalCompleted, KeeperPaid, FeesWithdrawn,
+        VaultSwapped, EnvelopeUpdated, EscrowFunded, EscrowClaimed, EscrowCancelled,
+        AutoStakeConfigured, AutoStaked, AutoStakeUnwound, UnstakeWithdrawalQueued,
+        UnstakeWithdrawalFulfilled, ReservationUpdated, RoleGranted, RoleRevoked,
+        AdminActionQueued, AdminActionExecuted, AdminActionCancelled, InternalTransfer,
+        DepositRoutingConfigured, DepositRouted, ClaimableTransferHeld, TransfersClaimed,
+        ClaimableTransferRefunded, ContractRecipientAllowlistUpdated
+    ],
     errors = Error
 )]
 pub struct AutomationVault {
@@ -23,6 +81,95 @@ pub struct AutomationVault {
     balances: Mapping<Address, U512>,
     /// The automation engine contract authorized to execute transfers
     authorized_engine: Var<Option<Address>>,
+    /// The admin allowed to manage the token allowlist
+    admin: Var<Address>,
+    /// Mapping of (owner, token) to the owner's CEP-18 balance in the vault
+    token_balances: Mapping<(Address, Address), U256>,
+    /// CEP-18 tokens that may be deposited into the vault
+    allowed_tokens: Mapping<Address, bool>,
+    /// Every token that has been allowlisted, in the order first listed
+    listed_tokens: Var<Vec<Address>>,
+    /// Mapping of stream ID (the engine rule ID) to payment stream
+    streams: Mapping<u64, PaymentStream>,
+    /// Mapping of vesting ID (the engine rule ID) to vesting schedule
+    vestings: Mapping<u64, VestingSchedule>,
+    /// Treasury that receives protocol fees
+    treasury: Var<Option<Address>>,
+    /// Protocol fees collected but not yet paid out to the treasury
+    accrued_fees: Var<U512>,
+    /// Total protocol fees ever collected
+    total_fees_collected: Var<U512>,
+    /// CSPR owed to users, kept separate from fees so fee payouts cannot touch it
+    total_user_deposits: Var<U512>,
+    /// Total keeper bounties ever paid out of user balances
+    total_keeper_bounties: Var<U512>,
+    /// CSPR staked out of vault balances and not yet returned by the adapter
+    total_delegated: Var<U512>,
+    /// Staking adapter that receives CSPR staked from the vault
+    staking_adapter: Var<Option<Address>>,
+    /// Swap adapter that converts between CSPR and CEP-18 balances
+    swap_adapter: Var<Option<Address>>,
+    /// Mapping of owner to their withdrawal delay in seconds (zero for instant withdrawals)
+    withdrawal_delays: Mapping<Address, u64>,
+    /// Mapping of owner to a lowered delay and the time it takes effect
+    pending_delay_changes: Mapping<Address, (u64, u64)>,
+    /// Counter for generating unique withdrawal request IDs
+    next_withdrawal_id: Var<u64>,
+    /// Mapping of withdrawal request ID to request
+    withdrawal_requests: Mapping<u64, WithdrawalRequest>,
+    /// Most CSPR the engine may move out of one owner's vault per day (zero for no limit)
+    max_daily_outflow: Var<U512>,
+    /// Mapping of owner to the current day and the CSPR the engine has moved out during it
+    daily_outflow: Mapping<Address, (u64, U512)>,
+    /// Mapping of (owner, envelope name) to the part of their CSPR balance earmarked for it
+    envelopes: Mapping<(Address, String), U512>,
+    /// Mapping of owner to the names of their envelopes
+    envelope_names: Mapping<Address, Vec<String>>,
+    /// Mapping of owner to the sum of their envelope balances
+    envelope_totals: Mapping<Address, U512>,
+    /// Mapping of rule ID to the part of its owner's balance reserved for its next execution
+    reservations: Mapping<u64, U512>,
+    /// Mapping of owner to the sum of their rules' reservations
+    reserved_totals: Mapping<Address, U512>,
+    /// Contract registry whose entries override the addresses set here
+    registry: Var<Option<Address>>,
+    /// Counter for generating unique escrowed payment IDs
+    next_escrow_id: Var<u64>,
+    /// Mapping of escrow ID to escrowed payment
+    escrows: Mapping<u64, EscrowPayment>,
+    /// Mapping of engine rule ID to the escrowed payments it funded
+    rule_escrows: Mapping<u64, Vec<u64>>,
+    /// Mapping of owner to their auto-staking settings (None = opted out)
+    auto_stake_configs: Mapping<Address, Option<AutoStakeConfig>>,
+    /// Mapping of owner to the CSPR auto-staked from their balance and not yet unstaked
+    custodial_stakes: Mapping<Address, U512>,
+    /// Mapping of owner to unstake proceeds owed straight to their account on arrival
+    unstake_withdrawals: Mapping<Address, U512>,
+    /// Mapping of owner to how their deposits are split (None = kept liquid)
+    deposit_routings: Mapping<Address, Option<DepositRouting>>,
+    /// Lock held by payout entrypoints while they run
+    reentrancy_guard: SubModule<ReentrancyGuard>,
+    /// Admin functions delegated to operator and fee manager keys
+    roles: SubModule<AccessControl>,
+    /// Engine changes waiting out the admin delay
+    timelock: SubModule<Timelock>,
+    /// Sequence number stamped on emitted events
+    event_nonce: SubModule<EventNonce>,
+    // Fields are keyed by position: add new ones below, never above
+    /// Counter for generating unique claimable transfer IDs
+    next_claimable_id: Var<u64>,
+    /// Mapping of claimable transfer ID to the transfer
+    claimable_transfers: Mapping<u64, ClaimableTransfer>,
+    /// Mapping of (recipient, per-recipient index) to a transfer held for them
+    recipient_claim_ids: Mapping<(Address, u32), u64>,
+    /// Mapping of recipient to how many transfers have been held for them
+    recipient_claim_counts: Mapping<Address, u32>,
+    /// Mapping of recipient to the total of their transfers still held
+    claimable_balances: Mapping<Address, U512>,
+    /// (contract, entrypoint) pairs rules may pay into
+    allowed_contract_recipients: Mapping<(Address, String), bool>,
+    /// Mapping of owner to the block time of their last deposit or withdrawal
+    last_activity: Mapping<Address, u64>,
 }
 
 #[odra::module]
@@ -31,14 +178,45 @@ impl AutomationVault {
     /// The engine address can be set later if not known at deploy time
     pub fn init(&mut self, automation_engine: Option<Address>) {
         self.authorized_engine.set(automation_engine);
+        self.admin.set(self.env().caller());
+    }
+
+    /// Adopt the first released vault when its code is upgraded
+    /// 
+    /// That vault kept only per-user balances, so all the CSPR it holds is
+    /// owed to users: the running total of user deposits is seeded from it
+    /// so `reconcile` and the solvency checks start out balanced. The
+    /// account installing the upgrade becomes admin. Vaults that already
+    /// have an admin are left as they are.
+    pub fn upgrade(&mut self) {
+        if self.admin.get().is_some() {
+            return;
+        }
+        self.admin.set(self.env().caller());
+        self.total_user_deposits.set(self.env().self_balance());
     }
 
     /// Deposit CSPR into the caller's vault
     /// 
     /// This is a payable function - attach CSPR when calling.
+    /// If the caller has set a deposit routing, the deposit is split
+    /// between stake, savings and liquid funds accordingly.
     #[odra(payable)]
     pub fn deposit(&mut self) {
         let caller = self.env().caller();
+        self.record_activity(caller);
+        let amount = self.env().attached_value();
+        self.credit_deposit(caller, amount);
+        self.route_deposit(caller, amount);
+    }
+
+    /// Deposit CSPR into another user's vault
+    /// 
+    /// Lets employers, DAOs or faucets fund a user's automations directly.
+    /// This is a payable function - attach CSPR when calling.
+    #[odra(payable)]
+    pub fn deposit_for(&mut self, owner: Address) {
+        let funder = self.env().caller();
         let amount = self.env().attached_value();
         
         if amount.is_zero() {
@@ -46,162 +224,2619 @@ impl AutomationVault {
         }
         
         // Update balance
-        let current_balance = self.balances.get_or_default(&caller);
-        let new_balance = current_balance + amount;
-        self.balances.set(&caller, new_balance);
+        let new_balance = checked_add(&self.env(), self.balances.get_or_default(&owner), amount);
+        self.balances.set(&owner, new_balance);
+        self.credit_user_deposits(amount);
+        self.check_invariants();
         
         // Emit event
-        self.env().emit_event(Deposited {
-            owner: caller,
+        self.env().emit_event(DepositedFor {
+            funder,
+            owner,
             amount,
             new_balance,
+            nonce: self.event_nonce.next(),
         });
     }
 
-    /// Withdraw CSPR from the caller's vault to their account
-    pub fn withdraw(&mut self, amount: U512) {
+    /// Move part of the caller's vault balance to another user's vault
+    /// 
+    /// The CSPR never leaves the contract, so this is cheaper than a
+    /// withdrawal and the recipient can spend it at once. Like a withdrawal,
+    /// it cannot touch funds reserved for rules or earmarked for envelopes,
+    /// and is unavailable while the caller's withdrawals are timelocked.
+    pub fn transfer_internal(&mut self, to: Address, amount: U512) {
         let caller = self.env().caller();
-        
+        self.record_activity(caller);
+        self.assert_no_withdrawal_delay(caller);
         if amount.is_zero() {
             self.env().revert(Error::ZeroAmount);
         }
-        
-        // Check balance
         let current_balance = self.balances.get_or_default(&caller);
         if current_balance < amount {
             self.env().revert(Error::InsufficientBalance);
         }
+        self.assert_spendable(caller, amount);
+        self.assert_unreserved(caller, amount);
         
-        // Update balance
-        let new_balance = current_balance - amount;
-        self.balances.set(&caller, new_balance);
-        
-        // Transfer to caller
-        self.env().transfer_tokens(&caller, &amount);
+        self.balances.set(&caller, checked_sub(&self.env(), current_balance, amount));
+        let to_balance = self.balances.get_or_default(&to);
+        self.balances.set(&to, checked_add(&self.env(), to_balance, amount));
+        self.check_invariants();
         
-        // Emit event
-        self.env().emit_event(Withdrawn {
-            owner: caller,
+        self.env().emit_event(InternalTransfer {
+            from: caller,
+            to,
             amount,
-            new_balance,
+            nonce: self.event_nonce.next(),
         });
     }
 
+    /// Withdraw CSPR from the caller's vault to their account
+    /// 
+    /// Funds reserved for the caller's upcoming rule executions are left
+    /// alone. If the rest of the spendable balance falls short and the
+    /// caller's auto-staked funds cover the difference, the liquid part is
+    /// paid out now and the shortfall is unstaked; its proceeds return to
+    /// the vault balance once unbonded.
+    pub fn withdraw(&mut self, amount: U512) {
+        self.reentrancy_guard.enter();
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        self.assert_no_withdrawal_delay(caller);
+        
+        if amount.is_zero() {
+            self.env().revert(Error::ZeroAmount);
+        }
+        
+        // Unstake any shortfall auto-staked funds can cover and pay out the rest now
+        let shortfall = amount.saturating_sub(self.get_withdrawable_balance(caller));
+        let covered = shortfall <= self.custodial_stakes.get_or_default(&caller);
+        let amount = if !shortfall.is_zero() && covered {
+            self.unwind_auto_stake(caller, shortfall);
+            amount - shortfall
+        } else {
+            amount
+        };
+        if !amount.is_zero() {
+            self.pay_withdrawal(caller, amount);
+        }
+        self.check_invariants();
+        self.reentrancy_guard.exit();
+    }
+
+    /// Withdraw CSPR, unstaking whatever the caller's vault balance cannot cover
+    /// 
+    /// The spendable balance not reserved for rules is paid out now. The
+    /// rest is undelegated from the caller's stake in the staking adapter,
+    /// and its proceeds are paid straight to the caller's account instead of
+    /// the vault balance once they unbond. Returns the pending unstake ID, if one was queued.
+    pub fn withdraw_with_unstake(&mut self, amount: U512) -> Option<u64> {
+        self.reentrancy_guard.enter();
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        self.assert_no_withdrawal_delay(caller);
+        
+        if amount.is_zero() {
+            self.env().revert(Error::ZeroAmount);
+        }
+        
+        let liquid = amount.min(self.get_withdrawable_balance(caller));
+        let shortfall = amount - liquid;
+        let unstake_id = if shortfall.is_zero() {
+            None
+        } else {
+            let adapter = match self.resolved_staking_adapter() {
+                Some(adapter) => adapter,
+                None => self.env().revert(Error::StakingAdapterNotSet),
+            };
+            let unstake_id = StakingAdapterContractRef::new(self.env(), adapter)
+                .queue_vault_unstake(caller, shortfall);
+            
+            // Auto-staked funds are unstaked first
+            let custodial_stake = self.custodial_stakes.get_or_default(&caller);
+            self.custodial_stakes.set(&caller, custodial_stake.saturating_sub(shortfall));
+            let owed = self.unstake_withdrawals.get_or_default(&caller);
+            self.unstake_withdrawals.set(&caller, checked_add(&self.env(), owed, shortfall));
+            
+            self.env().emit_event(UnstakeWithdrawalQueued {
+                owner: caller,
+                amount: shortfall,
+                unstake_id,
+                nonce: self.event_nonce.next(),
+            });
+            Some(unstake_id)
+        };
+        
+        if !liquid.is_zero() {
+            self.pay_withdrawal(caller, liquid);
+        }
+        self.check_invariants();
+        self.reentrancy_guard.exit();
+        unstake_id
+    }
+
     /// Execute a transfer from a user's vault (called by automation engine)
     /// 
     /// This function can only be called by the authorized automation engine.
-    /// It transfers funds from the owner's vault to the specified recipient.
+    /// It transfers `amount` from the owner's vault to the specified recipient
+    /// and moves `fee` from the owner's vault into the protocol fee pool.
     pub fn execute_transfer(
         &mut self,
         owner: Address,
         recipient: Address,
         amount: U512,
+        fee: U512,
         rule_id: u64,
+        execution_id: u64,
     ) {
-        let caller = self.env().caller();
-        
+        self.reentrancy_guard.enter();
         // Verify caller is the authorized automation engine
-        let authorized = self.authorized_engine.get_or_default();
-        match authorized {
-            Some(engine_addr) => {
-                if caller != engine_addr {
-                    self.env().revert(Error::UnauthorizedExecutor);
-                }
-            }
-            None => {
-                self.env().revert(Error::UnauthorizedExecutor);
-            }
+        self.assert_authorized_engine();
+        self.transfer_out(owner, recipient, amount, fee, rule_id, execution_id, None);
+        self.check_invariants();
+        self.reentrancy_guard.exit();
+    }
+
+    /// Pay from a user's vault into a contract (called by automation engine)
+    /// 
+    /// Like `execute_transfer`, but for recipients that cannot take a plain
+    /// transfer: `amount` is attached to a call to `deposit.entry_point` on
+    /// the recipient instead, e.g. another vault's `deposit_for`.
+    pub fn execute_contract_transfer(
+        &mut self,
+        owner: Address,
+        recipient: Address,
+        deposit: ContractRecipient,
+        amount: U512,
+        fee: U512,
+        rule_id: u64,
+        execution_id: u64,
+    ) {
+        self.reentrancy_guard.enter();
+        self.assert_authorized_engine();
+        self.transfer_out(owner, recipient, amount, fee, rule_id, execution_id, Some(deposit));
+        self.check_invariants();
+        self.reentrancy_guard.exit();
+    }
+
+    /// Transfer everything above `floor` from an owner's vault, fee included
+    /// 
+    /// Can only be called by the authorized automation engine. The amount is
+    /// computed from the balance at execution time so that the transfer plus
+    /// its fee under `fee_config` leaves exactly `floor` (up to rounding).
+    /// Funds earmarked for envelopes or reserved for other rules are left alone.
+    /// Returns the amount transferred, which is zero if nothing is above the floor.
+    pub fn execute_sweep(
+        &mut self,
+        owner: Address,
+        recipient: Address,
+        floor: U512,
+        fee_config: FeeConfig,
+        rule_id: u64,
+        execution_id: u64,
+    ) -> U512 {
+        self.reentrancy_guard.enter();
+        self.assert_authorized_engine();
+        
+        let balance = self.get_withdrawable_balance(owner);
+        let amount = fee_config.max_amount_within(balance.saturating_sub(floor));
+        if !amount.is_zero() {
+            let fee = fee_config.fee_for(amount);
+            self.transfer_out(owner, recipient, amount, fee, rule_id, execution_id, None);
         }
+        self.check_invariants();
+        self.reentrancy_guard.exit();
+        amount
+    }
+
+    /// Move CSPR from one owner's vault balance to another's
+    /// 
+    /// Can only be called by the authorized automation engine. The funds
+    /// stay in the vault and are credited to `recipient`, who can withdraw
+    /// them like any other deposit.
+    pub fn execute_top_up(
+        &mut self,
+        owner: Address,
+        recipient: Address,
+        amount: U512,
+        fee: U512,
+        rule_id: u64,
+        execution_id: u64,
+    ) {
+        self.assert_authorized_engine();
         
-        // Check balance
+        let total = checked_add(&self.env(), amount, fee);
         let current_balance = self.balances.get_or_default(&owner);
-        if current_balance < amount {
+        if current_balance < total {
             self.env().revert(Error::InsufficientBalance);
         }
-        
-        // Update balance
-        let new_balance = current_balance - amount;
+        self.assert_spendable(owner, total);
+        self.record_outflow(owner, total);
+        let new_balance = checked_sub(&self.env(), current_balance, total);
         self.balances.set(&owner, new_balance);
         
-        // Transfer to recipient
-        self.env().transfer_tokens(&recipient, &amount);
+        let recipient_balance = self.balances.get_or_default(&recipient);
+        self.balances.set(&recipient, checked_add(&self.env(), recipient_balance, amount));
+        
+        // Only the fee leaves the users' side of the books
+        self.debit_user_deposits(fee);
         
-        // Emit event
         self.env().emit_event(AutomationExecuted {
             owner,
             rule_id,
+            execution_id,
             recipient,
             amount,
+            fee,
+            balance_before: current_balance,
+            balance_after: new_balance,
+            nonce: self.event_nonce.next(),
         });
+        
+        self.accrue_fee(owner, rule_id, fee);
     }
 
-    /// Set the authorized automation engine address
-    /// This should only be callable once or by an admin in production
-    pub fn set_automation_engine(&mut self, engine: Address) {
-        // For MVP, anyone can set this. In production, add access control.
-        self.authorized_engine.set(Some(engine));
+    /// Pay a keeper the bounty for executing one of `owner`'s rules
+    /// 
+    /// Can only be called by the authorized automation engine. The bounty
+    /// comes out of the owner's vault balance and counts toward their daily
+    /// outflow.
+    pub fn pay_keeper(&mut self, owner: Address, keeper: Address, amount: U512, rule_id: u64) {
+        self.reentrancy_guard.enter();
+        self.assert_authorized_engine();
+        
+        let current_balance = self.balances.get_or_default(&owner);
+        if current_balance < amount {
+            self.env().revert(Error::InsufficientBalance);
+        }
+        self.assert_spendable(owner, amount);
+        self.record_outflow(owner, amount);
+        self.balances.set(&owner, checked_sub(&self.env(), current_balance, amount));
+        self.debit_user_deposits(amount);
+        let bounties = checked_add(&self.env(), self.total_keeper_bounties.get_or_default(), amount);
+        self.total_keeper_bounties.set(bounties);
+        
+        self.env().transfer_tokens(&keeper, &amount);
+        
+        self.env().emit_event(KeeperPaid {
+            rule_id,
+            owner,
+            keeper,
+            amount,
+            nonce: self.event_nonce.next(),
+        });
+        self.check_invariants();
+        self.reentrancy_guard.exit();
     }
 
-    // ========================================================================
-    // View Functions
-    // ========================================================================
+    /// Swap between an owner's CSPR and CEP-18 vault balances (called by automation engine)
+    /// 
+    /// Buying `token` spends `amount` motes plus `fee` from the CSPR balance;
+    /// selling spends `amount` token units and carries no protocol fee. The
+    /// proceeds are measured as the change in the vault's own holdings and
+    /// credited to `owner`; the swap reverts if they fall short of `min_out`,
+    /// in the output's units, whatever the adapter itself checked. Returns
+    /// the amount received.
+    pub fn execute_swap(
+        &mut self,
+        owner: Address,
+        token: Address,
+        direction: SwapDirection,
+        amount: U512,
+        fee: U512,
+        max_slippage_bps: u16,
+        min_out: U512,
+        rule_id: u64,
+    ) -> U512 {
+        self.reentrancy_guard.enter();
+        self.assert_authorized_engine();
+        
+        if amount.is_zero() {
+            self.env().revert(Error::ZeroAmount);
+        }
+        if !self.allowed_tokens.get_or_default(&token) {
+            self.env().revert(Error::TokenNotAllowed);
+        }
+        let adapter = match self.resolved_swap_adapter() {
+            Some(adapter) => adapter,
+            None => self.env().revert(Error::SwapAdapterNotSet),
+        };
+        let mut swap_adapter = SwapAdapterContractRef::new(self.env(), adapter);
+        let mut token_contract = Cep18TokenContractRef::new(self.env(), token);
+        let vault_address = self.env().self_address();
+        
+        let amount_out = match direction {
+            SwapDirection::CsprToToken => {
+                let total = checked_add(&self.env(), amount, fee);
+                let current_balance = self.balances.get_or_default(&owner);
+                if current_balance < total {
+                    self.env().revert(Error::InsufficientBalance);
+                }
+                self.assert_spendable(owner, total);
+                self.record_outflow(owner, total);
+                self.balances.set(&owner, checked_sub(&self.env(), current_balance, total));
+                self.debit_user_deposits(total);
+                
+                let min_tokens = match to_token_amount(min_out) {
+                    Some(min_tokens) => min_tokens,
+                    None => self.env().revert(Error::SlippageExceeded),
+                };
+                let held_before = token_contract.balance_of(&vault_address);
+                swap_adapter
+                    .with_tokens(amount)
+                    .swap_cspr_for_tokens(token, max_slippage_bps, min_tokens, vault_address);
+                let received = checked_sub(
+                    &self.env(),
+                    token_contract.balance_of(&vault_address),
+                    held_before,
+                );
+                if received < min_tokens {
+                    self.env().revert(Error::SlippageExceeded);
+                }
+                let token_balance = self.token_balances.get_or_default(&(owner, token));
+                let new_token_balance = checked_add(&self.env(), token_balance, received);
+                self.token_balances.set(&(owner, token), new_token_balance);
+                
+                self.accrue_fee(owner, rule_id, fee);
+                from_token_amount(received)
+            }
+            SwapDirection::TokenToCspr => {
+                let amount_in = match to_token_amount(amount) {
+                    Some(amount_in) => amount_in,
+                    None => self.env().revert(Error::InsufficientBalance),
+                };
+                let token_balance = self.token_balances.get_or_default(&(owner, token));
+                if token_balance < amount_in {
+                    self.env().revert(Error::InsufficientBalance);
+                }
+                let new_token_balance = checked_sub(&self.env(), token_balance, amount_in);
+                self.token_balances.set(&(owner, token), new_token_balance);
+                
+                // The adapter trades tokens it holds, so hand them over first
+                token_contract.transfer(&adapter, &amount_in);
+                let held_before = self.env().self_balance();
+                swap_adapter.swap_tokens_for_cspr(
+                    token,
+                    amount_in,
+                    max_slippage_bps,
+                    min_out,
+                    vault_address,
+                );
+                let received = checked_sub(&self.env(), self.env().self_balance(), held_before);
+                if received < min_out {
+                    self.env().revert(Error::SlippageExceeded);
+                }
+                let current_balance = self.balances.get_or_default(&owner);
+                self.balances.set(&owner, checked_add(&self.env(), current_balance, received));
+                self.credit_user_deposits(received);
+                received
+            }
+        };
+        
+        self.env().emit_event(VaultSwapped {
+            owner,
+            rule_id,
+            token,
+            direction,
+            amount_in: amount,
+            amount_out,
+            nonce: self.event_nonce.next(),
+        });
+        self.check_invariants();
+        self.reentrancy_guard.exit();
+        amount_out
+    }
 
-    /// Get the vault balance for a specific address
-    pub fn get_balance(&self, owner: Address) -> U512 {
-        self.balances.get_or_default(&owner)
+    /// Stake CSPR from an owner's vault balance through the staking adapter
+    /// 
+    /// Can only be called by the authorized automation engine. The staked
+    /// CSPR leaves the vault and the resulting sCSPR is credited to `owner`.
+    pub fn execute_stake(
+        &mut self,
+        owner: Address,
+        validator: PublicKey,
+        amount: U512,
+        fee: U512,
+        rule_id: u64,
+    ) {
+        self.reentrancy_guard.enter();
+        self.assert_authorized_engine();
+        
+        let adapter = match self.resolved_staking_adapter() {
+            Some(adapter) => adapter,
+            None => self.env().revert(Error::StakingAdapterNotSet),
+        };
+        
+        // Check balance
+        let total = checked_add(&self.env(), amount, fee);
+        let current_balance = self.balances.get_or_default(&owner);
+        if current_balance < total {
+            self.env().revert(Error::InsufficientBalance);
+        }
+        self.assert_spendable(owner, total);
+        self.record_outflow(owner, total);
+        self.balances.set(&owner, checked_sub(&self.env(), current_balance, total));
+        self.debit_user_deposits(total);
+        
+        let delegated = checked_add(&self.env(), self.total_delegated.get_or_default(), amount);
+        self.total_delegated.set(delegated);
+        
+        // Release the funds directly to the adapter
+        StakingAdapterContractRef::new(self.env(), adapter)
+            .with_tokens(amount)
+            .stake_for(owner, validator.clone());
+        
+        self.env().emit_event(VaultStaked {
+            owner,
+            rule_id,
+            validator,
+            amount,
+            nonce: self.event_nonce.next(),
+        });
+        
+        self.accrue_fee(owner, rule_id, fee);
+        self.check_invariants();
+        self.reentrancy_guard.exit();
     }
 
-    /// Get the authorized automation engine address
-    pub fn get_automation_engine(&self) -> Option<Address> {
-        self.authorized_engine.get_or_default()
+    /// Opt into staking the caller's idle balance above `config.floor`, or out with None
+    /// 
+    /// The automation engine stakes the excess through the staking adapter
+    /// under the caller's account. Withdrawals the liquid balance cannot
+    /// cover unstake auto-staked funds; opting out leaves existing stake as is.
+    pub fn set_auto_stake(&mut self, config: Option<AutoStakeConfig>) {
+        let owner = self.env().caller();
+        self.auto_stake_configs.set(&owner, config.clone());
+        
+        self.env().emit_event(AutoStakeConfigured {
+            owner,
+            config,
+            nonce: self.event_nonce.next(),
+        });
     }
 
-    /// Get the contract's total CSPR balance
-    pub fn get_contract_balance(&self) -> U512 {
-        self.env().self_balance()
+    /// Stake an owner's unreserved balance above their floor (called by automation engine)
+    /// 
+    /// Funds reserved for rules stay liquid. No fee is charged and the amount
+    /// does not count towards the daily outflow cap, since the stake stays the
+    /// owner's. Returns the amount staked, which is zero if nothing is above
+    /// the floor.
+    pub fn execute_auto_stake(&mut self, owner: Address) -> U512 {
+        self.reentrancy_guard.enter();
+        self.assert_authorized_engine();
+        
+        let config = match self.auto_stake_configs.get_or_default(&owner) {
+            Some(config) => config,
+            None => self.env().revert(Error::AutoStakeNotEnabled),
+        };
+        let adapter = match self.resolved_staking_adapter() {
+            Some(adapter) => adapter,
+            None => self.env().revert(Error::StakingAdapterNotSet),
+        };
+        
+        let amount = self.get_withdrawable_balance(owner).saturating_sub(config.floor);
+        if amount.is_zero() {
+            self.reentrancy_guard.exit();
+            return amount;
+        }
+        self.stake_custodial(adapter, owner, config.validator, amount);
+        self.check_invariants();
+        self.reentrancy_guard.exit();
+        amount
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use odra::host::{Deployer, HostRef};
+    /// Split each later `deposit` of the caller's between stake, savings and liquid funds
+    /// 
+    /// The staked share is auto-staked under the caller's account with
+    /// `routing.validator` and the savings share is earmarked for
+    /// `routing.savings_envelope`; the rest stays spendable. None turns
+    /// routing off. Deposits made for the caller by others, or straight into
+    /// an envelope, are never split.
+    pub fn set_deposit_routing(&mut self, routing: Option<DepositRouting>) {
+        let owner = self.env().caller();
+        if let Some(routing) = &routing {
+            if !routing.is_valid() || routing.savings_envelope.len() > MAX_ENVELOPE_NAME_LEN {
+                self.env().revert(Error::InvalidDepositRouting);
+            }
+        }
+        self.deposit_routings.set(&owner, routing.clone());
+        
+        self.env().emit_event(DepositRoutingConfigured {
+            owner,
+            routing,
+            nonce: self.event_nonce.next(),
+        });
+    }
 
-    #[test]
-    fn test_deposit_and_withdraw() {
-        let env = odra_test::env();
-        let mut vault = AutomationVault::deploy(&env, AutomationVaultInitArgs {
-            automation_engine: None,
+    /// Set the caller's withdrawal delay
+    /// 
+    /// With a non-zero delay, withdrawals must be requested and can only be
+    /// completed once the delay has passed, leaving time to cancel them.
+    /// Raising the delay applies immediately; lowering it only takes effect
+    /// after the current delay, so a stolen key cannot switch it off.
+    pub fn set_withdrawal_delay(&mut self, delay: u64) {
+        let owner = self.env().caller();
+        let current_delay = self.get_withdrawal_delay(owner);
+        let effective_at = if delay >= current_delay {
+            self.withdrawal_delays.set(&owner, delay);
+            self.pending_delay_changes.set(&owner, (delay, 0));
+            self.env().get_block_time()
+        } else {
+            let effective_at = self.env().get_block_time() + current_delay;
+            self.pending_delay_changes.set(&owner, (delay, effective_at));
+            effective_at
+        };
+        
+        self.env().emit_event(WithdrawalDelayUpdated {
+            owner,
+            delay,
+            effective_at,
+            nonce: self.event_nonce.next(),
         });
+    }
 
-        let depositor = env.get_account(0);
-        let deposit_amount = U512::from(1_000_000_000u64); // 1 CSPR
+    /// Request a timelocked CSPR withdrawal, reserving the funds
+    /// 
+    /// Reserved funds can no longer be used by automations. Returns the request ID.
+    pub fn request_withdrawal(&mut self, amount: U512) -> u64 {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        if amount.is_zero() {
+            self.env().revert(Error::ZeroAmount);
+        }
+        
+        let current_balance = self.balances.get_or_default(&caller);
+        if current_balance < amount {
+            self.env().revert(Error::InsufficientBalance);
+        }
+        self.assert_spendable(caller, amount);
+        self.assert_unreserved(caller, amount);
+        self.balances.set(&caller, checked_sub(&self.env(), current_balance, amount));
+        
+        self.open_withdrawal_request(caller, None, amount)
+    }
 
-        // Deposit
-        env.set_caller(depositor);
-        vault.with_tokens(deposit_amount).deposit();
+    /// Request a timelocked CEP-18 withdrawal, reserving the tokens
+    pub fn request_token_withdrawal(&mut self, token: Address, amount: U256) -> u64 {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        if amount.is_zero() {
+            self.env().revert(Error::ZeroAmount);
+        }
         
-        assert_eq!(vault.get_balance(depositor), deposit_amount);
+        let current_balance = self.token_balances.get_or_default(&(caller, token));
+        if current_balance < amount {
+            self.env().revert(Error::InsufficientBalance);
+        }
+        self.token_balances.set(&(caller, token), checked_sub(&self.env(), current_balance, amount));
+        
+        self.open_withdrawal_request(caller, Some(token), from_token_amount(amount))
+    }
 
-        // Withdraw half
-        let withdraw_amount = U512::from(500_000_000u64);
-        vault.withdraw(withdraw_amount);
+    /// Cancel a pending withdrawal and return the funds to the vault balance
+    pub fn cancel_withdrawal(&mut self, request_id: u64) {
+        let mut request = self.get_withdrawal_request_or_revert(request_id);
+        request.closed = true;
+        self.withdrawal_requests.set(&request_id, request.clone());
         
-        assert_eq!(vault.get_balance(depositor), deposit_amount - withdraw_amount);
+        match request.token {
+            Some(token) => {
+                let amount = to_token_amount(request.amount).unwrap_or_default();
+                let balance = self.token_balances.get_or_default(&(request.owner, token));
+                self.token_balances.set(&(request.owner, token), checked_add(&self.env(), balance, amount));
+            }
+            None => {
+                let balance = self.balances.get_or_default(&request.owner);
+                self.balances.set(&request.owner, checked_add(&self.env(), balance, request.amount));
+            }
+        }
+        
+        self.env().emit_event(WithdrawalCancelled {
+            request_id,
+            owner: request.owner,
+            amount: request.amount,
+            nonce: self.event_nonce.next(),
+        });
     }
 
-    #[test]
-    fn test_insufficient_balance() {
-        let env = odra_test::env();
-        let mut vault = AutomationVault::deploy(&env, AutomationVaultInitArgs {
-            automation_engine: None,
+    /// Pay out a withdrawal once its delay has passed
+    pub fn complete_withdrawal(&mut self, request_id: u64) {
+        self.reentrancy_guard.enter();
+        let mut request = self.get_withdrawal_request_or_revert(request_id);
+        if self.env().get_block_time() < request.unlock_time {
+            self.env().revert(Error::WithdrawalNotReady);
+        }
+        request.closed = true;
+        self.withdrawal_requests.set(&request_id, request.clone());
+        
+        match request.token {
+            Some(token) => {
+                let amount = to_token_amount(request.amount).unwrap_or_default();
+                Cep18TokenContractRef::new(self.env(), token).transfer(&request.owner, &amount);
+            }
+            None => {
+                self.debit_user_deposits(request.amount);
+                self.env().transfer_tokens(&request.owner, &request.amount);
+            }
+        }
+        
+        self.env().emit_event(WithdrawalCompleted {
+            request_id,
+            owner: request.owner,
+            token: request.token,
+            amount: request.amount,
+            nonce: self.event_nonce.next(),
         });
+        self.check_invariants();
+        self.reentrancy_guard.exit();
+    }
 
-        let depositor = env.get_account(0);
-        let deposit_amount = U512::from(100_000_000u64);
-        let withdraw_amount = U512::from(200_000_000u64);
+    /// Credit unbonded unstake proceeds to an owner's vault balance
+    /// 
+    /// Payable; can only be called by the configured staking adapter.
+    /// Proceeds owed to a `withdraw_with_unstake` are paid out to the owner
+    /// first, and only the remainder is credited.
+    #[odra(payable)]
+    pub fn receive_unstaked(&mut self, owner: Address) {
+        let caller = self.env().caller();
+        if self.resolved_staking_adapter() != Some(caller) {
+            self.env().revert(Error::UnauthorizedExecutor);
+        }
+        
+        let amount = self.env().attached_value();
+        
+        // Proceeds include rewards, so they can exceed the principal still tracked
+        let delegated = self.total_delegated.get_or_default().saturating_sub(amount);
+        self.total_delegated.set(delegated);
+        
+        // Fulfil queued withdrawals before crediting the vault balance
+        let owed = self.unstake_withdrawals.get_or_default(&owner);
+        let payout = owed.min(amount);
+        if !payout.is_zero() {
+            self.unstake_withdrawals.set(&owner, owed - payout);
+            self.env().transfer_tokens(&owner, &payout);
+            self.env().emit_event(UnstakeWithdrawalFulfilled {
+                owner,
+                amount: payout,
+                nonce: self.event_nonce.next(),
+            });
+        }
+        
+        let credited = amount - payout;
+        if !credited.is_zero() {
+            let new_balance = checked_add(&self.env(), self.balances.get_or_default(&owner), credited);
+            self.balances.set(&owner, new_balance);
+            self.credit_user_deposits(credited);
+            
+            self.env().emit_event(UnstakeProceedsReceived {
+                owner,
+                amount: credited,
+                new_balance,
+                nonce: self.event_nonce.next(),
+            });
+        }
+        self.check_invariants();
+    }
 
-        // Deposit
+    /// Pay out all accrued protocol fees to the treasury
+    /// 
+    /// Anyone may trigger the payout since the destination is fixed by the admin.
+    pub fn distribute_fees(&mut self) {
+        self.reentrancy_guard.enter();
+        let treasury = match self.resolved_treasury() {
+            Some(treasury) => treasury,
+            None => self.env().revert(Error::TreasuryNotSet),
+        };
+        
+        let amount = self.accrued_fees.get_or_default();
+        if amount.is_zero() {
+            self.env().revert(Error::ZeroAmount);
+        }
+        
+        self.accrued_fees.set(U512::zero());
+        self.env().transfer_tokens(&treasury, &amount);
+        self.assert_solvent();
+        
+        self.env().emit_event(FeesDistributed { treasury, amount, nonce: self.event_nonce.next() });
+        self.check_invariants();
+        self.reentrancy_guard.exit();
+    }
+
+    /// Withdraw accrued protocol fees to `recipient` (fee manager role)
+    /// 
+    /// Only the fee bucket can be withdrawn; the vault must still hold at
+    /// least everything it owes users afterwards.
+    pub fn withdraw_fees(&mut self, recipient: Address, amount: U512) {
+        self.reentrancy_guard.enter();
+        self.assert_role(Role::FeeManager);
+        
+        let accrued = self.accrued_fees.get_or_default();
+        if amount.is_zero() || amount > accrued {
+            self.env().revert(Error::InsufficientFees);
+        }
+        self.accrued_fees.set(checked_sub(&self.env(), accrued, amount));
+        self.env().transfer_tokens(&recipient, &amount);
+        self.assert_solvent();
+        
+        self.env().emit_event(FeesWithdrawn {
+            recipient,
+            amount,
+            nonce: self.event_nonce.next(),
+        });
+        self.check_invariants();
+        self.reentrancy_guard.exit();
+    }
+
+    /// Set the treasury that receives protocol fees (admin only)
+    pub fn set_treasury(&mut self, treasury: Address) {
+        self.assert_admin();
+        self.treasury.set(Some(treasury));
+    }
+
+    /// Set the staking adapter used for staking from the vault (admin only)
+    /// 
+    /// Reverts with TimelockRequired while the admin timelock is enabled;
+    /// use `queue_staking_adapter` instead.
+    pub fn set_staking_adapter(&mut self, adapter: Address) {
+        self.assert_admin();
+        self.assert_no_timelock();
+        self.staking_adapter.set(Some(adapter));
+    }
+
+    /// Set the swap adapter used for swap rules (admin only)
+    pub fn set_swap_adapter(&mut self, adapter: Address) {
+        self.assert_admin();
+        self.swap_adapter.set(Some(adapter));
+    }
+
+    /// Point the vault at a contract registry, or detach it with `None` (admin only)
+    /// 
+    /// While set and the admin timelock is disabled, the registry's engine,
+    /// treasury and adapter entries take precedence over the addresses set
+    /// on the vault. Registry entries change without a delay, so the vault
+    /// ignores them while the timelock is enabled. Reverts with
+    /// TimelockRequired while the admin timelock is enabled; use
+    /// `queue_registry` instead.
+    pub fn set_registry(&mut self, registry: Option<Address>) {
+        self.assert_admin();
+        self.assert_no_timelock();
+        self.registry.set(registry);
+    }
+
+    /// Cap the CSPR the engine may move out of any one vault per day (operator role)
+    /// 
+    /// Applies to everything the engine moves or locks out of a vault, from
+    /// transfers and stakes to the funds behind streams, vestings and
+    /// escrows, fees included, so a compromised engine cannot drain vaults
+    /// faster than the cap.
+    /// Owners' own withdrawals are not limited. Zero removes the cap.
+    pub fn set_max_daily_outflow(&mut self, limit: U512) {
+        self.assert_role(Role::Operator);
+        self.max_daily_outflow.set(limit);
+    }
+
+    /// Grant a role to an account (admin only)
+    /// 
+    /// Operators manage the token allowlist and outflow cap, and fee
+    /// managers withdraw fees. The owner role stays with the admin.
+    pub fn grant_role(&mut self, role: Role, account: Address) {
+        self.assert_admin();
+        self.roles.set_role(role.clone(), account, true);
+        
+        self.env().emit_event(RoleGranted {
+            role,
+            account,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Revoke a role from an account (admin only)
+    pub fn revoke_role(&mut self, role: Role, account: Address) {
+        self.assert_admin();
+        self.roles.set_role(role.clone(), account, false);
+        
+        self.env().emit_event(RoleRevoked {
+            role,
+            account,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Enable the admin timelock or lengthen its delay (admin only)
+    /// 
+    /// While the delay is non-zero, a new automation engine, staking adapter
+    /// or registry must be queued and can only take effect once the delay
+    /// has passed, giving users time to withdraw. Shortening or disabling
+    /// the delay is itself queued with `queue_timelock_delay`.
+    pub fn set_admin_timelock_delay(&mut self, delay: u64) {
+        self.assert_admin();
+        if delay < self.timelock.delay() {
+            self.env().revert(Error::TimelockRequired);
+        }
+        self.timelock.set_delay(delay);
+    }
+
+    /// Queue a change of the authorized engine behind the timelock (admin only)
+    pub fn queue_automation_engine(&mut self, engine: Address) -> u64 {
+        self.assert_admin();
+        self.queue_admin_action(AdminAction {
+            kind: AdminActionKind::SetAutomationEngine,
+            target: Some(engine),
+            fee_bps: 0,
+            flat_fee: U512::zero(),
+            delay: 0,
+            eta: 0,
+        })
+    }
+
+    /// Queue a staking adapter change behind the timelock (admin only)
+    pub fn queue_staking_adapter(&mut self, adapter: Address) -> u64 {
+        self.assert_admin();
+        self.queue_admin_action(AdminAction {
+            kind: AdminActionKind::SetStakingAdapter,
+            target: Some(adapter),
+            fee_bps: 0,
+            flat_fee: U512::zero(),
+            delay: 0,
+            eta: 0,
+        })
+    }
+
+    /// Queue attaching or detaching a contract registry behind the timelock (admin only)
+    pub fn queue_registry(&mut self, registry: Option<Address>) -> u64 {
+        self.assert_admin();
+        self.queue_admin_action(AdminAction {
+            kind: AdminActionKind::SetRegistry,
+            target: registry,
+            fee_bps: 0,
+            flat_fee: U512::zero(),
+            delay: 0,
+            eta: 0,
+        })
+    }
+
+    /// Queue a change of the timelock delay behind the current delay (admin only)
+    pub fn queue_timelock_delay(&mut self, delay: u64) -> u64 {
+        self.assert_admin();
+        self.queue_admin_action(AdminAction {
+            kind: AdminActionKind::SetTimelockDelay,
+            target: None,
+            fee_bps: 0,
+            flat_fee: U512::zero(),
+            delay,
+            eta: 0,
+        })
+    }
+
+    /// Apply a queued admin change once its delay has passed
+    /// 
+    /// Anyone may call this: the change was authorized when it was queued.
+    pub fn execute_admin_action(&mut self, action_id: u64) {
+        let action = self.timelock.take_ready(action_id);
+        match action.kind {
+            AdminActionKind::SetAutomationEngine => self.authorized_engine.set(action.target),
+            AdminActionKind::SetTimelockDelay => self.timelock.set_delay(action.delay),
+            AdminActionKind::SetRegistry => self.registry.set(action.target),
+            AdminActionKind::SetStakingAdapter => self.staking_adapter.set(action.target),
+            // Fee, oracle and vault changes are queued on the engine
+            AdminActionKind::SetFeeConfig
+            | AdminActionKind::SetPriceOracle
+            | AdminActionKind::SetAttestationOracle
+            | AdminActionKind::SetVaultAddress => {
+                self.env().revert(Error::AdminActionNotFound)
+            }
+        }
+        
+        self.env().emit_event(AdminActionExecuted {
+            action_id,
+            kind: action.kind,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Drop a queued admin change (admin only)
+    pub fn cancel_admin_action(&mut self, action_id: u64) {
+        self.assert_admin();
+        let action = self.timelock.cancel(action_id);
+        
+        self.env().emit_event(AdminActionCancelled {
+            action_id,
+            kind: action.kind,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    // ========================================================================
+    // CEP-18 Token Functions
+    // ========================================================================
+
+    /// Deposit CEP-18 tokens into the caller's vault
+    /// 
+    /// The caller must first approve the vault to spend `amount` on the
+    /// token contract; the vault pulls the tokens via `transfer_from`.
+    pub fn deposit_token(&mut self, token: Address, amount: U256) {
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        
+        if amount.is_zero() {
+            self.env().revert(Error::ZeroAmount);
+        }
+        if !self.allowed_tokens.get_or_default(&token) {
+            self.env().revert(Error::TokenNotAllowed);
+        }
+        
+        // Pull tokens from the caller into the vault
+        let vault_address = self.env().self_address();
+        let mut token_contract = Cep18TokenContractRef::new(self.env(), token);
+        token_contract.transfer_from(&caller, &vault_address, &amount);
+        
+        // Update balance
+        let current_balance = self.token_balances.get_or_default(&(caller, token));
+        let new_balance = checked_add(&self.env(), current_balance, amount);
+        self.token_balances.set(&(caller, token), new_balance);
+        
+        // Emit event
+        self.env().emit_event(TokenDeposited {
+            owner: caller,
+            token,
+            amount,
+            new_balance,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Withdraw CEP-18 tokens from the caller's vault to their account
+    pub fn withdraw_token(&mut self, token: Address, amount: U256) {
+        self.reentrancy_guard.enter();
+        let caller = self.env().caller();
+        self.record_activity(caller);
+        self.assert_no_withdrawal_delay(caller);
+        
+        if amount.is_zero() {
+            self.env().revert(Error::ZeroAmount);
+        }
+        
+        // Check balance
+        let current_balance = self.token_balances.get_or_default(&(caller, token));
+        if current_balance < amount {
+            self.env().revert(Error::InsufficientBalance);
+        }
+        
+        // Update balance
+        let new_balance = checked_sub(&self.env(), current_balance, amount);
+        self.token_balances.set(&(caller, token), new_balance);
+        
+        // Transfer to caller
+        let mut token_contract = Cep18TokenContractRef::new(self.env(), token);
+        token_contract.transfer(&caller, &amount);
+        
+        // Emit event
+        self.env().emit_event(TokenWithdrawn {
+            owner: caller,
+            token,
+            amount,
+            new_balance,
+            nonce: self.event_nonce.next(),
+        });
+        self.check_invariants();
+        self.reentrancy_guard.exit();
+    }
+
+    /// Execute a CEP-18 transfer from a user's vault (called by automation engine)
+    pub fn execute_token_transfer(
+        &mut self,
+        owner: Address,
+        token: Address,
+        recipient: Address,
+        amount: U256,
+        rule_id: u64,
+        execution_id: u64,
+    ) {
+        self.reentrancy_guard.enter();
+        // Verify caller is the authorized automation engine
+        self.assert_authorized_engine();
+        
+        // Check balance
+        let current_balance = self.token_balances.get_or_default(&(owner, token));
+        if current_balance < amount {
+            self.env().revert(Error::InsufficientBalance);
+        }
+        
+        // Update balance
+        let new_balance = checked_sub(&self.env(), current_balance, amount);
+        self.token_balances.set(&(owner, token), new_balance);
+        
+        // Transfer to recipient
+        let mut token_contract = Cep18TokenContractRef::new(self.env(), token);
+        token_contract.transfer(&recipient, &amount);
+        
+        // Emit event
+        self.env().emit_event(TokenAutomationExecuted {
+            owner,
+            rule_id,
+            execution_id,
+            token,
+            recipient,
+            amount,
+            balance_before: current_balance,
+            balance_after: new_balance,
+            nonce: self.event_nonce.next(),
+        });
+        self.check_invariants();
+        self.reentrancy_guard.exit();
+    }
+
+    /// Allow or disallow paying `contract` through `entry_point` (admin only)
+    /// 
+    /// Rules paying a contract recipient call arbitrary code with the
+    /// owner's funds attached, so only vetted deposit entrypoints qualify.
+    pub fn set_contract_recipient_allowed(
+        &mut self,
+        contract: Address,
+        entry_point: String,
+        allowed: bool,
+    ) {
+        self.assert_admin();
+        self.allowed_contract_recipients.set(&(contract, entry_point.clone()), allowed);
+        
+        self.env().emit_event(ContractRecipientAllowlistUpdated {
+            contract,
+            entry_point,
+            allowed,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Add or remove a CEP-18 token from the allowlist (operator role)
+    pub fn set_token_allowed(&mut self, token: Address, allowed: bool) {
+        self.assert_role(Role::Operator);
+        self.allowed_tokens.set(&token, allowed);
+        let mut listed = self.listed_tokens.get_or_default();
+        if allowed && !listed.contains(&token) {
+            listed.push(token);
+            self.listed_tokens.set(listed);
+        }
+        
+        self.env().emit_event(TokenAllowlistUpdated {
+            token,
+            allowed,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    // ========================================================================
+    // Payment Stream Functions
+    // ========================================================================
+
+    /// Open a payment stream funded from the owner's vault (called by automation engine)
+    /// 
+    /// The full stream amount is deducted from the owner's balance up front
+    /// and held until it is claimed by the recipient or refunded on cancel.
+    pub fn open_stream(
+        &mut self,
+        owner: Address,
+        stream_id: u64,
+        recipient: Address,
+        rate_per_second: U512,
+        start_time: u64,
+        end_time: u64,
+    ) {
+        self.assert_authorized_engine();
+        
+        let stream = PaymentStream {
+            owner,
+            recipient,
+            rate_per_second,
+            start_time,
+            end_time,
+            withdrawn: U512::zero(),
+        };
+        let total = match stream.total() {
+            Some(total) => total,
+            None => self.env().revert(Error::Overflow),
+        };
+        
+        // Check balance
+        let current_balance = self.balances.get_or_default(&owner);
+        if current_balance < total {
+            self.env().revert(Error::InsufficientBalance);
+        }
+        self.assert_spendable(owner, total);
+        self.record_outflow(owner, total);
+        
+        // Lock the stream funds
+        self.balances.set(&owner, checked_sub(&self.env(), current_balance, total));
+        self.streams.set(&stream_id, stream);
+        
+        // Emit event
+        self.env().emit_event(StreamCreated {
+            stream_id,
+            owner,
+            recipient,
+            rate_per_second,
+            start_time,
+            end_time,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Pay out everything accrued on a stream to its recipient
+    /// 
+    /// Callable by the recipient or by the automation engine on a keeper push,
+    /// unless the engine blocklists the recipient. Returns the amount paid out.
+    pub fn claim_stream(&mut self, stream_id: u64) -> U512 {
+        self.reentrancy_guard.enter();
+        let caller = self.env().caller();
+        let mut stream = self.get_stream_or_revert(stream_id);
+        
+        if caller != stream.recipient && Some(caller) != self.resolved_engine() {
+            self.env().revert(Error::NotStreamRecipient);
+        }
+        self.assert_not_blocked(stream.recipient);
+        
+        let amount = stream.claimable_at(self.env().get_block_time());
+        if amount.is_zero() {
+            self.reentrancy_guard.exit();
+            return amount;
+        }
+        
+        // Update stream
+        stream.withdrawn = checked_add(&self.env(), stream.withdrawn, amount);
+        let recipient = stream.recipient;
+        self.streams.set(&stream_id, stream);
+        
+        // Transfer to recipient
+        self.debit_user_deposits(amount);
+        self.env().transfer_tokens(&recipient, &amount);
+        
+        // Emit event
+        self.env().emit_event(StreamClaimed {
+            stream_id,
+            recipient,
+            amount,
+            nonce: self.event_nonce.next(),
+        });
+        
+        self.check_invariants();
+        self.reentrancy_guard.exit();
+        amount
+    }
+
+    /// Cancel a stream (called by automation engine)
+    /// 
+    /// Pays the recipient what has accrued so far and refunds the unstreamed
+    /// remainder to the owner's vault balance. A recipient on the engine's
+    /// blocklist is not paid; what has accrued stays claimable on the
+    /// stream in case they are unblocked.
+    pub fn cancel_stream(&mut self, stream_id: u64) {
+        self.reentrancy_guard.enter();
+        self.assert_authorized_engine();
+        let mut stream = self.get_stream_or_revert(stream_id);
+        let now = self.env().get_block_time();
+        
+        let paid_to_recipient = if self.is_blocked(stream.recipient) {
+            U512::zero()
+        } else {
+            stream.claimable_at(now)
+        };
+        // Opening the stream checked its total fits
+        let total = stream.total().unwrap_or_default();
+        let refunded = checked_sub(&self.env(), total, stream.accrued_at(now));
+        
+        // Truncate the stream so nothing further accrues
+        stream.end_time = now.clamp(stream.start_time, stream.end_time);
+        stream.withdrawn = checked_add(&self.env(), stream.withdrawn, paid_to_recipient);
+        let owner = stream.owner;
+        let recipient = stream.recipient;
+        self.streams.set(&stream_id, stream);
+        
+        // Refund the remainder to the owner's vault balance
+        let current_balance = self.balances.get_or_default(&owner);
+        self.balances.set(&owner, checked_add(&self.env(), current_balance, refunded));
+        
+        // Pay the recipient what has already accrued
+        if !paid_to_recipient.is_zero() {
+            self.debit_user_deposits(paid_to_recipient);
+            self.env().transfer_tokens(&recipient, &paid_to_recipient);
+        }
+        
+        // Emit event
+        self.env().emit_event(StreamCancelled {
+            stream_id,
+            owner,
+            paid_to_recipient,
+            refunded,
+            nonce: self.event_nonce.next(),
+        });
+        self.check_invariants();
+        self.reentrancy_guard.exit();
+    }
+
+    // ========================================================================
+    // Escrow Functions
+    // ========================================================================
+
+    /// Move a payment from the owner's vault into escrow (called by automation engine)
+    /// 
+    /// The amount and fee are deducted from the owner's balance; the fee is
+    /// charged now and the amount is held until the recipient claims it
+    /// from `release_at` or the owner cancels it before then. Returns the
+    /// escrow ID.
+    pub fn open_escrow(
+        &mut self,
+        owner: Address,
+        recipient: Address,
+        amount: U512,
+        fee: U512,
+        rule_id: u64,
+        release_at: u64,
+    ) -> u64 {
+        self.assert_authorized_engine();
+        if amount.is_zero() {
+            self.env().revert(Error::ZeroAmount);
+        }
+        
+        // Check balance
+        let total = checked_add(&self.env(), amount, fee);
+        let current_balance = self.balances.get_or_default(&owner);
+        if current_balance < total {
+            self.env().revert(Error::InsufficientBalance);
+        }
+        self.assert_spendable(owner, total);
+        
+        // The amount stays on the users' side of the books until it is claimed
+        self.record_outflow(owner, total);
+        self.balances.set(&owner, checked_sub(&self.env(), current_balance, total));
+        self.debit_user_deposits(fee);
+        
+        let escrow_id = self.next_escrow_id.get_or_default() + 1;
+        self.next_escrow_id.set(escrow_id);
+        self.escrows.set(&escrow_id, EscrowPayment {
+            rule_id,
+            owner,
+            recipient,
+            amount,
+            release_at,
+            status: EscrowStatus::Held,
+        });
+        let mut rule_escrow_ids = self.rule_escrows.get_or_default(&rule_id);
+        rule_escrow_ids.push(escrow_id);
+        self.rule_escrows.set(&rule_id, rule_escrow_ids);
+        
+        self.env().emit_event(EscrowFunded {
+            escrow_id,
+            rule_id,
+            owner,
+            recipient,
+            amount,
+            release_at,
+            nonce: self.event_nonce.next(),
+        });
+        self.accrue_fee(owner, rule_id, fee);
+        self.check_invariants();
+        escrow_id
+    }
+
+    /// Claim an escrowed payment once its dispute window has ended (recipient only)
+    /// 
+    /// Recipients on the engine's blocklist cannot claim.
+    pub fn claim_escrow(&mut self, escrow_id: u64) -> U512 {
+        self.reentrancy_guard.enter();
+        let mut escrow = self.get_held_escrow_or_revert(escrow_id);
+        if self.env().caller() != escrow.recipient {
+            self.env().revert(Error::NotEscrowParty);
+        }
+        self.assert_not_blocked(escrow.recipient);
+        if self.env().get_block_time() < escrow.release_at {
+            self.env().revert(Error::EscrowLocked);
+        }
+        
+        escrow.status = EscrowStatus::Claimed;
+        let recipient = escrow.recipient;
+        let amount = escrow.amount;
+        self.escrows.set(&escrow_id, escrow);
+        
+        // Transfer to recipient
+        self.debit_user_deposits(amount);
+        self.env().transfer_tokens(&recipient, &amount);
+        
+        self.env().emit_event(EscrowClaimed {
+            escrow_id,
+            recipient,
+            amount,
+            nonce: self.event_nonce.next(),
+        });
+        self.check_invariants();
+        self.reentrancy_guard.exit();
+        amount
+    }
+
+    /// Cancel an escrowed payment during its dispute window (owner only)
+    /// 
+    /// The amount returns to the owner's vault balance; the fee is not refunded.
+    pub fn cancel_escrow(&mut self, escrow_id: u64) {
+        let mut escrow = self.get_held_escrow_or_revert(escrow_id);
+        if self.env().caller() != escrow.owner {
+            self.env().revert(Error::NotEscrowParty);
+        }
+        if self.env().get_block_time() >= escrow.release_at {
+            self.env().revert(Error::DisputeWindowClosed);
+        }
+        
+        escrow.status = EscrowStatus::Cancelled;
+        let owner = escrow.owner;
+        let amount = escrow.amount;
+        self.escrows.set(&escrow_id, escrow);
+        
+        // Refund to the owner's vault balance
+        let current_balance = self.balances.get_or_default(&owner);
+        self.balances.set(&owner, checked_add(&self.env(), current_balance, amount));
+        
+        self.env().emit_event(EscrowCancelled {
+            escrow_id,
+            owner,
+            amount,
+            nonce: self.event_nonce.next(),
+        });
+        self.check_invariants();
+    }
+
+    // ========================================================================
+    // Claimable Transfer Functions
+    // ========================================================================
+
+    /// Hold a transfer for its recipient to claim (called by automation engine)
+    /// 
+    /// The amount and fee are deducted from the owner's balance; the fee is
+    /// charged now and the amount waits under the recipient's claimable
+    /// balance until they `claim` it, or the owner reclaims it once it
+    /// expires at `expires_at`. Returns the transfer ID.
+    pub fn hold_claimable(
+        &mut self,
+        owner: Address,
+        recipient: Address,
+        amount: U512,
+        fee: U512,
+        rule_id: u64,
+        execution_id: u64,
+        expires_at: u64,
+    ) -> u64 {
+        self.assert_authorized_engine();
+        if amount.is_zero() {
+            self.env().revert(Error::ZeroAmount);
+        }
+        
+        // Check balance
+        let total = checked_add(&self.env(), amount, fee);
+        let current_balance = self.balances.get_or_default(&owner);
+        if current_balance < total {
+            self.env().revert(Error::InsufficientBalance);
+        }
+        self.assert_spendable(owner, total);
+        
+        // The amount stays on the users' side of the books until it is claimed
+        self.record_outflow(owner, total);
+        let new_balance = checked_sub(&self.env(), current_balance, total);
+        self.balances.set(&owner, new_balance);
+        self.debit_user_deposits(fee);
+        
+        let transfer_id = self.next_claimable_id.get_or_default() + 1;
+        self.next_claimable_id.set(transfer_id);
+        self.claimable_transfers.set(&transfer_id, ClaimableTransfer {
+            rule_id,
+            execution_id,
+            owner,
+            recipient,
+            amount,
+            expires_at,
+            status: ClaimStatus::Held,
+        });
+        let index = self.recipient_claim_counts.get_or_default(&recipient);
+        self.recipient_claim_ids.set(&(recipient, index), transfer_id);
+        self.recipient_claim_counts.set(&recipient, index + 1);
+        let claimable = self.claimable_balances.get_or_default(&recipient);
+        self.claimable_balances.set(&recipient, checked_add(&self.env(), claimable, amount));
+        
+        self.env().emit_event(AutomationExecuted {
+            owner,
+            rule_id,
+            execution_id,
+            recipient,
+            amount,
+            fee,
+            balance_before: current_balance,
+            balance_after: new_balance,
+            nonce: self.event_nonce.next(),
+        });
+        self.env().emit_event(ClaimableTransferHeld {
+            transfer_id,
+            rule_id,
+            execution_id,
+            owner,
+            recipient,
+            amount,
+            expires_at,
+            nonce: self.event_nonce.next(),
+        });
+        self.accrue_fee(owner, rule_id, fee);
+        self.check_invariants();
+        transfer_id
+    }
+
+    /// Claim transfers held for the caller, expired or not, returning the total paid
+    /// 
+    /// At most `MAX_CLAIM_BATCH` transfers are claimed per call, so however
+    /// many transfers others send the caller, each call stays affordable.
+    /// Find held transfers with `get_claimable_transfer_ids`. Recipients on
+    /// the engine's blocklist cannot claim; their senders can still reclaim
+    /// expired transfers.
+    pub fn claim(&mut self, transfer_ids: Vec<u64>) -> U512 {
+        self.reentrancy_guard.enter();
+        let recipient = self.env().caller();
+        self.assert_not_blocked(recipient);
+        if transfer_ids.is_empty() {
+            self.env().revert(Error::NothingToClaim);
+        }
+        if transfer_ids.len() > MAX_CLAIM_BATCH as usize {
+            self.env().revert(Error::BatchTooLarge);
+        }
+        
+        let mut amount = U512::zero();
+        for transfer_id in &transfer_ids {
+            let mut transfer = self.get_held_claimable_or_revert(*transfer_id);
+            if transfer.recipient != recipient {
+                self.env().revert(Error::NotClaimableRecipient);
+            }
+            amount = checked_add(&self.env(), amount, transfer.amount);
+            transfer.status = ClaimStatus::Claimed;
+            self.claimable_transfers.set(transfer_id, transfer);
+        }
+        let claimable = self.claimable_balances.get_or_default(&recipient);
+        self.claimable_balances.set(&recipient, checked_sub(&self.env(), claimable, amount));
+        
+        // Transfer to recipient
+        self.debit_user_deposits(amount);
+        self.env().transfer_tokens(&recipient, &amount);
+        
+        self.env().emit_event(TransfersClaimed {
+            recipient,
+            transfer_ids,
+            amount,
+            nonce: self.event_nonce.next(),
+        });
+        self.check_invariants();
+        self.reentrancy_guard.exit();
+        amount
+    }
+
+    /// Reclaim a transfer its recipient left unclaimed past its expiry (owner only)
+    /// 
+    /// The amount returns to the owner's vault balance; the fee is not refunded.
+    pub fn refund_claimable(&mut self, transfer_id: u64) {
+        let mut transfer = self.get_held_claimable_or_revert(transfer_id);
+        if self.env().caller() != transfer.owner {
+            self.env().revert(Error::NotClaimableSender);
+        }
+        if self.env().get_block_time() < transfer.expires_at {
+            self.env().revert(Error::ClaimNotExpired);
+        }
+        
+        transfer.status = ClaimStatus::Refunded;
+        let owner = transfer.owner;
+        let recipient = transfer.recipient;
+        let amount = transfer.amount;
+        self.claimable_transfers.set(&transfer_id, transfer);
+        let claimable = self.claimable_balances.get_or_default(&recipient);
+        self.claimable_balances.set(&recipient, checked_sub(&self.env(), claimable, amount));
+        
+        // Refund to the owner's vault balance
+        let current_balance = self.balances.get_or_default(&owner);
+        self.balances.set(&owner, checked_add(&self.env(), current_balance, amount));
+        
+        self.env().emit_event(ClaimableTransferRefunded {
+            transfer_id,
+            owner,
+            amount,
+            nonce: self.event_nonce.next(),
+        });
+        self.check_invariants();
+    }
+
+    // ========================================================================
+    // Vesting Functions
+    // ========================================================================
+
+    /// Lock funds from the owner's vault into a vesting schedule (called by automation engine)
+    pub fn open_vesting(
+        &mut self,
+        owner: Address,
+        vesting_id: u64,
+        beneficiary: Address,
+        amount: U512,
+        start_time: u64,
+        cliff_time: u64,
+        end_time: u64,
+    ) {
+        self.assert_authorized_engine();
+        
+        // Check balance
+        let current_balance = self.balances.get_or_default(&owner);
+        if current_balance < amount {
+            self.env().revert(Error::InsufficientBalance);
+        }
+        self.assert_spendable(owner, amount);
+        self.record_outflow(owner, amount);
+        
+        // Lock the vesting funds
+        self.balances.set(&owner, checked_sub(&self.env(), current_balance, amount));
+        self.vestings.set(&vesting_id, VestingSchedule {
+            owner,
+            beneficiary,
+            total_amount: amount,
+            start_time,
+            cliff_time,
+            end_time,
+            claimed: U512::zero(),
+            revoked: false,
+        });
+        
+        // Emit event
+        self.env().emit_event(VestingCreated {
+            vesting_id,
+            owner,
+            beneficiary,
+            amount,
+            start_time,
+            cliff_time,
+            end_time,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Pay out everything vested so far to the beneficiary
+    /// 
+    /// Callable by the beneficiary or by the automation engine on a keeper push,
+    /// unless the engine blocklists the beneficiary. Returns the amount paid out.
+    pub fn claim_vesting(&mut self, vesting_id: u64) -> U512 {
+        self.reentrancy_guard.enter();
+        let caller = self.env().caller();
+        let mut vesting = self.get_vesting_or_revert(vesting_id);
+        
+        if caller != vesting.beneficiary && Some(caller) != self.resolved_engine() {
+            self.env().revert(Error::NotVestingBeneficiary);
+        }
+        self.assert_not_blocked(vesting.beneficiary);
+        
+        let amount = vesting.claimable_at(self.env().get_block_time());
+        if amount.is_zero() {
+            self.reentrancy_guard.exit();
+            return amount;
+        }
+        
+        // Update vesting
+        vesting.claimed = checked_add(&self.env(), vesting.claimed, amount);
+        let beneficiary = vesting.beneficiary;
+        self.vestings.set(&vesting_id, vesting);
+        
+        // Transfer to beneficiary
+        self.debit_user_deposits(amount);
+        self.env().transfer_tokens(&beneficiary, &amount);
+        
+        // Emit event
+        self.env().emit_event(VestingClaimed {
+            vesting_id,
+            beneficiary,
+            amount,
+            nonce: self.event_nonce.next(),
+        });
+        
+        self.check_invariants();
+        self.reentrancy_guard.exit();
+        amount
+    }
+
+    /// Revoke a vesting schedule (called by automation engine)
+    /// 
+    /// Pays the beneficiary what has vested so far and refunds the unvested
+    /// remainder to the owner's vault balance. A beneficiary on the engine's
+    /// blocklist is not paid; what has vested stays claimable on the
+    /// schedule in case they are unblocked.
+    pub fn revoke_vesting(&mut self, vesting_id: u64) {
+        self.reentrancy_guard.enter();
+        self.assert_authorized_engine();
+        let mut vesting = self.get_vesting_or_revert(vesting_id);
+        let now = self.env().get_block_time();
+        
+        let vested = vesting.vested_at(now);
+        let paid_to_beneficiary = if self.is_blocked(vesting.beneficiary) {
+            U512::zero()
+        } else {
+            checked_sub(&self.env(), vested, vesting.claimed)
+        };
+        let refunded = checked_sub(&self.env(), vesting.total_amount, vested);
+        
+        // Freeze the schedule at what has vested so far
+        vesting.total_amount = vested;
+        vesting.claimed = checked_add(&self.env(), vesting.claimed, paid_to_beneficiary);
+        vesting.revoked = true;
+        let owner = vesting.owner;
+        let beneficiary = vesting.beneficiary;
+        self.vestings.set(&vesting_id, vesting);
+        
+        // Refund the remainder to the owner's vault balance
+        let current_balance = self.balances.get_or_default(&owner);
+        self.balances.set(&owner, checked_add(&self.env(), current_balance, refunded));
+        
+        // Pay the beneficiary what has already vested
+        if !paid_to_beneficiary.is_zero() {
+            self.debit_user_deposits(paid_to_beneficiary);
+            self.env().transfer_tokens(&beneficiary, &paid_to_beneficiary);
+        }
+        
+        // Emit event
+        self.env().emit_event(VestingRevoked {
+            vesting_id,
+            owner,
+            paid_to_beneficiary,
+            refunded,
+            nonce: self.event_nonce.next(),
+        });
+        self.check_invariants();
+        self.reentrancy_guard.exit();
+    }
+
+    /// Set the authorized automation engine address (admin only)
+    /// 
+    /// Reverts with TimelockRequired while the admin timelock is enabled;
+    /// use `queue_automation_engine` instead.
+    pub fn set_automation_engine(&mut self, engine: Address) {
+        self.assert_admin();
+        self.assert_no_timelock();
+        self.authorized_engine.set(Some(engine));
+    }
+
+    // ========================================================================
+    // Envelope Functions
+    // ========================================================================
+
+    /// Earmark part of the caller's CSPR balance for a named envelope, e.g. "bills"
+    /// 
+    /// Earmarked funds stay in the caller's balance but can only be spent by
+    /// rules bound to the envelope, or withdrawn with `withdraw_from_envelope`.
+    /// The envelope is created on first use.
+    pub fn fund_envelope(&mut self, name: String, amount: U512) {
+        let caller = self.env().caller();
+        if amount.is_zero() {
+            self.env().revert(Error::ZeroAmount);
+        }
+        if self.get_spendable_balance(caller) < amount {
+            self.env().revert(Error::InsufficientBalance);
+        }
+        self.assert_unreserved(caller, amount);
+        let balance = self.envelopes.get_or_default(&(caller, name.clone()));
+        self.set_envelope(caller, name, checked_add(&self.env(), balance, amount));
+    }
+
+    /// Deposit the attached CSPR straight into one of the caller's envelopes
+    /// 
+    /// This is a payable function - attach CSPR when calling.
+    #[odra(payable)]
+    pub fn deposit_to_envelope(&mut self, name: String) {
+        let caller = self.env().caller();
+        let amount = self.env().attached_value();
+        self.credit_deposit(caller, amount);
+        self.fund_envelope(name, amount);
+    }
+
+    /// Return funds from an envelope to the caller's unassigned balance
+    pub fn release_envelope(&mut self, name: String, amount: U512) {
+        let caller = self.env().caller();
+        self.debit_envelope(caller, name, amount);
+    }
+
+    /// Move earmarked funds from one of the caller's envelopes to another
+    pub fn move_between_envelopes(&mut self, from: String, to: String, amount: U512) {
+        let caller = self.env().caller();
+        self.debit_envelope(caller, from, amount);
+        let balance = self.envelopes.get_or_default(&(caller, to.clone()));
+        self.set_envelope(caller, to, checked_add(&self.env(), balance, amount));
+    }
+
+    /// Withdraw CSPR from one of the caller's envelopes to their account
+    /// 
+    /// Subject to the same withdrawal delay as `withdraw`.
+    pub fn withdraw_from_envelope(&mut self, name: String, amount: U512) {
+        let caller = self.env().caller();
+        self.debit_envelope(caller, name, amount);
+        self.withdraw(amount);
+    }
+
+    /// Release an envelope's funds for one rule execution (called by automation engine)
+    /// 
+    /// Returns the envelope's balance, which the engine must restore with
+    /// `close_envelope` minus whatever the execution spent.
+    pub fn open_envelope(&mut self, owner: Address, name: String) -> U512 {
+        self.assert_authorized_engine();
+        let balance = self.envelopes.get_or_default(&(owner, name.clone()));
+        if !balance.is_zero() {
+            self.set_envelope(owner, name, U512::zero());
+        }
+        balance
+    }
+
+    /// Earmark what is left of an envelope after a rule execution (called by automation engine)
+    pub fn close_envelope(&mut self, owner: Address, name: String, remaining: U512) {
+        self.assert_authorized_engine();
+        if remaining.is_zero() {
+            return;
+        }
+        if self.get_spendable_balance(owner) < remaining {
+            self.env().revert(Error::InsufficientBalance);
+        }
+        let balance = self.envelopes.get_or_default(&(owner, name.clone()));
+        self.set_envelope(owner, name, checked_add(&self.env(), balance, remaining));
+    }
+
+    // ========================================================================
+    // Reservation Functions
+    // ========================================================================
+
+    /// Reserve part of an owner's balance for a rule's next execution (called by automation engine)
+    /// 
+    /// Reserved funds stay spendable by rules but cannot be withdrawn or
+    /// moved into envelopes. Zero releases the rule's reservation.
+    pub fn set_reservation(&mut self, owner: Address, rule_id: u64, amount: U512) {
+        self.assert_authorized_engine();
+        let previous = self.reservations.get_or_default(&rule_id);
+        if previous == amount {
+            return;
+        }
+        let total = checked_sub(&self.env(), self.reserved_totals.get_or_default(&owner), previous);
+        let total = checked_add(&self.env(), total, amount);
+        self.reservations.set(&rule_id, amount);
+        self.reserved_totals.set(&owner, total);
+        
+        self.env().emit_event(ReservationUpdated {
+            owner,
+            rule_id,
+            amount,
+            total_reserved: total,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    // ========================================================================
+    // View Functions
+    // ========================================================================
+
+    /// Get the vault balance for a specific address
+    pub fn get_balance(&self, owner: Address) -> U512 {
+        self.balances.get_or_default(&owner)
+    }
+
+    /// Get the block time of an owner's last deposit or withdrawal
+    pub fn get_last_activity(&self, owner: Address) -> u64 {
+        self.last_activity.get_or_default(&owner)
+    }
+
+    /// Get the kind and interface version of this contract
+    pub fn get_version(&self) -> ContractVersion {
+        ContractVersion { kind: ContractKind::Vault, version: INTERFACE_VERSION }
+    }
+
+    /// Get the nonce of the most recently emitted event
+    pub fn get_event_nonce(&self) -> u64 {
+        self.event_nonce.current()
+    }
+
+    /// Get the authorized automation engine address
+    pub fn get_automation_engine(&self) -> Option<Address> {
+        self.resolved_engine()
+    }
+
+    /// Get an escrowed payment by ID
+    pub fn get_escrow(&self, escrow_id: u64) -> Option<EscrowPayment> {
+        self.escrows.get(&escrow_id)
+    }
+
+    /// Get the IDs of the escrowed payments a rule has funded
+    pub fn get_rule_escrows(&self, rule_id: u64) -> Vec<u64> {
+        self.rule_escrows.get_or_default(&rule_id)
+    }
+
+    /// Get a claimable transfer by ID
+    pub fn get_claimable_transfer(&self, transfer_id: u64) -> Option<ClaimableTransfer> {
+        self.claimable_transfers.get(&transfer_id)
+    }
+
+    /// Get the IDs of transfers still held for a recipient, oldest first
+    /// 
+    /// Scans at most `MAX_CLAIM_BATCH` of the transfers ever held for them,
+    /// starting at position `start`; page through with increasing `start`.
+    pub fn get_claimable_transfer_ids(&self, recipient: Address, start: u32) -> Vec<u64> {
+        let end = self
+            .recipient_claim_counts
+            .get_or_default(&recipient)
+            .min(start.saturating_add(MAX_CLAIM_BATCH));
+        (start..end)
+            .filter_map(|index| self.recipient_claim_ids.get(&(recipient, index)))
+            .filter(|transfer_id| {
+                self.claimable_transfers
+                    .get(transfer_id)
+                    .is_some_and(|transfer| transfer.status == ClaimStatus::Held)
+            })
+            .collect()
+    }
+
+    /// Get how many transfers have ever been held for a recipient
+    pub fn get_claimable_transfer_count(&self, recipient: Address) -> u32 {
+        self.recipient_claim_counts.get_or_default(&recipient)
+    }
+
+    /// Get the total a recipient can claim
+    pub fn get_claimable_balance(&self, recipient: Address) -> U512 {
+        self.claimable_balances.get_or_default(&recipient)
+    }
+
+    /// Get an owner's auto-staking settings
+    pub fn get_auto_stake(&self, owner: Address) -> Option<AutoStakeConfig> {
+        self.auto_stake_configs.get_or_default(&owner)
+    }
+
+    /// Get how an owner's deposits are split
+    pub fn get_deposit_routing(&self, owner: Address) -> Option<DepositRouting> {
+        self.deposit_routings.get_or_default(&owner)
+    }
+
+    /// Get the unstake proceeds still owed straight to an owner's account
+    pub fn get_pending_unstake_withdrawal(&self, owner: Address) -> U512 {
+        self.unstake_withdrawals.get_or_default(&owner)
+    }
+
+    /// Get the CSPR auto-staked from an owner's balance and not yet unstaked
+    pub fn get_custodial_stake(&self, owner: Address) -> U512 {
+        self.custodial_stakes.get_or_default(&owner)
+    }
+
+    /// Get the contract registry the vault resolves its peers from
+    pub fn get_registry(&self) -> Option<Address> {
+        self.registry.get_or_default()
+    }
+
+    /// Get the contract's total CSPR balance
+    pub fn get_contract_balance(&self) -> U512 {
+        self.env().self_balance()
+    }
+
+    /// Get the CEP-18 token balance for a specific address
+    pub fn get_token_balance(&self, owner: Address, token: Address) -> U256 {
+        self.token_balances.get_or_default(&(owner, token))
+    }
+
+    /// Get an owner's balance of every token that has been allowlisted
+    pub fn get_token_balances(&self, owner: Address) -> Vec<(Address, U256)> {
+        self.listed_tokens
+            .get_or_default()
+            .into_iter()
+            .map(|token| (token, self.token_balances.get_or_default(&(owner, token))))
+            .collect()
+    }
+
+    /// Check whether a CEP-18 token is on the allowlist
+    pub fn is_token_allowed(&self, token: Address) -> bool {
+        self.allowed_tokens.get_or_default(&token)
+    }
+
+    /// Check whether rules may pay `contract` through `entry_point`
+    pub fn is_contract_recipient_allowed(&self, contract: Address, entry_point: String) -> bool {
+        self.allowed_contract_recipients.get_or_default(&(contract, entry_point))
+    }
+
+    /// Get the admin address
+    pub fn get_admin(&self) -> Option<Address> {
+        self.admin.get()
+    }
+
+    /// Whether the account holds the role (the admin holds every role)
+    pub fn has_role(&self, role: Role, account: Address) -> bool {
+        self.roles.holds(self.admin.get(), role, account)
+    }
+
+    /// Get the admin timelock delay in seconds (zero when disabled)
+    pub fn get_admin_timelock_delay(&self) -> u64 {
+        self.timelock.delay()
+    }
+
+    /// Get a queued admin change by ID
+    pub fn get_admin_action(&self, action_id: u64) -> Option<AdminAction> {
+        self.timelock.get(action_id)
+    }
+
+    /// Get every queued admin change with its ID, oldest first
+    pub fn get_pending_admin_actions(&self) -> Vec<(u64, AdminAction)> {
+        self.timelock.pending()
+    }
+
+    /// Get the treasury address
+    pub fn get_treasury(&self) -> Option<Address> {
+        self.resolved_treasury()
+    }
+
+    /// Get an owner's withdrawal delay in seconds, including any lowered delay now in effect
+    pub fn get_withdrawal_delay(&self, owner: Address) -> u64 {
+        let delay = self.withdrawal_delays.get_or_default(&owner);
+        match self.pending_delay_changes.get(&owner) {
+            Some((lowered, effective_at))
+                if effective_at != 0 && self.env().get_block_time() >= effective_at =>
+            {
+                lowered
+            }
+            _ => delay,
+        }
+    }
+
+    /// Get a withdrawal request by ID
+    pub fn get_withdrawal_request(&self, request_id: u64) -> Option<WithdrawalRequest> {
+        self.withdrawal_requests.get(&request_id)
+    }
+
+    /// Get the staking adapter address
+    pub fn get_staking_adapter(&self) -> Option<Address> {
+        self.resolved_staking_adapter()
+    }
+
+    /// Get the balance earmarked for one of an owner's envelopes
+    pub fn get_envelope_balance(&self, owner: Address, name: String) -> U512 {
+        self.envelopes.get_or_default(&(owner, name))
+    }
+
+    /// Get all of an owner's envelopes with their balances
+    pub fn get_envelopes(&self, owner: Address) -> Vec<(String, U512)> {
+        self.envelope_names
+            .get_or_default(&owner)
+            .into_iter()
+            .map(|name| {
+                let balance = self.envelopes.get_or_default(&(owner, name.clone()));
+                (name, balance)
+            })
+            .collect()
+    }
+
+    /// Get the part of an owner's CSPR balance not earmarked for any envelope
+    pub fn get_spendable_balance(&self, owner: Address) -> U512 {
+        self.balances
+            .get_or_default(&owner)
+            .saturating_sub(self.envelope_totals.get_or_default(&owner))
+    }
+
+    /// Get the part of an owner's balance reserved for a rule's next execution
+    pub fn get_reservation(&self, rule_id: u64) -> U512 {
+        self.reservations.get_or_default(&rule_id)
+    }
+
+    /// Get the part of an owner's balance reserved across all their rules
+    pub fn get_reserved_balance(&self, owner: Address) -> U512 {
+        self.reserved_totals.get_or_default(&owner)
+    }
+
+    /// Get the part of an owner's spendable balance they can withdraw, i.e. not reserved
+    pub fn get_withdrawable_balance(&self, owner: Address) -> U512 {
+        self.get_spendable_balance(owner)
+            .saturating_sub(self.reserved_totals.get_or_default(&owner))
+    }
+
+    /// Get the swap adapter address
+    pub fn get_swap_adapter(&self) -> Option<Address> {
+        self.resolved_swap_adapter()
+    }
+
+    /// Get the daily cap on engine-driven outflow per vault (zero for no limit)
+    pub fn get_max_daily_outflow(&self) -> U512 {
+        self.max_daily_outflow.get_or_default()
+    }
+
+    /// Get the CSPR the engine has moved out of an owner's vault today
+    pub fn get_daily_outflow(&self, owner: Address) -> U512 {
+        let today = self.env().get_block_time() / SECONDS_PER_DAY;
+        match self.daily_outflow.get(&owner) {
+            Some((day, spent)) if day == today => spent,
+            _ => U512::zero(),
+        }
+    }
+
+    /// Get the vault's CSPR accounting buckets alongside its actual balance
+    pub fn get_accounting(&self) -> VaultAccounting {
+        VaultAccounting {
+            total_user_deposits: self.total_user_deposits.get_or_default(),
+            total_fees: self.accrued_fees.get_or_default(),
+            total_keeper_bounties: self.total_keeper_bounties.get_or_default(),
+            contract_balance: self.env().self_balance(),
+        }
+    }
+
+    /// Compare the tracked CSPR running totals against the vault's actual balance
+    /// 
+    /// A non-zero `deficit` means the vault owes more than it holds. CSPR
+    /// delegated through the staking adapter is reported separately since it
+    /// is no longer owed from the vault's own balance.
+    pub fn reconcile(&self) -> VaultReconciliation {
+        let tracked_total = checked_add(
+            &self.env(),
+            self.total_user_deposits.get_or_default(),
+            self.accrued_fees.get_or_default(),
+        );
+        let contract_balance = self.env().self_balance();
+        VaultReconciliation {
+            tracked_total,
+            contract_balance,
+            delegated: self.total_delegated.get_or_default(),
+            surplus: contract_balance.saturating_sub(tracked_total),
+            deficit: tracked_total.saturating_sub(contract_balance),
+        }
+    }
+
+    /// Get the protocol fees collected but not yet paid out
+    pub fn get_accrued_fees(&self) -> U512 {
+        self.accrued_fees.get_or_default()
+    }
+
+    /// Get the total protocol fees ever collected
+    pub fn get_total_fees_collected(&self) -> U512 {
+        self.total_fees_collected.get_or_default()
+    }
+
+    /// Get a payment stream by ID
+    pub fn get_stream(&self, stream_id: u64) -> Option<PaymentStream> {
+        self.streams.get(&stream_id)
+    }
+
+    /// Get the amount currently claimable from a stream
+    pub fn get_stream_claimable(&self, stream_id: u64) -> U512 {
+        match self.streams.get(&stream_id) {
+            Some(stream) => stream.claimable_at(self.env().get_block_time()),
+            None => U512::zero(),
+        }
+    }
+
+    /// Get a vesting schedule by ID
+    pub fn get_vesting(&self, vesting_id: u64) -> Option<VestingSchedule> {
+        self.vestings.get(&vesting_id)
+    }
+
+    /// Get the amount currently claimable from a vesting schedule
+    pub fn get_vesting_claimable(&self, vesting_id: u64) -> U512 {
+        match self.vestings.get(&vesting_id) {
+            Some(vesting) => vesting.claimable_at(self.env().get_block_time()),
+            None => U512::zero(),
+        }
+    }
+}
+
+impl AutomationVault {
+    /// Look a contract up in the registry, falling back to the address set on the vault
+    /// 
+    /// Registry entries change without a delay, so while the admin timelock
+    /// is enabled only the vault's own, timelocked addresses are used.
+    fn resolve_address(&self, kind: ContractKind, local: Option<Address>) -> Option<Address> {
+        if self.timelock.delay() > 0 {
+            return local;
+        }
+        match self.registry.get_or_default() {
+            Some(registry) => ContractRegistryContractRef::new(self.env(), registry)
+                .get_address(kind)
+                .or(local),
+            None => local,
+        }
+    }
+
+    /// Get the automation engine address, from the registry if it has one
+    fn resolved_engine(&self) -> Option<Address> {
+        self.resolve_address(ContractKind::Engine, self.authorized_engine.get_or_default())
+    }
+
+    /// Get the treasury address, from the registry if it has one
+    fn resolved_treasury(&self) -> Option<Address> {
+        self.resolve_address(ContractKind::Treasury, self.treasury.get_or_default())
+    }
+
+    /// Get the staking adapter address, from the registry if it has one
+    fn resolved_staking_adapter(&self) -> Option<Address> {
+        self.resolve_address(ContractKind::StakingAdapter, self.staking_adapter.get_or_default())
+    }
+
+    /// Get the swap adapter address, from the registry if it has one
+    fn resolved_swap_adapter(&self) -> Option<Address> {
+        self.resolve_address(ContractKind::SwapAdapter, self.swap_adapter.get_or_default())
+    }
+
+    /// Revert unless the caller is the authorized automation engine
+    fn assert_authorized_engine(&self) {
+        let caller = self.env().caller();
+        match self.resolved_engine() {
+            Some(engine_addr) if caller == engine_addr => {}
+            _ => self.env().revert(Error::UnauthorizedExecutor),
+        }
+    }
+
+    /// Get a stream or revert if not found
+    fn get_stream_or_revert(&self, stream_id: u64) -> PaymentStream {
+        match self.streams.get(&stream_id) {
+            Some(stream) => stream,
+            None => self.env().revert(Error::StreamNotFound),
+        }
+    }
+
+    /// Pay `amount` of an owner's vault balance out to their account
+    fn pay_withdrawal(&mut self, owner: Address, amount: U512) {
+        // Check balance
+        let current_balance = self.balances.get_or_default(&owner);
+        if current_balance < amount {
+            self.env().revert(Error::InsufficientBalance);
+        }
+        self.assert_spendable(owner, amount);
+        self.assert_unreserved(owner, amount);
+        
+        // Update balance
+        let new_balance = checked_sub(&self.env(), current_balance, amount);
+        self.balances.set(&owner, new_balance);
+        self.debit_user_deposits(amount);
+        
+        // Transfer to owner
+        self.env().transfer_tokens(&owner, &amount);
+        self.assert_solvent();
+        
+        // Emit event
+        self.env().emit_event(Withdrawn {
+            owner,
+            amount,
+            new_balance,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Queue an unstake of `amount` of an owner's auto-staked funds back to their vault balance
+    fn unwind_auto_stake(&mut self, owner: Address, amount: U512) {
+        let adapter = match self.resolved_staking_adapter() {
+            Some(adapter) => adapter,
+            None => self.env().revert(Error::StakingAdapterNotSet),
+        };
+        let unstake_id = StakingAdapterContractRef::new(self.env(), adapter)
+            .queue_vault_unstake(owner, amount);
+        let custodial_stake = self.custodial_stakes.get_or_default(&owner);
+        let custodial_stake = checked_sub(&self.env(), custodial_stake, amount);
+        self.custodial_stakes.set(&owner, custodial_stake);
+        
+        self.env().emit_event(AutoStakeUnwound {
+            owner,
+            amount,
+            unstake_id,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Get a claimable transfer that is still held, or revert
+    fn get_held_claimable_or_revert(&self, transfer_id: u64) -> ClaimableTransfer {
+        match self.claimable_transfers.get(&transfer_id) {
+            Some(transfer) if transfer.status == ClaimStatus::Held => transfer,
+            _ => self.env().revert(Error::ClaimableTransferNotFound),
+        }
+    }
+
+    /// Get an escrowed payment that is still held, or revert
+    fn get_held_escrow_or_revert(&self, escrow_id: u64) -> EscrowPayment {
+        match self.escrows.get(&escrow_id) {
+            Some(escrow) if escrow.status == EscrowStatus::Held => escrow,
+            _ => self.env().revert(Error::EscrowNotFound),
+        }
+    }
+
+    /// Get a vesting schedule or revert if not found
+    fn get_vesting_or_revert(&self, vesting_id: u64) -> VestingSchedule {
+        match self.vestings.get(&vesting_id) {
+            Some(vesting) => vesting,
+            None => self.env().revert(Error::VestingNotFound),
+        }
+    }
+
+    /// Revert unless the caller is the admin
+    fn assert_admin(&self) {
+        self.roles.assert_admin(self.admin.get());
+    }
+
+    /// Whether the engine's blocklist bars `recipient` from being paid
+    fn is_blocked(&self, recipient: Address) -> bool {
+        self.resolved_engine().is_some_and(|engine| {
+            AutomationEngineContractRef::new(self.env(), engine).is_recipient_blocked(recipient)
+        })
+    }
+
+    /// Revert if the engine's blocklist bars `recipient` from being paid
+    fn assert_not_blocked(&self, recipient: Address) {
+        if self.is_blocked(recipient) {
+            self.env().revert(Error::RecipientBlocked);
+        }
+    }
+
+    /// Revert unless the caller is the admin or has been granted `role`
+    fn assert_role(&self, role: Role) {
+        self.roles.assert_role(self.admin.get(), role);
+    }
+
+    /// Revert if the admin timelock is enabled, so a change must be queued
+    fn assert_no_timelock(&self) {
+        if self.timelock.delay() > 0 {
+            self.env().revert(Error::TimelockRequired);
+        }
+    }
+
+    /// Queue an admin change in the timelock and emit AdminActionQueued
+    fn queue_admin_action(&mut self, action: AdminAction) -> u64 {
+        let (action_id, action) = self.timelock.queue(action);
+        self.env().emit_event(AdminActionQueued {
+            action_id,
+            action,
+            nonce: self.event_nonce.next(),
+        });
+        action_id
+    }
+
+    /// Revert if the owner has opted into timelocked withdrawals
+    fn assert_no_withdrawal_delay(&self, owner: Address) {
+        if self.get_withdrawal_delay(owner) > 0 {
+            self.env().revert(Error::WithdrawalTimelocked);
+        }
+    }
+
+    /// Store a withdrawal request for funds already removed from the owner's balance
+    fn open_withdrawal_request(&mut self, owner: Address, token: Option<Address>, amount: U512) -> u64 {
+        let request_id = self.next_withdrawal_id.get_or_default() + 1;
+        self.next_withdrawal_id.set(request_id);
+        let unlock_time = self.env().get_block_time() + self.get_withdrawal_delay(owner);
+        self.withdrawal_requests.set(&request_id, WithdrawalRequest {
+            owner,
+            token,
+            amount,
+            unlock_time,
+            closed: false,
+        });
+        
+        self.env().emit_event(WithdrawalRequested {
+            request_id,
+            owner,
+            token,
+            amount,
+            unlock_time,
+            nonce: self.event_nonce.next(),
+        });
+        request_id
+    }
+
+    /// Get an open withdrawal request owned by the caller or revert
+    fn get_withdrawal_request_or_revert(&self, request_id: u64) -> WithdrawalRequest {
+        let request = match self.withdrawal_requests.get(&request_id) {
+            Some(request) if !request.closed => request,
+            _ => self.env().revert(Error::WithdrawalRequestNotFound),
+        };
+        if request.owner != self.env().caller() {
+            self.env().revert(Error::NotVaultOwner);
+        }
+        request
+    }
+
+    /// Debit `amount` plus `fee` from an owner's balance and pay `amount` to the recipient
+    /// 
+    /// Contract recipients are paid through their `deposit` entrypoint when given.
+    fn transfer_out(
+        &mut self,
+        owner: Address,
+        recipient: Address,
+        amount: U512,
+        fee: U512,
+        rule_id: u64,
+        execution_id: u64,
+        deposit: Option<ContractRecipient>,
+    ) {
+        // Check balance
+        let total = checked_add(&self.env(), amount, fee);
+        let current_balance = self.balances.get_or_default(&owner);
+        if current_balance < total {
+            self.env().revert(Error::InsufficientBalance);
+        }
+        self.assert_spendable(owner, total);
+        
+        // Count against the daily cap, then update balance
+        self.record_outflow(owner, total);
+        let new_balance = checked_sub(&self.env(), current_balance, total);
+        self.balances.set(&owner, new_balance);
+        self.debit_user_deposits(total);
+        
+        // Transfer to recipient
+        match deposit {
+            Some(deposit) => self.deposit_into_contract(recipient, deposit, amount),
+            None => self.env().transfer_tokens(&recipient, &amount),
+        }
+        
+        // Emit event
+        self.env().emit_event(AutomationExecuted {
+            owner,
+            rule_id,
+            execution_id,
+            recipient,
+            amount,
+            fee,
+            balance_before: current_balance,
+            balance_after: new_balance,
+            nonce: self.event_nonce.next(),
+        });
+        
+        // Accrue the protocol fee
+        self.accrue_fee(owner, rule_id, fee);
+    }
+
+    /// Pay `amount` into a contract by calling its deposit entrypoint with the value attached
+    fn deposit_into_contract(&self, contract: Address, deposit: ContractRecipient, amount: U512) {
+        let allowed_key = (contract, deposit.entry_point.clone());
+        if !self.allowed_contract_recipients.get_or_default(&allowed_key) {
+            self.env().revert(Error::InvalidContractRecipient);
+        }
+        let mut args = RuntimeArgs::new();
+        if let Some(beneficiary) = deposit.beneficiary {
+            args.insert("owner", beneficiary)
+                .unwrap_or_else(|_| self.env().revert(Error::InvalidContractRecipient));
+        }
+        let call = CallDef::new(deposit.entry_point, true, args).with_amount(amount);
+        self.env().call_contract::<()>(contract, call);
+    }
+
+    /// Add engine-driven outflow to an owner's daily total, reverting past the cap
+    fn record_outflow(&mut self, owner: Address, amount: U512) {
+        let limit = self.max_daily_outflow.get_or_default();
+        if limit.is_zero() {
+            return;
+        }
+        
+        let today = self.env().get_block_time() / SECONDS_PER_DAY;
+        let spent = checked_add(&self.env(), self.get_daily_outflow(owner), amount);
+        if spent > limit {
+            self.env().revert(Error::DailyOutflowLimitExceeded);
+        }
+        self.daily_outflow.set(&owner, (today, spent));
+    }
+
+    /// Add CSPR that now belongs to users to the user deposits bucket
+    fn credit_user_deposits(&mut self, amount: U512) {
+        let total = checked_add(&self.env(), self.total_user_deposits.get_or_default(), amount);
+        self.total_user_deposits.set(total);
+    }
+
+    /// Remove CSPR that left the users' side of the books (paid out or charged as a fee)
+    fn debit_user_deposits(&mut self, amount: U512) {
+        let total = checked_sub(&self.env(), self.total_user_deposits.get_or_default(), amount);
+        self.total_user_deposits.set(total);
+    }
+
+    /// Revert unless the vault holds enough CSPR to cover user deposits and accrued fees
+    fn assert_solvent(&self) {
+        if !self.reconcile().deficit.is_zero() {
+            self.env().revert(Error::SolvencyViolation);
+        }
+    }
+
+    /// Revert if spending `amount` would dip into funds earmarked for envelopes
+    fn assert_spendable(&self, owner: Address, amount: U512) {
+        if self.get_spendable_balance(owner) < amount {
+            self.env().revert(Error::EnvelopeFundsLocked);
+        }
+    }
+
+    /// Revert if taking `amount` out of the owner's hands would dip into reserved funds
+    fn assert_unreserved(&self, owner: Address, amount: U512) {
+        if self.get_withdrawable_balance(owner) < amount {
+            self.env().revert(Error::FundsReserved);
+        }
+    }
+
+    /// Record a deposit or withdrawal by `owner`, keeping their dead-man switch rules from firing
+    fn record_activity(&mut self, owner: Address) {
+        self.last_activity.set(&owner, self.env().get_block_time());
+    }
+
+    /// Credit a deposit to the owner's balance
+    fn credit_deposit(&mut self, owner: Address, amount: U512) {
+        if amount.is_zero() {
+            self.env().revert(Error::ZeroAmount);
+        }
+        
+        // Update balance
+        let current_balance = self.balances.get_or_default(&owner);
+        let new_balance = checked_add(&self.env(), current_balance, amount);
+        self.balances.set(&owner, new_balance);
+        self.credit_user_deposits(amount);
+        self.check_invariants();
+        
+        // Emit event
+        self.env().emit_event(Deposited {
+            owner,
+            amount,
+            new_balance,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Split a freshly credited deposit according to the owner's routing, if any
+    fn route_deposit(&mut self, owner: Address, amount: U512) {
+        let routing = match self.deposit_routings.get_or_default(&owner) {
+            Some(routing) => routing,
+            None => return,
+        };
+        let (staked, saved, liquid) = routing.split(amount);
+        
+        if !saved.is_zero() {
+            let name = routing.savings_envelope;
+            let balance = self.envelopes.get_or_default(&(owner, name.clone()));
+            self.set_envelope(owner, name, checked_add(&self.env(), balance, saved));
+        }
+        if let (false, Some(validator)) = (staked.is_zero(), routing.validator) {
+            self.reentrancy_guard.enter();
+            let adapter = match self.resolved_staking_adapter() {
+                Some(adapter) => adapter,
+                None => self.env().revert(Error::StakingAdapterNotSet),
+            };
+            self.stake_custodial(adapter, owner, validator, staked);
+            self.reentrancy_guard.exit();
+        }
+        
+        self.env().emit_event(DepositRouted {
+            owner,
+            amount,
+            staked,
+            saved,
+            liquid,
+            nonce: self.event_nonce.next(),
+        });
+        self.check_invariants();
+    }
+
+    /// Stake part of an owner's balance under their account, tracked as custodial stake
+    fn stake_custodial(
+        &mut self,
+        adapter: Address,
+        owner: Address,
+        validator: PublicKey,
+        amount: U512,
+    ) {
+        let current_balance = self.balances.get_or_default(&owner);
+        self.balances.set(&owner, checked_sub(&self.env(), current_balance, amount));
+        self.debit_user_deposits(amount);
+        
+        let delegated = checked_add(&self.env(), self.total_delegated.get_or_default(), amount);
+        self.total_delegated.set(delegated);
+        let custodial_stake = self.custodial_stakes.get_or_default(&owner);
+        let custodial_stake = checked_add(&self.env(), custodial_stake, amount);
+        self.custodial_stakes.set(&owner, custodial_stake);
+        
+        // Release the funds directly to the adapter
+        StakingAdapterContractRef::new(self.env(), adapter)
+            .with_tokens(amount)
+            .stake_for(owner, validator.clone());
+        
+        self.env().emit_event(AutoStaked {
+            owner,
+            validator,
+            amount,
+            custodial_stake,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Take `amount` out of an envelope, reverting if it holds less
+    fn debit_envelope(&mut self, owner: Address, name: String, amount: U512) {
+        if amount.is_zero() {
+            self.env().revert(Error::ZeroAmount);
+        }
+        let balance = self.envelopes.get_or_default(&(owner, name.clone()));
+        if balance < amount {
+            self.env().revert(Error::InsufficientEnvelopeBalance);
+        }
+        self.set_envelope(owner, name, balance - amount);
+    }
+
+    /// Set an envelope's balance, keeping the owner's envelope total and name list in step
+    fn set_envelope(&mut self, owner: Address, name: String, balance: U512) {
+        let previous = self.envelopes.get_or_default(&(owner, name.clone()));
+        let total = self.envelope_totals.get_or_default(&owner);
+        let total = checked_add(&self.env(), checked_sub(&self.env(), total, previous), balance);
+        self.envelope_totals.set(&owner, total);
+        
+        let mut names = self.envelope_names.get_or_default(&owner);
+        if !names.contains(&name) {
+            if name.is_empty() || name.len() > MAX_ENVELOPE_NAME_LEN || names.len() >= MAX_ENVELOPES {
+                self.env().revert(Error::InvalidEnvelope);
+            }
+            names.push(name.clone());
+            self.envelope_names.set(&owner, names);
+        }
+        self.envelopes.set(&(owner, name.clone()), balance);
+        
+        self.env().emit_event(EnvelopeUpdated {
+            owner,
+            name,
+            balance,
+            nonce: self.event_nonce.next(),
+        });
+    }
+
+    /// Re-check solvency after every balance-changing call in test builds
+    /// 
+    /// Kept out of production builds to save gas; `reconcile` serves
+    /// monitoring there.
+    fn check_invariants(&self) {
+        if cfg!(test) {
+            self.assert_solvent();
+        }
+    }
+
+    /// Accrue a protocol fee charged on one of `owner`'s automated actions
+    fn accrue_fee(&mut self, owner: Address, rule_id: u64, fee: U512) {
+        if fee.is_zero() {
+            return;
+        }
+        let accrued = checked_add(&self.env(), self.accrued_fees.get_or_default(), fee);
+        self.accrued_fees.set(accrued);
+        let collected = checked_add(&self.env(), self.total_fees_collected.get_or_default(), fee);
+        self.total_fees_collected.set(collected);
+        self.env().emit_event(FeeCharged {
+            owner,
+            rule_id,
+            amount: fee,
+            nonce: self.event_nonce.next(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use odra::host::{Deployer, HostRef, NoArgs};
+    use crate::cep18::test_token::TestToken;
+    use crate::staking_adapter::{StakingAdapter, StakingAdapterInitArgs};
+
+    #[test]
+    fn test_deposit_and_withdraw() {
+        let env = odra_test::env();
+        let mut vault = AutomationVault::deploy(&env, AutomationVaultInitArgs {
+            automation_engine: None,
+        });
+
+        let depositor = env.get_account(0);
+        let deposit_amount = U512::from(1_000_000_000u64); // 1 CSPR
+
+        // Deposit
+        env.set_caller(depositor);
+        vault.with_tokens(deposit_amount).deposit();
+        
+        assert_eq!(vault.get_balance(depositor), deposit_amount);
+
+        // Withdraw half
+        let withdraw_amount = U512::from(500_000_000u64);
+        vault.withdraw(withdraw_amount);
+        
+        assert_eq!(vault.get_balance(depositor), deposit_amount - withdraw_amount);
+    }
+
+    #[test]
+    fn test_insufficient_balance() {
+        let env = odra_test::env();
+        let mut vault = AutomationVault::deploy(&env, AutomationVaultInitArgs {
+            automation_engine: None,
+        });
+
+        let depositor = env.get_account(0);
+        let deposit_amount = U512::from(100_000_000u64);
+        let withdraw_amount = U512::from(200_000_000u64);
+
+        // Deposit
         env.set_caller(depositor);
         vault.with_tokens(deposit_amount).deposit();
 
@@ -209,4 +2844,348 @@ mod tests {
         let result = vault.try_withdraw(withdraw_amount);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_token_deposit_and_withdraw() {
+        let env = odra_test::env();
+        let admin = env.get_account(0);
+        let user = env.get_account(1);
+        env.set_caller(admin);
+        let mut vault = AutomationVault::deploy(&env, AutomationVaultInitArgs {
+            automation_engine: None,
+        });
+        let mut token = TestToken::deploy(&env, NoArgs);
+        let amount = U256::from(1_000u64);
+        token.mint(&user, &amount);
+
+        // Token must be allowlisted first
+        env.set_caller(user);
+        token.approve(vault.address(), &amount);
+        let result = vault.try_deposit_token(*token.address(), amount);
+        assert_eq!(result, Err(Error::TokenNotAllowed.into()));
+
+        // Only the admin may update the allowlist
+        let result = vault.try_set_token_allowed(*token.address(), true);
+        assert_eq!(result, Err(Error::NotAdmin.into()));
+        env.set_caller(admin);
+        vault.set_token_allowed(*token.address(), true);
+
+        env.set_caller(user);
+        vault.deposit_token(*token.address(), amount);
+        assert_eq!(vault.get_token_balance(user, *token.address()), amount);
+        assert_eq!(token.balance_of(vault.address()), amount);
+
+        vault.withdraw_token(*token.address(), U256::from(400u64));
+        assert_eq!(vault.get_token_balance(user, *token.address()), U256::from(600u64));
+        assert_eq!(token.balance_of(&user), U256::from(400u64));
+    }
+
+    #[test]
+    fn test_timelocked_withdrawal() {
+        let env = odra_test::env();
+        let mut vault = AutomationVault::deploy(&env, AutomationVaultInitArgs {
+            automation_engine: None,
+        });
+        let owner = env.get_account(0);
+        let amount = U512::from(1_000_000_000u64);
+        env.set_caller(owner);
+        vault.with_tokens(amount).deposit();
+        vault.set_withdrawal_delay(3_600);
+        
+        // Instant withdrawals are disabled
+        let result = vault.try_withdraw(amount);
+        assert_eq!(result, Err(Error::WithdrawalTimelocked.into()));
+        
+        // Lowering the delay only takes effect after the current delay
+        vault.set_withdrawal_delay(0);
+        assert_eq!(vault.get_withdrawal_delay(owner), 3_600);
+        
+        let request_id = vault.request_withdrawal(amount);
+        assert_eq!(vault.get_balance(owner), U512::zero());
+        let result = vault.try_complete_withdrawal(request_id);
+        assert_eq!(result, Err(Error::WithdrawalNotReady.into()));
+        
+        // Cancelling returns the funds; a new request waits out the delay
+        vault.cancel_withdrawal(request_id);
+        assert_eq!(vault.get_balance(owner), amount);
+        let request_id = vault.request_withdrawal(amount);
+        env.advance_block_time(3_600);
+        vault.complete_withdrawal(request_id);
+        assert!(vault.get_withdrawal_request(request_id).unwrap().closed);
+        assert_eq!(vault.get_withdrawal_delay(owner), 0);
+    }
+
+    #[test]
+    fn test_deposit_for() {
+        let env = odra_test::env();
+        let mut vault = AutomationVault::deploy(&env, AutomationVaultInitArgs {
+            automation_engine: None,
+        });
+        let employer = env.get_account(0);
+        let employee = env.get_account(1);
+        let amount = U512::from(2_000_000_000u64);
+        
+        env.set_caller(employer);
+        vault.with_tokens(amount).deposit_for(employee);
+        
+        assert_eq!(vault.get_balance(employee), amount);
+        assert_eq!(vault.get_balance(employer), U512::zero());
+    }
+
+    #[test]
+    fn test_events_carry_nonce() {
+        let env = odra_test::env();
+        let mut vault = AutomationVault::deploy(&env, AutomationVaultInitArgs {
+            automation_engine: None,
+        });
+        let owner = env.get_account(0);
+        env.set_caller(owner);
+        
+        vault.with_tokens(U512::from(1_000u64)).deposit();
+        vault.withdraw(U512::from(400u64));
+        
+        // Each event takes the next nonce, so indexers can spot gaps
+        assert_eq!(vault.get_event_nonce(), 2);
+        assert!(env.emitted_event(
+            vault.address(),
+            Withdrawn {
+                owner,
+                amount: U512::from(400u64),
+                new_balance: U512::from(600u64),
+                nonce: 2,
+            }
+        ));
+    }
+
+    #[test]
+    fn test_fee_withdrawal_never_touches_deposits() {
+        let env = odra_test::env();
+        let admin = env.get_account(0);
+        let owner = env.get_account(1);
+        let recipient = env.get_account(2);
+        let engine = env.get_account(3);
+        let mut vault = AutomationVault::deploy(&env, AutomationVaultInitArgs {
+            automation_engine: Some(engine),
+        });
+        
+        env.set_caller(owner);
+        vault.with_tokens(U512::from(1_000u64)).deposit();
+        env.set_caller(engine);
+        vault.execute_transfer(owner, recipient, U512::from(300u64), U512::from(10u64), 1, 1);
+        
+        let accounting = vault.get_accounting();
+        assert_eq!(accounting.total_user_deposits, U512::from(690u64));
+        assert_eq!(accounting.total_fees, U512::from(10u64));
+        assert_eq!(accounting.contract_balance, U512::from(700u64));
+        
+        // Only the admin can withdraw, and never more than the fee bucket
+        assert_eq!(
+            vault.try_withdraw_fees(admin, U512::from(10u64)),
+            Err(Error::NotAdmin.into())
+        );
+        env.set_caller(admin);
+        assert_eq!(
+            vault.try_withdraw_fees(admin, U512::from(11u64)),
+            Err(Error::InsufficientFees.into())
+        );
+        vault.withdraw_fees(admin, U512::from(10u64));
+        
+        let accounting = vault.get_accounting();
+        assert_eq!(accounting.total_fees, U512::zero());
+        assert_eq!(accounting.contract_balance, accounting.total_user_deposits);
+    }
+
+    #[test]
+    fn test_reconcile() {
+        let env = odra_test::env();
+        let owner = env.get_account(1);
+        let recipient = env.get_account(2);
+        let engine = env.get_account(3);
+        let mut vault = AutomationVault::deploy(&env, AutomationVaultInitArgs {
+            automation_engine: Some(engine),
+        });
+        
+        env.set_caller(owner);
+        vault.with_tokens(U512::from(1_000u64)).deposit();
+        vault.request_withdrawal(U512::from(100u64));
+        env.set_caller(engine);
+        vault.execute_transfer(owner, recipient, U512::from(200u64), U512::from(5u64), 1, 1);
+        
+        // Pending withdrawals stay tracked; the fee moves to the fee bucket
+        let report = vault.reconcile();
+        assert_eq!(report.tracked_total, U512::from(800u64));
+        assert_eq!(report.contract_balance, U512::from(800u64));
+        assert_eq!(report.delegated, U512::zero());
+        assert_eq!(report.surplus, U512::zero());
+        assert_eq!(report.deficit, U512::zero());
+    }
+
+    #[test]
+    fn test_envelopes() {
+        let env = odra_test::env();
+        let mut vault = AutomationVault::deploy(&env, AutomationVaultInitArgs {
+            automation_engine: None,
+        });
+        let owner = env.get_account(0);
+        env.set_caller(owner);
+        vault.with_tokens(U512::from(10_000u64)).deposit();
+        vault.with_tokens(U512::from(3_000u64)).deposit_to_envelope("bills".to_string());
+        vault.fund_envelope("savings".to_string(), U512::from(5_000u64));
+        assert_eq!(vault.get_spendable_balance(owner), U512::from(5_000u64));
+        
+        // Earmarked funds cannot be withdrawn as unassigned balance
+        let result = vault.try_withdraw(U512::from(6_000u64));
+        assert_eq!(result, Err(Error::EnvelopeFundsLocked.into()));
+        
+        vault.move_between_envelopes("savings".to_string(), "bills".to_string(), U512::from(1_000u64));
+        let result = vault.try_withdraw_from_envelope("savings".to_string(), U512::from(5_000u64));
+        assert_eq!(result, Err(Error::InsufficientEnvelopeBalance.into()));
+        vault.withdraw_from_envelope("bills".to_string(), U512::from(4_000u64));
+        
+        assert_eq!(vault.get_balance(owner), U512::from(9_000u64));
+        assert_eq!(vault.get_envelopes(owner), vec![
+            ("bills".to_string(), U512::zero()),
+            ("savings".to_string(), U512::from(4_000u64)),
+        ]);
+    }
+
+    #[test]
+    fn test_withdraw_with_unstake() {
+        let env = odra_test::env();
+        let mut vault = AutomationVault::deploy(&env, AutomationVaultInitArgs {
+            automation_engine: None,
+        });
+        let mut adapter = StakingAdapter::deploy(&env, StakingAdapterInitArgs {
+            default_validator: Some(env.get_validator(0)),
+        });
+        adapter.set_vault_address(*vault.address());
+        vault.set_staking_adapter(*adapter.address());
+        
+        let owner = env.get_account(0);
+        let cspr = U512::from(1_000_000_000_000u64);
+        env.set_caller(owner);
+        vault.with_tokens(cspr).deposit();
+        adapter.with_tokens(cspr * 2).stake();
+        
+        // Only the adapter may call the vault-side unstake
+        let result = adapter.try_queue_vault_unstake(owner, cspr);
+        assert_eq!(result, Err(Error::UnauthorizedExecutor.into()));
+        
+        // Covered by the vault balance: a plain withdrawal
+        assert_eq!(vault.withdraw_with_unstake(cspr / 2), None);
+        assert_eq!(vault.get_balance(owner), cspr / 2);
+        
+        // The shortfall is unstaked and owed straight to the owner
+        let unstake_id = vault.withdraw_with_unstake(cspr).unwrap();
+        assert_eq!(vault.get_balance(owner), U512::zero());
+        assert_eq!(vault.get_pending_unstake_withdrawal(owner), cspr / 2);
+        assert_eq!(adapter.get_user_stake(owner), cspr * 3 / 2);
+        assert!(env.emitted_event(
+            vault.address(),
+            UnstakeWithdrawalQueued {
+                owner,
+                amount: cspr / 2,
+                unstake_id,
+                nonce: vault.get_event_nonce() - 1,
+            }
+        ));
+        
+        // Without enough stake nothing is withdrawn
+        let result = vault.try_withdraw_with_unstake(cspr * 2);
+        assert_eq!(result, Err(Error::InsufficientStakingBalance.into()));
+    }
+
+    #[test]
+    fn test_transfer_internal() {
+        let env = odra_test::env();
+        let mut vault = AutomationVault::deploy(&env, AutomationVaultInitArgs {
+            automation_engine: None,
+        });
+        let (alice, bob) = (env.get_account(1), env.get_account(2));
+        env.set_caller(alice);
+        vault.with_tokens(U512::from(1_000u64)).deposit();
+        
+        // The balance moves inside the vault; no CSPR leaves the contract
+        let contract_balance = env.balance_of(vault.address());
+        vault.transfer_internal(bob, U512::from(300u64));
+        assert_eq!(vault.get_balance(alice), U512::from(700u64));
+        assert_eq!(vault.get_balance(bob), U512::from(300u64));
+        assert_eq!(env.balance_of(vault.address()), contract_balance);
+        assert!(env.emitted_event(
+            vault.address(),
+            InternalTransfer {
+                from: alice,
+                to: bob,
+                amount: U512::from(300u64),
+                nonce: vault.get_event_nonce(),
+            }
+        ));
+        
+        // The recipient can spend it at once
+        env.set_caller(bob);
+        vault.withdraw(U512::from(300u64));
+        let result = vault.try_transfer_internal(alice, U512::from(1u64));
+        assert_eq!(result, Err(Error::InsufficientBalance.into()));
+        let result = vault.try_transfer_internal(alice, U512::zero());
+        assert_eq!(result, Err(Error::ZeroAmount.into()));
+    }
+
+    #[test]
+    fn test_deposit_routing() {
+        let env = odra_test::env();
+        let mut vault = AutomationVault::deploy(&env, AutomationVaultInitArgs {
+            automation_engine: None,
+        });
+        let validator = env.get_validator(0);
+        let mut adapter = StakingAdapter::deploy(&env, StakingAdapterInitArgs {
+            default_validator: Some(validator.clone()),
+        });
+        adapter.set_validator_allowed(validator.clone(), true);
+        adapter.set_vault_address(*vault.address());
+        vault.set_staking_adapter(*adapter.address());
+        
+        let owner = env.get_account(0);
+        let cspr = U512::from(1_000_000_000_000u64);
+        env.set_caller(owner);
+        let mut routing = DepositRouting {
+            stake_bps: 5_000,
+            savings_bps: 2_000,
+            validator: None,
+            savings_envelope: "savings".to_string(),
+        };
+        
+        // The staked share needs a validator
+        let result = vault.try_set_deposit_routing(Some(routing.clone()));
+        assert_eq!(result, Err(Error::InvalidDepositRouting.into()));
+        routing.validator = Some(validator.clone());
+        vault.set_deposit_routing(Some(routing));
+        
+        // Half is staked, a fifth saved and the rest stays liquid
+        vault.with_tokens(cspr * 10).deposit();
+        assert_eq!(vault.get_balance(owner), cspr * 5);
+        assert_eq!(vault.get_custodial_stake(owner), cspr * 5);
+        assert_eq!(adapter.get_user_stake(owner), cspr * 5);
+        assert_eq!(vault.get_envelope_balance(owner, "savings".to_string()), cspr * 2);
+        assert_eq!(vault.get_spendable_balance(owner), cspr * 3);
+        assert!(env.emitted_event(
+            vault.address(),
+            DepositRouted {
+                owner,
+                amount: cspr * 10,
+                staked: cspr * 5,
+                saved: cspr * 2,
+                liquid: cspr * 3,
+                nonce: vault.get_event_nonce(),
+            }
+        ));
+        
+        // Deposits straight into an envelope are not split
+        vault.with_tokens(cspr).deposit_to_envelope("bills".to_string());
+        assert_eq!(vault.get_custodial_stake(owner), cspr * 5);
+        
+        // With routing off, deposits stay liquid
+        vault.set_deposit_routing(None);
+        vault.with_tokens(cspr).deposit();
+        assert_eq!(vault.get_spendable_balance(owner), cspr * 4);
+    }
 }
@@ -43,6 +43,7 @@ export const SCHEDULES = [
     { value: 0, label: 'Daily' },
     { value: 1, label: 'Weekly' },
     { value: 2, label: 'Monthly' },
+    { value: 3, label: 'Once' },
 ] as const
 
 // Rule status mappings
@@ -50,6 +51,7 @@ export const RULE_STATUS = {
     0: 'Active',
     1: 'Paused',
     2: 'Deleted',
+    4: 'Completed',
 } as const
 
 // CSPR conversion (1 CSPR = 1e9 motes)
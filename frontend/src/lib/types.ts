@@ -9,6 +9,8 @@ export const RuleStatus = {
     Active: 0,
     Paused: 1,
     Deleted: 2,
+    PendingApproval: 3,
+    Completed: 4,
 } as const
 export type RuleStatus = (typeof RuleStatus)[keyof typeof RuleStatus]
 
@@ -19,6 +21,7 @@ export const Schedule = {
     Daily: 0,
     Weekly: 1,
     Monthly: 2,
+    Once: 3,
 } as const
 export type Schedule = (typeof Schedule)[keyof typeof Schedule]
 
@@ -39,21 +42,95 @@ export const ActionType = {
     Transfer: 0,
     Split: 1,
     Compound: 2,
+    Stream: 3,
+    Vesting: 4,
+    StakeFromVault: 5,
+    Unstake: 6,
+    Rebalance: 7,
+    Inheritance: 8,
+    Sweep: 9,
+    TopUp: 10,
+    Swap: 11,
+    Restake: 12,
+    Payroll: 13,
+    Escrow: 14,
+    Redelegate: 15,
 } as const
 export type ActionType = (typeof ActionType)[keyof typeof ActionType]
 
+/**
+ * Rule executor policy matching smart contract
+ */
+export const ExecutorPolicy = {
+    Anyone: 0,
+    OwnerOnly: 1,
+    AllowlistedKeepers: 2,
+} as const
+export type ExecutorPolicy = (typeof ExecutorPolicy)[keyof typeof ExecutorPolicy]
+
+/**
+ * Swap direction matching smart contract
+ */
+export const SwapDirection = {
+    CsprToToken: 0,
+    TokenToCspr: 1,
+} as const
+export type SwapDirection = (typeof SwapDirection)[keyof typeof SwapDirection]
+
+/**
+ * Rule recipient kind matching smart contract
+ */
+export const RecipientKind = {
+    Account: 0,
+    Contract: 1,
+    Vault: 2,
+} as const
+export type RecipientKind = (typeof RecipientKind)[keyof typeof RecipientKind]
+
+/**
+ * Compounding reward destination matching smart contract
+ */
+export const RewardDestination = {
+    Restake: 0,
+    Vault: 1,
+    External: 2,
+} as const
+export type RewardDestination = (typeof RewardDestination)[keyof typeof RewardDestination]
+
+/**
+ * Missed execution policy matching smart contract
+ */
+export const MissedExecutionPolicy = {
+    Skip: 0,
+    ExecuteOnceLate: 1,
+    CatchUpAll: 2,
+} as const
+export type MissedExecutionPolicy = (typeof MissedExecutionPolicy)[keyof typeof MissedExecutionPolicy]
+
+/**
+ * Rule amount spec matching smart contract
+ */
+export const AmountSpec = {
+    Fixed: 0,
+    PercentOfVault: 1,
+    PercentOfRewards: 2,
+} as const
+export type AmountSpec = (typeof AmountSpec)[keyof typeof AmountSpec]
+
 /**
  * Automation rule as returned from contract
  */
 export interface Rule {
     id: number
     template_name: string
+    template_name_id: number
     owner: string
     status: RuleStatus
     trigger_type: TriggerType
     schedule: Schedule
     action_type: ActionType
     recipient: string
+    recipient_kind: RecipientKind
     amount: string
     next_execution: number
     last_executed: number | null